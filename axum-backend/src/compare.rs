@@ -0,0 +1,227 @@
+use axum::{http::StatusCode, Json};
+use room_matching::{bbox_iou, match_boxes};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::validated_json::ValidatedJson;
+use crate::{
+    detect_auto_handler_inner, metrics, room_detector, AutoDetectMethod, AutoDetectRequest, AutoDetectResponse, ErrorResponse,
+    Room,
+};
+
+/// IoU a before/after pair needs to count as the same room
+const DEFAULT_COMPARE_IOU: f64 = 0.5;
+
+fn default_compare_iou() -> f64 {
+    DEFAULT_COMPARE_IOU
+}
+
+/// Two floorplans to diff; each side accepts anything `/detect/auto` does
+#[derive(Debug, Deserialize)]
+pub struct CompareRequest {
+    before: AutoDetectRequest,
+    after: AutoDetectRequest,
+    #[serde(default = "default_compare_iou")]
+    iou_threshold: f64,
+}
+
+/// A room present in both plans
+#[derive(Debug, Serialize)]
+pub struct MatchedRoom {
+    pub before: Room,
+    pub after: Room,
+    pub iou: f64,
+    /// `after.area - before.area`, in input units
+    pub area_delta: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompareResponse {
+    pub matched: Vec<MatchedRoom>,
+    /// Rooms only in the after plan
+    pub added: Vec<Room>,
+    /// Rooms only in the before plan
+    pub removed: Vec<Room>,
+    pub iou_threshold: f64,
+}
+
+/// `POST /detect/compare`: detect rooms in a before and after plan and report what changed
+pub async fn compare_handler(
+    ValidatedJson(request): ValidatedJson<CompareRequest>,
+) -> Result<Json<CompareResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let result = compare_handler_inner(Json(request)).await;
+    metrics::track_detection("compare", result, |response| response.matched.len() + response.added.len())
+}
+
+async fn compare_handler_inner(
+    Json(request): Json<CompareRequest>,
+) -> Result<Json<CompareResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !(request.iou_threshold > 0.0 && request.iou_threshold <= 1.0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_IOU_THRESHOLD".to_string(),
+                message: "IoU threshold must be in (0, 1]".to_string(),
+            }),
+        ));
+    }
+
+    let (Json(before), Json(after)) = tokio::try_join!(
+        detect_auto_handler_inner(Json(request.before)),
+        detect_auto_handler_inner(Json(request.after)),
+    )?;
+
+    let (before, after) = share_line_bounds(before, after);
+    let response = diff_rooms(before.detection.rooms, after.detection.rooms, request.iou_threshold);
+    info!(
+        "Compared plans: {} matched, {} added, {} removed",
+        response.matched.len(),
+        response.added.len(),
+        response.removed.len()
+    );
+    Ok(Json(response))
+}
+
+/// Line plans are each normalized to their own extent; renormalize both to their combined
+/// extent so a plan that grew doesn't shift every unchanged room
+fn share_line_bounds(mut before: AutoDetectResponse, mut after: AutoDetectResponse) -> (AutoDetectResponse, AutoDetectResponse) {
+    let bounds = |side: &AutoDetectResponse| {
+        side.detection
+            .metadata
+            .as_ref()
+            .and_then(|m| m.source_bounds)
+            .filter(|_| side.method_used == AutoDetectMethod::Graph)
+    };
+    let (Some(a), Some(b)) = (bounds(&before), bounds(&after)) else {
+        return (before, after);
+    };
+    let union = [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])];
+
+    for (side, own) in [(&mut before, a), (&mut after, b)] {
+        room_detector::denormalize_rooms_from_1000(&mut side.detection.rooms, own);
+        room_detector::normalize_rooms_to_1000(&mut side.detection.rooms, union);
+    }
+    (before, after)
+}
+
+/// Pair before/after rooms one-to-one by bounding-box IoU
+fn diff_rooms(before: Vec<Room>, after: Vec<Room>, iou_threshold: f64) -> CompareResponse {
+    let before_boxes: Vec<[f64; 4]> = before.iter().map(|r| r.bounding_box).collect();
+    let after_boxes: Vec<[f64; 4]> = after.iter().map(|r| r.bounding_box).collect();
+    let matching = match_boxes(&before_boxes, &after_boxes, iou_threshold);
+
+    let mut matched: Vec<MatchedRoom> = matching
+        .matched
+        .iter()
+        .map(|&(b, a)| MatchedRoom {
+            iou: bbox_iou(&before_boxes[b], &after_boxes[a]),
+            area_delta: after[a].area - before[b].area,
+            before: before[b].clone(),
+            after: after[a].clone(),
+        })
+        .collect();
+    matched.sort_by_key(|m| m.before.id);
+
+    CompareResponse {
+        matched,
+        added: matching.only_b.iter().map(|&a| after[a].clone()).collect(),
+        removed: matching.only_a.iter().map(|&b| before[b].clone()).collect(),
+        iou_threshold,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(x1: f64, y1: f64, x2: f64, y2: f64) -> serde_json::Value {
+        serde_json::json!({"start": {"x": x1, "y": y1}, "end": {"x": x2, "y": y2}})
+    }
+
+    /// Two 100x100 rooms side by side; `split` divides the right one horizontally
+    fn plan(split: bool) -> serde_json::Value {
+        let mut lines = vec![
+            wall(0.0, 0.0, 200.0, 0.0),
+            wall(200.0, 0.0, 200.0, 100.0),
+            wall(200.0, 100.0, 0.0, 100.0),
+            wall(0.0, 100.0, 0.0, 0.0),
+            wall(100.0, 0.0, 100.0, 100.0),
+        ];
+        if split {
+            lines.push(wall(100.0, 50.0, 200.0, 50.0));
+        }
+        serde_json::json!({ "lines": lines, "door_threshold": 0.0 })
+    }
+
+    async fn compare(iou_threshold: f64) -> CompareResponse {
+        let request: CompareRequest = serde_json::from_value(serde_json::json!({
+            "before": plan(false),
+            "after": plan(true),
+            "iou_threshold": iou_threshold,
+        }))
+        .unwrap();
+        let Json(response) = compare_handler_inner(Json(request)).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_split_room_is_removed_and_replaced_at_strict_iou() {
+        let response = compare(0.6).await;
+
+        assert_eq!(response.matched.len(), 1);
+        assert_eq!(response.matched[0].after.bounding_box, [0.0, 0.0, 500.0, 1000.0]);
+        assert_eq!(response.matched[0].area_delta, 0.0);
+        assert_eq!(response.removed.len(), 1);
+        assert_eq!(response.removed[0].bounding_box, [500.0, 0.0, 1000.0, 1000.0]);
+        assert_eq!(response.added.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_split_room_half_matches_as_resized_at_default_iou() {
+        let response = compare(DEFAULT_COMPARE_IOU).await;
+
+        // Each half has IoU 0.5 with the original room, so one pairs with it as a shrunk room
+        assert_eq!(response.matched.len(), 2);
+        assert!(response.matched.iter().any(|m| m.area_delta == -5000.0 && (m.iou - 0.5).abs() < 1e-12));
+        assert_eq!(response.added.len(), 1);
+        assert!(response.removed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_room_matches_when_plan_grows() {
+        // After adds a third room to the right, widening the plan from 200 to 300
+        let mut after = plan(false);
+        let lines = after["lines"].as_array_mut().unwrap();
+        lines.extend([
+            wall(200.0, 0.0, 300.0, 0.0),
+            wall(300.0, 0.0, 300.0, 100.0),
+            wall(300.0, 100.0, 200.0, 100.0),
+        ]);
+        let request: CompareRequest = serde_json::from_value(serde_json::json!({
+            "before": plan(false),
+            "after": after,
+        }))
+        .unwrap();
+
+        let Json(response) = compare_handler_inner(Json(request)).await.unwrap();
+
+        assert_eq!(response.matched.len(), 2);
+        assert!(response.matched.iter().all(|m| (m.iou - 1.0).abs() < 1e-9));
+        assert_eq!(response.added.len(), 1);
+        assert_eq!(response.added[0].bounding_box[2], 1000.0);
+    }
+
+    #[tokio::test]
+    async fn test_out_of_range_iou_rejected() {
+        let request: CompareRequest = serde_json::from_value(serde_json::json!({
+            "before": plan(false),
+            "after": plan(false),
+            "iou_threshold": 0.0,
+        }))
+        .unwrap();
+
+        let (status, Json(error)) = compare_handler_inner(Json(request)).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error, "INVALID_IOU_THRESHOLD");
+    }
+}
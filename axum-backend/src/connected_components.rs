@@ -1,7 +1,35 @@
 use image::{GrayImage, Luma};
-use std::collections::VecDeque;
+use rayon::prelude::*;
+use serde::Deserialize;
 use crate::Room;
 
+/// Pixel neighborhood used when flood filling components
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Connectivity {
+    /// Edge neighbors only; diagonal pixel bridges through wall corners don't merge rooms
+    Four,
+    /// Edge and corner neighbors
+    #[default]
+    Eight,
+}
+
+impl Connectivity {
+    /// Neighbor offsets (dx, dy) for this connectivity
+    pub fn offsets(self) -> &'static [(i32, i32)] {
+        const FOUR: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+        const EIGHT: [(i32, i32); 8] = [
+            (-1, -1), (0, -1), (1, -1),
+            (-1, 0), (1, 0),
+            (-1, 1), (0, 1), (1, 1),
+        ];
+        match self {
+            Connectivity::Four => &FOUR,
+            Connectivity::Eight => &EIGHT,
+        }
+    }
+}
+
 fn threshold_image(img: &GrayImage, threshold: u8) -> GrayImage {
     let mut binary = GrayImage::new(img.width(), img.height());
     for (x, y, pixel) in img.enumerate_pixels() {
@@ -11,92 +39,318 @@ fn threshold_image(img: &GrayImage, threshold: u8) -> GrayImage {
     binary
 }
 
+/// How grayscale pixels are split into free space (255) and walls (0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdMode {
+    /// One threshold for the whole image
+    #[default]
+    Global,
+    /// Threshold against the mean of each pixel's neighborhood
+    AdaptiveMean,
+    /// Threshold against a Gaussian-weighted neighborhood mean
+    AdaptiveGaussian,
+}
+
+/// Default adaptive neighborhood side in pixels
+pub const DEFAULT_ADAPTIVE_BLOCK_SIZE: u32 = 31;
+
+/// Default constant subtracted from the adaptive neighborhood mean
+pub const DEFAULT_ADAPTIVE_C: i32 = 10;
+
+/// Thresholding settings; a bare `u8` converts to a global threshold
+#[derive(Debug, Clone, Copy)]
+pub struct ThresholdConfig {
+    pub mode: ThresholdMode,
+    /// Global threshold, used in `Global` mode
+    pub threshold: u8,
+    /// Odd neighborhood side for adaptive modes
+    pub block_size: u32,
+    /// Subtracted from the neighborhood mean for adaptive modes
+    pub c: i32,
+}
+
+impl From<u8> for ThresholdConfig {
+    fn from(threshold: u8) -> Self {
+        ThresholdConfig {
+            mode: ThresholdMode::Global,
+            threshold,
+            block_size: DEFAULT_ADAPTIVE_BLOCK_SIZE,
+            c: DEFAULT_ADAPTIVE_C,
+        }
+    }
+}
+
+impl ThresholdConfig {
+    pub fn apply(&self, img: &GrayImage) -> GrayImage {
+        match self.mode {
+            ThresholdMode::Global => threshold_image(img, self.threshold),
+            ThresholdMode::AdaptiveMean => adaptive_threshold(img, self.block_size, self.c),
+            ThresholdMode::AdaptiveGaussian => adaptive_threshold_gaussian(img, self.block_size, self.c),
+        }
+    }
+}
+
+/// Adaptive mean threshold, like OpenCV's `ADAPTIVE_THRESH_MEAN_C` with `THRESH_BINARY`
+///
+/// A pixel is free space when brighter than the mean of its `block_size` x `block_size`
+/// neighborhood minus `c`. Windows are clipped at the image border. `block_size` must be odd.
+pub fn adaptive_threshold(img: &GrayImage, block_size: u32, c: i32) -> GrayImage {
+    assert!(block_size % 2 == 1, "block_size must be odd, got {}", block_size);
+    let (width, height) = img.dimensions();
+    let (w, h) = (width as usize, height as usize);
+    let radius = (block_size / 2) as usize;
+
+    // Summed-area table with a zero row and column in front
+    let mut integral = vec![0u64; (w + 1) * (h + 1)];
+    for y in 0..h {
+        let mut row_sum = 0u64;
+        for x in 0..w {
+            row_sum += img.get_pixel(x as u32, y as u32)[0] as u64;
+            integral[(y + 1) * (w + 1) + x + 1] = integral[y * (w + 1) + x + 1] + row_sum;
+        }
+    }
+
+    let mut binary = GrayImage::new(width, height);
+    for y in 0..h {
+        let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(h));
+        for x in 0..w {
+            let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(w));
+            let sum = integral[y1 * (w + 1) + x1] + integral[y0 * (w + 1) + x0]
+                - integral[y0 * (w + 1) + x1]
+                - integral[y1 * (w + 1) + x0];
+            let mean = sum as f64 / ((x1 - x0) * (y1 - y0)) as f64;
+
+            let pixel = img.get_pixel(x as u32, y as u32)[0] as f64;
+            let val = if pixel > mean - c as f64 { 255 } else { 0 };
+            binary.put_pixel(x as u32, y as u32, Luma([val]));
+        }
+    }
+    binary
+}
+
+/// Adaptive Gaussian threshold, like OpenCV's `ADAPTIVE_THRESH_GAUSSIAN_C` with `THRESH_BINARY`
+///
+/// Same as [`adaptive_threshold`] but the neighborhood mean is Gaussian weighted, with
+/// OpenCV's default sigma for the block size and replicated borders.
+pub fn adaptive_threshold_gaussian(img: &GrayImage, block_size: u32, c: i32) -> GrayImage {
+    assert!(block_size % 2 == 1, "block_size must be odd, got {}", block_size);
+    let (width, height) = img.dimensions();
+    let (w, h) = (width as usize, height as usize);
+    let radius = (block_size / 2) as i64;
+
+    let sigma = 0.3 * ((block_size as f64 - 1.0) * 0.5 - 1.0) + 0.8;
+    let mut kernel: Vec<f64> = (-radius..=radius)
+        .map(|i| (-((i * i) as f64) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f64 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|k| *k /= total);
+
+    let clamp = |i: i64, len: usize| i.clamp(0, len as i64 - 1) as usize;
+
+    // Separable blur: rows, then columns
+    let mut horizontal = vec![0f64; w * h];
+    for y in 0..h {
+        for x in 0..w {
+            horizontal[y * w + x] = kernel
+                .iter()
+                .enumerate()
+                .map(|(k, weight)| {
+                    let sx = clamp(x as i64 + k as i64 - radius, w);
+                    weight * img.get_pixel(sx as u32, y as u32)[0] as f64
+                })
+                .sum();
+        }
+    }
+
+    let mut binary = GrayImage::new(width, height);
+    for y in 0..h {
+        for x in 0..w {
+            let mean: f64 = kernel
+                .iter()
+                .enumerate()
+                .map(|(k, weight)| weight * horizontal[clamp(y as i64 + k as i64 - radius, h) * w + x])
+                .sum();
+
+            let pixel = img.get_pixel(x as u32, y as u32)[0] as f64;
+            let val = if pixel > mean - c as f64 { 255 } else { 0 };
+            binary.put_pixel(x as u32, y as u32, Luma([val]));
+        }
+    }
+    binary
+}
+
 fn find_connected_components(
     img: &GrayImage,
     min_area: usize,
     max_area: f32,
+    connectivity: Connectivity,
+    on_progress: &mut dyn FnMut(u8),
 ) -> Vec<(usize, (u32, u32, u32, u32))> {
-    let (width, height) = img.dimensions();
-    let mut visited = vec![false; width as usize * height as usize];
-    let mut components = Vec::new();
+    label_components(img, connectivity, on_progress)
+        .into_iter()
+        .filter(|&(area, bbox)| is_room_shaped(area, bbox, min_area, max_area))
+        .collect()
+}
 
-    for y in 0..height {
-        for x in 0..width {
-            let idx = (y as usize * width as usize) + x as usize;
-            if img.get_pixel(x, y)[0] == 255 && !visited[idx] {
-                let (area, bbox) = flood_fill(img, x, y, &mut visited, width, height);
-                let (min_x, min_y, max_x, max_y) = bbox;
-
-                // Calculate dimensions
-                let w = max_x - min_x;
-                let h = max_y - min_y;
-
-                // Calculate aspect ratio (width/height or height/width, whichever is larger)
-                let aspect_ratio = if w > h {
-                    w as f64 / h.max(1) as f64
-                } else {
-                    h as f64 / w.max(1) as f64
-                };
-
-                // Filter by area and aspect ratio
-                // Reject thin elongated shapes (aspect ratio > 15) which are likely walls
-                if area >= min_area && (area as f32) < max_area && aspect_ratio < 15.0 {
-                    components.push((area, bbox));
-                }
+/// Area within `[min_area, max_area)` and not a thin elongated shape (likely a wall)
+fn is_room_shaped(area: usize, bbox: (u32, u32, u32, u32), min_area: usize, max_area: f32) -> bool {
+    let (min_x, min_y, max_x, max_y) = bbox;
+
+    // Calculate dimensions
+    let w = max_x - min_x;
+    let h = max_y - min_y;
+
+    // Calculate aspect ratio (width/height or height/width, whichever is larger)
+    let aspect_ratio = if w > h {
+        w as f64 / h.max(1) as f64
+    } else {
+        h as f64 / w.max(1) as f64
+    };
+
+    // Reject thin elongated shapes (aspect ratio > 15) which are likely walls
+    area >= min_area && (area as f32) < max_area && aspect_ratio < 15.0
+}
+
+/// Horizontal run of white pixels on one row, `start..=end`
+type Run = (u32, u32);
+
+fn row_runs(img: &GrayImage, y: u32) -> Vec<Run> {
+    let width = img.width() as usize;
+    let row = &img.as_raw()[y as usize * width..(y as usize + 1) * width];
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (x, &value) in row.iter().enumerate() {
+        match (value == 255, start) {
+            (true, None) => start = Some(x as u32),
+            (false, Some(s)) => {
+                runs.push((s, x as u32 - 1));
+                start = None;
             }
+            _ => {}
         }
     }
-    components
+    if let Some(s) = start {
+        runs.push((s, width as u32 - 1));
+    }
+    runs
+}
+
+/// Index pairs of runs on consecutive rows that touch under `connectivity`
+fn touching_runs(above: &[Run], below: &[Run], connectivity: Connectivity) -> Vec<(usize, usize)> {
+    // Eight-connected runs also touch when they are only diagonally adjacent
+    let reach = match connectivity {
+        Connectivity::Four => 0,
+        Connectivity::Eight => 1,
+    };
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < above.len() && j < below.len() {
+        let (a, b) = (above[i], below[j]);
+        if a.0 <= b.1 + reach && b.0 <= a.1 + reach {
+            pairs.push((i, j));
+        }
+        if a.1 < b.1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Union-find over run indices; each set's root is its first run in raster order
+struct RunSets {
+    parent: Vec<usize>,
 }
 
-fn flood_fill(
+impl RunSets {
+    fn new(len: usize) -> Self {
+        Self { parent: (0..len).collect() }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+/// Two-pass connected component labeling of white pixels: area and bounding box per component
+///
+/// Rows are split into runs and linked to the row above in parallel, then the links
+/// are merged in a union-find. Components come out in raster order of their first
+/// pixel, the same order a scanline flood fill discovers them. Progress (0-100) is
+/// reported while merging.
+fn label_components(
     img: &GrayImage,
-    start_x: u32,
-    start_y: u32,
-    visited: &mut Vec<bool>,
-    width: u32,
-    height: u32,
-) -> (usize, (u32, u32, u32, u32)) {
-    let mut queue = VecDeque::new();
-    let mut area = 0;
-    let mut min_x = start_x;
-    let mut min_y = start_y;
-    let mut max_x = start_x;
-    let mut max_y = start_y;
-
-    queue.push_back((start_x, start_y));
-    let start_idx = (start_y as usize * width as usize) + start_x as usize;
-    visited[start_idx] = true;
-
-    while let Some((x, y)) = queue.pop_front() {
-        area += 1;
-        min_x = min_x.min(x);
-        min_y = min_y.min(y);
-        max_x = max_x.max(x);
-        max_y = max_y.max(y);
-
-        // 8-connectivity neighbors
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                let nx = x as i32 + dx;
-                let ny = y as i32 + dy;
-                if nx >= 0 && nx < width as i32 && ny >= 0 && ny < height as i32 {
-                    let px = nx as u32;
-                    let py = ny as u32;
-                    let nidx = (py as usize * width as usize) + px as usize;
-                    if !visited[nidx] && img.get_pixel(px, py)[0] == 255 {
-                        visited[nidx] = true;
-                        queue.push_back((px, py));
-                    }
-                }
+    connectivity: Connectivity,
+    on_progress: &mut dyn FnMut(u8),
+) -> Vec<(usize, (u32, u32, u32, u32))> {
+    let height = img.height();
+    let runs: Vec<Vec<Run>> = (0..height).into_par_iter().map(|y| row_runs(img, y)).collect();
+
+    let mut offsets = Vec::with_capacity(runs.len());
+    let mut run_count = 0;
+    for row in &runs {
+        offsets.push(run_count);
+        run_count += row.len();
+    }
+
+    let links: Vec<Vec<(usize, usize)>> = (1..runs.len())
+        .into_par_iter()
+        .map(|y| {
+            touching_runs(&runs[y - 1], &runs[y], connectivity)
+                .into_iter()
+                .map(|(above, below)| (offsets[y - 1] + above, offsets[y] + below))
+                .collect()
+        })
+        .collect();
+
+    let mut sets = RunSets::new(run_count);
+    let mut last_reported = 0;
+    for y in 0..height {
+        // Coarse progress from scanline position, in 10% steps
+        let percent = ((y as u64 * 100) / height as u64) as u8 / 10 * 10;
+        if percent > last_reported {
+            last_reported = percent;
+            on_progress(percent);
+        }
+
+        if y > 0 {
+            for &(above, below) in &links[y as usize - 1] {
+                sets.union(above, below);
             }
         }
     }
 
-    (area, (min_x, min_y, max_x, max_y))
+    let mut component_of = vec![usize::MAX; run_count];
+    let mut components: Vec<(usize, (u32, u32, u32, u32))> = Vec::new();
+    for (y, row) in runs.iter().enumerate() {
+        let y = y as u32;
+        for (k, &(start, end)) in row.iter().enumerate() {
+            let root = sets.find(offsets[y as usize] + k);
+            if component_of[root] == usize::MAX {
+                component_of[root] = components.len();
+                components.push((0, (start, y, end, y)));
+            }
+            let (area, bbox) = &mut components[component_of[root]];
+            *area += (end - start + 1) as usize;
+            bbox.0 = bbox.0.min(start);
+            bbox.2 = bbox.2.max(end);
+            bbox.3 = y;
+        }
+    }
+    components
 }
 
 /// Detect rooms using connected components analysis on a binary image
@@ -106,19 +360,38 @@ use image::imageops::invert;
 
 pub fn detect_rooms_connected_components(
     img: &GrayImage,
-    threshold: u8,
+    threshold: impl Into<ThresholdConfig>,
+    min_area: usize,
+    max_area_ratio: f32,
+    connectivity: Connectivity,
+) -> Vec<Room> {
+    detect_rooms_connected_components_with_progress(
+        img,
+        threshold,
+        min_area,
+        max_area_ratio,
+        connectivity,
+        |_| {},
+    )
+}
+
+/// Same as [`detect_rooms_connected_components`], reporting flood fill progress (0-100)
+pub fn detect_rooms_connected_components_with_progress(
+    img: &GrayImage,
+    threshold: impl Into<ThresholdConfig>,
     min_area: usize,
     max_area_ratio: f32,
+    connectivity: Connectivity,
+    mut on_progress: impl FnMut(u8),
 ) -> Vec<Room> {
     // Simple threshold - no morphological operations (like Algorithm 1)
-    let binary = threshold_image(img, threshold);
+    let binary = threshold.into().apply(img);
 
     // Basic absolute thresholds
     let max_area = (img.width() as usize * img.height() as usize) * 3 / 10; // 30% of image
-    let min_area = 500;
 
     // Find connected components
-    let components = find_connected_components(&binary, min_area, max_area as f32);
+    let components = find_connected_components(&binary, min_area, max_area as f32, connectivity, &mut on_progress);
 
     // Convert components to rooms
     let mut rooms = Vec::new();
@@ -174,6 +447,7 @@ pub fn detect_rooms_connected_components(
             area: *area as f64,
             name_hint: generate_room_name(*area as f64),
             points,
+            ..Room::default()
         });
 
         room_id += 1;
@@ -185,111 +459,275 @@ pub fn detect_rooms_connected_components(
     rooms
 }
 
-pub fn threshold_image_enhanced(img: &GrayImage, threshold: u8) -> GrayImage {
-    let mut binary = GrayImage::new(img.width(), img.height());
-    for (x, y, pixel) in img.enumerate_pixels() {
-        let val = if pixel[0] > threshold { 255 } else { 0 };
-        binary.put_pixel(x, y, Luma([val]));
+/// Default closing kernel size (3x3)
+pub const DEFAULT_MORPH_KERNEL_SIZE: u32 = 3;
+
+/// Default number of closing passes
+pub const DEFAULT_MORPH_ITERATIONS: u32 = 1;
+
+/// Threshold and then morphologically close the walls (dark pixels)
+///
+/// `morph_kernel_size` is the side of the square kernel (odd: 3, 5 or 7) and
+/// `morph_iterations` is how many times the close is applied. A close bridges wall
+/// gaps up to `morph_kernel_size - 1` pixels wide, so larger kernels and more passes
+/// merge more aggressively and risk sealing off or swallowing thin rooms.
+pub fn threshold_image_enhanced(
+    img: &GrayImage,
+    threshold: impl Into<ThresholdConfig>,
+    morph_kernel_size: u32,
+    morph_iterations: u32,
+) -> GrayImage {
+    let binary = threshold.into().apply(img);
+    close_walls(&binary, morph_kernel_size, morph_iterations)
+}
+
+/// Morphological close of the dark (wall) pixels with a square kernel
+fn close_walls(binary: &GrayImage, kernel_size: u32, iterations: u32) -> GrayImage {
+    // A (2r+1)x(2r+1) square kernel is an L-infinity ball of radius r
+    let radius = (kernel_size / 2).min(u8::MAX as u32) as u8;
+    if radius == 0 || iterations == 0 {
+        return binary.clone();
     }
-    binary
+
+    // Work on inverted image so walls are foreground
+    let mut walls = binary.clone();
+    invert(&mut walls);
+    for _ in 0..iterations {
+        let dilated = dilate(&walls, Norm::LInf, radius);
+        walls = erode(&dilated, Norm::LInf, radius);
+    }
+    invert(&mut walls);
+    walls
 }
 
+/// Room-shaped free-space components at least `min_area` pixels and under `max_area_ratio`
+/// of the image
 pub fn find_connected_components_enhanced(
     img: &GrayImage,
     min_area: usize,
-    max_area: f32,
+    max_area_ratio: f32,
+    connectivity: Connectivity,
 ) -> Vec<(usize, (u32, u32, u32, u32))> {
-    let (width, height) = img.dimensions();
-    let mut visited = vec![false; (width as usize * height as usize)];
-    let mut components = Vec::new();
+    let max_area = img.width() as f32 * img.height() as f32 * max_area_ratio;
+    find_connected_components(img, min_area, max_area, connectivity, &mut |_| {})
+}
 
-    for y in 0..height {
-        for x in 0..width {
-            let idx = (y as usize * width as usize) + x as usize;
-            if img.get_pixel(x, y)[0] == 255 && !visited[idx] {
-                let (area, bbox) = flood_fill_enhanced(img, x, y, &mut visited, width, height);
-                let (min_x, min_y, max_x, max_y) = bbox;
-
-                // Calculate dimensions
-                let w = max_x - min_x;
-                let h = max_y - min_y;
-
-                // Calculate aspect ratio (width/height or height/width, whichever is larger)
-                let aspect_ratio = if w > h {
-                    w as f64 / h.max(1) as f64
-                } else {
-                    h as f64 / w.max(1) as f64
-                };
-
-                // Filter by area and aspect ratio
-                // Reject thin elongated shapes (aspect ratio > 10) which are likely walls
-                if area >= min_area && (area as f32) < max_area && aspect_ratio < 15.0 {
-                    components.push((area, bbox));
-                }
-            }
-        }
+pub fn generate_room_name(area: f64) -> String {
+    if area < 5000.0 {
+        "Small Room".to_string()
+    } else if area < 20000.0 {
+        "Medium Room".to_string()
+    } else {
+        "Large Room".to_string()
     }
-    components
 }
 
-fn flood_fill_enhanced(
-    img: &GrayImage,
-    start_x: u32,
-    start_y: u32,
-    visited: &mut Vec<bool>,
-    width: u32,
-    height: u32,
-) -> (usize, (u32, u32, u32, u32)) {
-    let mut queue = VecDeque::new();
-    let mut area = 0;
-    let mut min_x = start_x;
-    let mut min_y = start_y;
-    let mut max_x = start_x;
-    let mut max_y = start_y;
-
-    queue.push_back((start_x, start_y));
-    let start_idx = (start_y as usize * width as usize) + start_x as usize;
-    visited[start_idx] = true;
-
-    while let Some((x, y)) = queue.pop_front() {
-        area += 1;
-        min_x = min_x.min(x);
-        min_y = min_y.min(y);
-        max_x = max_x.max(x);
-        max_y = max_y.max(y);
-
-        // 8-connectivity neighbors
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                if dx == 0 && dy == 0 {
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// The original scanline BFS flood fill, kept as a reference for the union-find labeling
+    fn flood_fill_components(img: &GrayImage, connectivity: Connectivity) -> Vec<(usize, (u32, u32, u32, u32))> {
+        let (width, height) = img.dimensions();
+        let mut visited = vec![false; width as usize * height as usize];
+        let mut components = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                if img.get_pixel(x, y)[0] != 255 || visited[(y * width + x) as usize] {
                     continue;
                 }
-                let nx = x as i32 + dx;
-                let ny = y as i32 + dy;
-                if nx >= 0 && nx < width as i32 && ny >= 0 && ny < height as i32 {
-                    let px = nx as u32;
-                    let py = ny as u32;
-                    let nidx = (py as usize * width as usize) + px as usize;
-                    if !visited[nidx] && img.get_pixel(px, py)[0] == 255 {
-                        visited[nidx] = true;
-                        queue.push_back((px, py));
+                let mut queue = VecDeque::from([(x, y)]);
+                visited[(y * width + x) as usize] = true;
+                let (mut area, mut bbox) = (0, (x, y, x, y));
+
+                while let Some((x, y)) = queue.pop_front() {
+                    area += 1;
+                    bbox = (bbox.0.min(x), bbox.1.min(y), bbox.2.max(x), bbox.3.max(y));
+                    for &(dx, dy) in connectivity.offsets() {
+                        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                        if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                            continue;
+                        }
+                        let (nx, ny) = (nx as u32, ny as u32);
+                        let nidx = (ny * width + nx) as usize;
+                        if !visited[nidx] && img.get_pixel(nx, ny)[0] == 255 {
+                            visited[nidx] = true;
+                            queue.push_back((nx, ny));
+                        }
                     }
                 }
+                components.push((area, bbox));
             }
         }
+        components
     }
 
-    (area, (min_x, min_y, max_x, max_y))
-}
+    /// Rooms of varied shapes: an L, a ring around an island, a U that wraps back, and diagonal neighbors
+    fn multi_room_image() -> GrayImage {
+        let mut img = GrayImage::new(160, 120);
+        let mut fill = |x0: u32, y0: u32, x1: u32, y1: u32, value: u8| {
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    img.put_pixel(x, y, Luma([value]));
+                }
+            }
+        };
+        fill(5, 5, 40, 20, 255);
+        fill(5, 20, 20, 50, 255);
+        fill(50, 5, 100, 50, 255);
+        fill(60, 15, 90, 40, 0);
+        fill(70, 22, 80, 32, 255);
+        fill(110, 5, 150, 60, 255);
+        fill(120, 5, 140, 50, 0);
+        fill(5, 60, 30, 80, 255);
+        fill(30, 80, 60, 110, 255);
+        fill(100, 70, 101, 115, 255);
+        img
+    }
 
-pub fn generate_room_name(area: f64) -> String {
-    if area < 5000.0 {
-        "Small Room".to_string()
-    } else if area < 20000.0 {
-        "Medium Room".to_string()
-    } else {
-        "Large Room".to_string()
+    #[test]
+    fn test_union_find_labeling_matches_flood_fill() {
+        let fixture = image::load_from_memory(include_bytes!("../../test-data/test_blueprint_001.png"))
+            .unwrap()
+            .to_luma8();
+        let images = [
+            multi_room_image(),
+            diagonal_rooms_image(),
+            threshold_image(&fixture, 200),
+            GrayImage::from_pixel(7, 3, Luma([255])),
+            GrayImage::new(5, 5),
+        ];
+
+        for img in &images {
+            for connectivity in [Connectivity::Four, Connectivity::Eight] {
+                assert_eq!(
+                    label_components(img, connectivity, &mut |_| {}),
+                    flood_fill_components(img, connectivity)
+                );
+            }
+        }
+        assert_eq!(label_components(&images[0], Connectivity::Four, &mut |_| {}).len(), 7);
+    }
+
+    /// Two white squares on black that touch only at one diagonal corner
+    fn diagonal_rooms_image() -> GrayImage {
+        let mut img = GrayImage::new(200, 200);
+        for y in 0..40 {
+            for x in 0..40 {
+                img.put_pixel(20 + x, 20 + y, Luma([255]));
+                img.put_pixel(60 + x, 60 + y, Luma([255]));
+            }
+        }
+        img
+    }
+
+    #[test]
+    fn test_connectivity_diagonal_corner() {
+        let img = diagonal_rooms_image();
+
+        let four = detect_rooms_connected_components(&img, 200, 500, 0.3, Connectivity::Four);
+        let eight = detect_rooms_connected_components(&img, 200, 500, 0.3, Connectivity::Eight);
+
+        assert_eq!(four.len(), 2);
+        assert_eq!(eight.len(), 1);
+        assert_eq!(eight[0].area, 3200.0);
+    }
+
+    #[test]
+    fn test_connectivity_defaults_to_eight() {
+        assert_eq!(Connectivity::default(), Connectivity::Eight);
+        let parsed: Connectivity = serde_json::from_str("\"four\"").unwrap();
+        assert_eq!(parsed, Connectivity::Four);
+    }
+
+    /// Free space split by a vertical 4px wall with a 4px gap in the middle
+    fn broken_wall_image() -> GrayImage {
+        let mut img = GrayImage::from_pixel(60, 40, Luma([255]));
+        for y in (0..18).chain(22..40) {
+            for x in 28..32 {
+                img.put_pixel(x, y, Luma([0]));
+            }
+        }
+        img
     }
-}
 
+    #[test]
+    fn test_morph_kernel_size_bridges_gap() {
+        let img = broken_wall_image();
 
+        let small = threshold_image_enhanced(&img, 128, 3, 1);
+        let large = threshold_image_enhanced(&img, 128, 5, 1);
+
+        // 3x3 leaves the gap open, 5x5 seals it
+        assert_eq!(small.get_pixel(29, 20)[0], 255);
+        assert_eq!(large.get_pixel(29, 20)[0], 0);
+
+        let small_components = find_connected_components_enhanced(&small, 100, 1.0, Connectivity::Four);
+        let large_components = find_connected_components_enhanced(&large, 100, 1.0, Connectivity::Four);
+        assert_eq!(small_components.len(), 1);
+        assert_eq!(large_components.len(), 2);
+    }
+
+    #[test]
+    fn test_enhanced_max_area_is_a_fraction_of_the_image() {
+        let sealed = threshold_image_enhanced(&broken_wall_image(), 128, 5, 1);
+
+        // Each 28x40 half is under half of the 60x40 image, but over 30% of it
+        assert_eq!(find_connected_components_enhanced(&sealed, 100, 0.5, Connectivity::Four).len(), 2);
+        assert!(find_connected_components_enhanced(&sealed, 100, 0.3, Connectivity::Four).is_empty());
+    }
+
+    #[test]
+    fn test_flood_fill_progress_reported() {
+        let img = diagonal_rooms_image();
+        let mut reported = Vec::new();
+
+        detect_rooms_connected_components_with_progress(&img, 200, 500, 0.3, Connectivity::Eight, |p| {
+            reported.push(p)
+        });
+
+        assert_eq!(reported, vec![10, 20, 30, 40, 50, 60, 70, 80, 90]);
+    }
+
+    /// Background brightening left to right (40..254) with two 2px walls 40 levels darker
+    fn gradient_image() -> GrayImage {
+        let mut img = GrayImage::new(120, 60);
+        for (x, _, pixel) in img.enumerate_pixels_mut() {
+            let background = 40.0 + x as f64 * 1.8;
+            let is_wall = (30..32).contains(&x) || (90..92).contains(&x);
+            *pixel = Luma([(if is_wall { background - 40.0 } else { background }) as u8]);
+        }
+        img
+    }
+
+    #[test]
+    fn test_adaptive_threshold_handles_gradient() {
+        let img = gradient_image();
+
+        let global = threshold_image(&img, 128);
+        // The dark side's background falls below the global threshold and merges with the wall
+        assert_eq!(global.get_pixel(10, 30)[0], 0);
+        assert_eq!(global.get_pixel(30, 30)[0], 0);
+
+        for binary in [adaptive_threshold(&img, 15, 10), adaptive_threshold_gaussian(&img, 15, 10)] {
+            assert_eq!(binary.get_pixel(10, 30)[0], 255);
+            assert_eq!(binary.get_pixel(30, 30)[0], 0);
+            assert_eq!(binary.get_pixel(60, 30)[0], 255);
+            assert_eq!(binary.get_pixel(91, 30)[0], 0);
+            assert_eq!(binary.get_pixel(110, 30)[0], 255);
+        }
+
+        let components = find_connected_components_enhanced(&adaptive_threshold(&img, 15, 10), 100, 1.0, Connectivity::Four);
+        assert_eq!(components.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "block_size must be odd")]
+    fn test_adaptive_threshold_rejects_even_block() {
+        adaptive_threshold(&gradient_image(), 16, 10);
+    }
+}
@@ -0,0 +1,120 @@
+use aws_credential_types::provider::ProvideCredentials;
+use axum::Json;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::{image_vectorizer, PYTHON_VENV_PATH};
+
+/// Longest any single probe may take; the AWS chain can otherwise wait on IMDS for a while
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Result of probing one subsystem
+#[derive(Debug, Serialize)]
+pub struct SubsystemHealth {
+    pub available: bool,
+    pub latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeepHealthResponse {
+    /// "healthy" when every subsystem is available, otherwise "degraded"
+    pub status: String,
+    pub version: String,
+    pub subsystems: BTreeMap<&'static str, SubsystemHealth>,
+}
+
+/// `GET /health/deep`: which detection paths can actually run on this instance
+pub async fn deep_health_handler() -> Json<DeepHealthResponse> {
+    let (openai, vtracer, python_venv, aws_credentials) =
+        tokio::join!(probe(check_openai()), probe(check_vtracer()), probe(check_python_venv()), probe(check_aws()));
+
+    let subsystems = BTreeMap::from([
+        ("openai", openai),
+        ("vtracer", vtracer),
+        ("python_venv", python_venv),
+        ("aws_credentials", aws_credentials),
+    ]);
+    let status = if subsystems.values().all(|s| s.available) { "healthy" } else { "degraded" };
+
+    Json(DeepHealthResponse {
+        status: status.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        subsystems,
+    })
+}
+
+/// Time a check, treating errors and timeouts as unavailable
+async fn probe(check: impl Future<Output = Result<(), String>>) -> SubsystemHealth {
+    let start = Instant::now();
+    let outcome = match tokio::time::timeout(PROBE_TIMEOUT, check).await {
+        Ok(outcome) => outcome,
+        Err(_) => Err(format!("Timed out after {}s", PROBE_TIMEOUT.as_secs())),
+    };
+
+    SubsystemHealth {
+        available: outcome.is_ok(),
+        latency_ms: start.elapsed().as_millis(),
+        detail: outcome.err(),
+    }
+}
+
+async fn check_openai() -> Result<(), String> {
+    match std::env::var("OPENAI_API_KEY") {
+        Ok(key) if !key.trim().is_empty() => Ok(()),
+        _ => Err("OPENAI_API_KEY is not set".to_string()),
+    }
+}
+
+/// Vectorize a 1px image without the cache, so the probe really exercises VTracer
+async fn check_vtracer() -> Result<(), String> {
+    tokio::task::spawn_blocking(|| {
+        let mut png = Vec::new();
+        image::GrayImage::from_pixel(1, 1, image::Luma([0]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .map_err(|e| e.to_string())?;
+        image_vectorizer::vectorize_uncached(&png, image_vectorizer::VTracerSettings::default().to_config())
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+async fn check_python_venv() -> Result<(), String> {
+    if std::path::Path::new(PYTHON_VENV_PATH).exists() {
+        Ok(())
+    } else {
+        Err(format!("{} not found", PYTHON_VENV_PATH))
+    }
+}
+
+async fn check_aws() -> Result<(), String> {
+    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+    let provider = config
+        .credentials_provider()
+        .ok_or_else(|| "No AWS credentials provider configured".to_string())?;
+    provider.provide_credentials().await.map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_deep_health_reports_every_subsystem() {
+        let Json(response) = deep_health_handler().await;
+        let json = serde_json::to_value(&response).unwrap();
+
+        for key in ["openai", "vtracer", "python_venv", "aws_credentials"] {
+            let subsystem = &json["subsystems"][key];
+            assert!(subsystem["available"].is_boolean(), "missing {}", key);
+            assert!(subsystem["latency_ms"].is_u64(), "missing latency for {}", key);
+        }
+        assert_eq!(json["subsystems"].as_object().unwrap().len(), 4);
+        assert!(json["status"] == "healthy" || json["status"] == "degraded");
+    }
+}
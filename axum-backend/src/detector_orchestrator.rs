@@ -1,5 +1,10 @@
+use crate::image_vectorizer::{VTracerSettings, VectorizerBackend};
+use crate::room_detector::spatial_order;
 use crate::{Line, Room};
+use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::time::Instant;
 use tracing::{info, warn};
 use unified_detector::yolo::YoloDetector;
@@ -8,6 +13,10 @@ use unified_detector::yolo::YoloDetector;
 pub struct DetectorOrchestrator {
     /// Configuration for detection
     config: DetectorConfig,
+    /// Language-model SVG parser for the `ai` and `combined` SVG parsers
+    svg_ai_parser: Arc<dyn SvgAiParser>,
+    /// Vision model asked for walls on the hybrid path; built from the environment when unset
+    wall_vision: Option<Arc<vision_classifier::VisionClassifier>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,15 +34,38 @@ pub struct DetectorConfig {
     /// Confidence threshold for hybrid vision merge strategy (0.0-1.0)
     #[serde(default = "default_confidence_threshold")]
     pub confidence_threshold: f64,
+    /// Minimum confidence for keeping GPT-5 walls not confirmed by VTracer (0.0-1.0)
+    #[serde(default = "default_min_wall_confidence")]
+    pub min_wall_confidence: f64,
     /// Vision model to use (gpt-4o-mini, gpt-4o, gpt-5, etc.)
     #[serde(default = "default_vision_model")]
     pub vision_model: String,
+    /// Parser(s) used to turn SVG content into walls
+    #[serde(default)]
+    pub svg_parser: SvgParser,
+    /// Engine used to trace raster images into walls
+    #[serde(default)]
+    pub vectorizer: VectorizerBackend,
+    /// Tracing settings for the VTracer backend
+    #[serde(default)]
+    pub vtracer: VTracerSettings,
+    /// SVG elements with a `stroke-width` at or below this are dropped as annotations (0 keeps
+    /// all). Only the algorithmic SVG parser applies it; the AI parser reads the whole SVG.
+    #[serde(default)]
+    pub min_stroke_width: f64,
+    /// Collapse traced wall faces up to this far apart (canvas pixels) into centerlines; `None` keeps both faces
+    #[serde(default)]
+    pub wall_thickness_tolerance: Option<f64>,
 }
 
 fn default_confidence_threshold() -> f64 {
     0.75
 }
 
+fn default_min_wall_confidence() -> f64 {
+    crate::wall_merger::DEFAULT_MIN_WALL_CONFIDENCE
+}
+
 fn default_vision_model() -> String {
     // Use gpt-4o-mini by default for speed and cost efficiency
     // Can be overridden with VISION_MODEL env var
@@ -49,11 +81,58 @@ impl Default for DetectorConfig {
             enable_yolo: false,   // Disabled until model is trained
             strategy: CombinationStrategy::GraphOnly,
             confidence_threshold: 0.75,
+            min_wall_confidence: default_min_wall_confidence(),
             vision_model: default_vision_model(),
+            svg_parser: SvgParser::default(),
+            vectorizer: VectorizerBackend::default(),
+            vtracer: VTracerSettings::default(),
+            min_stroke_width: 0.0,
+            wall_thickness_tolerance: None,
         }
     }
 }
 
+/// Parser(s) that turn SVG markup into wall lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SvgParser {
+    /// Read `<path>`, `<line>` and `<rect>` geometry directly
+    #[default]
+    Algorithmic,
+    /// Ask GPT-5-nano to interpret the SVG text
+    Ai,
+    /// Run both and reconcile their rooms by bounding-box overlap
+    Combined,
+}
+
+/// Minimum bounding-box IoU for the two SVG parsers to agree on a room
+const SVG_PARSER_MATCH_IOU: f64 = 0.5;
+
+/// Extracts wall lines from SVG text with a language model
+pub trait SvgAiParser: Send + Sync {
+    fn parse<'a>(&'a self, svg: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<Line>>>;
+}
+
+/// GPT-5-nano through the OpenAI API
+struct OpenAiSvgParser;
+
+impl SvgAiParser for OpenAiSvgParser {
+    fn parse<'a>(&'a self, svg: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<Line>>> {
+        Box::pin(async move {
+            let lines = crate::image_vectorizer::ai_parse_svg_to_lines(svg).await?;
+            Ok(lines.into_iter().map(from_vectorizer_line).collect())
+        })
+    }
+}
+
+fn from_vectorizer_line(vl: crate::image_vectorizer::Line) -> Line {
+    Line {
+        start: crate::Point { x: vl.start.x, y: vl.start.y },
+        end: crate::Point { x: vl.end.x, y: vl.end.y },
+        is_load_bearing: vl.is_load_bearing,
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum CombinationStrategy {
     /// Use only graph-based geometric detection
@@ -81,6 +160,9 @@ pub struct DetectionResult {
     pub rooms: Vec<EnhancedRoom>,
     pub method_used: String,
     pub execution_time_ms: u128,
+    /// Rooms per top-level SVG group, when detection ran layer by layer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layers: Option<BTreeMap<String, Vec<EnhancedRoom>>>,
     pub metadata: DetectionMetadata,
 }
 
@@ -121,11 +203,89 @@ pub struct DetectionMetadata {
     pub merge_strategy: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub merged_walls: Option<Vec<crate::wall_merger::Line>>,
+    /// Whether VTracer output came from the vectorization cache
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vtracer_cache_hit: Option<bool>,
+    /// SVG parsers that ran ("algorithmic", "ai")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub svg_parsers: Option<Vec<String>>,
+    /// Share of rooms both SVG parsers found, when both ran
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub svg_parser_agreement: Option<f64>,
+    /// Wall segments the SVG parser or VTracer produced, before room detection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parsed_walls: Option<Vec<Line>>,
+    /// Input extent `[min_x, min_y, max_x, max_y]` that coordinates were normalized from into 0-1000
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_bounds: Option<[f64; 4]>,
+    /// Wall thickness in source image pixels, when traced wall faces were collapsed into centerlines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wall_thickness: Option<f64>,
+}
+
+impl DetectionMetadata {
+    /// Record a VTracer/vision wall merge and the vision confidence it used
+    fn record_merge(&mut self, merge: Option<(crate::wall_merger::MergeResult, f64)>) {
+        let Some((merged, confidence)) = merge else {
+            return;
+        };
+        self.gpt5_walls_count = Some(merged.metadata.gpt5_count);
+        self.merged_walls_count = Some(merged.metadata.merged_count);
+        self.consensus_walls_count = Some(merged.metadata.consensus_count);
+        self.gpt5_confidence = Some(confidence);
+        self.merge_strategy = Some(merged.metadata.strategy_used);
+        self.merged_walls = Some(merged.walls);
+    }
+}
+
+impl DetectionResult {
+    /// Scale rooms, layers and parsed walls from input units into the shared 0-1000 space
+    pub fn normalize_to_1000(&mut self, bounds: [f64; 4]) {
+        let layer_rooms = self.layers.iter_mut().flat_map(|layers| layers.values_mut().flatten());
+        for enhanced in self.rooms.iter_mut().chain(layer_rooms) {
+            crate::room_detector::normalize_rooms_to_1000(std::slice::from_mut(&mut enhanced.room), bounds);
+        }
+        if let Some(walls) = self.metadata.parsed_walls.as_mut() {
+            crate::room_detector::normalize_lines_to_1000(walls, bounds);
+        }
+        if let Some(walls) = self.metadata.merged_walls.as_mut() {
+            let map = crate::room_detector::point_to_1000(bounds);
+            for point in walls.iter_mut().flat_map(|wall| [&mut wall.start, &mut wall.end]) {
+                (point.x, point.y) = map(point.x, point.y);
+            }
+        }
+        self.metadata.source_bounds = Some(bounds);
+    }
+
+    /// Map a result traced on the letterboxed vectorizing canvas onto the image's own axes,
+    /// each stretched to 0-1000 the way the connected-components detectors report rooms
+    fn normalize_from_canvas(&mut self, placement: CanvasPlacement) {
+        self.normalize_to_1000(placement.content_bounds);
+        self.metadata.source_bounds = Some(placement.image_bounds);
+    }
 }
 
 impl DetectorOrchestrator {
     pub fn new(config: DetectorConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            svg_ai_parser: Arc::new(OpenAiSvgParser),
+            wall_vision: None,
+        }
+    }
+
+    /// Replace the vision model the hybrid strategy reads walls from
+    #[cfg(test)]
+    pub fn with_wall_vision(mut self, classifier: vision_classifier::VisionClassifier) -> Self {
+        self.wall_vision = Some(Arc::new(classifier));
+        self
+    }
+
+    /// Replace the language-model SVG parser
+    #[cfg(test)]
+    pub fn with_svg_ai_parser(mut self, parser: impl SvgAiParser + 'static) -> Self {
+        self.svg_ai_parser = Arc::new(parser);
+        self
     }
 
     pub fn with_defaults() -> Self {
@@ -178,6 +338,7 @@ impl DetectorOrchestrator {
             }
         }
         .map(|mut result| {
+            number_rooms_spatially(&mut result.rooms);
             result.execution_time_ms = start.elapsed().as_millis();
             result.metadata.total_execution_time_ms = start.elapsed().as_millis();
             result.metadata.method_timings = method_timings;
@@ -185,6 +346,55 @@ impl DetectorOrchestrator {
         })
     }
 
+    /// Detect rooms in each `(name, svg)` layer separately, keyed by layer name in `layers`
+    ///
+    /// `rooms` lists every layer's rooms in layer order, numbered across layers so ids stay
+    /// unique within the response.
+    pub async fn detect_rooms_by_layer(
+        &self,
+        layers: &[(String, String)],
+    ) -> anyhow::Result<DetectionResult> {
+        let start = Instant::now();
+        let mut by_layer: BTreeMap<String, Vec<EnhancedRoom>> = BTreeMap::new();
+        let mut combined: Option<DetectionResult> = None;
+
+        for (name, svg) in layers {
+            let mut result = self.detect_rooms(&[], None, Some(svg)).await?;
+            let offset = combined.as_ref().map_or(0, |c| c.rooms.len());
+            for enhanced in &mut result.rooms {
+                enhanced.room.id += offset;
+            }
+            by_layer.entry(name.clone()).or_default().extend(result.rooms.iter().cloned());
+
+            combined = Some(match combined {
+                None => result,
+                Some(mut acc) => {
+                    acc.metadata.graph_based_rooms += result.metadata.graph_based_rooms;
+                    acc.metadata.vision_classified += result.metadata.vision_classified;
+                    acc.metadata.method_timings.extend(result.metadata.method_timings);
+                    if let (Some(count), Some(more)) =
+                        (acc.metadata.merged_walls_count.as_mut(), result.metadata.merged_walls_count)
+                    {
+                        *count += more;
+                    }
+                    if let (Some(walls), Some(more)) =
+                        (acc.metadata.parsed_walls.as_mut(), result.metadata.parsed_walls)
+                    {
+                        walls.extend(more);
+                    }
+                    acc.rooms.extend(result.rooms);
+                    acc
+                }
+            });
+        }
+
+        let mut result = combined.ok_or_else(|| anyhow::anyhow!("No layers to detect"))?;
+        result.layers = Some(by_layer);
+        result.execution_time_ms = start.elapsed().as_millis();
+        result.metadata.total_execution_time_ms = start.elapsed().as_millis();
+        Ok(result)
+    }
+
     /// Graph-based detection only
     async fn detect_graph_only(
         &self,
@@ -210,21 +420,13 @@ impl DetectorOrchestrator {
 
         info!("Graph detection found {} rooms in {}ms", rooms.len(), elapsed);
 
-        let enhanced_rooms: Vec<EnhancedRoom> = rooms
-            .into_iter()
-            .map(|room| EnhancedRoom {
-                room,
-                room_type: None,
-                confidence: None,
-                features: Vec::new(),
-                detection_method: "graph".to_string(),
-            })
-            .collect();
+        let enhanced_rooms: Vec<EnhancedRoom> = tag_rooms(rooms, "graph");
 
         Ok(DetectionResult {
             rooms: enhanced_rooms.clone(),
             method_used: "graph_only".to_string(),
             execution_time_ms: elapsed,
+            layers: None,
             metadata: DetectionMetadata {
                 graph_based_rooms: enhanced_rooms.len(),
                 vision_classified: 0,
@@ -238,6 +440,12 @@ impl DetectorOrchestrator {
                 gpt5_confidence: None,
                 merge_strategy: None,
                 merged_walls: None,
+                vtracer_cache_hit: None,
+                svg_parsers: None,
+                svg_parser_agreement: None,
+                parsed_walls: None,
+                source_bounds: None,
+                wall_thickness: None,
             },
         })
     }
@@ -284,6 +492,7 @@ impl DetectorOrchestrator {
                     rooms: enhanced_rooms.clone(),
                     method_used: "graph_with_vision".to_string(),
                     execution_time_ms: graph_result.execution_time_ms + vision_elapsed,
+                    layers: None,
                     metadata: DetectionMetadata {
                         graph_based_rooms: enhanced_rooms.len(),
                         vision_classified,
@@ -297,6 +506,12 @@ impl DetectorOrchestrator {
                         gpt5_confidence: None,
                         merge_strategy: None,
                         merged_walls: None,
+                        vtracer_cache_hit: None,
+                        svg_parsers: None,
+                        svg_parser_agreement: None,
+                        parsed_walls: None,
+                        source_bounds: None,
+                        wall_thickness: None,
                     },
                 })
             }
@@ -324,13 +539,13 @@ impl DetectorOrchestrator {
         // Check if YOLO model is available
         if !unified_detector::yolo::is_yolo_available() {
             return Err(anyhow::anyhow!(
-                "YOLO model not found. Train model first: yolo-training/train_yolov8.py"
+                "YOLO model not found. Train model first (yolo-training/train_yolov8.py) or set YOLO_MODEL_PATH"
             ));
         }
 
-        // Create YOLO detector (stub for now, will be real when ONNX runtime is added)
+        // Create YOLO detector (ONNX runtime when built with the `onnx` feature)
         let config = unified_detector::yolo::YoloConfig::default();
-        let detector = unified_detector::yolo::StubYoloDetector::new(config)?;
+        let detector = unified_detector::yolo::create_detector(config)?;
 
         // Run detection
         let detections = detector.detect(image_bytes)?;
@@ -355,6 +570,7 @@ impl DetectorOrchestrator {
                 area: ((det.bbox[2] - det.bbox[0]) * (det.bbox[3] - det.bbox[1])) as f64,
                 name_hint: det.class_name.clone(),
                 points: Vec::new(), // YOLO doesn't provide polygon points
+                ..Room::default()
             })
             .collect();
 
@@ -375,6 +591,7 @@ impl DetectorOrchestrator {
             rooms: enhanced_rooms.clone(),
             method_used: "yolo_only".to_string(),
             execution_time_ms: elapsed,
+            layers: None,
             metadata: DetectionMetadata {
                 graph_based_rooms: 0,
                 vision_classified: 0,
@@ -388,6 +605,12 @@ impl DetectorOrchestrator {
                 gpt5_confidence: None,
                 merge_strategy: None,
                 merged_walls: None,
+                vtracer_cache_hit: None,
+                svg_parsers: None,
+                svg_parser_agreement: None,
+                parsed_walls: None,
+                source_bounds: None,
+                wall_thickness: None,
             },
         })
     }
@@ -459,7 +682,9 @@ impl DetectorOrchestrator {
         Ok(best)
     }
 
-    /// Hybrid vision detection: VTracer + GPT-5 Vision wall extraction
+    /// Hybrid vision detection: VTracer walls merged with the walls a vision model reads off
+    /// the same canvas. Without vision (disabled, unconfigured or failing) VTracer's walls are
+    /// used alone.
     async fn detect_hybrid_vision(
         &self,
         image_bytes: Option<&[u8]>,
@@ -469,73 +694,14 @@ impl DetectorOrchestrator {
             return Err(anyhow::anyhow!("Hybrid vision detection requires image data"));
         }
 
-        let image_bytes = image_bytes.unwrap();
-
-        // Step 1: Normalize image
-        let norm_start = Instant::now();
-        let normalized_image = crate::image_preprocessor::NormalizedImage::from_bytes(image_bytes)
-            .map_err(|e| anyhow::anyhow!("Image normalization failed: {}", e))?;
-
-        let norm_elapsed = norm_start.elapsed().as_millis();
-        timings.push(("image_normalization".to_string(), norm_elapsed));
-        info!("Image normalized in {}ms", norm_elapsed);
-
-        // Step 2: Run VTracer
-        let vtracer_start = Instant::now();
-
-        // VTracer requires file paths, use UUID for unique temp file names
-        let request_id = uuid::Uuid::new_v4();
-        let temp_path = std::env::temp_dir().join(format!("hybrid_{}_input.png", request_id));
-        let svg_path = std::env::temp_dir().join(format!("hybrid_{}_output.svg", request_id));
-
-    // Preprocess image for VTracer
-    let preprocessed_bytes = normalized_image.preprocess_for_vtracer()?;
-
-    // Save preprocessed image to temp file
-    std::fs::write(&temp_path, &preprocessed_bytes)?;
-
-        // Configure VTracer for blueprint detection
-        let config = vtracer::Config {
-            color_mode: vtracer::ColorMode::Color,  // Color mode works better with grayscale images
-            hierarchical: vtracer::Hierarchical::Stacked,
-            mode: visioncortex::PathSimplifyMode::Spline,
-            filter_speckle: 4,  // Moderate speckle filtering
-            color_precision: 8,  // Higher precision for grayscale tones
-            layer_difference: 5,  // Lower for grayscale images
-            corner_threshold: 60,  // Prefer straight lines for architectural drawings
-            length_threshold: 4.0,  // Capture meaningful line segments
-            max_iterations: 10,
-            splice_threshold: 45,
-            path_precision: Some(3),
+        let vectorized = vectorize_image(image_bytes.unwrap(), &self.config, timings)?;
+        let merge = self.merge_vision_walls(&vectorized, timings).await;
+        let lines: Vec<Line> = match &merge {
+            Some((merged, _)) => merged.walls.iter().map(from_merger_line).collect(),
+            None => vectorized.lines.clone(),
         };
 
-        // Convert to SVG
-        vtracer::convert_image_to_svg(&temp_path, &svg_path, config)
-            .map_err(|e| anyhow::anyhow!("VTracer failed: {}", e))?;
-
-        // Read SVG
-        let svg = std::fs::read_to_string(&svg_path)
-            .map_err(|e| anyhow::anyhow!("Failed to read SVG: {}", e))?;
-
-        // Clean up temp files
-        let _ = std::fs::remove_file(&temp_path);
-        let _ = std::fs::remove_file(&svg_path);
-
-        // Parse SVG to lines
-        let vectorizer_lines = crate::image_vectorizer::parse_svg_to_lines(&svg)?;
-
-        // Convert to crate::Line
-        let lines: Vec<Line> = vectorizer_lines.iter().map(|vl| Line {
-            start: crate::Point { x: vl.start.x, y: vl.start.y },
-            end: crate::Point { x: vl.end.x, y: vl.end.y },
-            is_load_bearing: false,
-        }).collect();
-
-        let vtracer_elapsed = vtracer_start.elapsed().as_millis();
-        timings.push(("vtracer_vectorization".to_string(), vtracer_elapsed));
-        info!("VTracer extracted {} lines in {}ms from preprocessed image", lines.len(), vtracer_elapsed);
-
-        // Step 3: Build graph from extracted lines
+        // Step 4: Build graph from the walls
         let graph_start = Instant::now();
         let graph = crate::graph_builder::build_graph_with_door_threshold(
             &lines,
@@ -546,7 +712,7 @@ impl DetectorOrchestrator {
         timings.push(("graph_building".to_string(), graph_elapsed));
         info!("Graph built in {}ms", graph_elapsed);
 
-        // Step 4: Detect rooms
+        // Step 5: Detect rooms
         let detection_start = Instant::now();
         let rooms = crate::room_detector::detect_rooms(
             &graph,
@@ -559,36 +725,97 @@ impl DetectorOrchestrator {
         info!("Detected {} rooms in {}ms", rooms.len(), detection_elapsed);
 
         // Convert to EnhancedRoom format (without vision classification)
-        let enhanced_rooms: Vec<EnhancedRoom> = rooms
-            .into_iter()
-            .map(|room| EnhancedRoom {
-                room,
-                room_type: None,
-                confidence: None,
-                features: Vec::new(),
-                detection_method: "hybrid_vision".to_string(),
-            })
-            .collect();
+        let enhanced_rooms: Vec<EnhancedRoom> = tag_rooms(rooms, "hybrid_vision");
 
-        Ok(DetectionResult {
+        let mut result = DetectionResult {
             rooms: enhanced_rooms.clone(),
             method_used: "hybrid_vision".to_string(),
             execution_time_ms: 0, // Will be set by caller
+            layers: None,
             metadata: DetectionMetadata {
                 graph_based_rooms: enhanced_rooms.len(),
                 vision_classified: 0,
                 yolo_detected: 0,
                 total_execution_time_ms: 0, // Will be set by caller
                 method_timings: timings.clone(),
-                vtracer_walls_count: Some(lines.len()),
+                vtracer_walls_count: Some(vectorized.lines.len()),
                 gpt5_walls_count: None,
                 merged_walls_count: None,
                 consensus_walls_count: None,
                 gpt5_confidence: None,
                 merge_strategy: None,
                 merged_walls: None,
+                vtracer_cache_hit: vectorized.cache_hit,
+                svg_parsers: None,
+                svg_parser_agreement: None,
+                parsed_walls: None,
+                source_bounds: None,
+                wall_thickness: vectorized.wall_thickness,
             },
-        })
+        };
+        result.metadata.record_merge(merge);
+        result.normalize_from_canvas(vectorized.placement);
+        Ok(result)
+    }
+
+    /// VTracer's walls merged with the vision model's, trusting vision by its confidence, and
+    /// that confidence; `None` when vision is disabled or gave no answer
+    async fn merge_vision_walls(
+        &self,
+        vectorized: &VectorizedImage,
+        timings: &mut Vec<(String, u128)>,
+    ) -> Option<(crate::wall_merger::MergeResult, f64)> {
+        if !self.config.enable_vision {
+            return None;
+        }
+        let vision = self.extract_vision_walls(&vectorized.canvas, timings).await?;
+
+        let vtracer_walls = vectorized.lines.iter().map(|line| to_merger_line(line, "vtracer")).collect();
+        let vision_walls = vision
+            .walls
+            .iter()
+            .map(|wall| crate::wall_merger::Line {
+                start: crate::wall_merger::Point { x: wall.start.x, y: wall.start.y },
+                end: crate::wall_merger::Point { x: wall.end.x, y: wall.end.y },
+                is_load_bearing: false,
+                source: Some("gpt5".to_string()),
+                confidence: None,
+            })
+            .collect();
+        let merged = crate::wall_merger::merge_wall_segments(
+            vtracer_walls,
+            vision_walls,
+            vision.confidence,
+            self.config.confidence_threshold,
+            self.config.min_wall_confidence,
+        );
+        Some((merged, vision.confidence))
+    }
+
+    /// Walls the vision model reads off the vectorizing canvas, or `None` when it is
+    /// unavailable or fails
+    async fn extract_vision_walls(
+        &self,
+        canvas: &crate::image_preprocessor::NormalizedImage,
+        timings: &mut Vec<(String, u128)>,
+    ) -> Option<vision_classifier::VisionWallData> {
+        let classifier = match &self.wall_vision {
+            Some(classifier) => classifier.clone(),
+            None => match vision_classifier::VisionClassifier::from_env() {
+                Ok(classifier) => Arc::new(classifier),
+                Err(e) => {
+                    warn!("Hybrid vision continuing with VTracer walls only: {}", e);
+                    return None;
+                }
+            },
+        };
+
+        let start = Instant::now();
+        let walls = classifier.extract_wall_segments(&canvas.to_data_url()).await;
+        timings.push(("vision_wall_extraction".to_string(), start.elapsed().as_millis()));
+        walls
+            .map_err(|e| warn!("Vision wall extraction failed, using VTracer walls only: {}", e))
+            .ok()
     }
 
     /// VTracer-only detection: Extract lines from raster image, then graph-based detection
@@ -601,75 +828,9 @@ impl DetectorOrchestrator {
             return Err(anyhow::anyhow!("VTracer detection requires image data"));
         }
 
-        let image_bytes = image_bytes.unwrap();
-
         info!("Starting VTracer-only detection");
-
-        // Step 1: Normalize image
-        let norm_start = Instant::now();
-        let normalized_image = crate::image_preprocessor::NormalizedImage::from_bytes(image_bytes)
-            .map_err(|e| anyhow::anyhow!("Image normalization failed: {}", e))?;
-
-        let norm_elapsed = norm_start.elapsed().as_millis();
-        timings.push(("image_normalization".to_string(), norm_elapsed));
-        info!("Image normalized in {}ms", norm_elapsed);
-
-        // Step 2: Run VTracer
-        let vtracer_start = Instant::now();
-
-        // VTracer requires file paths, use UUID for unique temp file names
-        let request_id = uuid::Uuid::new_v4();
-        let temp_path = std::env::temp_dir().join(format!("vtracer_{}_input.png", request_id));
-        let svg_path = std::env::temp_dir().join(format!("vtracer_{}_output.svg", request_id));
-
-// Preprocess image for VTracer
-let preprocessed_bytes = normalized_image.preprocess_for_vtracer()
-    .map_err(|e| anyhow::anyhow!("VTracer preprocessing failed: {}", e))?;
-
-// Save preprocessed image to temp file
-std::fs::write(&temp_path, &preprocessed_bytes)?;
-
-        // Configure VTracer for blueprint detection
-        // Use Color mode to handle grayscale blueprint images
-        let config = vtracer::Config {
-            color_mode: vtracer::ColorMode::Color,  // Color mode works better with grayscale images
-            hierarchical: vtracer::Hierarchical::Stacked,
-        mode: visioncortex::PathSimplifyMode::Spline,
-        filter_speckle: 4,  // Moderate speckle filtering
-        color_precision: 8,  // Higher precision for grayscale tones
-        layer_difference: 5,  // Lower for grayscale images
-        corner_threshold: 60,  // Prefer straight lines for architectural drawings
-        length_threshold: 4.0,  // Capture meaningful line segments
-        max_iterations: 10,
-        splice_threshold: 45,
-        path_precision: Some(3),
-        };
-
-        // Convert to SVG
-        vtracer::convert_image_to_svg(&temp_path, &svg_path, config)
-            .map_err(|e| anyhow::anyhow!("VTracer failed: {}", e))?;
-
-        // Read SVG
-        let svg = std::fs::read_to_string(&svg_path)
-            .map_err(|e| anyhow::anyhow!("Failed to read SVG: {}", e))?;
-
-        // Clean up temp files
-        let _ = std::fs::remove_file(&temp_path);
-        let _ = std::fs::remove_file(&svg_path);
-
-        // Parse SVG to lines
-        let vectorizer_lines = crate::image_vectorizer::parse_svg_to_lines(&svg)?;
-
-        // Convert to crate::Line
-        let lines: Vec<Line> = vectorizer_lines.iter().map(|vl| Line {
-            start: crate::Point { x: vl.start.x, y: vl.start.y },
-            end: crate::Point { x: vl.end.x, y: vl.end.y },
-            is_load_bearing: false,
-        }).collect();
-
-        let vtracer_elapsed = vtracer_start.elapsed().as_millis();
-        timings.push(("vtracer_vectorization".to_string(), vtracer_elapsed));
-        info!("VTracer extracted {} lines in {}ms from preprocessed image", lines.len(), vtracer_elapsed);
+        let VectorizedImage { lines, cache_hit: vtracer_cache_hit, placement, wall_thickness, .. } =
+            vectorize_image(image_bytes.unwrap(), &self.config, timings)?;
 
         // Step 3: Build graph from extracted lines
         let graph_start = Instant::now();
@@ -692,21 +853,13 @@ std::fs::write(&temp_path, &preprocessed_bytes)?;
         info!("Detected {} rooms in {}ms", rooms.len(), detection_elapsed);
 
         // Convert to EnhancedRoom format (without vision classification)
-        let enhanced_rooms: Vec<EnhancedRoom> = rooms
-            .into_iter()
-            .map(|room| EnhancedRoom {
-                room,
-                room_type: None,
-                confidence: None,
-                features: Vec::new(),
-                detection_method: "vtracer_only".to_string(),
-            })
-            .collect();
+        let enhanced_rooms: Vec<EnhancedRoom> = tag_rooms(rooms, "vtracer_only");
 
-        Ok(DetectionResult {
+        let mut result = DetectionResult {
             rooms: enhanced_rooms.clone(),
             method_used: "vtracer_only".to_string(),
             execution_time_ms: 0, // Will be set by caller
+            layers: None,
             metadata: DetectionMetadata {
                 graph_based_rooms: enhanced_rooms.len(),
                 vision_classified: 0,
@@ -720,44 +873,156 @@ std::fs::write(&temp_path, &preprocessed_bytes)?;
                 gpt5_confidence: None,
                 merge_strategy: None,
                 merged_walls: None,
+                vtracer_cache_hit,
+                svg_parsers: None,
+                svg_parser_agreement: None,
+                parsed_walls: None,
+                source_bounds: None,
+                wall_thickness,
             },
-        })
+        };
+        result.normalize_from_canvas(placement);
+        Ok(result)
     }
 
-    /// SVG-only detection: Parse SVG directly and detect rooms geometrically
+    /// Vectorize an image into walls only, skipping graph building and room detection.
+    /// The hybrid vision strategy also merges in the vision model's walls.
+    pub async fn extract_walls(&self, image_bytes: &[u8]) -> anyhow::Result<DetectionResult> {
+        let start = Instant::now();
+        let mut timings = Vec::new();
+        let vectorized = vectorize_image(image_bytes, &self.config, &mut timings)?;
+        let (merge, method_used) = match self.config.strategy {
+            CombinationStrategy::HybridVision => {
+                (self.merge_vision_walls(&vectorized, &mut timings).await, "hybrid_vision_walls")
+            }
+            _ => (None, self.config.vectorizer.walls_method()),
+        };
+        let VectorizedImage { lines, cache_hit: vtracer_cache_hit, placement, wall_thickness, .. } = vectorized;
+        let execution_time_ms = start.elapsed().as_millis();
+
+        let mut result = DetectionResult {
+            rooms: Vec::new(),
+            method_used: method_used.to_string(),
+            execution_time_ms,
+            layers: None,
+            metadata: DetectionMetadata {
+                graph_based_rooms: 0,
+                vision_classified: 0,
+                yolo_detected: 0,
+                total_execution_time_ms: execution_time_ms,
+                method_timings: timings,
+                vtracer_walls_count: Some(lines.len()),
+                gpt5_walls_count: None,
+                merged_walls_count: None,
+                consensus_walls_count: None,
+                gpt5_confidence: None,
+                merge_strategy: None,
+                merged_walls: None,
+                vtracer_cache_hit,
+                svg_parsers: None,
+                svg_parser_agreement: None,
+                parsed_walls: Some(lines),
+                source_bounds: None,
+                wall_thickness,
+            },
+        };
+        result.metadata.record_merge(merge);
+        result.normalize_from_canvas(placement);
+        Ok(result)
+    }
+
+    /// SVG-only detection: Parse SVG with the configured parser(s) and detect rooms geometrically
     async fn detect_svg_only(
         &self,
         svg_content: Option<&str>,
         timings: &mut Vec<(String, u128)>,
     ) -> anyhow::Result<DetectionResult> {
-        if svg_content.is_none() {
-            return Err(anyhow::anyhow!("SVG detection requires SVG content"));
+        let svg_content =
+            svg_content.ok_or_else(|| anyhow::anyhow!("SVG detection requires SVG content"))?;
+
+        match self.config.svg_parser {
+            SvgParser::Algorithmic => {
+                let lines = self.parse_svg_algorithmic(svg_content, timings)?;
+                let rooms = self.detect_svg_rooms(&lines, "graph_detection", timings);
+                Ok(svg_result(tag_rooms(rooms, "svg"), "svg_only", lines, &["algorithmic"], None))
+            }
+            SvgParser::Ai => {
+                let lines = self.parse_svg_ai(svg_content, timings).await?;
+                let rooms = self.detect_svg_rooms(&lines, "graph_detection", timings);
+                Ok(svg_result(tag_rooms(rooms, "svg_ai"), "svg_ai", lines, &["ai"], None))
+            }
+            SvgParser::Combined => {
+                let lines = self.parse_svg_algorithmic(svg_content, timings)?;
+                let algorithmic_rooms = self.detect_svg_rooms(&lines, "graph_detection", timings);
+
+                // An unavailable AI parser leaves the algorithmic result standing
+                let ai_lines = match self.parse_svg_ai(svg_content, timings).await {
+                    Ok(ai_lines) => ai_lines,
+                    Err(e) => {
+                        warn!("AI SVG parsing failed, using algorithmic rooms only: {}", e);
+                        return Ok(svg_result(
+                            tag_rooms(algorithmic_rooms, "svg"),
+                            "svg_only",
+                            lines,
+                            &["algorithmic"],
+                            None,
+                        ));
+                    }
+                };
+                let ai_rooms = self.detect_svg_rooms(&ai_lines, "ai_graph_detection", timings);
+
+                let (rooms, agreement) = reconcile_svg_rooms(algorithmic_rooms, ai_rooms);
+                info!(
+                    "Combined SVG parsing: {} rooms, {:.0}% agreement",
+                    rooms.len(),
+                    agreement * 100.0
+                );
+                Ok(svg_result(rooms, "svg_combined", lines, &["algorithmic", "ai"], Some(agreement)))
+            }
         }
+    }
 
-        let svg_content = svg_content.unwrap();
+    /// Extract wall lines with the built-in SVG geometry parser
+    fn parse_svg_algorithmic(
+        &self,
+        svg_content: &str,
+        timings: &mut Vec<(String, u128)>,
+    ) -> anyhow::Result<Vec<Line>> {
         let start = Instant::now();
-
-        // Parse SVG to extract lines
-        let svg_lines = crate::image_vectorizer::parse_svg_to_lines(svg_content)?;
-
-        // Convert to crate::Line format
-        let lines: Vec<Line> = svg_lines
+        let lines: Vec<Line> = crate::image_vectorizer::parse_svg_to_lines_with_min_stroke(
+            svg_content,
+            self.config.min_stroke_width,
+        )?
             .into_iter()
-            .map(|vl| Line {
-                start: crate::Point { x: vl.start.x, y: vl.start.y },
-                end: crate::Point { x: vl.end.x, y: vl.end.y },
-                is_load_bearing: vl.is_load_bearing,
-            })
+            .map(from_vectorizer_line)
             .collect();
 
         let parse_elapsed = start.elapsed().as_millis();
         timings.push(("svg_parsing".to_string(), parse_elapsed));
         info!("Parsed {} lines from SVG in {}ms", lines.len(), parse_elapsed);
+        Ok(lines)
+    }
 
-        // Build graph and detect rooms
+    /// Extract wall lines with the language-model SVG parser
+    async fn parse_svg_ai(
+        &self,
+        svg_content: &str,
+        timings: &mut Vec<(String, u128)>,
+    ) -> anyhow::Result<Vec<Line>> {
+        let start = Instant::now();
+        let lines = self.svg_ai_parser.parse(svg_content).await?;
+
+        let parse_elapsed = start.elapsed().as_millis();
+        timings.push(("svg_ai_parsing".to_string(), parse_elapsed));
+        info!("AI parser extracted {} lines from SVG in {}ms", lines.len(), parse_elapsed);
+        Ok(lines)
+    }
+
+    /// Build the wall graph from SVG lines and detect rooms
+    fn detect_svg_rooms(&self, lines: &[Line], timing_label: &str, timings: &mut Vec<(String, u128)>) -> Vec<Room> {
         let graph_start = Instant::now();
         let graph = crate::graph_builder::build_graph_with_door_threshold(
-            &lines,
+            lines,
             self.config.door_threshold,
         );
 
@@ -768,40 +1033,9 @@ std::fs::write(&temp_path, &preprocessed_bytes)?;
         );
 
         let graph_elapsed = graph_start.elapsed().as_millis();
-        timings.push(("graph_detection".to_string(), graph_elapsed));
-
-        info!("SVG detection found {} rooms in {}ms", rooms.len(), parse_elapsed + graph_elapsed);
-
-        let enhanced_rooms: Vec<EnhancedRoom> = rooms
-            .into_iter()
-            .map(|room| EnhancedRoom {
-                room,
-                room_type: None,
-                confidence: None,
-                features: Vec::new(),
-                detection_method: "svg".to_string(),
-            })
-            .collect();
-
-        Ok(DetectionResult {
-            rooms: enhanced_rooms.clone(),
-            method_used: "svg_only".to_string(),
-            execution_time_ms: parse_elapsed + graph_elapsed,
-            metadata: DetectionMetadata {
-                graph_based_rooms: enhanced_rooms.len(),
-                vision_classified: 0,
-                yolo_detected: 0,
-                total_execution_time_ms: parse_elapsed + graph_elapsed,
-                method_timings: timings.clone(),
-                vtracer_walls_count: None,
-                gpt5_walls_count: None,
-                merged_walls_count: Some(lines.len()),
-                consensus_walls_count: None,
-                gpt5_confidence: None,
-                merge_strategy: None,
-                merged_walls: None,
-            },
-        })
+        timings.push((timing_label.to_string(), graph_elapsed));
+        info!("SVG detection found {} rooms in {}ms", rooms.len(), graph_elapsed);
+        rooms
     }
 
     /// SVG detection + Vision classification
@@ -861,7 +1095,10 @@ std::fs::write(&temp_path, &preprocessed_bytes)?;
             .iter()
             .zip(enhanced.iter())
             .map(|(geo_room, enhanced_room)| EnhancedRoom {
-                room: geo_room.room.clone(),
+                room: Room {
+                    confidence: None,
+                    ..geo_room.room.clone()
+                },
                 room_type: enhanced_room
                     .classification
                     .as_ref()
@@ -869,7 +1106,8 @@ std::fs::write(&temp_path, &preprocessed_bytes)?;
                 confidence: enhanced_room
                     .classification
                     .as_ref()
-                    .map(|c| c.confidence),
+                    .map(|c| c.confidence)
+                    .or(geo_room.room.confidence),
                 features: enhanced_room
                     .classification
                     .as_ref()
@@ -882,3 +1120,459 @@ std::fs::write(&temp_path, &preprocessed_bytes)?;
         Ok(result)
     }
 }
+
+/// Where a raster image sits on the letterboxed 1000x1000 canvas it is vectorized on
+#[derive(Debug, Clone, Copy)]
+struct CanvasPlacement {
+    /// The image's extent on the canvas
+    content_bounds: [f64; 4],
+    /// The image's own `[0, 0, width, height]` in pixels
+    image_bounds: [f64; 4],
+}
+
+/// Walls traced from a raster image, in the coordinates of its letterboxed canvas
+struct VectorizedImage {
+    lines: Vec<Line>,
+    /// Whether VTracer's output came from the cache; `None` for other backends
+    cache_hit: Option<bool>,
+    placement: CanvasPlacement,
+    /// The canvas the walls were traced on
+    canvas: crate::image_preprocessor::NormalizedImage,
+    /// Estimated wall thickness in source image pixels, when faces were collapsed
+    wall_thickness: Option<f64>,
+}
+
+/// Normalize and vectorize a raster image into wall lines, recording step timings
+fn vectorize_image(
+    image_bytes: &[u8],
+    config: &DetectorConfig,
+    timings: &mut Vec<(String, u128)>,
+) -> anyhow::Result<VectorizedImage> {
+    // Step 1: Normalize image
+    let norm_start = Instant::now();
+    let normalized_image = crate::image_preprocessor::NormalizedImage::from_bytes(image_bytes)
+        .map_err(|e| anyhow::anyhow!("Image normalization failed: {}", e))?;
+
+    let norm_elapsed = norm_start.elapsed().as_millis();
+    timings.push(("image_normalization".to_string(), norm_elapsed));
+    info!("Image normalized in {}ms", norm_elapsed);
+
+    // Step 2: Vectorize with the configured backend
+    let vtracer_start = Instant::now();
+
+    // Preprocess image for vectorization
+    let preprocessed_bytes = normalized_image.preprocess_for_vtracer()
+        .map_err(|e| anyhow::anyhow!("VTracer preprocessing failed: {}", e))?;
+
+    let backend = config.vectorizer;
+    let (vectorizer_lines, vtracer_cache_hit) = match backend {
+        // Vectorize (cached by image + config hash) and parse SVG to lines
+        VectorizerBackend::VTracer => {
+            let (lines, cache_hit) =
+                crate::image_vectorizer::vectorize_cached(&preprocessed_bytes, config.vtracer.to_config())?;
+            (lines, Some(cache_hit))
+        }
+        backend => (backend.vectorizer().vectorize(&preprocessed_bytes)?, None),
+    };
+
+    // Convert to crate::Line
+    let lines: Vec<Line> = vectorizer_lines.iter().map(|vl| Line {
+        start: crate::Point { x: vl.start.x, y: vl.start.y },
+        end: crate::Point { x: vl.end.x, y: vl.end.y },
+        is_load_bearing: false,
+    }).collect();
+
+    let vtracer_elapsed = vtracer_start.elapsed().as_millis();
+    timings.push(("vtracer_vectorization".to_string(), vtracer_elapsed));
+    info!(
+        "{:?} extracted {} lines in {}ms from preprocessed image (cache {})",
+        backend,
+        lines.len(),
+        vtracer_elapsed,
+        match vtracer_cache_hit {
+            Some(true) => "hit",
+            Some(false) => "miss",
+            None => "unused",
+        }
+    );
+
+    // Both faces of a filled wall band trace as separate lines; optionally keep one centerline
+    let (lines, wall_thickness) = match config.wall_thickness_tolerance {
+        Some(tolerance) => {
+            let faces: Vec<_> = lines.iter().map(|line| to_merger_line(line, "vtracer")).collect();
+            let extracted = crate::wall_merger::extract_centerlines(&faces, tolerance);
+            let thickness = extracted.thickness.map(|t| t / normalized_image.scale_factor);
+            (extracted.centerlines.iter().map(from_merger_line).collect(), thickness)
+        }
+        None => (lines, None),
+    };
+
+    let placement = CanvasPlacement {
+        content_bounds: normalized_image.content_bounds(),
+        image_bounds: [
+            0.0,
+            0.0,
+            normalized_image.original_width as f64,
+            normalized_image.original_height as f64,
+        ],
+    };
+    Ok(VectorizedImage {
+        lines,
+        cache_hit: vtracer_cache_hit,
+        placement,
+        canvas: normalized_image,
+        wall_thickness,
+    })
+}
+
+/// Wall for the merger, tagged with the source that traced it
+fn to_merger_line(line: &Line, source: &str) -> crate::wall_merger::Line {
+    crate::wall_merger::Line {
+        start: crate::wall_merger::Point { x: line.start.x, y: line.start.y },
+        end: crate::wall_merger::Point { x: line.end.x, y: line.end.y },
+        is_load_bearing: line.is_load_bearing,
+        source: Some(source.to_string()),
+        confidence: None,
+    }
+}
+
+fn from_merger_line(line: &crate::wall_merger::Line) -> Line {
+    Line {
+        start: crate::Point { x: line.start.x, y: line.start.y },
+        end: crate::Point { x: line.end.x, y: line.end.y },
+        is_load_bearing: line.is_load_bearing,
+    }
+}
+
+/// Give every strategy's output the same position-based ids as plain graph detection
+fn number_rooms_spatially(rooms: &mut [EnhancedRoom]) {
+    rooms.sort_by(|a, b| spatial_order(&a.room.bounding_box, &b.room.bounding_box));
+    for (id, enhanced) in rooms.iter_mut().enumerate() {
+        enhanced.room.id = id;
+    }
+}
+
+/// Wrap geometric rooms without vision classification. A room's own geometric confidence moves
+/// up to the wrapper, so the flattened JSON carries a single `confidence`.
+fn tag_rooms(rooms: Vec<Room>, detection_method: &str) -> Vec<EnhancedRoom> {
+    rooms
+        .into_iter()
+        .map(|mut room| EnhancedRoom {
+            confidence: room.confidence.take(),
+            room,
+            room_type: None,
+            features: Vec::new(),
+            detection_method: detection_method.to_string(),
+        })
+        .collect()
+}
+
+fn svg_result(
+    rooms: Vec<EnhancedRoom>,
+    method_used: &str,
+    walls: Vec<Line>,
+    parsers: &[&str],
+    agreement: Option<f64>,
+) -> DetectionResult {
+    DetectionResult {
+        method_used: method_used.to_string(),
+        execution_time_ms: 0, // Will be set by caller
+        layers: None,
+        metadata: DetectionMetadata {
+            graph_based_rooms: rooms.len(),
+            vision_classified: 0,
+            yolo_detected: 0,
+            total_execution_time_ms: 0, // Will be set by caller
+            method_timings: Vec::new(), // Will be set by caller
+            vtracer_walls_count: None,
+            gpt5_walls_count: None,
+            merged_walls_count: Some(walls.len()),
+            consensus_walls_count: None,
+            gpt5_confidence: None,
+            merge_strategy: None,
+            merged_walls: None,
+            vtracer_cache_hit: None,
+            svg_parsers: Some(parsers.iter().map(|p| p.to_string()).collect()),
+            svg_parser_agreement: agreement,
+            parsed_walls: Some(walls),
+            source_bounds: None,
+            wall_thickness: None,
+        },
+        rooms,
+    }
+}
+
+/// Merge rooms from the algorithmic and AI SVG parsers. Rooms both found keep the algorithmic
+/// geometry with their overlap as confidence; rooms only one parser found are kept but flagged
+/// through `detection_method`. Returns the rooms and the share of rooms both parsers agreed on.
+fn reconcile_svg_rooms(algorithmic: Vec<Room>, ai: Vec<Room>) -> (Vec<EnhancedRoom>, f64) {
+    let boxes = |rooms: &[Room]| rooms.iter().map(|r| r.bounding_box).collect::<Vec<_>>();
+    let (algorithmic_boxes, ai_boxes) = (boxes(&algorithmic), boxes(&ai));
+    let matching = room_matching::match_boxes(&algorithmic_boxes, &ai_boxes, SVG_PARSER_MATCH_IOU);
+
+    let mut rooms = Vec::with_capacity(matching.matched.len() + matching.only_a.len() + matching.only_b.len());
+    for &(a, b) in &matching.matched {
+        rooms.push(EnhancedRoom {
+            room: Room {
+                confidence: None,
+                ..algorithmic[a].clone()
+            },
+            room_type: None,
+            confidence: Some(room_matching::bbox_iou(&algorithmic_boxes[a], &ai_boxes[b])),
+            features: Vec::new(),
+            detection_method: "svg_combined".to_string(),
+        });
+    }
+    rooms.extend(tag_rooms(
+        matching.only_a.iter().map(|&a| algorithmic[a].clone()).collect(),
+        "svg_algorithmic_only",
+    ));
+    rooms.extend(tag_rooms(
+        matching.only_b.iter().map(|&b| ai[b].clone()).collect(),
+        "svg_ai_only",
+    ));
+    for (id, room) in rooms.iter_mut().enumerate() {
+        room.room.id = id;
+    }
+
+    let agreement = if rooms.is_empty() {
+        1.0
+    } else {
+        matching.matched.len() as f64 / rooms.len() as f64
+    };
+    (rooms, agreement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+
+    /// Returns fixed lines instead of calling a model
+    struct MockSvgAiParser(anyhow::Result<Vec<Line>>);
+
+    impl SvgAiParser for MockSvgAiParser {
+        fn parse<'a>(&'a self, _svg: &'a str) -> BoxFuture<'a, anyhow::Result<Vec<Line>>> {
+            Box::pin(async move {
+                match &self.0 {
+                    Ok(lines) => Ok(lines.clone()),
+                    Err(e) => Err(anyhow::anyhow!("{}", e)),
+                }
+            })
+        }
+    }
+
+    fn square(x0: f64, y0: f64, size: f64) -> Vec<Line> {
+        let corners = [(x0, y0), (x0 + size, y0), (x0 + size, y0 + size), (x0, y0 + size)];
+        (0..4)
+            .map(|i| {
+                let (sx, sy) = corners[i];
+                let (ex, ey) = corners[(i + 1) % 4];
+                Line {
+                    start: Point { x: sx, y: sy },
+                    end: Point { x: ex, y: ey },
+                    is_load_bearing: false,
+                }
+            })
+            .collect()
+    }
+
+    fn room(id: usize, bounding_box: [f64; 4]) -> Room {
+        Room {
+            id,
+            bounding_box,
+            area: (bounding_box[2] - bounding_box[0]) * (bounding_box[3] - bounding_box[1]),
+            name_hint: String::new(),
+            ..Room::default()
+        }
+    }
+
+    fn combined_orchestrator(ai_lines: anyhow::Result<Vec<Line>>) -> DetectorOrchestrator {
+        DetectorOrchestrator::new(DetectorConfig {
+            strategy: CombinationStrategy::SvgOnly,
+            svg_parser: SvgParser::Combined,
+            ..DetectorConfig::default()
+        })
+        .with_svg_ai_parser(MockSvgAiParser(ai_lines))
+    }
+
+    const SQUARE_SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg"><rect x="0" y="0" width="100" height="100"/></svg>"#;
+
+    #[tokio::test]
+    async fn test_combined_svg_parsers_reconcile_matching_rooms() {
+        // The AI parser sees the same room, a little off
+        let orchestrator = combined_orchestrator(Ok(square(2.0, 2.0, 100.0)));
+
+        let result = orchestrator.detect_rooms(&[], None, Some(SQUARE_SVG)).await.unwrap();
+
+        assert_eq!(result.method_used, "svg_combined");
+        assert_eq!(
+            result.metadata.svg_parsers,
+            Some(vec!["algorithmic".to_string(), "ai".to_string()])
+        );
+        assert_eq!(result.metadata.svg_parser_agreement, Some(1.0));
+        assert!(!result.rooms.is_empty());
+        for room in &result.rooms {
+            assert_eq!(room.detection_method, "svg_combined");
+            assert!(room.confidence.unwrap() > 0.9);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_combined_svg_falls_back_when_ai_parser_fails() {
+        let orchestrator = combined_orchestrator(Err(anyhow::anyhow!("OPENAI_API_KEY not set")));
+
+        let result = orchestrator.detect_rooms(&[], None, Some(SQUARE_SVG)).await.unwrap();
+
+        assert_eq!(result.method_used, "svg_only");
+        assert_eq!(result.metadata.svg_parsers, Some(vec!["algorithmic".to_string()]));
+        assert_eq!(result.metadata.svg_parser_agreement, None);
+        assert!(!result.rooms.is_empty());
+    }
+
+    /// 1000x1000 PNG of one walled room, so canvas and image coordinates coincide
+    fn one_room_png() -> Vec<u8> {
+        let mut img = image::GrayImage::from_pixel(1000, 1000, image::Luma([255]));
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            let inside = (100..900).contains(&x) && (100..900).contains(&y);
+            let interior = (110..890).contains(&x) && (110..890).contains(&y);
+            if inside && !interior {
+                *pixel = image::Luma([0]);
+            }
+        }
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+        png
+    }
+
+    /// Vision model reply: the room's walls plus a partition at x=500 the image doesn't show
+    fn vision_walls_reply(confidence: f64) -> String {
+        let wall = |x1: f64, y1: f64, x2: f64, y2: f64| {
+            serde_json::json!({ "start": { "x": x1, "y": y1 }, "end": { "x": x2, "y": y2 } })
+        };
+        serde_json::json!({
+            "walls": [
+                wall(105.0, 105.0, 895.0, 105.0),
+                wall(895.0, 105.0, 895.0, 895.0),
+                wall(895.0, 895.0, 105.0, 895.0),
+                wall(105.0, 895.0, 105.0, 105.0),
+                wall(500.0, 105.0, 500.0, 895.0),
+            ],
+            "rooms": [],
+            "confidence": confidence,
+        })
+        .to_string()
+    }
+
+    fn hybrid_orchestrator(vision: vision_classifier::VisionClassifier) -> DetectorOrchestrator {
+        DetectorOrchestrator::new(DetectorConfig {
+            strategy: CombinationStrategy::HybridVision,
+            enable_vision: true,
+            ..DetectorConfig::default()
+        })
+        .with_wall_vision(vision)
+    }
+
+    fn has_partition(result: &DetectionResult) -> bool {
+        result.metadata.merged_walls.as_ref().unwrap().iter().any(|wall| {
+            (wall.start.x - 500.0).abs() < 5.0 && (wall.end.x - 500.0).abs() < 5.0 && (wall.end.y - wall.start.y).abs() > 500.0
+        })
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_vision_merges_vision_and_vtracer_walls() {
+        let orchestrator = hybrid_orchestrator(vision_classifier::VisionClassifier::mock(vec![vision_walls_reply(0.9)]));
+
+        let result = orchestrator.detect_rooms(&[], Some(&one_room_png()), None).await.unwrap();
+
+        assert_eq!(result.method_used, "hybrid_vision");
+        assert_eq!(result.metadata.gpt5_walls_count, Some(5));
+        assert_eq!(result.metadata.gpt5_confidence, Some(0.9));
+        assert_eq!(result.metadata.merge_strategy.as_deref(), Some("vision_primary"));
+        assert!(result.metadata.consensus_walls_count.unwrap() > 0);
+        assert!(has_partition(&result));
+        assert!(result.metadata.method_timings.iter().any(|(step, _)| step == "vision_wall_extraction"));
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_vision_drops_low_confidence_vision_walls() {
+        // Below the default minimum, the vision-only partition is dropped
+        let orchestrator = hybrid_orchestrator(vision_classifier::VisionClassifier::mock(vec![vision_walls_reply(0.3)]));
+        let result = orchestrator.detect_rooms(&[], Some(&one_room_png()), None).await.unwrap();
+        assert_eq!(result.metadata.merge_strategy.as_deref(), Some("vtracer_primary"));
+        assert!(!has_partition(&result));
+
+        // A confident reply is still dropped under a stricter minimum
+        let orchestrator = DetectorOrchestrator::new(DetectorConfig {
+            strategy: CombinationStrategy::HybridVision,
+            enable_vision: true,
+            min_wall_confidence: 0.95,
+            ..DetectorConfig::default()
+        })
+        .with_wall_vision(vision_classifier::VisionClassifier::mock(vec![vision_walls_reply(0.9)]));
+        let result = orchestrator.detect_rooms(&[], Some(&one_room_png()), None).await.unwrap();
+        assert!(!has_partition(&result));
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_vision_walls_only_returns_merged_walls() {
+        let orchestrator = hybrid_orchestrator(vision_classifier::VisionClassifier::mock(vec![vision_walls_reply(0.9)]));
+
+        let result = orchestrator.extract_walls(&one_room_png()).await.unwrap();
+
+        assert_eq!(result.method_used, "hybrid_vision_walls");
+        assert!(result.rooms.is_empty());
+        assert_eq!(result.metadata.gpt5_walls_count, Some(5));
+        assert!(has_partition(&result));
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_vision_falls_back_to_vtracer_walls() {
+        // An exhausted mock fails like an unreachable model
+        let orchestrator = hybrid_orchestrator(vision_classifier::VisionClassifier::mock(Vec::new()));
+
+        let result = orchestrator.detect_rooms(&[], Some(&one_room_png()), None).await.unwrap();
+
+        assert_eq!(result.method_used, "hybrid_vision");
+        assert!(result.metadata.vtracer_walls_count.unwrap() > 0);
+        assert_eq!(result.metadata.gpt5_walls_count, None);
+        assert!(result.metadata.merged_walls.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wall_faces_collapse_to_centerlines() {
+        let extract = |wall_thickness_tolerance| async move {
+            DetectorOrchestrator::new(DetectorConfig {
+                strategy: CombinationStrategy::VTracerOnly,
+                wall_thickness_tolerance,
+                ..DetectorConfig::default()
+            })
+            .extract_walls(&one_room_png())
+            .await
+            .unwrap()
+        };
+
+        let faces = extract(None).await;
+        let centerlines = extract(Some(15.0)).await;
+
+        assert_eq!(faces.metadata.wall_thickness, None);
+        let thickness = centerlines.metadata.wall_thickness.unwrap();
+        assert!((8.0..=12.0).contains(&thickness), "thickness {}", thickness);
+        assert!(centerlines.metadata.parsed_walls.unwrap().len() < faces.metadata.parsed_walls.unwrap().len());
+    }
+
+    #[test]
+    fn test_reconcile_svg_rooms_flags_disagreements() {
+        let algorithmic = vec![room(0, [0.0, 0.0, 100.0, 100.0]), room(1, [200.0, 0.0, 300.0, 100.0])];
+        let ai = vec![room(0, [1.0, 1.0, 101.0, 101.0]), room(1, [0.0, 200.0, 100.0, 300.0])];
+
+        let (rooms, agreement) = reconcile_svg_rooms(algorithmic, ai);
+
+        let methods: Vec<&str> = rooms.iter().map(|r| r.detection_method.as_str()).collect();
+        assert_eq!(methods, ["svg_combined", "svg_algorithmic_only", "svg_ai_only"]);
+        assert_eq!(rooms[0].room.bounding_box, [0.0, 0.0, 100.0, 100.0]);
+        assert_eq!(rooms[2].room.bounding_box, [0.0, 200.0, 100.0, 300.0]);
+        assert_eq!(rooms.iter().map(|r| r.room.id).collect::<Vec<_>>(), [0, 1, 2]);
+        assert!((agreement - 1.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,128 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{DetectRoomsResponse, ErrorResponse, Room};
+
+/// Response body format selected with `?format=`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// The regular `DetectRoomsResponse` shape
+    #[default]
+    Json,
+    /// A GeoJSON `FeatureCollection` of room polygons
+    Geojson,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct FormatQuery {
+    #[serde(default)]
+    pub format: OutputFormat,
+}
+
+/// GeoJSON `Feature` for a room; rooms with fewer than 3 points get a null geometry
+pub fn room_feature(room: &Room, room_type: Option<&str>, confidence: Option<f64>) -> Value {
+    let geometry = if room.points.len() >= 3 {
+        let mut ring: Vec<[f64; 2]> = room.points.iter().map(|p| [p.x, p.y]).collect();
+        // GeoJSON linear rings must end where they start
+        if ring.first() != ring.last() {
+            ring.push(ring[0]);
+        }
+        json!({ "type": "Polygon", "coordinates": [ring] })
+    } else {
+        Value::Null
+    };
+
+    json!({
+        "type": "Feature",
+        "id": room.id,
+        "geometry": geometry,
+        "properties": {
+            "area": room.area,
+            "name_hint": room.name_hint,
+            "room_type": room_type,
+            "confidence": confidence,
+        },
+    })
+}
+
+/// GeoJSON `FeatureCollection` with one feature per room
+pub fn feature_collection(rooms: &[Room]) -> Value {
+    json!({
+        "type": "FeatureCollection",
+        "features": rooms.iter().map(|room| room_feature(room, None, None)).collect::<Vec<_>>(),
+    })
+}
+
+/// Render a detection result in the requested format; errors are always plain JSON
+pub fn format_rooms_response(
+    format: OutputFormat,
+    result: Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let Json(response) = result?;
+    Ok(match format {
+        OutputFormat::Json => Json(response).into_response(),
+        OutputFormat::Geojson => (
+            [(header::CONTENT_TYPE, "application/geo+json")],
+            Json(feature_collection(&response.rooms)),
+        )
+            .into_response(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+
+    fn room(id: usize, points: &[(f64, f64)]) -> Room {
+        Room {
+            id,
+            bounding_box: [0.0, 0.0, 10.0, 10.0],
+            area: 100.0,
+            name_hint: "Room".to_string(),
+            points: points.iter().map(|&(x, y)| Point { x, y }).collect(),
+            ..Room::default()
+        }
+    }
+
+    #[test]
+    fn test_feature_collection_has_closed_rings() {
+        let rooms = vec![
+            room(0, &[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]),
+            // Already closed; must not be closed twice
+            room(1, &[(10.0, 0.0), (20.0, 0.0), (20.0, 10.0), (10.0, 0.0)]),
+        ];
+
+        let collection = feature_collection(&rooms);
+
+        assert_eq!(collection["type"], "FeatureCollection");
+        let features = collection["features"].as_array().unwrap();
+        assert_eq!(features.len(), rooms.len());
+        for feature in features {
+            assert_eq!(feature["geometry"]["type"], "Polygon");
+            let ring = feature["geometry"]["coordinates"][0].as_array().unwrap();
+            assert!(ring.len() >= 4);
+            assert_eq!(ring.first(), ring.last());
+            assert_eq!(feature["properties"]["area"], 100.0);
+            assert_eq!(feature["properties"]["name_hint"], "Room");
+            assert!(feature["properties"]["room_type"].is_null());
+        }
+        assert_eq!(features[0]["geometry"]["coordinates"][0].as_array().unwrap().len(), 5);
+        assert_eq!(features[1]["geometry"]["coordinates"][0].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_degenerate_room_has_null_geometry() {
+        let feature = room_feature(&room(0, &[(0.0, 0.0), (1.0, 1.0)]), Some("bedroom"), Some(0.9));
+
+        assert!(feature["geometry"].is_null());
+        assert_eq!(feature["properties"]["room_type"], "bedroom");
+        assert_eq!(feature["properties"]["confidence"], 0.9);
+    }
+}
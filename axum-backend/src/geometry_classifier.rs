@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet};
+
+use vision_classifier::RoomClassification;
+
+use crate::room_detector::{compute_adjacency, RoomKind};
+use crate::Room;
+
+/// Long side over short side at which a room reads as a hallway
+const HALLWAY_ELONGATION: f64 = 3.0;
+/// Rooms below this fraction of the largest room's area are bathrooms or closets
+const SMALL_ROOM_RATIO: f64 = 0.2;
+/// Small rooms below this fraction that don't open onto a bedroom are closets
+const CLOSET_RATIO: f64 = 0.06;
+
+/// Guess room types from geometry alone, for inputs with no image to show a vision model
+///
+/// Priors, in order: long thin rooms are hallways, the largest remaining room is the
+/// living room, mid-sized rooms are bedrooms, and small rooms are bathrooms (when next to
+/// a bedroom) or closets. Sizes are relative to the largest room, so drawing units don't
+/// matter. Confidence grows with how far a room is past the thresholds that typed it.
+/// The footprint is not classified.
+pub fn classify_by_geometry(rooms: &[Room]) -> Vec<RoomClassification> {
+    let rooms: Vec<&Room> = rooms.iter().filter(|r| r.kind != RoomKind::Footprint).collect();
+    let largest_area = rooms.iter().map(|r| r.area).fold(0.0, f64::max);
+    if largest_area <= 0.0 {
+        return Vec::new();
+    }
+
+    let owned: Vec<Room> = rooms.iter().map(|&r| r.clone()).collect();
+    let mut neighbors: HashMap<usize, HashSet<usize>> = HashMap::new();
+    for (a, b) in compute_adjacency(&owned, &[]) {
+        neighbors.entry(a).or_default().insert(b);
+        neighbors.entry(b).or_default().insert(a);
+    }
+    let neighbor_count = |id: usize| neighbors.get(&id).map_or(0, HashSet::len);
+
+    let mut types: HashMap<usize, (&str, f64, Vec<String>)> = HashMap::new();
+
+    for room in &rooms {
+        let elongation = elongation(&room.bounding_box);
+        if elongation >= HALLWAY_ELONGATION {
+            let strength = ((elongation - HALLWAY_ELONGATION) / HALLWAY_ELONGATION).min(1.0);
+            types.insert(room.id, ("hallway", 0.5 + 0.4 * strength, vec![format!("elongation {:.1}", elongation)]));
+        }
+    }
+
+    // Largest room that isn't a hallway; more confident when it clearly dominates and is the hub
+    let mut by_area: Vec<&&Room> = rooms.iter().filter(|r| !types.contains_key(&r.id)).collect();
+    by_area.sort_by(|a, b| b.area.total_cmp(&a.area));
+    if let Some(living) = by_area.first() {
+        let dominance = by_area.get(1).map_or(1.0, |second| (living.area / second.area - 1.0).min(1.0));
+        let most_connected = rooms.iter().all(|r| neighbor_count(r.id) <= neighbor_count(living.id));
+        let mut features = vec!["largest room".to_string()];
+        if most_connected && neighbor_count(living.id) > 0 {
+            features.push("most neighbors".to_string());
+        }
+        let confidence = 0.5 + 0.25 * dominance + if most_connected { 0.15 } else { 0.0 };
+        types.insert(living.id, ("living_room", confidence, features));
+    }
+
+    for room in &rooms {
+        let ratio = room.area / largest_area;
+        if types.contains_key(&room.id) || ratio < SMALL_ROOM_RATIO {
+            continue;
+        }
+        // Squarer rooms make likelier bedrooms
+        let squareness = (HALLWAY_ELONGATION - elongation(&room.bounding_box)) / (HALLWAY_ELONGATION - 1.0);
+        types.insert(room.id, ("bedroom", 0.4 + 0.3 * squareness, vec![format!("{:.0}% of largest room", ratio * 100.0)]));
+    }
+
+    let bedrooms: HashSet<usize> = types
+        .iter()
+        .filter(|(_, (room_type, _, _))| *room_type == "bedroom")
+        .map(|(&id, _)| id)
+        .collect();
+    for room in &rooms {
+        if types.contains_key(&room.id) {
+            continue;
+        }
+        let ratio = room.area / largest_area;
+        let smallness = 1.0 - ratio / SMALL_ROOM_RATIO;
+        let next_to_bedroom = neighbors.get(&room.id).is_some_and(|n| !n.is_disjoint(&bedrooms));
+        let mut features = vec![format!("{:.0}% of largest room", ratio * 100.0)];
+        let classified = if next_to_bedroom {
+            features.push("adjacent to bedroom".to_string());
+            ("bathroom", 0.6 + 0.25 * smallness, features)
+        } else if ratio < CLOSET_RATIO {
+            ("closet", 0.45 + 0.25 * (1.0 - ratio / CLOSET_RATIO), features)
+        } else {
+            ("bathroom", 0.4 + 0.2 * smallness, features)
+        };
+        types.insert(room.id, classified);
+    }
+
+    rooms
+        .iter()
+        .filter_map(|room| {
+            let (room_type, confidence, features) = types.remove(&room.id)?;
+            Some(RoomClassification {
+                room_id: room.id,
+                room_type: room_type.to_string(),
+                confidence: confidence.clamp(0.0, 1.0),
+                description: format!("Guessed from geometry: {}", features.join(", ")),
+                features,
+                center: None,
+            })
+        })
+        .collect()
+}
+
+/// Long side over short side of a `[min_x, min_y, max_x, max_y]` box
+fn elongation(bbox: &[f64; 4]) -> f64 {
+    let width = bbox[2] - bbox[0];
+    let height = bbox[3] - bbox[1];
+    let short = width.min(height);
+    if short <= 0.0 {
+        return f64::INFINITY;
+    }
+    width.max(height) / short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+
+    fn room(id: usize, [min_x, min_y, max_x, max_y]: [f64; 4]) -> Room {
+        Room {
+            id,
+            bounding_box: [min_x, min_y, max_x, max_y],
+            area: (max_x - min_x) * (max_y - min_y),
+            name_hint: "Room".to_string(),
+            points: vec![
+                Point { x: min_x, y: min_y },
+                Point { x: max_x, y: min_y },
+                Point { x: max_x, y: max_y },
+                Point { x: min_x, y: max_y },
+            ],
+            ..Room::default()
+        }
+    }
+
+    fn type_of(classifications: &[RoomClassification], id: usize) -> (&str, f64) {
+        let c = classifications.iter().find(|c| c.room_id == id).unwrap();
+        (c.room_type.as_str(), c.confidence)
+    }
+
+    /// Living room and bedroom above a full-width hallway, with a tiny room off the bedroom
+    fn apartment() -> Vec<Room> {
+        vec![
+            room(0, [0.0, 0.0, 600.0, 400.0]),
+            room(1, [600.0, 0.0, 900.0, 300.0]),
+            room(2, [900.0, 0.0, 980.0, 80.0]),
+            room(3, [0.0, 400.0, 1000.0, 500.0]),
+        ]
+    }
+
+    #[test]
+    fn test_long_thin_room_is_hallway() {
+        let classifications = classify_by_geometry(&apartment());
+
+        assert_eq!(classifications.len(), 4);
+        assert_eq!(type_of(&classifications, 3).0, "hallway");
+        assert_eq!(type_of(&classifications, 0).0, "living_room");
+        assert_eq!(type_of(&classifications, 1).0, "bedroom");
+        assert!(classifications.iter().all(|c| c.confidence > 0.0 && c.confidence <= 1.0));
+    }
+
+    #[test]
+    fn test_tiny_room_is_bathroom_or_closet() {
+        let classifications = classify_by_geometry(&apartment());
+        assert!(["bathroom", "closet"].contains(&type_of(&classifications, 2).0));
+
+        // Nowhere near a bedroom, a tiny room reads as a closet
+        let rooms = vec![room(0, [0.0, 0.0, 600.0, 400.0]), room(1, [2000.0, 0.0, 2050.0, 50.0])];
+        assert_eq!(type_of(&classify_by_geometry(&rooms), 1).0, "closet");
+    }
+
+    #[test]
+    fn test_confidence_tracks_how_strongly_rule_matched() {
+        let rooms = vec![
+            room(0, [0.0, 0.0, 600.0, 400.0]),
+            room(1, [0.0, 400.0, 350.0, 500.0]),
+            room(2, [0.0, 500.0, 1000.0, 600.0]),
+        ];
+        let classifications = classify_by_geometry(&rooms);
+
+        let (slight, slight_confidence) = type_of(&classifications, 1);
+        let (strong, strong_confidence) = type_of(&classifications, 2);
+        assert_eq!((slight, strong), ("hallway", "hallway"));
+        assert!(strong_confidence > slight_confidence);
+    }
+
+    #[test]
+    fn test_footprint_is_not_classified() {
+        let mut footprint = room(9, [0.0, 0.0, 1000.0, 500.0]);
+        footprint.kind = RoomKind::Footprint;
+        let mut rooms = apartment();
+        rooms.push(footprint);
+
+        let classifications = classify_by_geometry(&rooms);
+        assert!(classifications.iter().all(|c| c.room_id != 9));
+        assert_eq!(type_of(&classifications, 0).0, "living_room");
+    }
+}
@@ -0,0 +1,217 @@
+//! End-to-end detection regression tests against committed golden results
+//!
+//! Each detection endpoint runs over the `test-data/` fixtures and its room count and
+//! bounding boxes are compared with `test-data/golden/<algorithm>/<fixture>.json`.
+//! Run with `UPDATE_GOLDEN=1` to rewrite the golden files after an intended change.
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tower::ServiceExt;
+
+/// Largest bounding-box coordinate change, in the algorithm's output units, that isn't drift
+const BBOX_TOLERANCE: f64 = 1.0;
+
+const LINE_FIXTURES: [&str; 5] = [
+    "test-3-rooms-horizontal",
+    "test-apartment-6-rooms",
+    "test-corridor",
+    "test-grid-4-rooms",
+    "test-l-shaped",
+];
+
+const IMAGE_FIXTURES: [&str; 5] = [
+    "test_blueprint_001",
+    "test_blueprint_002",
+    "test_blueprint_003",
+    "test_blueprint_004",
+    "test_blueprint_005",
+];
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Golden {
+    total_rooms: usize,
+    bounding_boxes: Vec<[f64; 4]>,
+}
+
+fn test_data() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../test-data")
+}
+
+fn golden_path(algorithm: &str, fixture: &str) -> PathBuf {
+    test_data().join("golden").join(algorithm).join(format!("{}.json", fixture))
+}
+
+fn updating() -> bool {
+    std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1")
+}
+
+fn line_body(fixture: &str, extra: serde_json::Value) -> serde_json::Value {
+    let lines: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(test_data().join(format!("{}.json", fixture))).unwrap()).unwrap();
+    let mut body = serde_json::json!({ "lines": lines });
+    body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+    body
+}
+
+fn image_body(fixture: &str, extra: serde_json::Value) -> serde_json::Value {
+    let bytes = std::fs::read(test_data().join(format!("{}.png", fixture))).unwrap();
+    let mut body = serde_json::json!({ "image": base64::engine::general_purpose::STANDARD.encode(bytes) });
+    body.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+    body
+}
+
+async fn detect(route: &str, body: serde_json::Value) -> Golden {
+    let request = Request::post(route)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = crate::create_app().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK, "{} failed", route);
+
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let bounding_boxes = json["rooms"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|room| serde_json::from_value(room["bounding_box"].clone()).unwrap())
+        .collect();
+    Golden {
+        total_rooms: json["total_rooms"].as_u64().unwrap() as usize,
+        bounding_boxes,
+    }
+}
+
+/// How `actual` drifted from `expected`, if beyond tolerance
+fn drift(expected: &Golden, actual: &Golden) -> Option<String> {
+    if expected.total_rooms != actual.total_rooms || expected.bounding_boxes.len() != actual.bounding_boxes.len() {
+        return Some(format!("{} rooms, golden has {}", actual.total_rooms, expected.total_rooms));
+    }
+    expected
+        .bounding_boxes
+        .iter()
+        .zip(&actual.bounding_boxes)
+        .enumerate()
+        .find(|(_, (e, a))| e.iter().zip(a.iter()).any(|(e, a)| (e - a).abs() > BBOX_TOLERANCE))
+        .map(|(i, (e, a))| format!("room {} bounding box {:?}, golden has {:?}", i, a, e))
+}
+
+/// Golden file contents, one bounding box per line so diffs show which room moved
+fn golden_json(golden: &Golden) -> String {
+    let boxes: Vec<String> = golden
+        .bounding_boxes
+        .iter()
+        .map(|bbox| format!("\n    {}", serde_json::to_string(bbox).unwrap()))
+        .collect();
+    let boxes = if boxes.is_empty() { String::new() } else { boxes.join(",") + "\n  " };
+    format!(
+        "{{\n  \"total_rooms\": {},\n  \"bounding_boxes\": [{}]\n}}\n",
+        golden.total_rooms, boxes
+    )
+}
+
+/// Compare (or with `UPDATE_GOLDEN=1`, rewrite) the golden result for each fixture
+async fn check_golden(algorithm: &str, route: &str, cases: Vec<(&str, serde_json::Value)>) {
+    let mut failures = Vec::new();
+    for (fixture, body) in cases {
+        let actual = detect(route, body).await;
+        let path = golden_path(algorithm, fixture);
+
+        if updating() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, golden_json(&actual)).unwrap();
+            continue;
+        }
+        let Ok(expected) = std::fs::read_to_string(&path) else {
+            failures.push(format!("{}: no golden file at {}", fixture, path.display()));
+            continue;
+        };
+        let expected: Golden = serde_json::from_str(&expected).unwrap();
+        if let Some(drift) = drift(&expected, &actual) {
+            failures.push(format!("{}: {}", fixture, drift));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} drifted from its golden results (rerun with UPDATE_GOLDEN=1 if intended):\n{}",
+        algorithm,
+        failures.join("\n")
+    );
+}
+
+fn line_cases(extra: serde_json::Value) -> Vec<(&'static str, serde_json::Value)> {
+    LINE_FIXTURES.iter().map(|&f| (f, line_body(f, extra.clone()))).collect()
+}
+
+fn image_cases(extra: serde_json::Value) -> Vec<(&'static str, serde_json::Value)> {
+    IMAGE_FIXTURES.iter().map(|&f| (f, image_body(f, extra.clone()))).collect()
+}
+
+// test-apartment-6-rooms records more than its six rooms in both graph goldens. Its door
+// gaps meet where the wall crossings would be, at (200, 200) and (400, 200), and the
+// virtual door edges bridging them close a small 40x40 face at each junction, so faces
+// finds 8. DFS additionally keeps cycles that run around several rooms at once and finds 24.
+#[tokio::test]
+async fn golden_graph_faces() {
+    check_golden("graph-faces", "/api/detect", line_cases(serde_json::json!({ "cycle_method": "faces" }))).await;
+}
+
+#[tokio::test]
+async fn golden_graph_dfs() {
+    check_golden("graph-dfs", "/api/detect", line_cases(serde_json::json!({ "cycle_method": "dfs" }))).await;
+}
+
+#[tokio::test]
+async fn golden_simple() {
+    check_golden("simple", "/api/detect/simple", line_cases(serde_json::json!({}))).await;
+}
+
+#[tokio::test]
+async fn golden_connected_components() {
+    check_golden("connected-components", "/api/detect/connected-components", image_cases(serde_json::json!({}))).await;
+}
+
+#[tokio::test]
+async fn golden_connected_components_enhanced() {
+    check_golden(
+        "connected-components-enhanced",
+        "/api/detect/connected-components-enhanced",
+        image_cases(serde_json::json!({})),
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn golden_rust_floodfill() {
+    let extra = serde_json::json!({ "threshold": 200, "min_area": 200, "max_area_ratio": 0.3 });
+    check_golden("rust-floodfill", "/api/detect/rust-floodfill", image_cases(extra)).await;
+}
+
+#[test]
+fn test_drift_tolerates_small_bbox_changes() {
+    let golden = Golden {
+        total_rooms: 1,
+        bounding_boxes: vec![[0.0, 0.0, 100.0, 100.0]],
+    };
+    let nudged = Golden {
+        total_rooms: 1,
+        bounding_boxes: vec![[0.5, 0.0, 100.0, 100.5]],
+    };
+    let moved = Golden {
+        total_rooms: 1,
+        bounding_boxes: vec![[0.0, 0.0, 110.0, 100.0]],
+    };
+    let extra_room = Golden {
+        total_rooms: 2,
+        bounding_boxes: vec![[0.0, 0.0, 100.0, 100.0], [100.0, 0.0, 200.0, 100.0]],
+    };
+
+    assert_eq!(drift(&golden, &nudged), None);
+    assert!(drift(&golden, &moved).unwrap().contains("room 0"));
+    assert!(drift(&golden, &extra_room).unwrap().contains("2 rooms"));
+}
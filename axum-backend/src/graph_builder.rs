@@ -1,6 +1,9 @@
 use crate::{Line, Point, PointKey};
+use petgraph::algo::connected_components;
 use petgraph::graph::{NodeIndex, UnGraph};
-use std::collections::HashMap;
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 
 // Use UnGraph (undirected graph) for floorplan lines since walls connect points bidirectionally
 pub type FloorplanGraph = UnGraph<Point, EdgeType>;
@@ -42,8 +45,11 @@ pub fn build_graph_with_door_threshold(lines: &[Line], door_threshold: f64) -> F
     let mut graph = UnGraph::new_undirected();
     let mut point_to_node: HashMap<PointKey, NodeIndex> = HashMap::new();
 
+    // Phase 0: Split walls at T- and X-junctions so they share nodes
+    let lines = split_at_junctions(lines);
+
     // Phase 1: Add all real wall segments
-    for line in lines {
+    for line in &lines {
         // Skip degenerate lines (start == end)
         if line.start == line.end {
             continue;
@@ -118,6 +124,468 @@ fn bridge_door_gaps(
     }
 }
 
+/// A `FloorplanGraph` kept in step with a changing set of walls, so each edit touches only
+/// the walls and nodes around it. After any sequence of edits the graph matches what
+/// `build_graph_with_door_threshold` builds from the current lines.
+pub struct IncrementalGraph {
+    graph: FloorplanGraph,
+    door_threshold: f64,
+    lines: Vec<Line>,
+    /// Wall pieces each entry of `lines` contributes after junction splitting
+    pieces: Vec<Vec<Line>>,
+    point_to_node: HashMap<PointKey, NodeIndex>,
+}
+
+impl IncrementalGraph {
+    /// An empty graph; door gaps up to `door_threshold` are bridged (0.0 = off)
+    pub fn new(door_threshold: f64) -> Self {
+        Self {
+            graph: FloorplanGraph::default(),
+            door_threshold,
+            lines: Vec::new(),
+            pieces: Vec::new(),
+            point_to_node: HashMap::new(),
+        }
+    }
+
+    pub fn graph(&self) -> &FloorplanGraph {
+        &self.graph
+    }
+
+    pub fn add_line(&mut self, line: &Line) {
+        let mut touched = HashSet::new();
+
+        // Existing walls the new one lands on or crosses get re-split
+        let affected: Vec<usize> = (0..self.lines.len())
+            .filter(|&i| !junction_points(&self.lines[i], line).is_empty())
+            .collect();
+        self.lines.push(line.clone());
+        self.pieces.push(Vec::new());
+
+        for i in affected.into_iter().chain(std::iter::once(self.lines.len() - 1)) {
+            self.resplit(i, &mut touched);
+        }
+        self.refresh(&touched);
+    }
+
+    /// Remove one wall equal to `line`; false if there is none
+    pub fn remove_line(&mut self, line: &Line) -> bool {
+        let Some(index) = self.lines.iter().position(|l| l.start == line.start && l.end == line.end) else {
+            return false;
+        };
+
+        let mut touched = HashSet::new();
+        for piece in self.pieces.remove(index) {
+            self.remove_wall(&piece, &mut touched);
+        }
+        let removed = self.lines.remove(index);
+
+        // Walls it used to split are joined back up
+        let affected: Vec<usize> = (0..self.lines.len())
+            .filter(|&i| !junction_points(&self.lines[i], &removed).is_empty())
+            .collect();
+        for i in affected {
+            self.resplit(i, &mut touched);
+        }
+        self.refresh(&touched);
+        true
+    }
+
+    /// Replace the pieces of `lines[index]` with a fresh split against every other line
+    fn resplit(&mut self, index: usize, touched: &mut HashSet<PointKey>) {
+        for piece in std::mem::take(&mut self.pieces[index]) {
+            self.remove_wall(&piece, touched);
+        }
+
+        let others = self.lines.iter().enumerate().filter(|&(j, _)| j != index).map(|(_, other)| other);
+        let pieces = split_line(&self.lines[index], others);
+        for piece in &pieces {
+            self.add_wall(piece, touched);
+        }
+        self.pieces[index] = pieces;
+    }
+
+    fn add_wall(&mut self, piece: &Line, touched: &mut HashSet<PointKey>) {
+        // Skip degenerate lines (start == end), as a full build does
+        if piece.start == piece.end {
+            return;
+        }
+        let start = self.node_for(&piece.start);
+        let end = self.node_for(&piece.end);
+        self.graph.add_edge(start, end, EdgeType::Wall(piece.clone()));
+        touched.extend([PointKey::from(&piece.start), PointKey::from(&piece.end)]);
+    }
+
+    fn remove_wall(&mut self, piece: &Line, touched: &mut HashSet<PointKey>) {
+        let start_key = PointKey::from(&piece.start);
+        let end_key = PointKey::from(&piece.end);
+        let (Some(&start), Some(&end)) = (self.point_to_node.get(&start_key), self.point_to_node.get(&end_key)) else {
+            return;
+        };
+        let wall = self
+            .graph
+            .edges_connecting(start, end)
+            .find(|edge| !edge.weight().is_virtual())
+            .map(|edge| edge.id());
+        if let Some(wall) = wall {
+            self.graph.remove_edge(wall);
+        }
+        touched.extend([start_key, end_key]);
+    }
+
+    fn node_for(&mut self, point: &Point) -> NodeIndex {
+        let graph = &mut self.graph;
+        *self
+            .point_to_node
+            .entry(PointKey::from(point))
+            .or_insert_with(|| graph.add_node(point.clone()))
+    }
+
+    /// Drop nodes left without walls, then redo door bridging around the touched nodes
+    fn refresh(&mut self, touched: &HashSet<PointKey>) {
+        for key in touched {
+            let Some(&node) = self.point_to_node.get(key) else {
+                continue;
+            };
+            if self.graph.edges(node).any(|edge| !edge.weight().is_virtual()) {
+                continue;
+            }
+
+            // Removing a node moves the last node into its index
+            let last = NodeIndex::new(self.graph.node_count() - 1);
+            self.graph.remove_node(node);
+            self.point_to_node.remove(key);
+            if node != last {
+                self.point_to_node.insert(PointKey::from(&self.graph[node]), node);
+            }
+        }
+
+        if self.door_threshold <= 0.0 {
+            return;
+        }
+        for key in touched {
+            if let Some(&node) = self.point_to_node.get(key) {
+                self.bridge_doors_from(node);
+            }
+        }
+    }
+
+    /// Make `node`'s door edges match what `bridge_door_gaps` would add for it
+    fn bridge_doors_from(&mut self, node: NodeIndex) {
+        let others: Vec<NodeIndex> = self.graph.node_indices().filter(|&other| other != node).collect();
+        for other in others {
+            let walled = self
+                .graph
+                .edges_connecting(node, other)
+                .any(|edge| !edge.weight().is_virtual());
+            let distance = self.graph[node].distance_to(&self.graph[other]);
+            let wanted = !walled && distance > 0.0 && distance <= self.door_threshold;
+
+            let door = self
+                .graph
+                .edges_connecting(node, other)
+                .find(|edge| edge.weight().is_virtual())
+                .map(|edge| edge.id());
+            match (wanted, door) {
+                (true, None) => {
+                    let virtual_line = Line {
+                        start: self.graph[node].clone(),
+                        end: self.graph[other].clone(),
+                        is_load_bearing: false,
+                    };
+                    self.graph.add_edge(node, other, EdgeType::VirtualDoor(virtual_line));
+                }
+                (false, Some(door)) => {
+                    self.graph.remove_edge(door);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Connectivity counts for a built graph, to explain why rooms were or were not found
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphDiagnostics {
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub connected_components: usize,
+    /// Nodes with a single edge, i.e. wall ends that close no cycle
+    pub dangling_nodes: usize,
+    pub door_gaps_bridged: usize,
+}
+
+pub fn diagnose(graph: &FloorplanGraph) -> GraphDiagnostics {
+    GraphDiagnostics {
+        node_count: graph.node_count(),
+        edge_count: graph.edge_count(),
+        connected_components: connected_components(graph),
+        dangling_nodes: graph.node_indices().filter(|&node| graph.edges(node).count() == 1).count(),
+        door_gaps_bridged: graph.edge_weights().filter(|edge| edge.is_virtual()).count(),
+    }
+}
+
+/// Distance within which a point counts as lying on a wall
+const JUNCTION_TOLERANCE: f64 = 0.01;
+
+/// Split walls where another wall meets or crosses them mid-segment
+///
+/// An endpoint lying on the interior of another segment (T-junction) splits that
+/// segment at the endpoint; two segments crossing in both interiors (X-junction)
+/// are both split at the intersection.
+pub fn split_at_junctions(lines: &[Line]) -> Vec<Line> {
+    lines
+        .iter()
+        .enumerate()
+        .flat_map(|(i, line)| {
+            let others = lines.iter().enumerate().filter(move |&(j, _)| j != i).map(|(_, other)| other);
+            split_line(line, others)
+        })
+        .collect()
+}
+
+/// Pieces of one wall after splitting it at its junctions with `others`
+fn split_line<'a>(line: &Line, others: impl IntoIterator<Item = &'a Line>) -> Vec<Line> {
+    let mut points: Vec<(f64, Point)> = others.into_iter().flat_map(|other| junction_points(line, other)).collect();
+    points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut pieces = Vec::new();
+    let mut start = line.start.clone();
+    for (_, p) in points.into_iter().chain(std::iter::once((1.0, line.end.clone()))) {
+        if p == start {
+            continue;
+        }
+        pieces.push(Line {
+            start: start.clone(),
+            end: p.clone(),
+            is_load_bearing: line.is_load_bearing,
+        });
+        start = p;
+    }
+    pieces
+}
+
+/// Where `other` splits `line`, as (parameter along `line`, split point)
+fn junction_points(line: &Line, other: &Line) -> Vec<(f64, Point)> {
+    let len = line.start.distance_to(&line.end);
+    let other_len = other.start.distance_to(&other.end);
+    if len < JUNCTION_TOLERANCE {
+        return Vec::new();
+    }
+
+    // T-junction: other's endpoint on this line's interior
+    let mut points: Vec<(f64, Point)> = [&other.start, &other.end]
+        .into_iter()
+        .filter_map(|p| interior_junction(line, p).map(|t| (t, p.clone())))
+        .collect();
+
+    // X-junction: interiors cross
+    if other_len >= JUNCTION_TOLERANCE {
+        let interior = JUNCTION_TOLERANCE / len;
+        let other_interior = JUNCTION_TOLERANCE / other_len;
+        if let Some((t, u)) = segment_intersection(line, other) {
+            if t > interior && t < 1.0 - interior && u > other_interior && u < 1.0 - other_interior {
+                points.push((t, point_along(line, t)));
+            }
+        }
+    }
+
+    points
+}
+
+/// Parameter along `line` at which `p` touches its interior (a T-junction), if it does
+fn interior_junction(line: &Line, p: &Point) -> Option<f64> {
+    let len = line.start.distance_to(&line.end);
+    if len < JUNCTION_TOLERANCE {
+        return None;
+    }
+    let interior = JUNCTION_TOLERANCE / len;
+
+    let t = project_onto_segment(line, p);
+    (t > interior && t < 1.0 - interior && point_along(line, t).distance_to(p) <= JUNCTION_TOLERANCE).then_some(t)
+}
+
+/// Parameter of the projection of `p` onto the segment's supporting line
+fn project_onto_segment(line: &Line, p: &Point) -> f64 {
+    let dx = line.end.x - line.start.x;
+    let dy = line.end.y - line.start.y;
+    ((p.x - line.start.x) * dx + (p.y - line.start.y) * dy) / (dx * dx + dy * dy)
+}
+
+fn point_along(line: &Line, t: f64) -> Point {
+    Point {
+        x: line.start.x + t * (line.end.x - line.start.x),
+        y: line.start.y + t * (line.end.y - line.start.y),
+    }
+}
+
+/// Intersection parameters (t along `a`, u along `b`) of two segments, if they cross
+fn segment_intersection(a: &Line, b: &Line) -> Option<(f64, f64)> {
+    let ends = |line: &Line| ((line.start.x, line.start.y), (line.end.x, line.end.y));
+    segment_geometry::segment_intersection(ends(a), ends(b))
+}
+
+/// Drop walls shorter than `min_length`, such as vectorization speckle. 0.0 keeps every line.
+pub fn drop_short_walls(lines: &[Line], min_length: f64) -> Vec<Line> {
+    if min_length <= 0.0 {
+        return lines.to_vec();
+    }
+
+    lines
+        .iter()
+        .filter(|line| line.start.distance_to(&line.end) >= min_length)
+        .cloned()
+        .collect()
+}
+
+/// Collapse near-coincident endpoints so walls that visually meet share a graph node
+///
+/// Endpoints within `tolerance` of a cluster's centroid join that cluster, and every
+/// line endpoint is rewritten to its cluster centroid. A tolerance of 0.0 leaves lines unchanged.
+pub fn snap_endpoints(lines: &[Line], tolerance: f64) -> Vec<Line> {
+    if tolerance <= 0.0 {
+        return lines.to_vec();
+    }
+
+    // (centroid, member count) per cluster
+    let mut clusters: Vec<(Point, usize)> = Vec::new();
+    let mut assignment = Vec::with_capacity(lines.len() * 2);
+
+    for point in lines.iter().flat_map(|line| [&line.start, &line.end]) {
+        let nearest = clusters
+            .iter()
+            .enumerate()
+            .map(|(idx, (centroid, _))| (idx, centroid.distance_to(point)))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        match nearest {
+            Some((idx, _)) => {
+                let (centroid, count) = &mut clusters[idx];
+                let n = *count as f64;
+                centroid.x = (centroid.x * n + point.x) / (n + 1.0);
+                centroid.y = (centroid.y * n + point.y) / (n + 1.0);
+                *count += 1;
+                assignment.push(idx);
+            }
+            None => {
+                clusters.push((point.clone(), 1));
+                assignment.push(clusters.len() - 1);
+            }
+        }
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| Line {
+            start: clusters[assignment[2 * i]].0.clone(),
+            end: clusters[assignment[2 * i + 1]].0.clone(),
+            is_load_bearing: line.is_load_bearing,
+        })
+        .collect()
+}
+
+/// Gaps at or above this size are treated as open-plan space, not doorways
+const MAX_DOOR_GAP: f64 = 100.0;
+
+/// Maximum angle (as sine) between two walls considered collinear
+const COLLINEAR_SIN_TOLERANCE: f64 = 0.035;
+
+/// Maximum perpendicular offset between two walls considered collinear
+const COLLINEAR_OFFSET_TOLERANCE: f64 = 2.0;
+
+/// Estimate a door gap threshold from the gaps between collinear walls
+///
+/// Gaps under 100 units are split into "noise" and "doorway" clusters with a
+/// 1D two-means pass; the threshold is the 75th percentile of the doorway cluster.
+/// Returns 0.0 (no gap bridging) when there are no candidate gaps.
+pub fn estimate_door_threshold(lines: &[Line]) -> f64 {
+    let mut gaps = collinear_gaps(lines);
+    gaps.retain(|g| *g > f64::EPSILON && *g < MAX_DOOR_GAP);
+    if gaps.is_empty() {
+        return 0.0;
+    }
+    gaps.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let doorways = doorway_cluster(&gaps);
+    percentile(doorways, 0.75)
+}
+
+/// Gaps along the shared axis between every pair of collinear, non-overlapping walls
+fn collinear_gaps(lines: &[Line]) -> Vec<f64> {
+    let mut gaps = Vec::new();
+
+    for i in 0..lines.len() {
+        let a = &lines[i];
+        let (dx, dy) = (a.end.x - a.start.x, a.end.y - a.start.y);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len < f64::EPSILON {
+            continue;
+        }
+        let (ux, uy) = (dx / len, dy / len);
+
+        for b in &lines[i + 1..] {
+            let (bx, by) = (b.end.x - b.start.x, b.end.y - b.start.y);
+            let b_len = (bx * bx + by * by).sqrt();
+            if b_len < f64::EPSILON {
+                continue;
+            }
+
+            // Parallel and on the same infinite line
+            let sin = (ux * by - uy * bx).abs() / b_len;
+            let offset = (ux * (b.start.y - a.start.y) - uy * (b.start.x - a.start.x)).abs();
+            if sin > COLLINEAR_SIN_TOLERANCE || offset > COLLINEAR_OFFSET_TOLERANCE {
+                continue;
+            }
+
+            // Project both walls onto a's axis and measure the space between them
+            let project = |p: &Point| (p.x - a.start.x) * ux + (p.y - a.start.y) * uy;
+            let (b0, b1) = (project(&b.start), project(&b.end));
+            let gap = (b0.min(b1) - len).max(-b0.max(b1));
+            if gap > 0.0 {
+                gaps.push(gap);
+            }
+        }
+    }
+
+    gaps
+}
+
+/// Upper cluster of a sorted gap list after a 1D two-means split
+///
+/// Falls back to all gaps when the clusters aren't clearly separated.
+fn doorway_cluster(sorted_gaps: &[f64]) -> &[f64] {
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+
+    // Best split minimizing within-cluster squared error (optimal for 1D two-means)
+    let mut best: Option<(usize, f64)> = None;
+    for split in 1..sorted_gaps.len() {
+        let (low, high) = sorted_gaps.split_at(split);
+        let (low_mean, high_mean) = (mean(low), mean(high));
+        let error: f64 = low.iter().map(|g| (g - low_mean).powi(2)).sum::<f64>()
+            + high.iter().map(|g| (g - high_mean).powi(2)).sum::<f64>();
+        if !matches!(best, Some((_, e)) if e <= error) {
+            best = Some((split, error));
+        }
+    }
+
+    match best {
+        Some((split, _)) if mean(&sorted_gaps[split..]) >= 2.0 * mean(&sorted_gaps[..split]) => {
+            &sorted_gaps[split..]
+        }
+        _ => sorted_gaps,
+    }
+}
+
+/// Linearly interpolated percentile of a sorted, non-empty slice
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,4 +648,232 @@ mod tests {
         assert_eq!(graph.node_count(), 0);
         assert_eq!(graph.edge_count(), 0);
     }
+
+    fn wall(x1: f64, y1: f64, x2: f64, y2: f64) -> Line {
+        Line {
+            start: Point { x: x1, y: y1 },
+            end: Point { x: x2, y: y2 },
+            is_load_bearing: false,
+        }
+    }
+
+    /// Same layout as the test-floorplan `apartment_with_doors` case (two 40-unit doorways)
+    fn apartment_with_doors() -> Vec<Line> {
+        vec![
+            wall(0.0, 0.0, 300.0, 0.0),
+            wall(300.0, 0.0, 300.0, 80.0),
+            wall(300.0, 120.0, 300.0, 200.0),
+            wall(300.0, 200.0, 0.0, 200.0),
+            wall(0.0, 200.0, 0.0, 0.0),
+            wall(300.0, 0.0, 500.0, 0.0),
+            wall(500.0, 0.0, 500.0, 200.0),
+            wall(500.0, 200.0, 300.0, 200.0),
+            wall(0.0, 240.0, 0.0, 350.0),
+            wall(0.0, 350.0, 150.0, 350.0),
+            wall(150.0, 350.0, 150.0, 200.0),
+            wall(150.0, 200.0, 0.0, 200.0),
+        ]
+    }
+
+    #[test]
+    fn test_estimate_door_threshold_apartment() {
+        let threshold = estimate_door_threshold(&apartment_with_doors());
+        assert!((threshold - 40.0).abs() < 5.0, "estimated {}", threshold);
+    }
+
+    #[test]
+    fn test_estimate_door_threshold_ignores_noise() {
+        let mut lines = apartment_with_doors();
+        // Vectorization jitter: 1-2 unit breaks along the outer walls
+        lines.push(wall(500.0, 201.5, 500.0, 300.0));
+        lines.push(wall(0.0, 351.0, 0.0, 400.0));
+
+        let threshold = estimate_door_threshold(&lines);
+        assert!((threshold - 40.0).abs() < 5.0, "estimated {}", threshold);
+    }
+
+    #[test]
+    fn test_estimate_door_threshold_no_gaps() {
+        let lines = vec![wall(0.0, 0.0, 100.0, 0.0), wall(100.0, 0.0, 100.0, 100.0)];
+        assert_eq!(estimate_door_threshold(&lines), 0.0);
+    }
+
+    #[test]
+    fn test_snap_endpoints_closes_corner() {
+        // Square whose last corner misses the first by 0.5 units
+        let lines = vec![
+            wall(0.0, 0.0, 100.0, 0.0),
+            wall(100.0, 0.0, 100.0, 100.0),
+            wall(100.0, 100.0, 0.0, 100.0),
+            wall(0.0, 100.0, 0.0, 0.5),
+        ];
+
+        let unsnapped = build_graph(&lines);
+        assert!(crate::room_detector::find_all_cycles(&unsnapped).is_empty());
+
+        let snapped = snap_endpoints(&lines, 1.0);
+        assert_eq!(snapped[3].end, snapped[0].start);
+
+        let graph = build_graph(&snapped);
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(crate::room_detector::find_all_cycles(&graph).len(), 1);
+    }
+
+    #[test]
+    fn test_drop_short_walls_removes_speckle_only() {
+        let square = vec![
+            wall(0.0, 0.0, 100.0, 0.0),
+            wall(100.0, 0.0, 100.0, 100.0),
+            wall(100.0, 100.0, 0.0, 100.0),
+            wall(0.0, 100.0, 0.0, 0.0),
+        ];
+        let mut noisy = square.clone();
+        noisy.extend([
+            // Spur off the bottom wall, which would split it at a T-junction
+            wall(50.0, 0.0, 50.0, 2.0),
+            // Isolated speck
+            wall(20.0, 20.0, 21.0, 21.0),
+            // Tiny closed triangle
+            wall(60.0, 60.0, 62.0, 60.0),
+            wall(62.0, 60.0, 61.0, 62.0),
+            wall(61.0, 62.0, 60.0, 60.0),
+        ]);
+
+        assert_eq!(drop_short_walls(&noisy, 0.0).len(), noisy.len());
+        let cleaned = drop_short_walls(&noisy, 5.0);
+        assert_eq!(cleaned.len(), square.len());
+        assert!(cleaned.iter().zip(&square).all(|(a, b)| a.start == b.start && a.end == b.end));
+
+        let graph = build_graph(&cleaned);
+        assert_eq!(graph.node_count(), 4);
+        let rooms = crate::room_detector::detect_rooms(&graph, 1.0, 1.5);
+        let clean_rooms = crate::room_detector::detect_rooms(&build_graph(&square), 1.0, 1.5);
+        assert_eq!(rooms.len(), 1);
+        assert_eq!(rooms[0].bounding_box, clean_rooms[0].bounding_box);
+    }
+
+    #[test]
+    fn test_snap_endpoints_keeps_distant_points() {
+        let lines = vec![wall(0.0, 0.0, 10.0, 0.0), wall(12.0, 0.0, 20.0, 0.0)];
+        let snapped = snap_endpoints(&lines, 1.0);
+
+        assert_eq!(snapped[0].end, Point { x: 10.0, y: 0.0 });
+        assert_eq!(snapped[1].start, Point { x: 12.0, y: 0.0 });
+    }
+
+    #[test]
+    fn test_t_junction_splits_wall() {
+        // 200x100 rectangle with a divider ending on the midpoints of the long walls
+        let lines = vec![
+            wall(0.0, 0.0, 200.0, 0.0),
+            wall(200.0, 0.0, 200.0, 100.0),
+            wall(200.0, 100.0, 0.0, 100.0),
+            wall(0.0, 100.0, 0.0, 0.0),
+            wall(100.0, 0.0, 100.0, 100.0),
+        ];
+
+        let split = split_at_junctions(&lines);
+        assert_eq!(split.len(), 7);
+
+        let graph = build_graph(&lines);
+        assert_eq!(graph.node_count(), 6);
+
+        let rooms = crate::room_detector::detect_rooms(&graph, 100.0, 1.5);
+        assert_eq!(rooms.len(), 2);
+    }
+
+    #[test]
+    fn test_x_junction_splits_both_walls() {
+        let lines = vec![wall(0.0, 50.0, 100.0, 50.0), wall(50.0, 0.0, 50.0, 100.0)];
+
+        let split = split_at_junctions(&lines);
+
+        assert_eq!(split.len(), 4);
+        let center = Point { x: 50.0, y: 50.0 };
+        assert_eq!(split.iter().filter(|l| l.end == center || l.start == center).count(), 4);
+    }
+    /// Node positions and (unordered endpoints, is_virtual) edges, rounded and sorted for comparison
+    type Canonical = (Vec<(i64, i64)>, Vec<((i64, i64), (i64, i64), bool)>);
+
+    fn canonical(graph: &FloorplanGraph) -> Canonical {
+        let key = |p: &Point| ((p.x * 1e6).round() as i64, (p.y * 1e6).round() as i64);
+        let mut nodes: Vec<_> = graph.node_weights().map(key).collect();
+        nodes.sort();
+        let mut edges: Vec<_> = graph
+            .edge_indices()
+            .map(|edge| {
+                let (a, b) = graph.edge_endpoints(edge).unwrap();
+                let (a, b) = (key(&graph[a]), key(&graph[b]));
+                (a.min(b), a.max(b), graph[edge].is_virtual())
+            })
+            .collect();
+        edges.sort();
+        (nodes, edges)
+    }
+
+    /// Xorshift, so the "random" plans are the same on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+
+        /// Walls on a coarse grid, so T-junctions, crossings, overlaps and door gaps all come up
+        fn line(&mut self) -> Line {
+            let mut coordinate = || self.below(7) as f64 * 20.0 + if self.below(4) == 0 { 7.5 } else { 0.0 };
+            let (x1, y1, x2, y2) = (coordinate(), coordinate(), coordinate(), coordinate());
+            wall(x1, y1, x2, y2)
+        }
+    }
+
+    #[test]
+    fn test_incremental_graph_matches_batch_build() {
+        const DOOR_THRESHOLD: f64 = 25.0;
+
+        for seed in 1..=40u64 {
+            let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let mut incremental = IncrementalGraph::new(DOOR_THRESHOLD);
+            let mut lines: Vec<Line> = Vec::new();
+
+            for step in 0..30 {
+                if !lines.is_empty() && rng.below(3) == 0 {
+                    let line = lines.remove(rng.below(lines.len() as u64) as usize);
+                    assert!(incremental.remove_line(&line));
+                } else {
+                    let line = rng.line();
+                    incremental.add_line(&line);
+                    lines.push(line);
+                }
+
+                let batch = build_graph_with_door_threshold(&lines, DOOR_THRESHOLD);
+                assert_eq!(
+                    canonical(incremental.graph()),
+                    canonical(&batch),
+                    "seed {} diverged at step {}",
+                    seed,
+                    step
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_remove_rejoins_split_wall() {
+        let mut incremental = IncrementalGraph::new(0.0);
+        let long_wall = wall(0.0, 0.0, 200.0, 0.0);
+        let divider = wall(100.0, 0.0, 100.0, 100.0);
+
+        incremental.add_line(&long_wall);
+        incremental.add_line(&divider);
+        assert_eq!(incremental.graph().edge_count(), 3);
+
+        assert!(incremental.remove_line(&divider));
+        assert_eq!(incremental.graph().edge_count(), 1);
+        assert_eq!(incremental.graph().node_count(), 2, "orphaned divider nodes are cleaned up");
+        assert!(!incremental.remove_line(&divider));
+    }
 }
@@ -0,0 +1,355 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::ErrorResponse;
+
+/// Request header naming a retry-safe operation
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+/// Response header set on replayed responses
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Seconds a response stays replayable unless `IDEMPOTENCY_TTL_SECS` overrides it
+const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 900;
+/// Keys remembered at once; the oldest are dropped beyond this
+const MAX_IDEMPOTENCY_KEYS: usize = 1000;
+/// Response bytes remembered at once; the oldest are dropped beyond this, and a response
+/// larger than the whole budget is never stored
+const MAX_IDEMPOTENCY_BYTES: usize = 64 * 1024 * 1024;
+/// Longest accepted `Idempotency-Key`
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Replay window, overridable with `IDEMPOTENCY_TTL_SECS`
+pub fn idempotency_ttl() -> Duration {
+    let secs = std::env::var("IDEMPOTENCY_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_IDEMPOTENCY_TTL_SECS);
+    Duration::from_secs(secs)
+}
+
+#[derive(Clone)]
+struct StoredResponse {
+    status: StatusCode,
+    content_type: Option<HeaderValue>,
+    body: Bytes,
+}
+
+/// One key's slot. The async lock is held while the first request runs, so a retry that
+/// arrives mid-flight waits for that result instead of starting a second detection.
+struct Entry {
+    created_at: Instant,
+    body_hash: [u8; 32],
+    response: Arc<tokio::sync::Mutex<Option<StoredResponse>>>,
+    /// Size of the stored response body, 0 until one is stored
+    stored_bytes: usize,
+}
+
+/// Successful responses by client, path and `Idempotency-Key`, kept in memory for `ttl`
+#[derive(Clone)]
+pub struct IdempotencyCache {
+    ttl: Duration,
+    max_bytes: usize,
+    trust_proxy: bool,
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl IdempotencyCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            max_bytes: MAX_IDEMPOTENCY_BYTES,
+            trust_proxy: false,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Scope keys by the client address the proxy reports instead of the peer address
+    pub fn trusting_proxy(mut self, trust_proxy: bool) -> Self {
+        self.trust_proxy = trust_proxy;
+        self
+    }
+
+    #[cfg(test)]
+    fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// The slot for `key`, created if missing or expired; `None` if the key was used with a different body
+    fn slot(&self, key: &str, body_hash: [u8; 32]) -> Option<Arc<tokio::sync::Mutex<Option<StoredResponse>>>> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| now.duration_since(entry.created_at) < self.ttl);
+
+        if let Some(entry) = entries.get(key) {
+            return (entry.body_hash == body_hash).then(|| entry.response.clone());
+        }
+
+        if entries.len() >= MAX_IDEMPOTENCY_KEYS {
+            if let Some(oldest) = entries.iter().min_by_key(|(_, e)| e.created_at).map(|(k, _)| k.clone()) {
+                entries.remove(&oldest);
+            }
+        }
+        let response = Arc::new(tokio::sync::Mutex::new(None));
+        entries.insert(
+            key.to_string(),
+            Entry {
+                created_at: now,
+                body_hash,
+                response: response.clone(),
+                stored_bytes: 0,
+            },
+        );
+        Some(response)
+    }
+
+    /// Whether a `size`-byte response for `key` fits the byte budget. Counts it against the
+    /// budget, dropping the oldest other stored responses until it fits.
+    fn reserve(&self, key: &str, size: usize) -> bool {
+        if size > self.max_bytes {
+            return false;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.get_mut(key) else {
+            return false;
+        };
+        entry.stored_bytes = size;
+
+        let mut total: usize = entries.values().map(|e| e.stored_bytes).sum();
+        while total > self.max_bytes {
+            let Some((oldest, bytes)) = entries
+                .iter()
+                .filter(|(k, e)| k.as_str() != key && e.stored_bytes > 0)
+                .min_by_key(|(_, e)| e.created_at)
+                .map(|(k, e)| (k.clone(), e.stored_bytes))
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+            total -= bytes;
+        }
+        true
+    }
+}
+
+fn idempotency_error(status: StatusCode, error: &str, message: String) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+            message,
+        }),
+    )
+        .into_response()
+}
+
+/// Streamed bodies (batch NDJSON, progress events) are passed through uncached
+fn is_streamed(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/x-ndjson") || ct.starts_with("text/event-stream"))
+}
+
+/// Middleware replaying the stored response for a repeated POST from the same client with the
+/// same `Idempotency-Key`
+///
+/// Only successful responses are stored, so a failed attempt can be retried with the same key.
+/// Reusing a key with a different body is rejected with 422.
+pub async fn replay_idempotent(State(cache): State<IdempotencyCache>, request: Request, next: Next) -> Response {
+    let key = match request.headers().get(&IDEMPOTENCY_KEY) {
+        Some(key) if request.method() == Method::POST => key.to_str().ok().map(str::to_string),
+        _ => return next.run(request).await,
+    };
+    let Some(key) = key.filter(|k| !k.is_empty() && k.len() <= MAX_KEY_LENGTH) else {
+        return idempotency_error(
+            StatusCode::BAD_REQUEST,
+            "INVALID_IDEMPOTENCY_KEY",
+            format!("Idempotency-Key must be 1 to {} visible ASCII characters", MAX_KEY_LENGTH),
+        );
+    };
+
+    // Keys are per client, so one client can't replay or block another's requests
+    let client = crate::rate_limit::client_ip(&request, cache.trust_proxy);
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, crate::max_body_bytes()).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return idempotency_error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "INPUT_TOO_LARGE",
+                "Request body too large".to_string(),
+            )
+        }
+    };
+    let body_hash: [u8; 32] = Sha256::digest(&bytes).into();
+    let scoped_key = format!("{} {} {}", client, parts.uri.path(), key);
+
+    let Some(slot) = cache.slot(&scoped_key, body_hash) else {
+        return idempotency_error(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "IDEMPOTENCY_KEY_REUSED",
+            "Idempotency-Key was already used with a different request body".to_string(),
+        );
+    };
+    let mut stored = slot.lock().await;
+
+    if let Some(previous) = stored.as_ref() {
+        info!("Replaying stored response for idempotency key {}", key);
+        let mut response = (previous.status, previous.body.clone()).into_response();
+        if let Some(content_type) = &previous.content_type {
+            response.headers_mut().insert(header::CONTENT_TYPE, content_type.clone());
+        }
+        response.headers_mut().insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+        return response;
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    if !response.status().is_success() || is_streamed(&response) {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        return idempotency_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "RESPONSE_FAILED",
+            "Failed to read the detection response".to_string(),
+        );
+    };
+    if cache.reserve(&scoped_key, bytes.len()) {
+        *stored = Some(StoredResponse {
+            status: parts.status,
+            content_type: parts.headers.get(header::CONTENT_TYPE).cloned(),
+            body: bytes.clone(),
+        });
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn counting_app(cache: IdempotencyCache, calls: Arc<AtomicUsize>) -> Router {
+        Router::new()
+            .route(
+                "/detect",
+                post(move |body: String| async move {
+                    let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                    Json(serde_json::json!({ "call": call, "echo": body }))
+                }),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(cache, replay_idempotent))
+    }
+
+    fn detect(key: Option<&str>, body: &str) -> Request {
+        let mut request = Request::post("/detect");
+        if let Some(key) = key {
+            request = request.header(IDEMPOTENCY_KEY, key);
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn body_of(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_repeated_key_replays_first_response() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = counting_app(IdempotencyCache::new(Duration::from_secs(60)), calls.clone());
+
+        let first = app.clone().oneshot(detect(Some("retry-1"), "plan")).await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert!(first.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        let first = body_of(first).await;
+
+        let second = app.clone().oneshot(detect(Some("retry-1"), "plan")).await.unwrap();
+        assert_eq!(second.status(), StatusCode::OK);
+        assert_eq!(second.headers()[IDEMPOTENT_REPLAYED], "true");
+        assert_eq!(second.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(body_of(second).await, first);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Other keys and requests without a key run the handler
+        app.clone().oneshot(detect(Some("retry-2"), "plan")).await.unwrap();
+        app.clone().oneshot(detect(None, "plan")).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let reused = app.oneshot(detect(Some("retry-1"), "other plan")).await.unwrap();
+        assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_scoped_per_client() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = counting_app(IdempotencyCache::new(Duration::from_secs(60)).trusting_proxy(true), calls.clone());
+        let from = |ip: &str| {
+            let mut request = detect(Some("retry-1"), "plan");
+            request.headers_mut().insert("x-forwarded-for", HeaderValue::from_str(ip).unwrap());
+            request
+        };
+
+        app.clone().oneshot(from("10.0.0.1")).await.unwrap();
+        let other_client = app.clone().oneshot(from("10.0.0.2")).await.unwrap();
+        assert!(other_client.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let same_client = app.oneshot(from("10.0.0.1")).await.unwrap();
+        assert_eq!(same_client.headers()[IDEMPOTENT_REPLAYED], "true");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_byte_budget_drops_oldest_responses() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        // Each response is {"call":N,"echo":"plan"}, 24 bytes, so two fit
+        let cache = IdempotencyCache::new(Duration::from_secs(60)).with_max_bytes(60);
+        let app = counting_app(cache, calls.clone());
+
+        for key in ["retry-1", "retry-2", "retry-3"] {
+            app.clone().oneshot(detect(Some(key), "plan")).await.unwrap();
+        }
+        let newest = app.clone().oneshot(detect(Some("retry-3"), "plan")).await.unwrap();
+        assert_eq!(newest.headers()[IDEMPOTENT_REPLAYED], "true");
+        let oldest = app.clone().oneshot(detect(Some("retry-1"), "plan")).await.unwrap();
+        assert!(oldest.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // A response larger than the whole budget is never stored
+        let large = "x".repeat(100);
+        app.clone().oneshot(detect(Some("large"), &large)).await.unwrap();
+        let again = app.oneshot(detect(Some("large"), &large)).await.unwrap();
+        assert!(again.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn test_expired_key_runs_again() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let app = counting_app(IdempotencyCache::new(Duration::ZERO), calls.clone());
+
+        app.clone().oneshot(detect(Some("retry-1"), "plan")).await.unwrap();
+        app.oneshot(detect(Some("retry-1"), "plan")).await.unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
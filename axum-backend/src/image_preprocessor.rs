@@ -145,6 +145,14 @@ Ok(NormalizedImage {
             .collect()
     }
 
+    /// The original image's extent on the padded canvas, as `[min_x, min_y, max_x, max_y]`
+    pub fn content_bounds(&self) -> [f64; 4] {
+        let (pad_left, pad_top, _, _) = self.padding;
+        let width = (self.original_width as f64 * self.scale_factor).floor();
+        let height = (self.original_height as f64 * self.scale_factor).floor();
+        [pad_left as f64, pad_top as f64, pad_left as f64 + width, pad_top as f64 + height]
+    }
+
 /// Get the base64 data URL for use with vision APIs
 pub fn to_data_url(&self) -> String {
     format!("data:image/png;base64,{}", self.base64_data)
@@ -226,6 +234,27 @@ fn threshold_image(&self, img: &GrayImage, threshold: u8) -> GrayImage {
 }
 }
 
+/// Resize so the longest side is at most `max_dim`, preserving aspect ratio
+///
+/// Returns the resized image and the scale factor applied (1.0 if already small enough,
+/// or if `max_dim` is 0). Divide detected pixel coordinates by the factor to map them back.
+pub fn downscale_to_max_dimension(img: &DynamicImage, max_dim: u32) -> (DynamicImage, f64) {
+    let (width, height) = img.dimensions();
+    let longest = width.max(height);
+    if max_dim == 0 || longest <= max_dim {
+        return (img.clone(), 1.0);
+    }
+
+    let scale = max_dim as f64 / longest as f64;
+    let new_width = ((width as f64 * scale).round() as u32).max(1);
+    let new_height = ((height as f64 * scale).round() as u32).max(1);
+
+    // Triangle keeps thin walls dark enough to survive thresholding and is much faster than Lanczos
+    let resized = img.resize_exact(new_width, new_height, image::imageops::FilterType::Triangle);
+
+    (resized, scale)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -327,4 +356,17 @@ mod tests {
         let data_url = normalized.to_data_url();
         assert!(data_url.starts_with("data:image/png;base64,"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_downscale_to_max_dimension() {
+        let img = DynamicImage::ImageLuma8(GrayImage::new(4000, 3000));
+
+        let (resized, scale) = downscale_to_max_dimension(&img, 2000);
+        assert_eq!(resized.dimensions(), (2000, 1500));
+        assert_eq!(scale, 0.5);
+
+        let (unchanged, scale) = downscale_to_max_dimension(&resized, 2000);
+        assert_eq!(unchanged.dimensions(), (2000, 1500));
+        assert_eq!(scale, 1.0);
+    }
+}
@@ -1,4 +1,8 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
 use visioncortex::PathSimplifyMode;
 use vtracer::{convert_image_to_svg, Config, ColorMode, Hierarchical};
 
@@ -15,6 +19,170 @@ pub struct Line {
     pub is_load_bearing: bool,
 }
 
+/// Raster-to-line tracing engine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorizerBackend {
+    #[default]
+    #[serde(rename = "vtracer")]
+    VTracer,
+    /// Pixel-boundary outline tracing; keeps straight walls on clean line art
+    Potrace,
+}
+
+impl VectorizerBackend {
+    pub fn vectorizer(self) -> Box<dyn Vectorizer> {
+        match self {
+            VectorizerBackend::VTracer => Box::new(VTracerVectorizer::default()),
+            VectorizerBackend::Potrace => Box::new(PotraceVectorizer::default()),
+        }
+    }
+
+    /// `method_used` reported when this backend's walls are returned without room detection
+    pub fn walls_method(self) -> &'static str {
+        match self {
+            VectorizerBackend::VTracer => "vtracer_walls",
+            VectorizerBackend::Potrace => "potrace_walls",
+        }
+    }
+}
+
+/// Turns encoded image bytes into wall line segments
+pub trait Vectorizer: Send + Sync {
+    fn vectorize(&self, image_bytes: &[u8]) -> Result<Vec<Line>>;
+}
+
+/// VTracer through the shared line cache
+#[derive(Default)]
+pub struct VTracerVectorizer {
+    pub settings: VTracerSettings,
+}
+
+impl Vectorizer for VTracerVectorizer {
+    fn vectorize(&self, image_bytes: &[u8]) -> Result<Vec<Line>> {
+        vectorize_cached(image_bytes, self.settings.to_config()).map(|(lines, _)| lines)
+    }
+}
+
+/// Potrace-style outline tracing of dark pixels, simplified to straight segments
+pub struct PotraceVectorizer {
+    /// Luma below which a pixel counts as ink
+    pub threshold: u8,
+    /// Douglas-Peucker tolerance for outlines, in pixels
+    pub tolerance: f64,
+    /// Shorter segments are dropped as noise
+    pub min_length: f64,
+}
+
+impl Default for PotraceVectorizer {
+    fn default() -> Self {
+        Self {
+            threshold: 128,
+            tolerance: 1.5,
+            min_length: 4.0,
+        }
+    }
+}
+
+impl Vectorizer for PotraceVectorizer {
+    fn vectorize(&self, image_bytes: &[u8]) -> Result<Vec<Line>> {
+        let image = image::load_from_memory(image_bytes)
+            .context("Failed to load image from memory")?
+            .to_luma8();
+
+        let mut lines = Vec::new();
+        for ring in crate::outline_tracer::trace_outlines(&image, self.threshold) {
+            let ring = crate::room_detector::simplify_polygon(&ring, self.tolerance);
+            for pair in ring.windows(2) {
+                let length = ((pair[1].x - pair[0].x).powi(2) + (pair[1].y - pair[0].y).powi(2)).sqrt();
+                if length >= self.min_length {
+                    lines.push(Line {
+                        start: Point { x: pair[0].x, y: pair[0].y },
+                        end: Point { x: pair[1].x, y: pair[1].y },
+                        is_load_bearing: false,
+                    });
+                }
+            }
+        }
+
+        Ok(lines)
+    }
+}
+
+/// How VTracer fits curves to traced outlines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceMode {
+    Polygon,
+    #[default]
+    Spline,
+}
+
+/// Request-tunable VTracer settings; the rest of the config keeps the blueprint values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VTracerSettings {
+    /// Patches smaller than this many pixels across are discarded
+    pub filter_speckle: usize,
+    /// Minimum angle (degrees) counted as a corner
+    pub corner_threshold: i32,
+    /// Minimum segment length before subdivision
+    pub length_threshold: f64,
+    /// Decimal places in SVG path coordinates
+    pub path_precision: Option<u32>,
+    pub mode: TraceMode,
+}
+
+impl Default for VTracerSettings {
+    fn default() -> Self {
+        Self {
+            filter_speckle: 4,      // Moderate speckle filtering
+            corner_threshold: 60,   // Prefer straight lines for architectural drawings
+            length_threshold: 4.0,  // Capture meaningful line segments
+            path_precision: Some(3),
+            mode: TraceMode::Spline,
+        }
+    }
+}
+
+impl VTracerSettings {
+    /// Check every setting is in the range VTracer accepts
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.filter_speckle > 128 {
+            return Err(format!("filter_speckle must be between 0 and 128, got {}", self.filter_speckle));
+        }
+        if !(0..=180).contains(&self.corner_threshold) {
+            return Err(format!("corner_threshold must be between 0 and 180, got {}", self.corner_threshold));
+        }
+        if !(3.5..=10.0).contains(&self.length_threshold) {
+            return Err(format!("length_threshold must be between 3.5 and 10, got {}", self.length_threshold));
+        }
+        if let Some(precision) = self.path_precision.filter(|&p| p > 8) {
+            return Err(format!("path_precision must be between 0 and 8, got {}", precision));
+        }
+        Ok(())
+    }
+
+    pub fn to_config(&self) -> Config {
+        Config {
+            color_mode: ColorMode::Color,  // Color mode works better with grayscale images
+            hierarchical: Hierarchical::Stacked,
+            mode: match self.mode {
+                TraceMode::Polygon => PathSimplifyMode::Polygon,
+                TraceMode::Spline => PathSimplifyMode::Spline,
+            },
+            filter_speckle: self.filter_speckle,
+            color_precision: 8,  // Higher precision for grayscale tones
+            layer_difference: 5,  // Lower for grayscale images
+            corner_threshold: self.corner_threshold,
+            length_threshold: self.length_threshold,
+            max_iterations: 10,
+            splice_threshold: 45,
+            path_precision: self.path_precision,
+        }
+    }
+}
+
 /// Vectorize a raster image and extract line segments using AI parser
 pub async fn vectorize_image_ai(image_bytes: &[u8]) -> Result<Vec<Line>> {
     // Load image
@@ -62,76 +230,386 @@ pub async fn vectorize_image_ai(image_bytes: &[u8]) -> Result<Vec<Line>> {
     Ok(lines)
 }
 
+/// Env var overriding the vectorization cache size (0 disables caching)
+const VTRACER_CACHE_SIZE_ENV: &str = "VTRACER_CACHE_SIZE";
+const DEFAULT_VTRACER_CACHE_SIZE: usize = 64;
+
+type CacheKey = [u8; 32];
+
+/// Least-recently-used cache of extracted line sets
+struct LineCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, Vec<Line>>,
+    /// Keys from least to most recently used
+    order: VecDeque<CacheKey>,
+}
+
+impl LineCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&mut self, key: &CacheKey) -> Option<Vec<Line>> {
+        let lines = self.entries.get(key)?.clone();
+        self.touch(key);
+        Some(lines)
+    }
+
+    fn insert(&mut self, key: CacheKey, lines: Vec<Line>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.insert(key, lines).is_some() {
+            self.touch(&key);
+            return;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(*key);
+    }
+}
+
+fn line_cache() -> &'static Mutex<LineCache> {
+    static CACHE: OnceLock<Mutex<LineCache>> = OnceLock::new();
+    CACHE.get_or_init(|| {
+        let capacity = std::env::var(VTRACER_CACHE_SIZE_ENV)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_VTRACER_CACHE_SIZE);
+        Mutex::new(LineCache::new(capacity))
+    })
+}
+
+/// SHA-256 over the image bytes and every VTracer setting, so configs never share entries
+fn cache_key(image_bytes: &[u8], config: &Config) -> CacheKey {
+    let color_mode = match config.color_mode {
+        ColorMode::Color => "color",
+        ColorMode::Binary => "binary",
+    };
+    let hierarchical = match config.hierarchical {
+        Hierarchical::Stacked => "stacked",
+        Hierarchical::Cutout => "cutout",
+    };
+    let mode = match config.mode {
+        PathSimplifyMode::None => "none",
+        PathSimplifyMode::Polygon => "polygon",
+        PathSimplifyMode::Spline => "spline",
+    };
+    let settings = format!(
+        "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{:?}",
+        color_mode,
+        hierarchical,
+        mode,
+        config.filter_speckle,
+        config.color_precision,
+        config.layer_difference,
+        config.corner_threshold,
+        config.length_threshold,
+        config.max_iterations,
+        config.splice_threshold,
+        config.path_precision,
+    );
+
+    let mut hasher = Sha256::new();
+    hasher.update(settings.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(image_bytes);
+    hasher.finalize().into()
+}
+
+/// Run VTracer on encoded image bytes and parse the SVG to lines, reusing cached results
+///
+/// Returns the lines and whether they came from the cache.
+pub fn vectorize_cached(image_bytes: &[u8], config: Config) -> Result<(Vec<Line>, bool)> {
+    let key = cache_key(image_bytes, &config);
+    if let Some(lines) = line_cache().lock().unwrap().get(&key) {
+        return Ok((lines, true));
+    }
+
+    let lines = vectorize_uncached(image_bytes, config)?;
+    line_cache().lock().unwrap().insert(key, lines.clone());
+
+    Ok((lines, false))
+}
+
+/// Run VTracer on encoded image bytes and parse the SVG to lines, bypassing the cache
+pub fn vectorize_uncached(image_bytes: &[u8], config: Config) -> Result<Vec<Line>> {
+    // VTracer requires file paths, use UUID for unique temp file names
+    let request_id = uuid::Uuid::new_v4();
+    let temp_path = std::env::temp_dir().join(format!("vtracer_{}_input.png", request_id));
+    let svg_path = std::env::temp_dir().join(format!("vtracer_{}_output.svg", request_id));
+
+    std::fs::write(&temp_path, image_bytes).context("Failed to save temporary image")?;
+
+    let result = convert_image_to_svg(&temp_path, &svg_path, config)
+        .map_err(|e| anyhow::anyhow!("VTracer failed: {}", e))
+        .and_then(|_| std::fs::read_to_string(&svg_path).context("Failed to read SVG"));
+
+    // Clean up temp files
+    let _ = std::fs::remove_file(&temp_path);
+    let _ = std::fs::remove_file(&svg_path);
+
+    parse_svg_to_lines(&result?)
+}
+
 /// Parse SVG paths and convert to line segments (internal use only)
+///
+/// Malformed markup that makes the parser panic is reported as an error instead.
 pub(crate) fn parse_svg_to_lines(svg: &str) -> Result<Vec<Line>> {
-    let mut lines = Vec::new();
+    parse_svg_to_lines_with_min_stroke(svg, 0.0)
+}
 
-    // Parse <path> elements
-    for path_match in svg.split("<path").skip(1) {
-        if let Some(d_attr_start) = path_match.find(" d=\"") {
-            let path_data = &path_match[d_attr_start + 4..];
-            if let Some(end_quote) = path_data.find('"') {
-                let path_commands = &path_data[..end_quote];
+/// Like [`parse_svg_to_lines`], but drops elements whose `stroke-width` is not
+/// above `min_stroke_width` (thin dimension and annotation lines); 0 keeps everything
+pub(crate) fn parse_svg_to_lines_with_min_stroke(svg: &str, min_stroke_width: f64) -> Result<Vec<Line>> {
+    std::panic::catch_unwind(|| parse_svg_markup(svg, min_stroke_width))
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("SVG parser failed on malformed markup")))
+}
 
-                // Parse path commands (simplified - handles M, L, H, V)
-                let path_lines = parse_path_commands(path_commands);
-                lines.extend(path_lines);
+fn parse_svg_markup(svg: &str, min_stroke_width: f64) -> Result<Vec<Line>> {
+    let class_widths = style_class_stroke_widths(svg);
+    let (mut paths, mut lines, mut rects) = (Vec::new(), Vec::new(), Vec::new());
+    // Stroke widths set by the open <svg> and <g> elements, innermost last
+    let mut inherited: Vec<Option<f64>> = Vec::new();
+
+    for tag in svg.split('<').skip(1) {
+        let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+        let name_end = tag.find(|c: char| c.is_whitespace() || c == '/').unwrap_or(tag.len());
+        let (name, attrs) = tag.split_at(name_end);
+        let own_width = || own_stroke_width(attrs, &class_widths);
+        let width = || own_width().or(inherited.last().copied().flatten()).unwrap_or(1.0);
+
+        match name {
+            "svg" | "g" if !attrs.trim_end().ends_with('/') => {
+                let width = own_width().or(inherited.last().copied().flatten());
+                inherited.push(width);
+                continue;
             }
+            "/svg" | "/g" => {
+                inherited.pop();
+                continue;
+            }
+            "path" | "line" | "rect" if min_stroke_width > 0.0 && width() <= min_stroke_width => continue,
+            _ => {}
+        }
+
+        match name {
+            "path" => paths.extend(parse_path_element(attrs)),
+            "line" => lines.extend(parse_line_element(attrs)),
+            "rect" => rects.extend(parse_rect_element(attrs)),
+            _ => {}
         }
     }
 
-    // Parse <line> elements
-    for line_match in svg.split("<line").skip(1) {
-        if let Some(end_tag) = line_match.find('>') {
-            let line_attrs = &line_match[..end_tag];
+    paths.extend(lines);
+    paths.extend(rects);
+    Ok(paths)
+}
 
-            // Extract x1, y1, x2, y2 attributes
-            let x1 = extract_attr_value(line_attrs, "x1");
-            let y1 = extract_attr_value(line_attrs, "y1");
-            let x2 = extract_attr_value(line_attrs, "x2");
-            let y2 = extract_attr_value(line_attrs, "y2");
+/// Segments of a `<path>`, shifted by its `translate` transform
+fn parse_path_element(path_attrs: &str) -> Vec<Line> {
+    let Some(d_attr_start) = path_attrs.find(" d=\"") else {
+        return Vec::new();
+    };
+    let path_data = &path_attrs[d_attr_start + 4..];
+    let Some(end_quote) = path_data.find('"') else {
+        return Vec::new();
+    };
 
-            if let (Some(x1), Some(y1), Some(x2), Some(y2)) = (x1, y1, x2, y2) {
-                if let (Ok(x1), Ok(y1), Ok(x2), Ok(y2)) = (x1.parse::<f64>(), y1.parse::<f64>(), x2.parse::<f64>(), y2.parse::<f64>()) {
-                    lines.push(Line {
-                        start: Point { x: x1, y: y1 },
-                        end: Point { x: x2, y: y2 },
-                        is_load_bearing: false,
-                    });
+    // Parse path commands (simplified - handles M, L, H, V, and C as a chord)
+    let (dx, dy) = translate_offset(path_attrs);
+    parse_path_commands(&path_data[..end_quote])
+        .into_iter()
+        .map(|line| Line {
+            start: Point { x: line.start.x + dx, y: line.start.y + dy },
+            end: Point { x: line.end.x + dx, y: line.end.y + dy },
+            is_load_bearing: line.is_load_bearing,
+        })
+        .collect()
+}
+
+/// The segment of a `<line>` element
+fn parse_line_element(line_attrs: &str) -> Option<Line> {
+    // Extract x1, y1, x2, y2 attributes
+    let coord = |name| extract_attr_value(line_attrs, name)?.parse::<f64>().ok();
+    Some(Line {
+        start: Point { x: coord("x1")?, y: coord("y1")? },
+        end: Point { x: coord("x2")?, y: coord("y2")? },
+        is_load_bearing: false,
+    })
+}
+
+/// The four sides of a `<rect>` element
+fn parse_rect_element(rect_attrs: &str) -> Vec<Line> {
+    // Extract x, y, width, height attributes
+    let x = extract_attr_value(rect_attrs, "x").unwrap_or("0".to_string());
+    let y = extract_attr_value(rect_attrs, "y").unwrap_or("0".to_string());
+    let width = extract_attr_value(rect_attrs, "width");
+    let height = extract_attr_value(rect_attrs, "height");
+
+    let (Some(width), Some(height)) = (width, height) else {
+        return Vec::new();
+    };
+    let (Ok(x), Ok(y), Ok(w), Ok(h)) = (x.parse::<f64>(), y.parse::<f64>(), width.parse::<f64>(), height.parse::<f64>()) else {
+        return Vec::new();
+    };
+
+    // Convert rect to 4 lines
+    let x2 = x + w;
+    let y2 = y + h;
+    vec![
+        Line { start: Point { x, y }, end: Point { x: x2, y }, is_load_bearing: false }, // top
+        Line { start: Point { x: x2, y }, end: Point { x: x2, y: y2 }, is_load_bearing: false }, // right
+        Line { start: Point { x: x2, y: y2 }, end: Point { x, y: y2 }, is_load_bearing: false }, // bottom
+        Line { start: Point { x, y: y2 }, end: Point { x, y }, is_load_bearing: false }, // left
+    ]
+}
+
+/// Offset from a `transform="translate(x, y)"` attribute, as VTracer writes on every path
+fn translate_offset(attrs: &str) -> (f64, f64) {
+    let Some(transform) = extract_attr_value(attrs, "transform") else {
+        return (0.0, 0.0);
+    };
+    let Some(args) = transform
+        .trim()
+        .strip_prefix("translate(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    else {
+        return (0.0, 0.0);
+    };
+    let mut values = args
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(|v| v.parse::<f64>().unwrap_or(0.0));
+    (values.next().unwrap_or(0.0), values.next().unwrap_or(0.0))
+}
+
+/// Stroke width an element sets itself, by CSS precedence: inline `style`, then `<style>`
+/// class rules, then the `stroke-width` attribute. `None` inherits from the parent group.
+fn own_stroke_width(attrs: &str, class_widths: &[(String, f64)]) -> Option<f64> {
+    let from_style = extract_attr_value(attrs, "style").and_then(|style| stroke_width_declaration(&style));
+    let from_class = || {
+        let classes = extract_attr_value(attrs, "class")?;
+        let classes: Vec<&str> = classes.split_whitespace().collect();
+        // Later rules win
+        class_widths
+            .iter()
+            .rev()
+            .find(|(class, _)| classes.contains(&class.as_str()))
+            .map(|&(_, width)| width)
+    };
+    let from_attr = || extract_attr_value(attrs, "stroke-width").and_then(|value| parse_length(&value));
+    from_style.or_else(from_class).or_else(from_attr)
+}
+
+/// `stroke-width` from a CSS declaration block such as `stroke:black;stroke-width:2px`
+fn stroke_width_declaration(declarations: &str) -> Option<f64> {
+    declarations.split(';').rev().find_map(|decl| {
+        let (name, value) = decl.split_once(':')?;
+        (name.trim() == "stroke-width").then(|| parse_length(value)).flatten()
+    })
+}
+
+fn parse_length(value: &str) -> Option<f64> {
+    value.trim().trim_end_matches("px").trim().parse::<f64>().ok()
+}
+
+/// `stroke-width` set by class selectors (`.wall { stroke-width: 6 }`) in `<style>` blocks,
+/// in document order. Other selectors are ignored.
+fn style_class_stroke_widths(svg: &str) -> Vec<(String, f64)> {
+    let mut widths = Vec::new();
+    for block in svg.split("<style").skip(1) {
+        let Some(css) = block.split_once('>').map(|(_, rest)| rest) else {
+            continue;
+        };
+        let css = &css[..css.find("</style").unwrap_or(css.len())];
+        let css = css.replace("<![CDATA[", "").replace("]]>", "");
+        for rule in css.split('}') {
+            let Some((selectors, declarations)) = rule.split_once('{') else {
+                continue;
+            };
+            let Some(width) = stroke_width_declaration(declarations) else {
+                continue;
+            };
+            for selector in selectors.split(',') {
+                let class = selector.trim().strip_prefix('.');
+                if let Some(class) = class.filter(|c| c.chars().all(|ch| ch.is_alphanumeric() || ch == '-' || ch == '_')) {
+                    widths.push((class.to_string(), width));
                 }
             }
         }
     }
+    widths
+}
 
-    // Parse <rect> elements
-    for rect_match in svg.split("<rect").skip(1) {
-        if let Some(end_tag) = rect_match.find('>') {
-            let rect_attrs = &rect_match[..end_tag];
-
-            // Extract x, y, width, height attributes
-            let x = extract_attr_value(rect_attrs, "x").unwrap_or("0".to_string());
-            let y = extract_attr_value(rect_attrs, "y").unwrap_or("0".to_string());
-            let width = extract_attr_value(rect_attrs, "width");
-            let height = extract_attr_value(rect_attrs, "height");
-
-            if let (Some(width), Some(height)) = (width, height) {
-                if let (Ok(x), Ok(y), Ok(w), Ok(h)) = (x.parse::<f64>(), y.parse::<f64>(), width.parse::<f64>(), height.parse::<f64>()) {
-                    // Convert rect to 4 lines
-                    let x2 = x + w;
-                    let y2 = y + h;
+/// Split an SVG into one standalone document per top-level `<g>` group, named by the group's
+/// `id` (`layer-N` when it has none). Markup outside the top-level groups is left out.
+pub(crate) fn split_svg_layers(svg: &str) -> Vec<(String, String)> {
+    let Some(root_start) = svg.find("<svg") else {
+        return Vec::new();
+    };
+    let Some(root_len) = svg[root_start..].find('>') else {
+        return Vec::new();
+    };
+    let root_open = &svg[root_start..=root_start + root_len];
+
+    let mut layers = Vec::new();
+    // Element depth below the root <svg>, and the open top-level group
+    let mut depth = 0usize;
+    let mut group: Option<(String, usize)> = None;
+    let mut pos = root_start + root_len + 1;
+
+    while let Some(offset) = svg[pos..].find('<') {
+        let tag_start = pos + offset;
+        let rest = &svg[tag_start..];
+        let terminator = if rest.starts_with("<!--") { "-->" } else { ">" };
+        let Some(tag_len) = rest.find(terminator) else {
+            break;
+        };
+        let tag = &rest[..tag_len + terminator.len()];
+        pos = tag_start + tag.len();
 
-                    lines.extend(vec![
-                        Line { start: Point { x, y }, end: Point { x: x2, y }, is_load_bearing: false }, // top
-                        Line { start: Point { x: x2, y }, end: Point { x: x2, y: y2 }, is_load_bearing: false }, // right
-                        Line { start: Point { x: x2, y: y2 }, end: Point { x, y: y2 }, is_load_bearing: false }, // bottom
-                        Line { start: Point { x, y: y2 }, end: Point { x, y }, is_load_bearing: false }, // left
-                    ]);
+        if tag.starts_with("<!") || tag.starts_with("<?") || tag.ends_with("/>") {
+            continue;
+        }
+        if tag.starts_with("</") {
+            if depth == 0 {
+                break; // </svg>
+            }
+            depth -= 1;
+            if depth == 0 {
+                if let Some((name, start)) = group.take() {
+                    layers.push((name, format!("{}{}</svg>", root_open, &svg[start..pos])));
                 }
             }
+            continue;
         }
+
+        let is_group = tag[1..].starts_with('g')
+            && tag[2..].starts_with(|c: char| c.is_whitespace() || c == '>');
+        if depth == 0 && is_group {
+            let name = extract_attr_value(tag, "id")
+                .unwrap_or_else(|| format!("layer-{}", layers.len() + 1));
+            group = Some((name, tag_start));
+        }
+        depth += 1;
     }
 
-    Ok(lines)
+    layers
 }
 
 /// Extract attribute value from SVG element attributes
@@ -152,8 +630,19 @@ fn parse_path_commands(commands: &str) -> Vec<Line> {
     let mut current_pos = Point { x: 0.0, y: 0.0 };
     let mut path_start = current_pos.clone();
 
-    // Tokenize the path string
-    let tokens: Vec<&str> = commands
+    // Tokenize the path string, splitting command letters from numbers written against
+    // them ("M0 0 C66 0 ..."); `e`/`E` belong to exponents, not commands
+    let spaced: String = commands
+        .chars()
+        .flat_map(|c| {
+            if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+                vec![' ', c, ' ']
+            } else {
+                vec![c]
+            }
+        })
+        .collect();
+    let tokens: Vec<&str> = spaced
         .split(|c: char| c.is_whitespace() || c == ',')
         .filter(|s| !s.is_empty())
         .collect();
@@ -258,6 +747,29 @@ fn parse_path_commands(commands: &str) -> Vec<Line> {
                     i += 1;
                 }
             },
+            'C' | 'c' if i + 6 < tokens.len() => {
+                // Cubic Bezier, kept as the chord to its end point; VTracer's spline
+                // mode writes straight walls this way
+                let x = tokens[i + 5].parse::<f64>().unwrap_or(0.0);
+                let y = tokens[i + 6].parse::<f64>().unwrap_or(0.0);
+
+                let start = current_pos.clone();
+
+                if cmd.is_lowercase() {
+                    current_pos.x += x;
+                    current_pos.y += y;
+                } else {
+                    current_pos.x = x;
+                    current_pos.y = y;
+                }
+
+                lines.push(Line {
+                    start,
+                    end: current_pos.clone(),
+                    is_load_bearing: false,
+                });
+                i += 7;
+            },
             'Z' | 'z' => {
                 // Close path
                 if (current_pos.x - path_start.x).abs() > 0.1 ||
@@ -303,6 +815,185 @@ mod tests {
         assert_eq!(lines[0].end.x, 100.0);
     }
 
+    #[tokio::test]
+    async fn test_parse_vtracer_curve_path() {
+        let svg = r#"<path d="M0 0 C30 0 60 0 93 0 C93 30 93 60 93 90 Z " transform="translate(102,5)"/>"#;
+        let lines = parse_svg_to_lines(svg).unwrap();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!((lines[0].start.x, lines[0].start.y), (102.0, 5.0));
+        assert_eq!((lines[0].end.x, lines[0].end.y), (195.0, 5.0));
+        assert_eq!((lines[1].end.x, lines[1].end.y), (195.0, 95.0));
+    }
+
+    #[tokio::test]
+    async fn test_relative_cubic_parsed_as_chord() {
+        let lines = parse_path_commands("m10 10 c5 0 10 0 20 0 c0 5 0 10 0 20");
+
+        let ends: Vec<_> = lines.iter().map(|l| (l.start.x, l.start.y, l.end.x, l.end.y)).collect();
+        assert_eq!(ends, [(10.0, 10.0, 30.0, 10.0), (30.0, 10.0, 30.0, 30.0)]);
+
+        // A truncated curve is skipped rather than read past the end
+        assert!(parse_path_commands("M0 0 C10 0 20").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_path_tokens_split_at_commands_but_not_exponents() {
+        let lines = parse_path_commands("M0,0L1e2,0L100 1.5E2");
+
+        let ends: Vec<_> = lines.iter().map(|l| (l.start.x, l.start.y, l.end.x, l.end.y)).collect();
+        assert_eq!(ends, [(0.0, 0.0, 100.0, 0.0), (100.0, 0.0, 100.0, 150.0)]);
+    }
+
+    #[tokio::test]
+    async fn test_translate_offset() {
+        assert_eq!(translate_offset(r#" transform="translate(102,5)""#), (102.0, 5.0));
+        assert_eq!(translate_offset(r#" transform="translate(3 -4)""#), (3.0, -4.0));
+        assert_eq!(translate_offset(r#" transform="translate(7)""#), (7.0, 0.0));
+        // Other transforms are not applied
+        assert_eq!(translate_offset(r#" transform="rotate(45)""#), (0.0, 0.0));
+        assert_eq!(translate_offset(r#" d="M0 0""#), (0.0, 0.0));
+    }
+
+    fn blueprint_config() -> Config {
+        VTracerSettings::default().to_config()
+    }
+
+    fn line_of(x: f64) -> Vec<Line> {
+        vec![Line {
+            start: Point { x, y: 0.0 },
+            end: Point { x, y: 10.0 },
+            is_load_bearing: false,
+        }]
+    }
+
+    #[test]
+    async fn test_vectorize_cached_second_call_hits() {
+        // Unique image so other tests can't pre-populate the shared cache
+        let mut img = image::GrayImage::from_pixel(64, 64, image::Luma([255]));
+        for i in 0..64 {
+            img.put_pixel(i, 10, image::Luma([0]));
+            img.put_pixel(17, i, image::Luma([0]));
+        }
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+
+        let (first, first_hit) = vectorize_cached(&png, blueprint_config()).unwrap();
+        let (second, second_hit) = vectorize_cached(&png, blueprint_config()).unwrap();
+
+        assert!(!first_hit);
+        assert!(second_hit);
+        assert_eq!(first.len(), second.len());
+    }
+
+    /// 200x100 PNG with two walled rooms side by side
+    fn two_room_png() -> Vec<u8> {
+        let mut img = image::GrayImage::from_pixel(200, 100, image::Luma([255]));
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            if !(5..195).contains(&x) || !(5..95).contains(&y) || (98..102).contains(&x) {
+                *pixel = image::Luma([0]);
+            }
+        }
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+        png
+    }
+
+    #[test]
+    async fn test_both_backends_trace_two_room_raster() {
+        let png = two_room_png();
+        for backend in [VectorizerBackend::VTracer, VectorizerBackend::Potrace] {
+            let lines = backend.vectorizer().vectorize(&png).unwrap();
+            assert!(!lines.is_empty(), "{:?} produced no lines", backend);
+        }
+
+        // Outer outline plus one outline per room interior, four straight sides each
+        let potrace = PotraceVectorizer::default().vectorize(&png).unwrap();
+        assert_eq!(potrace.len(), 12);
+        assert!(potrace
+            .iter()
+            .all(|line| line.start.x == line.end.x || line.start.y == line.end.y));
+    }
+
+    #[test]
+    async fn test_higher_filter_speckle_drops_noise() {
+        let mut img = image::load_from_memory(&two_room_png()).unwrap().to_luma8();
+        // 6x6 specks: kept at the default speckle size of 4, dropped at 16
+        for (i, (x, y)) in [(20, 20), (40, 70), (60, 30), (130, 20), (150, 60), (170, 40)].into_iter().enumerate() {
+            let size = 6 + (i as u32 % 2);
+            for dy in 0..size {
+                for dx in 0..size {
+                    img.put_pixel(x + dx, y + dy, image::Luma([0]));
+                }
+            }
+        }
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+
+        let (noisy, _) = vectorize_cached(&png, VTracerSettings::default().to_config()).unwrap();
+        let filtered_settings = VTracerSettings {
+            filter_speckle: 16,
+            ..VTracerSettings::default()
+        };
+        let (filtered, _) = vectorize_cached(&png, filtered_settings.to_config()).unwrap();
+
+        assert!(!filtered.is_empty());
+        assert!(filtered.len() < noisy.len(), "{} >= {}", filtered.len(), noisy.len());
+    }
+
+    #[test]
+    async fn test_vtracer_settings_validation() {
+        assert!(VTracerSettings::default().validate().is_ok());
+
+        let settings: VTracerSettings = serde_json::from_str(r#"{"corner_threshold": 90, "mode": "polygon"}"#).unwrap();
+        assert_eq!(settings.mode, TraceMode::Polygon);
+        assert_eq!(settings.filter_speckle, 4);
+        assert!(settings.validate().is_ok());
+
+        for invalid in [
+            VTracerSettings { filter_speckle: 500, ..VTracerSettings::default() },
+            VTracerSettings { corner_threshold: 200, ..VTracerSettings::default() },
+            VTracerSettings { length_threshold: 1.0, ..VTracerSettings::default() },
+            VTracerSettings { path_precision: Some(12), ..VTracerSettings::default() },
+        ] {
+            assert!(invalid.validate().is_err(), "{:?} should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    async fn test_backend_parses_from_request_name() {
+        let backend: VectorizerBackend = serde_json::from_str(r#""potrace""#).unwrap();
+        assert_eq!(backend, VectorizerBackend::Potrace);
+        let backend: VectorizerBackend = serde_json::from_str(r#""vtracer""#).unwrap();
+        assert_eq!(backend, VectorizerBackend::VTracer);
+        assert_eq!(VectorizerBackend::default(), VectorizerBackend::VTracer);
+    }
+
+    #[test]
+    async fn test_cache_key_includes_config() {
+        let bytes = b"same image";
+        let mut other = blueprint_config();
+        other.filter_speckle = 8;
+
+        assert_eq!(cache_key(bytes, &blueprint_config()), cache_key(bytes, &blueprint_config()));
+        assert_ne!(cache_key(bytes, &blueprint_config()), cache_key(bytes, &other));
+    }
+
+    #[test]
+    async fn test_line_cache_evicts_least_recently_used() {
+        let mut cache = LineCache::new(2);
+        cache.insert([1; 32], line_of(1.0));
+        cache.insert([2; 32], line_of(2.0));
+        // Touch 1 so 2 becomes the eviction candidate
+        assert!(cache.get(&[1; 32]).is_some());
+        cache.insert([3; 32], line_of(3.0));
+
+        assert!(cache.get(&[2; 32]).is_none());
+        assert_eq!(cache.get(&[1; 32]).unwrap()[0].start.x, 1.0);
+        assert_eq!(cache.get(&[3; 32]).unwrap()[0].start.x, 3.0);
+        assert_eq!(cache.entries.len(), 2);
+    }
+
     #[tokio::test]
     async fn test_parse_svg_to_lines() {
         let svg = r#"<svg viewBox="0 0 400 300" xmlns="http://www.w3.org/2000/svg">
@@ -325,6 +1016,44 @@ mod tests {
         }
     }
 
+    #[test]
+    async fn test_min_stroke_width_drops_thin_annotation_lines() {
+        let svg = r#"<svg viewBox="0 0 400 300" xmlns="http://www.w3.org/2000/svg">
+            <line x1="0" y1="0" x2="300" y2="0" stroke="black" stroke-width="6"/>
+            <path d="M 0,20 L 300,20" style="stroke:black;stroke-width:0.5px"/>
+        </svg>"#;
+
+        assert_eq!(parse_svg_to_lines(svg).unwrap().len(), 2);
+
+        let walls = parse_svg_to_lines_with_min_stroke(svg, 2.0).unwrap();
+        assert_eq!(walls.len(), 1);
+        assert_eq!(walls[0].start.y, 0.0);
+        assert_eq!(walls[0].end.x, 300.0);
+    }
+
+    #[test]
+    async fn test_min_stroke_width_inherits_group_and_class_widths() {
+        let svg = r#"<svg viewBox="0 0 400 300" xmlns="http://www.w3.org/2000/svg">
+            <style>.dim, .note { stroke-width: 0.25 } .thick { stroke-width: 8px }</style>
+            <g stroke-width="6">
+                <g id="walls">
+                    <line x1="0" y1="0" x2="300" y2="0" stroke="black"/>
+                    <path d="M 0,100 L 300,100" class="dim thick"/>
+                </g>
+                <line x1="0" y1="200" x2="300" y2="200" class="dim"/>
+            </g>
+            <g stroke-width="0.5"><path d="M 0,20 L 300,20"/></g>
+            <line x1="0" y1="40" x2="300" y2="40" style="stroke-width:0.5" stroke-width="6"/>
+        </svg>"#;
+
+        assert_eq!(parse_svg_to_lines(svg).unwrap().len(), 5);
+
+        let walls = parse_svg_to_lines_with_min_stroke(svg, 2.0).unwrap();
+        let mut ys: Vec<f64> = walls.iter().map(|wall| wall.start.y).collect();
+        ys.sort_by(f64::total_cmp);
+        assert_eq!(ys, [0.0, 100.0]);
+    }
+
 #[tokio::test]
 async fn test_ai_parse_svg_to_lines() {
     // Requires OPENAI_API_KEY env var set
@@ -0,0 +1,277 @@
+use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+use crate::graph_builder::IncrementalGraph;
+use crate::{room_detector, Line, Room, MAX_LINES};
+
+/// One wall edit from the client; ids are chosen by the client and name a line until it is removed
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum LiveEdit {
+    Add { id: u64, line: Line },
+    Remove { id: u64 },
+    Clear,
+}
+
+/// Reply to every client message
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum LiveReply {
+    /// Rooms in the 0-1000 space of `/detect`, mapped from `source_bounds` of the current walls
+    Rooms {
+        rooms: Vec<Room>,
+        total_rooms: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source_bounds: Option<[f64; 4]>,
+    },
+    Error { error: String, message: String },
+}
+
+impl LiveReply {
+    fn error(error: &str, message: String) -> Self {
+        LiveReply::Error {
+            error: error.to_string(),
+            message,
+        }
+    }
+}
+
+/// Wall graph for one socket, updated in place as lines are added and removed
+struct LiveSession {
+    graph: IncrementalGraph,
+    lines: HashMap<u64, Line>,
+}
+
+impl Default for LiveSession {
+    fn default() -> Self {
+        Self {
+            graph: IncrementalGraph::new(0.0),
+            lines: HashMap::new(),
+        }
+    }
+}
+
+impl LiveSession {
+    /// Apply one text frame and describe the result; a bad frame leaves the session unchanged
+    fn apply_message(&mut self, text: &str) -> LiveReply {
+        let edit: LiveEdit = match serde_json::from_str(text) {
+            Ok(edit) => edit,
+            Err(e) => return LiveReply::error("INVALID_MESSAGE", format!("Could not parse edit: {}", e)),
+        };
+
+        let applied = match edit {
+            LiveEdit::Add { id, line } => self.add_line(id, line),
+            LiveEdit::Remove { id } => self.remove_line(id),
+            LiveEdit::Clear => {
+                *self = LiveSession::default();
+                Ok(())
+            }
+        };
+        if let Err(reply) = applied {
+            return reply;
+        }
+
+        let mut rooms = room_detector::detect_rooms(
+            self.graph.graph(),
+            crate::default_area_threshold(),
+            crate::default_outer_boundary_ratio(),
+        );
+        debug!("Live session has {} lines and {} rooms", self.lines.len(), rooms.len());
+        let lines: Vec<Line> = self.lines.values().cloned().collect();
+        let source_bounds = room_detector::lines_bounds(&lines);
+        if let Some(bounds) = source_bounds {
+            room_detector::normalize_rooms_to_1000(&mut rooms, bounds);
+        }
+        LiveReply::Rooms {
+            total_rooms: rooms.len(),
+            rooms,
+            source_bounds,
+        }
+    }
+
+    fn add_line(&mut self, id: u64, line: Line) -> Result<(), LiveReply> {
+        if self.lines.contains_key(&id) {
+            return Err(LiveReply::error("DUPLICATE_LINE", format!("Line {} already exists", id)));
+        }
+        if self.lines.len() >= MAX_LINES {
+            return Err(LiveReply::error(
+                "INPUT_TOO_LARGE",
+                format!("Too many lines. Maximum allowed: {}", MAX_LINES),
+            ));
+        }
+        if !line.start.is_valid() || !line.end.is_valid() {
+            return Err(LiveReply::error("INVALID_POINT", format!("Invalid point in line {}", id)));
+        }
+
+        self.graph.add_line(&line);
+        self.lines.insert(id, line);
+        Ok(())
+    }
+
+    fn remove_line(&mut self, id: u64) -> Result<(), LiveReply> {
+        let line = self
+            .lines
+            .remove(&id)
+            .ok_or_else(|| LiveReply::error("UNKNOWN_LINE", format!("No line with id {}", id)))?;
+        self.graph.remove_line(&line);
+        Ok(())
+    }
+}
+
+/// `GET /ws/detect`: stream wall edits and receive the updated rooms after each one
+pub async fn live_detect_handler(ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(run_session)
+}
+
+async fn run_session(mut socket: WebSocket) {
+    info!("Live detection session opened");
+    let mut session = LiveSession::default();
+
+    while let Some(Ok(message)) = socket.recv().await {
+        let reply = match message {
+            Message::Text(text) => session.apply_message(&text),
+            Message::Binary(_) => LiveReply::error("INVALID_MESSAGE", "Expected a JSON text frame".to_string()),
+            Message::Close(_) => break,
+            Message::Ping(_) | Message::Pong(_) => continue,
+        };
+
+        let Ok(json) = serde_json::to_string(&reply) else {
+            continue;
+        };
+        if socket.send(Message::Text(json)).await.is_err() {
+            break;
+        }
+    }
+
+    info!("Live detection session closed");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+    fn add(id: u64, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> String {
+        serde_json::json!({
+            "type": "add",
+            "id": id,
+            "line": { "start": { "x": x1, "y": y1 }, "end": { "x": x2, "y": y2 } },
+        })
+        .to_string()
+    }
+
+    fn square_edits() -> Vec<String> {
+        vec![
+            add(1, (0.0, 0.0), (100.0, 0.0)),
+            add(2, (100.0, 0.0), (100.0, 100.0)),
+            add(3, (100.0, 100.0), (0.0, 100.0)),
+            add(4, (0.0, 100.0), (0.0, 0.0)),
+        ]
+    }
+
+    fn total_rooms(reply: &LiveReply) -> usize {
+        match reply {
+            LiveReply::Rooms { total_rooms, .. } => *total_rooms,
+            LiveReply::Error { error, .. } => panic!("unexpected error reply {}", error),
+        }
+    }
+
+    #[test]
+    fn test_session_adds_and_removes_walls() {
+        let mut session = LiveSession::default();
+        let replies: Vec<LiveReply> = square_edits().iter().map(|edit| session.apply_message(edit)).collect();
+
+        assert_eq!(total_rooms(&replies[2]), 0);
+        assert_eq!(total_rooms(&replies[3]), 1);
+
+        let reply = session.apply_message(r#"{"type": "remove", "id": 4}"#);
+        assert_eq!(total_rooms(&reply), 0);
+        assert_eq!(session.graph.graph().edge_count(), 3);
+    }
+
+    #[test]
+    fn test_new_wall_endpoint_splits_existing_wall() {
+        let mut session = LiveSession::default();
+        for edit in square_edits() {
+            session.apply_message(&edit);
+        }
+
+        // A divider from the middle of the top wall to the middle of the bottom wall
+        let reply = session.apply_message(&add(5, (50.0, 0.0), (50.0, 100.0)));
+
+        assert_eq!(total_rooms(&reply), 2);
+        // Top and bottom walls in two pieces each, plus the divider
+        assert_eq!(session.graph.graph().edge_count(), 7);
+    }
+
+    #[test]
+    fn test_rooms_normalized_to_1000_per_axis() {
+        let mut session = LiveSession::default();
+        let edits = [
+            add(1, (0.0, 0.0), (200.0, 0.0)),
+            add(2, (200.0, 0.0), (200.0, 100.0)),
+            add(3, (200.0, 100.0), (0.0, 100.0)),
+            add(4, (0.0, 100.0), (0.0, 0.0)),
+        ];
+        let reply = edits.iter().map(|edit| session.apply_message(edit)).last().unwrap();
+
+        let LiveReply::Rooms { rooms, source_bounds, .. } = reply else {
+            panic!("expected rooms");
+        };
+        assert_eq!(rooms[0].bounding_box, [0.0, 0.0, 1000.0, 1000.0]);
+        assert_eq!(rooms[0].area, 20_000.0);
+        assert_eq!(source_bounds, Some([0.0, 0.0, 200.0, 100.0]));
+    }
+
+    #[test]
+    fn test_bad_messages_leave_session_unchanged() {
+        let mut session = LiveSession::default();
+        session.apply_message(&add(1, (0.0, 0.0), (100.0, 0.0)));
+
+        for (text, expected) in [
+            ("not json", "INVALID_MESSAGE"),
+            (r#"{"type": "remove", "id": 9}"#, "UNKNOWN_LINE"),
+            (add(1, (0.0, 0.0), (0.0, 100.0)).as_str(), "DUPLICATE_LINE"),
+        ] {
+            match session.apply_message(text) {
+                LiveReply::Error { error, .. } => assert_eq!(error, expected),
+                LiveReply::Rooms { .. } => panic!("{} should be rejected", text),
+            }
+        }
+        assert_eq!(session.graph.graph().edge_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_square_yields_room() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, crate::create_app()).await.unwrap() });
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/ws/detect", addr))
+            .await
+            .unwrap();
+
+        // A malformed frame gets an error reply and the socket stays open
+        socket.send(ClientMessage::Text("{".into())).await.unwrap();
+        let reply: serde_json::Value =
+            serde_json::from_str(socket.next().await.unwrap().unwrap().to_text().unwrap()).unwrap();
+        assert_eq!(reply["type"], "error");
+        assert_eq!(reply["error"], "INVALID_MESSAGE");
+
+        let mut last = serde_json::Value::Null;
+        for edit in square_edits() {
+            socket.send(ClientMessage::Text(edit)).await.unwrap();
+            last = serde_json::from_str(socket.next().await.unwrap().unwrap().to_text().unwrap()).unwrap();
+        }
+
+        assert_eq!(last["type"], "rooms");
+        assert_eq!(last["total_rooms"], 1);
+        assert!((last["rooms"][0]["area"].as_f64().unwrap() - 10_000.0).abs() < 1e-6);
+    }
+}
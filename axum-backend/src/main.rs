@@ -1,19 +1,24 @@
 use axum::{
-    extract::{DefaultBodyLimit, Json},
-    http::{header, Method, StatusCode},
-    response::IntoResponse,
+    body::Body,
+    extract::{DefaultBodyLimit, Json, Query},
+    http::{header, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Router,
 };
 use base64::Engine;
+use futures::StreamExt;
 use nalgebra::Point2;
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tracing::{info, warn};
-use std::process::{Command, Stdio};
-use std::io::Write;
-use std::time::Instant;
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
 
 mod graph_builder;
 mod room_detector;
@@ -24,16 +29,44 @@ mod wall_merger;
 mod connected_components;
 mod vector_graph;
 mod new_algorithms;
+mod metrics;
+mod request_id;
+mod progress;
+mod pdf_input;
+mod geojson;
+mod render;
+mod units;
+mod live_detect;
+mod outline_tracer;
+mod deep_health;
+mod shutdown;
+mod rate_limit;
+mod compare;
+mod idempotency;
+mod validated_json;
+mod geometry_classifier;
+#[cfg(test)]
+mod golden;
 
 use graph_builder::*;
-use room_detector::{detect_rooms, detect_rooms_simple};
+use room_detector::{detect_rooms, detect_rooms_simple, RoomKind};
 use new_algorithms::detect_rust_floodfill_handler;
 use vector_graph::detect_vector_graph_handler;
+use validated_json::ValidatedJson;
 
 // Security limits to prevent DoS attacks
 const MAX_LINES: usize = 10_000;
 const MAX_COORDINATE_VALUE: f64 = 1_000_000.0;
 const MIN_COORDINATE_VALUE: f64 = -1_000_000.0;
+const MAX_BATCH_IMAGES: usize = 100;
+/// Decoded image size limit unless `MAX_IMAGE_BYTES` overrides it
+const DEFAULT_MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+/// Room in the request body for JSON fields besides the base64 image
+const BODY_LIMIT_HEADROOM_BYTES: usize = 1024 * 1024;
+
+// Python subprocess limits
+const DEFAULT_PYTHON_CC_TIMEOUT_SECS: u64 = 30;
+const PYTHON_STDERR_LINES: usize = 20;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Point {
@@ -113,13 +146,32 @@ pub struct Line {
     pub is_load_bearing: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Room {
     pub id: usize,
     pub bounding_box: [f64; 4], // [min_x, min_y, max_x, max_y]
     pub area: f64,
     pub name_hint: String,
     pub points: Vec<Point>,
+    /// Only present when the request carried a `scale`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub area_sq_meters: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub area_sq_feet: Option<f64>,
+    #[serde(default)]
+    pub kind: RoomKind,
+    /// Geometric confidence in 0..1, for detectors that can judge their own output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    /// `points` index pairs whose edge is a load-bearing wall (graph detection only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub load_bearing_edges: Vec<[usize; 2]>,
+    /// Outline length in input units (graph detection only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub perimeter: Option<f64>,
+    /// Length of each edge between consecutive `points`, in input units
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wall_lengths: Vec<f64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -127,12 +179,43 @@ struct DetectRoomsRequest {
     lines: Vec<Line>,
     #[serde(default = "default_area_threshold")]
     area_threshold: f64,
-    #[serde(default = "default_door_threshold")]
-    door_threshold: f64,
+    /// Omitted or negative: estimated from the gaps between collinear walls
+    #[serde(default)]
+    door_threshold: Option<f64>,
     #[serde(default = "default_coverage_threshold")]
     coverage_threshold: f64,
     #[serde(default = "default_outer_boundary_ratio")]
     outer_boundary_ratio: f64,
+    /// Endpoints closer than this are merged before graph building (0 = off)
+    #[serde(default = "default_snap_tolerance")]
+    snap_tolerance: f64,
+    /// Lines shorter than this are dropped before snapping, e.g. vectorization speckle (0 = off)
+    #[serde(default)]
+    min_wall_length: f64,
+    /// Overrides the default room-naming heuristics
+    #[serde(default)]
+    naming_rules: Option<room_detector::NamingRules>,
+    /// Drawing-unit calibration for reporting areas in m² and ft²
+    #[serde(default)]
+    scale: Option<units::Scale>,
+    /// Douglas-Peucker tolerance for room outlines (0 = off)
+    #[serde(default)]
+    simplify_epsilon: f64,
+    /// Drop rooms classified as courtyards
+    #[serde(default)]
+    exclude_courtyards: bool,
+    /// Also return the building footprint as a room of kind `footprint`
+    #[serde(default)]
+    include_outer_boundary: bool,
+    /// Report node, component and dangling-wall counts for the built graph
+    #[serde(default)]
+    diagnostics: bool,
+    /// `faces` (default) or `dfs`, the exhaustive cycle search kept for comparison
+    #[serde(default)]
+    cycle_method: room_detector::CycleMethod,
+    /// Guess room types from size, shape and adjacency (no image needed)
+    #[serde(default)]
+    classify: bool,
 }
 
 fn default_area_threshold() -> f64 {
@@ -147,6 +230,10 @@ fn default_coverage_threshold() -> f64 {
     0.3  // 30% minimum height coverage for vertical dividers
 }
 
+fn default_snap_tolerance() -> f64 {
+    1.0
+}
+
 fn default_outer_boundary_ratio() -> f64 {
     1.5  // Outer boundary must be 1.5x larger than second-largest room
 }
@@ -155,6 +242,196 @@ fn default_outer_boundary_ratio() -> f64 {
 struct DetectRoomsResponse {
     rooms: Vec<Room>,
     total_rooms: usize,
+    summary: RoomSummary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<DetectRoomsMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diagnostics: Option<graph_builder::GraphDiagnostics>,
+    /// Why `rooms` is empty, when it is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<room_detector::EmptyReason>,
+    /// Geometric room-type guesses, when `classify` was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    classifications: Option<Vec<vision_classifier::RoomClassification>>,
+}
+
+impl DetectRoomsResponse {
+    /// Response for the rooms actually returned, so counts and summary always match them
+    fn new(mut rooms: Vec<Room>, metadata: Option<DetectRoomsMetadata>) -> Self {
+        room_detector::assign_spatial_ids(&mut rooms);
+        Self {
+            total_rooms: rooms.len(),
+            summary: summarize_rooms(&rooms),
+            rooms,
+            metadata,
+            diagnostics: None,
+            reason: None,
+            classifications: None,
+        }
+    }
+
+    /// Explain an empty result; ignored when rooms were found
+    fn with_reason(mut self, reason: Option<room_detector::EmptyReason>) -> Self {
+        self.reason = reason.filter(|_| self.rooms.is_empty());
+        self
+    }
+
+    /// Classify rooms by geometry and count them per type in the summary
+    fn with_classifications(mut self, classify: bool) -> Self {
+        if classify {
+            let classifications = geometry_classifier::classify_by_geometry(&self.rooms);
+            self.summary.rooms_by_type = count_room_types(classifications.iter().map(|c| Some(c.room_type.as_str())));
+            self.classifications = Some(classifications);
+        }
+        self
+    }
+
+    /// Scale rooms from input units into the shared 0-1000 space, recording the input bounds
+    fn normalized(mut self, bounds: Option<[f64; 4]>) -> Self {
+        if let Some(bounds) = bounds {
+            room_detector::normalize_rooms_to_1000(&mut self.rooms, bounds);
+        }
+        self.with_source_bounds(bounds)
+    }
+
+    /// Record the input extent of rooms already in 0-1000 space
+    fn with_source_bounds(mut self, bounds: Option<[f64; 4]>) -> Self {
+        if bounds.is_some() {
+            self.metadata.get_or_insert_with(Default::default).source_bounds = bounds;
+        }
+        self
+    }
+
+    fn with_diagnostics(mut self, diagnostics: Option<graph_builder::GraphDiagnostics>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Add real-world areas to every room and the summary total
+    fn with_units_per_meter(mut self, units_per_meter: Option<f64>) -> Self {
+        let Some(units_per_meter) = units_per_meter else {
+            return self;
+        };
+        for room in &mut self.rooms {
+            let sq_meters = units::area_sq_meters(room.area, units_per_meter);
+            room.area_sq_meters = Some(sq_meters);
+            room.area_sq_feet = Some(units::sq_meters_to_sq_feet(sq_meters));
+        }
+        let total_sq_meters = units::area_sq_meters(self.summary.total_area, units_per_meter);
+        self.summary.total_area_sq_meters = Some(total_sq_meters);
+        self.summary.total_area_sq_feet = Some(units::sq_meters_to_sq_feet(total_sq_meters));
+        self
+    }
+}
+
+/// Aggregate areas over a set of rooms, leaving out the footprint; all zero when there are no rooms
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+struct RoomSummary {
+    total_area: f64,
+    average_area: f64,
+    largest_area: f64,
+    smallest_area: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_area_sq_meters: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_area_sq_feet: Option<f64>,
+    /// Sum of room perimeters, so walls shared by two rooms count twice; absent when no room has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_wall_length: Option<f64>,
+    /// Room count per classified type; only filled in when types are known
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    rooms_by_type: BTreeMap<String, usize>,
+}
+
+fn summarize_rooms(rooms: &[Room]) -> RoomSummary {
+    // The footprint spans the other rooms, so counting it would double their area
+    let rooms: Vec<&Room> = rooms.iter().filter(|r| r.kind != RoomKind::Footprint).collect();
+    if rooms.is_empty() {
+        return RoomSummary::default();
+    }
+
+    let total_area: f64 = rooms.iter().map(|r| r.area).sum();
+    RoomSummary {
+        total_area,
+        average_area: total_area / rooms.len() as f64,
+        largest_area: rooms.iter().map(|r| r.area).fold(f64::MIN, f64::max),
+        smallest_area: rooms.iter().map(|r| r.area).fold(f64::MAX, f64::min),
+        total_area_sq_meters: None,
+        total_area_sq_feet: None,
+        total_wall_length: rooms
+            .iter()
+            .filter_map(|r| r.perimeter)
+            .reduce(|total, perimeter| total + perimeter),
+        rooms_by_type: BTreeMap::new(),
+    }
+}
+
+/// Count rooms per type, skipping unclassified rooms
+fn count_room_types<'a>(room_types: impl IntoIterator<Item = Option<&'a str>>) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for room_type in room_types.into_iter().flatten() {
+        *counts.entry(room_type.to_string()).or_default() += 1;
+    }
+    counts
+}
+
+#[derive(Debug, Default, Serialize)]
+struct DetectRoomsMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    door_threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    door_threshold_estimated: Option<bool>,
+    /// Input extent `[min_x, min_y, max_x, max_y]` in input units (pixels for images) that
+    /// room coordinates were normalized from into 0-1000
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_bounds: Option<[f64; 4]>,
+    /// With `cycle_method: dfs`, whether the search deadline passed before every cycle was
+    /// enumerated, so some rooms may be missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    truncated: Option<bool>,
+}
+
+/// Use the requested door threshold, or estimate one when omitted or negative
+fn resolve_door_threshold(requested: Option<f64>, lines: &[Line]) -> (f64, bool) {
+    match requested {
+        Some(threshold) if threshold >= 0.0 => (threshold, false),
+        _ => (graph_builder::estimate_door_threshold(lines), true),
+    }
+}
+
+/// Drawing units per meter for an optional request scale; a degenerate scale is a client error
+fn resolve_units_per_meter(scale: Option<&units::Scale>) -> Result<Option<f64>, (StatusCode, Json<ErrorResponse>)> {
+    let Some(scale) = scale else {
+        return Ok(None);
+    };
+    match scale.units_per_meter() {
+        Some(units_per_meter) => Ok(Some(units_per_meter)),
+        None => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_SCALE".to_string(),
+                message: "Scale must map a positive drawing length to a positive real-world distance".to_string(),
+            }),
+        )),
+    }
+}
+
+/// Requested VTracer settings, or the defaults, rejecting out-of-range values
+fn resolve_vtracer_settings(
+    settings: Option<image_vectorizer::VTracerSettings>,
+) -> Result<image_vectorizer::VTracerSettings, (StatusCode, Json<ErrorResponse>)> {
+    let settings = settings.unwrap_or_default();
+    settings.validate().map_err(|message| {
+        warn!("Invalid VTracer config: {}", message);
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_VTRACER_CONFIG".to_string(),
+                message,
+            }),
+        )
+    })?;
+    Ok(settings)
 }
 
 #[derive(Debug, Serialize)]
@@ -176,8 +453,11 @@ async fn health_check() -> impl IntoResponse {
     })
 }
 
+/// Interpreter used by `/detect/python-cc`
+const PYTHON_VENV_PATH: &str = ".venv/bin/python";
+
 async fn detect_rooms_simple_handler(
-    Json(request): Json<DetectRoomsRequest>,
+    ValidatedJson(request): ValidatedJson<DetectRoomsRequest>,
 ) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
     info!("Received simple detection request with {} lines", request.lines.len());
 
@@ -203,10 +483,7 @@ async fn detect_rooms_simple_handler(
 
     if request.lines.is_empty() {
         warn!("Empty lines input");
-        return Ok(Json(DetectRoomsResponse {
-            rooms: vec![],
-            total_rooms: 0,
-        }));
+        return Ok(Json(DetectRoomsResponse::new(vec![], None)));
     }
 
     // Validate points
@@ -231,21 +508,89 @@ async fn detect_rooms_simple_handler(
         }
     }
 
+    let units_per_meter = resolve_units_per_meter(request.scale.as_ref())?;
+
     // Use simplified divider-based detection
     let rooms = detect_rooms_simple(&request.lines, request.area_threshold, request.coverage_threshold);
     info!("Detected {} rooms using simple algorithm", rooms.len());
 
-    Ok(Json(DetectRoomsResponse {
-        total_rooms: rooms.len(),
-        rooms,
-    }))
+    Ok(Json(
+        DetectRoomsResponse::new(rooms, None)
+            .with_units_per_meter(units_per_meter)
+            .normalized(room_detector::lines_bounds(&request.lines)),
+    ))
 }
 
 async fn detect_rooms_handler(
+    Query(query): Query<geojson::FormatQuery>,
+    ValidatedJson(request): ValidatedJson<DetectRoomsRequest>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let result = detect_rooms_handler_inner(Json(request)).await;
+    let result = metrics::track_detection("detect", result, |response| response.total_rooms);
+    geojson::format_rooms_response(query.format, result)
+}
+
+async fn detect_rooms_handler_inner(
     Json(request): Json<DetectRoomsRequest>,
 ) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
     info!("Received detection request with {} lines", request.lines.len());
 
+    if request.lines.is_empty() {
+        warn!("Empty lines input");
+        return Ok(Json(
+            DetectRoomsResponse::new(vec![], None).with_reason(Some(room_detector::EmptyReason::NoLines)),
+        ));
+    }
+
+    validate_detect_request(&request)?;
+    let units_per_meter = resolve_units_per_meter(request.scale.as_ref())?;
+    let bounds = room_detector::lines_bounds(&request.lines);
+
+    let (graph, door_threshold, door_threshold_estimated) = build_detection_graph(&request);
+    let diagnostics = request.diagnostics.then(|| graph_builder::diagnose(&graph));
+
+    // Detect rooms using cycle detection (the working algorithm from room-detection-rust)
+    let naming_rules = request.naming_rules.unwrap_or_default();
+    let room_detector::RoomDetection { mut rooms, empty_reason, truncated } = room_detector::detect_rooms_explained(
+        &graph,
+        request.area_threshold,
+        1.5,
+        &naming_rules,
+        request.include_outer_boundary,
+        request.cycle_method,
+    );
+    info!("Detected {} rooms using GraphOnly cycle detection", rooms.len());
+
+    if request.exclude_courtyards {
+        rooms.retain(|room| room.kind != RoomKind::Courtyard);
+    }
+
+    if request.simplify_epsilon > 0.0 {
+        for room in &mut rooms {
+            room_detector::simplify_room(room, request.simplify_epsilon);
+        }
+    }
+
+    Ok(Json(
+        DetectRoomsResponse::new(
+            rooms,
+            Some(DetectRoomsMetadata {
+                door_threshold: Some(door_threshold),
+                door_threshold_estimated: Some(door_threshold_estimated),
+                source_bounds: None,
+                truncated: (request.cycle_method == room_detector::CycleMethod::Dfs).then_some(truncated),
+            }),
+        )
+        .with_units_per_meter(units_per_meter)
+        .normalized(bounds)
+        .with_classifications(request.classify)
+        .with_diagnostics(diagnostics)
+        .with_reason(empty_reason),
+    ))
+}
+
+/// Reject oversized inputs, bad area thresholds and non-finite points
+fn validate_detect_request(request: &DetectRoomsRequest) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
     // Validate input size to prevent DoS
     if request.lines.len() > MAX_LINES {
         warn!(
@@ -266,14 +611,6 @@ async fn detect_rooms_handler(
         ));
     }
 
-    if request.lines.is_empty() {
-        warn!("Empty lines input");
-        return Ok(Json(DetectRoomsResponse {
-            rooms: vec![],
-            total_rooms: 0,
-        }));
-    }
-
     // Validate area threshold
     if !request.area_threshold.is_finite() || request.area_threshold < 0.0 {
         return Err((
@@ -285,6 +622,26 @@ async fn detect_rooms_handler(
         ));
     }
 
+    if !request.simplify_epsilon.is_finite() || request.simplify_epsilon < 0.0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_EPSILON".to_string(),
+                message: "Simplify epsilon must be a non-negative finite number".to_string(),
+            }),
+        ));
+    }
+
+    if !request.min_wall_length.is_finite() || request.min_wall_length < 0.0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_MIN_WALL_LENGTH".to_string(),
+                message: "Minimum wall length must be a non-negative finite number".to_string(),
+            }),
+        ));
+    }
+
     // Validate all points
     for (idx, line) in request.lines.iter().enumerate() {
         if !line.start.is_valid() {
@@ -307,23 +664,86 @@ async fn detect_rooms_handler(
         }
     }
 
+    Ok(())
+}
+
+/// Drop short walls, snap endpoints and build the wall graph, returning the door threshold used
+/// and whether it was estimated
+fn build_detection_graph(request: &DetectRoomsRequest) -> (graph_builder::FloorplanGraph, f64, bool) {
+    let lines = graph_builder::drop_short_walls(&request.lines, request.min_wall_length);
+    let lines = graph_builder::snap_endpoints(&lines, request.snap_tolerance);
+
+    let (door_threshold, door_threshold_estimated) =
+        resolve_door_threshold(request.door_threshold, &lines);
+    if door_threshold_estimated {
+        info!("Estimated door threshold from wall gaps: {:.1}", door_threshold);
+    }
+
     // For JSON input, always use GraphOnly (cycle detection) - the algorithm that works
-    let graph = if request.door_threshold > 0.0 {
-        info!("Building graph with door threshold: {}", request.door_threshold);
-        graph_builder::build_graph_with_door_threshold(&request.lines, request.door_threshold)
+    let graph = if door_threshold > 0.0 {
+        info!("Building graph with door threshold: {}", door_threshold);
+        graph_builder::build_graph_with_door_threshold(&lines, door_threshold)
     } else {
-        graph_builder::build_graph(&request.lines)
+        graph_builder::build_graph(&lines)
     };
 
     info!("Built graph with {} nodes and {} edges", graph.node_count(), graph.edge_count());
 
-    // Detect rooms using cycle detection (the working algorithm from room-detection-rust)
-    let rooms = room_detector::detect_rooms(&graph, request.area_threshold, 1.5);
-    info!("Detected {} rooms using GraphOnly cycle detection", rooms.len());
+    (graph, door_threshold, door_threshold_estimated)
+}
+
+#[derive(Debug, Serialize)]
+struct DetectAdjacencyResponse {
+    rooms: Vec<Room>,
+    total_rooms: usize,
+    /// Room id pairs `[a, b]` (a < b) sharing a wall or a door gap
+    edges: Vec<(usize, usize)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<DetectRoomsMetadata>,
+}
+
+/// `POST /detect/adjacency`: detect rooms and which of them neighbor each other
+async fn detect_adjacency_handler(
+    ValidatedJson(request): ValidatedJson<DetectRoomsRequest>,
+) -> Result<Json<DetectAdjacencyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!("Received adjacency request with {} lines", request.lines.len());
+
+    if request.lines.is_empty() {
+        return Ok(Json(DetectAdjacencyResponse {
+            rooms: vec![],
+            total_rooms: 0,
+            edges: vec![],
+            metadata: None,
+        }));
+    }
+
+    validate_detect_request(&request)?;
+
+    let (graph, _, _) = build_detection_graph(&request);
+    let naming_rules = request.naming_rules.unwrap_or_default();
+    let mut rooms = room_detector::detect_rooms_with_naming(&graph, request.area_threshold, 1.5, &naming_rules, false);
+
+    let door_gaps: Vec<Line> = graph
+        .edge_weights()
+        .filter(|edge| edge.is_virtual())
+        .map(|edge| edge.line().clone())
+        .collect();
+    let edges = room_detector::compute_adjacency(&rooms, &door_gaps);
+    info!("Detected {} rooms with {} adjacencies", rooms.len(), edges.len());
+
+    let source_bounds = room_detector::lines_bounds(&request.lines);
+    if let Some(bounds) = source_bounds {
+        room_detector::normalize_rooms_to_1000(&mut rooms, bounds);
+    }
 
-    Ok(Json(DetectRoomsResponse {
+    Ok(Json(DetectAdjacencyResponse {
         total_rooms: rooms.len(),
         rooms,
+        edges,
+        metadata: Some(DetectRoomsMetadata {
+            source_bounds,
+            ..Default::default()
+        }),
     }))
 }
 
@@ -354,10 +774,7 @@ async fn detect_rooms_handler_old(
 
     if request.lines.is_empty() {
         warn!("Empty lines input");
-        return Ok(Json(DetectRoomsResponse {
-            rooms: vec![],
-            total_rooms: 0,
-        }));
+        return Ok(Json(DetectRoomsResponse::new(vec![], None)));
     }
 
     // Validate area threshold
@@ -394,9 +811,10 @@ async fn detect_rooms_handler_old(
     }
 
     // Build graph from lines with door gap detection
-    let graph = if request.door_threshold > 0.0 {
-        info!("Building graph with door threshold: {}", request.door_threshold);
-        graph_builder::build_graph_with_door_threshold(&request.lines, request.door_threshold)
+    let (door_threshold, _) = resolve_door_threshold(request.door_threshold, &request.lines);
+    let graph = if door_threshold > 0.0 {
+        info!("Building graph with door threshold: {}", door_threshold);
+        graph_builder::build_graph_with_door_threshold(&request.lines, door_threshold)
     } else {
         build_graph(&request.lines)
     };
@@ -406,9 +824,88 @@ async fn detect_rooms_handler_old(
     let rooms = detect_rooms(&graph, request.area_threshold, request.outer_boundary_ratio);
     info!("Detected {} rooms", rooms.len());
 
-    Ok(Json(DetectRoomsResponse {
-        total_rooms: rooms.len(),
-        rooms,
+    Ok(Json(DetectRoomsResponse::new(rooms, None)))
+}
+
+#[derive(Debug, Serialize)]
+struct CycleDebugInfo {
+    points: Vec<Point>,
+    area: f64,
+    is_outer_boundary: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct CycleDebugMetadata {
+    node_count: usize,
+    edge_count: usize,
+    max_cycles: usize,
+    max_cycle_length: usize,
+    cycle_limit_reached: bool,
+    unexplored_start_nodes: usize,
+    truncated_by_length: usize,
+    /// The search deadline passed before every cycle was enumerated
+    truncated: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DetectCyclesResponse {
+    cycles: Vec<CycleDebugInfo>,
+    total_cycles: usize,
+    metadata: CycleDebugMetadata,
+}
+
+/// Debug handler returning the raw cycles found before any room filtering
+async fn detect_cycles_handler(
+    ValidatedJson(request): ValidatedJson<DetectRoomsRequest>,
+) -> Result<Json<DetectCyclesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!("Received cycle debug request with {} lines", request.lines.len());
+
+    validate_detect_request(&request)?;
+    let (graph, _, _) = build_detection_graph(&request);
+    let enumeration = room_detector::enumerate_cycles(&graph);
+
+    let cycle_points: Vec<Vec<Point>> = enumeration
+        .cycles
+        .iter()
+        .map(|cycle| room_detector::cycle_points(&graph, cycle))
+        .collect();
+    let areas: Vec<f64> = cycle_points
+        .iter()
+        .map(|points| room_detector::calculate_polygon_area(points))
+        .collect();
+    let outer = room_detector::outer_boundary_index(&areas, request.outer_boundary_ratio);
+
+    let cycles: Vec<CycleDebugInfo> = cycle_points
+        .into_iter()
+        .zip(areas)
+        .enumerate()
+        .map(|(idx, (points, area))| CycleDebugInfo {
+            points,
+            area,
+            is_outer_boundary: outer == Some(idx),
+        })
+        .collect();
+
+    info!(
+        "Found {} raw cycles ({} truncated by length, limit reached: {})",
+        cycles.len(),
+        enumeration.truncated_by_length,
+        enumeration.cycle_limit_reached
+    );
+
+    Ok(Json(DetectCyclesResponse {
+        total_cycles: cycles.len(),
+        cycles,
+        metadata: CycleDebugMetadata {
+            node_count: graph.node_count(),
+            edge_count: graph.edge_count(),
+            max_cycles: room_detector::MAX_CYCLES,
+            max_cycle_length: room_detector::MAX_CYCLE_LENGTH,
+            cycle_limit_reached: enumeration.cycle_limit_reached,
+            unexplored_start_nodes: enumeration.unexplored_start_nodes,
+            truncated_by_length: enumeration.truncated_by_length,
+            truncated: enumeration.truncated,
+        },
     }))
 }
 
@@ -442,11 +939,24 @@ struct SvgDetectRequest {
     strategy: Option<detector_orchestrator::CombinationStrategy>,
     #[serde(default)]
     enable_vision: Option<bool>,
+    /// `algorithmic` (default), `ai` or `combined`
+    #[serde(default)]
+    svg_parser: detector_orchestrator::SvgParser,
+    /// Return the parsed wall segments in `metadata.parsed_walls`
+    #[serde(default)]
+    include_walls: bool,
+    /// Only keep elements whose `stroke-width` exceeds this (0 keeps all). Applies to the
+    /// algorithmic parser only: rejected with `ai`, and the `combined` AI side sees every element.
+    #[serde(default)]
+    min_stroke_width: f64,
+    /// Detect each top-level `<g>` group separately and key its rooms by group id in `layers`
+    #[serde(default)]
+    group_by_layer: bool,
 }
 
 /// Enhanced detection handler using the orchestrator
 async fn enhanced_detect_handler(
-    Json(request): Json<EnhancedDetectRequest>,
+    ValidatedJson(request): ValidatedJson<EnhancedDetectRequest>,
 ) -> Result<Json<detector_orchestrator::DetectionResult>, (StatusCode, Json<ErrorResponse>)> {
     info!("Received enhanced detection request with {} lines", request.lines.len());
 
@@ -477,7 +987,10 @@ async fn enhanced_detect_handler(
     // Decode image if provided
     let image_bytes = if let Some(ref b64) = request.image_base64 {
         match base64::engine::general_purpose::STANDARD.decode(b64) {
-            Ok(bytes) => Some(bytes),
+            Ok(bytes) => {
+                check_image_size(&bytes)?;
+                Some(bytes)
+            }
             Err(e) => {
                 return Err((
                     StatusCode::BAD_REQUEST,
@@ -502,7 +1015,13 @@ async fn enhanced_detect_handler(
             .strategy
             .unwrap_or(detector_orchestrator::CombinationStrategy::GraphOnly),
         confidence_threshold: 0.75, // Default for enhanced endpoint
+        min_wall_confidence: wall_merger::DEFAULT_MIN_WALL_CONFIDENCE,
         vision_model: std::env::var("VISION_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string()),
+        svg_parser: detector_orchestrator::SvgParser::default(),
+        vectorizer: image_vectorizer::VectorizerBackend::default(),
+        vtracer: image_vectorizer::VTracerSettings::default(),
+        min_stroke_width: 0.0,
+        wall_thickness_tolerance: None,
     };
 
     // Auto-enable vision if API key is set and strategy requires it
@@ -511,11 +1030,19 @@ async fn enhanced_detect_handler(
         detector_orchestrator::CombinationStrategy::GraphWithVision
             | detector_orchestrator::CombinationStrategy::BestAvailable
             | detector_orchestrator::CombinationStrategy::Ensemble
+            | detector_orchestrator::CombinationStrategy::HybridVision
     ) && std::env::var("OPENAI_API_KEY").is_ok()
     {
         config.enable_vision = true;
     }
 
+    // Rooms from line-based strategies are in input units; image strategies already normalize
+    let line_based = matches!(
+        config.strategy,
+        detector_orchestrator::CombinationStrategy::GraphOnly
+            | detector_orchestrator::CombinationStrategy::GraphWithVision
+    );
+
     // Create orchestrator and run detection
     let orchestrator = detector_orchestrator::DetectorOrchestrator::new(config);
 
@@ -523,7 +1050,10 @@ async fn enhanced_detect_handler(
         .detect_rooms(&request.lines, image_bytes.as_deref(), None)
         .await
     {
-        Ok(result) => {
+        Ok(mut result) => {
+            if let Some(bounds) = room_detector::lines_bounds(&request.lines).filter(|_| line_based) {
+                result.normalize_to_1000(bounds);
+            }
             info!(
                 "Enhanced detection completed: {} rooms, method: {}, time: {}ms",
                 result.rooms.len(),
@@ -545,9 +1075,45 @@ async fn enhanced_detect_handler(
     }
 }
 
+/// References to resources outside the document, e.g. `xlink:href="file:///etc/passwd"`
+fn external_href_regex() -> &'static regex::Regex {
+    static REGEX: OnceLock<regex::Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        regex::Regex::new(r#"(?i)\bhref\s*=\s*["']\s*(file|https?|ftp)://"#).expect("valid href regex")
+    })
+}
+
+/// Reject SVGs with DTDs (entity expansion / XXE) or links to external resources
+fn validate_svg_content(svg: &str) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let lower = svg.to_ascii_lowercase();
+    let reason = if lower.contains("<!doctype") {
+        Some("DOCTYPE declarations are not allowed")
+    } else if lower.contains("<!entity") {
+        Some("ENTITY declarations are not allowed")
+    } else if external_href_regex().is_match(svg) {
+        Some("external resource references (file://, http://) are not allowed")
+    } else {
+        None
+    };
+
+    match reason {
+        Some(reason) => {
+            warn!("Rejected SVG: {}", reason);
+            Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "UNSAFE_SVG".to_string(),
+                    message: format!("SVG rejected: {}", reason),
+                }),
+            ))
+        }
+        None => Ok(()),
+    }
+}
+
 /// SVG detection handler
 async fn svg_detect_handler(
-    Json(request): Json<SvgDetectRequest>,
+    ValidatedJson(request): ValidatedJson<SvgDetectRequest>,
 ) -> Result<Json<detector_orchestrator::DetectionResult>, (StatusCode, Json<ErrorResponse>)> {
     info!("Received SVG detection request with {} chars of SVG content", request.svg_content.len());
 
@@ -572,6 +1138,27 @@ async fn svg_detect_handler(
         ));
     }
 
+    validate_svg_content(&request.svg_content)?;
+
+    if !request.min_stroke_width.is_finite() || request.min_stroke_width < 0.0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_MIN_STROKE_WIDTH".to_string(),
+                message: "Minimum stroke width must be a non-negative finite number".to_string(),
+            }),
+        ));
+    }
+    if request.min_stroke_width > 0.0 && request.svg_parser == detector_orchestrator::SvgParser::Ai {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_MIN_STROKE_WIDTH".to_string(),
+                message: "Minimum stroke width is not supported by the ai SVG parser".to_string(),
+            }),
+        ));
+    }
+
     // Build orchestrator config
     let mut config = detector_orchestrator::DetectorConfig {
         area_threshold: request.area_threshold,
@@ -582,7 +1169,13 @@ async fn svg_detect_handler(
             .strategy
             .unwrap_or(detector_orchestrator::CombinationStrategy::SvgOnly),
         confidence_threshold: 0.75, // Default for SVG endpoint
+        min_wall_confidence: wall_merger::DEFAULT_MIN_WALL_CONFIDENCE,
         vision_model: std::env::var("VISION_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string()),
+        svg_parser: request.svg_parser,
+        vectorizer: image_vectorizer::VectorizerBackend::default(),
+        vtracer: image_vectorizer::VTracerSettings::default(),
+        min_stroke_width: request.min_stroke_width,
+        wall_thickness_tolerance: None,
     };
 
     // Auto-enable vision if API key is set and strategy requires it
@@ -594,20 +1187,48 @@ async fn svg_detect_handler(
         config.enable_vision = true;
     }
 
+    let layers = if request.group_by_layer {
+        let layers = image_vectorizer::split_svg_layers(&request.svg_content);
+        if layers.is_empty() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "NO_LAYERS".to_string(),
+                    message: "SVG has no top-level <g> groups to detect by layer".to_string(),
+                }),
+            ));
+        }
+        Some(layers)
+    } else {
+        None
+    };
+
     // Create orchestrator and run detection
     let orchestrator = detector_orchestrator::DetectorOrchestrator::new(config);
 
-    match orchestrator
-        .detect_rooms(&[], None, Some(&request.svg_content))
-        .await
-    {
-        Ok(result) => {
+    let detection = match &layers {
+        Some(layers) => orchestrator.detect_rooms_by_layer(layers).await,
+        None => {
+            orchestrator
+                .detect_rooms(&[], None, Some(&request.svg_content))
+                .await
+        }
+    };
+
+    match detection {
+        Ok(mut result) => {
             info!(
                 "SVG detection completed: {} rooms, method: {}, time: {}ms",
                 result.rooms.len(),
                 result.method_used,
                 result.execution_time_ms
             );
+            if let Some(bounds) = result.metadata.parsed_walls.as_deref().and_then(room_detector::lines_bounds) {
+                result.normalize_to_1000(bounds);
+            }
+            if !request.include_walls {
+                result.metadata.parsed_walls = None;
+            }
             Ok(Json(result))
         }
         Err(e) => {
@@ -623,11 +1244,196 @@ async fn svg_detect_handler(
     }
 }
 
-#[derive(Deserialize)]
-struct UploadImageRequest {
-    /// Base64 encoded image (PNG or JPEG)
-    image: String,
-    /// Area threshold for room detection
+/// Input for `/detect/auto`; whichever of `lines`, `svg_content` or `image` is present picks the algorithm
+#[derive(Debug, Deserialize)]
+struct AutoDetectRequest {
+    #[serde(default)]
+    lines: Vec<Line>,
+    #[serde(default)]
+    svg_content: Option<String>,
+    /// Base64-encoded raster image or SVG document
+    #[serde(default)]
+    image: Option<String>,
+    #[serde(default = "default_area_threshold")]
+    area_threshold: f64,
+    /// Omitted or negative: estimated from the gaps between collinear walls
+    #[serde(default)]
+    door_threshold: Option<f64>,
+    /// Applied when detection runs on `lines`
+    #[serde(default)]
+    scale: Option<units::Scale>,
+}
+
+/// Detection path taken by `/detect/auto`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum AutoDetectMethod {
+    Graph,
+    Svg,
+    ConnectedComponents,
+}
+
+#[derive(Debug, Serialize)]
+struct AutoDetectResponse {
+    #[serde(flatten)]
+    detection: DetectRoomsResponse,
+    method_used: AutoDetectMethod,
+    /// Graph detection found no rooms, so the image was used instead
+    fell_back: bool,
+}
+
+/// Whether decoded image bytes are an SVG document rather than a raster format
+fn looks_like_svg(bytes: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]);
+    let head = head.trim_start();
+    head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg"))
+}
+
+/// Detect rooms with whichever algorithm fits the input
+async fn detect_auto_handler(
+    ValidatedJson(request): ValidatedJson<AutoDetectRequest>,
+) -> Result<Json<AutoDetectResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let result = detect_auto_handler_inner(Json(request)).await;
+    metrics::track_detection("auto", result, |response| response.detection.total_rooms)
+}
+
+async fn detect_auto_handler_inner(
+    Json(request): Json<AutoDetectRequest>,
+) -> Result<Json<AutoDetectResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!(
+        "Received auto detection request (lines: {}, svg: {}, image: {})",
+        request.lines.len(),
+        request.svg_content.is_some(),
+        request.image.is_some()
+    );
+
+    let mut fell_back = false;
+    if !request.lines.is_empty() {
+        let Json(detection) = detect_rooms_handler_inner(Json(DetectRoomsRequest {
+            lines: request.lines,
+            area_threshold: request.area_threshold,
+            door_threshold: request.door_threshold,
+            coverage_threshold: default_coverage_threshold(),
+            outer_boundary_ratio: default_outer_boundary_ratio(),
+            snap_tolerance: default_snap_tolerance(),
+            min_wall_length: 0.0,
+            naming_rules: None,
+            scale: request.scale,
+            simplify_epsilon: 0.0,
+            exclude_courtyards: false,
+            include_outer_boundary: false,
+            diagnostics: false,
+            cycle_method: room_detector::CycleMethod::default(),
+            classify: false,
+        }))
+        .await?;
+
+        if detection.total_rooms > 0 || request.image.is_none() {
+            return Ok(Json(AutoDetectResponse {
+                detection,
+                method_used: AutoDetectMethod::Graph,
+                fell_back,
+            }));
+        }
+        info!("Graph detection found no rooms, falling back to the image");
+        fell_back = true;
+    }
+
+    if let Some(svg_content) = request.svg_content {
+        return detect_auto_svg(svg_content, request.area_threshold, fell_back).await;
+    }
+
+    let Some(image) = request.image else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "MISSING_INPUT".to_string(),
+                message: "Provide one of lines, svg_content or image".to_string(),
+            }),
+        ));
+    };
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(&image)
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "INVALID_BASE64".to_string(),
+                    message: format!("Failed to decode base64 image: {}", e),
+                }),
+            )
+        })?;
+
+    check_image_size(&bytes)?;
+
+    if looks_like_svg(&bytes) {
+        let svg_content = String::from_utf8_lossy(&bytes).into_owned();
+        return detect_auto_svg(svg_content, request.area_threshold, fell_back).await;
+    }
+
+    if let Err(e) = image::guess_format(&bytes) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "UNSUPPORTED_FORMAT".to_string(),
+                message: format!("Unrecognized image format: {}", e),
+            }),
+        ));
+    }
+
+    let Json(detection) = detect_rooms_connected_components_handler_inner(Json(ImageDetectRequest {
+        image,
+        threshold: default_threshold(),
+        min_area: default_min_area(),
+        max_area_ratio: default_max_area_ratio(),
+        connectivity: connected_components::Connectivity::default(),
+        max_dimension: default_max_dimension(),
+        threshold_mode: connected_components::ThresholdMode::default(),
+        block_size: default_block_size(),
+        adaptive_c: default_adaptive_c(),
+        vtracer_config: None,
+    }))
+    .await?;
+
+    Ok(Json(AutoDetectResponse {
+        detection,
+        method_used: AutoDetectMethod::ConnectedComponents,
+        fell_back,
+    }))
+}
+
+async fn detect_auto_svg(
+    svg_content: String,
+    area_threshold: f64,
+    fell_back: bool,
+) -> Result<Json<AutoDetectResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let Json(result) = svg_detect_handler(ValidatedJson(SvgDetectRequest {
+        svg_content,
+        area_threshold,
+        door_threshold: default_door_threshold(),
+        strategy: None,
+        enable_vision: None,
+        svg_parser: detector_orchestrator::SvgParser::default(),
+        include_walls: false,
+        min_stroke_width: 0.0,
+        group_by_layer: false,
+    }))
+    .await?;
+
+    let rooms = result.rooms.into_iter().map(|r| r.room).collect();
+    Ok(Json(AutoDetectResponse {
+        detection: DetectRoomsResponse::new(rooms, None),
+        method_used: AutoDetectMethod::Svg,
+        fell_back,
+    }))
+}
+
+#[derive(Deserialize)]
+struct UploadImageRequest {
+    /// Base64 encoded image (PNG or JPEG)
+    image: String,
+    /// Area threshold for room detection
     #[serde(default = "default_area_threshold")]
     area_threshold: f64,
     /// Door gap bridging threshold
@@ -655,6 +1461,7 @@ async fn upload_image_handler(
         })?;
 
     info!("Image decoded, size: {} bytes", image_bytes.len());
+    check_image_size(&image_bytes)?;
 
     // Vectorize image to extract lines
     let extracted_lines = tokio::spawn(async move {
@@ -716,6 +1523,9 @@ struct VectorizeBlueprintRequest {
     /// Confidence threshold for GPT-5 vision (0.0-1.0)
     #[serde(default = "default_confidence_threshold")]
     confidence_threshold: f64,
+    /// GPT-5-only walls below this confidence are dropped (0.0-1.0)
+    #[serde(default = "default_min_wall_confidence")]
+    min_wall_confidence: f64,
     /// Area threshold for room detection
     #[serde(default = "default_area_threshold")]
     area_threshold: f64,
@@ -725,6 +1535,19 @@ struct VectorizeBlueprintRequest {
     /// Vision model to use (gpt-4o-mini, gpt-4o, gpt-5)
     #[serde(default = "default_vision_model_api")]
     vision_model: String,
+    /// When false, return walls only and skip graph building and room detection
+    #[serde(default = "default_detect_rooms")]
+    detect_rooms: bool,
+    /// Raster tracing engine: vtracer (default) or potrace
+    #[serde(default)]
+    vectorizer: image_vectorizer::VectorizerBackend,
+    /// VTracer tuning; omitted fields keep the blueprint defaults
+    #[serde(default)]
+    vtracer_config: Option<image_vectorizer::VTracerSettings>,
+    /// Collapse the two traced faces of each wall into a centerline when they are at most this
+    /// many canvas pixels apart; omitted keeps both faces
+    #[serde(default)]
+    wall_thickness_tolerance: Option<f64>,
 }
 
 fn default_vision_model_api() -> String {
@@ -739,10 +1562,19 @@ fn default_confidence_threshold() -> f64 {
     0.75
 }
 
+fn default_min_wall_confidence() -> f64 {
+    wall_merger::DEFAULT_MIN_WALL_CONFIDENCE
+}
+
+fn default_detect_rooms() -> bool {
+    true
+}
+
 #[derive(Debug, Serialize)]
 struct VectorizeBlueprintResponse {
     walls: Vec<WallWithSource>,
     rooms: Vec<EnhancedRoomResponse>,
+    summary: RoomSummary,
     metadata: VectorizationMetadata,
 }
 
@@ -777,12 +1609,34 @@ struct VectorizationMetadata {
     gpt5_confidence: f64,
     method_used: String,
     execution_time_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vtracer_cache_hit: Option<bool>,
+    /// Estimated wall thickness in image pixels, when `wall_thickness_tolerance` was set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wall_thickness: Option<f64>,
 }
 
 async fn vectorize_blueprint_handler(
+    request: Json<VectorizeBlueprintRequest>,
+) -> Result<Json<VectorizeBlueprintResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let result = vectorize_blueprint_handler_inner(request).await;
+    metrics::track_detection("vectorize_blueprint", result, |response| response.rooms.len())
+}
+
+async fn vectorize_blueprint_handler_inner(
     Json(payload): Json<VectorizeBlueprintRequest>,
 ) -> Result<Json<VectorizeBlueprintResponse>, (StatusCode, Json<ErrorResponse>)> {
     info!("Received vectorize-blueprint request (strategy: {})", payload.strategy);
+    let vtracer_settings = resolve_vtracer_settings(payload.vtracer_config)?;
+    if payload.wall_thickness_tolerance.is_some_and(|t| !t.is_finite() || t <= 0.0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_WALL_THICKNESS_TOLERANCE".to_string(),
+                message: "Wall thickness tolerance must be a positive finite number".to_string(),
+            }),
+        ));
+    }
 
     // Decode base64 image
     let image_bytes = base64::engine::general_purpose::STANDARD
@@ -799,6 +1653,7 @@ async fn vectorize_blueprint_handler(
         })?;
 
     info!("Image decoded, size: {} bytes", image_bytes.len());
+    check_image_size(&image_bytes)?;
 
     // Create orchestrator with hybrid vision strategy
     let config = detector_orchestrator::DetectorConfig {
@@ -812,15 +1667,24 @@ async fn vectorize_blueprint_handler(
             _ => detector_orchestrator::CombinationStrategy::HybridVision,
         },
         confidence_threshold: payload.confidence_threshold,
+        min_wall_confidence: payload.min_wall_confidence,
         vision_model: payload.vision_model,
+        svg_parser: detector_orchestrator::SvgParser::default(),
+        vectorizer: payload.vectorizer,
+        vtracer: vtracer_settings,
+        min_stroke_width: 0.0,
+        wall_thickness_tolerance: payload.wall_thickness_tolerance,
     };
 
     let orchestrator = detector_orchestrator::DetectorOrchestrator::new(config);
 
     // Run detection (for hybrid vision, lines are extracted internally)
-    let result = orchestrator
-        .detect_rooms(&[], Some(&image_bytes), None)
-        .await
+    let result = if payload.detect_rooms {
+        orchestrator.detect_rooms(&[], Some(&image_bytes), None).await
+    } else {
+        orchestrator.extract_walls(&image_bytes).await
+    };
+    let result = result
         .map_err(|e| {
             warn!("Detection failed: {}", e);
             (
@@ -839,22 +1703,26 @@ async fn vectorize_blueprint_handler(
         result.execution_time_ms
     );
 
-    // Extract walls from metadata (if available from hybrid vision)
-    let walls: Vec<WallWithSource> = result
-        .metadata
-        .merged_walls
-        .as_ref()
-        .map(|merged_walls| {
-            merged_walls
-                .iter()
-                .map(|w| WallWithSource {
-                    start: Point { x: w.start.x, y: w.start.y },
-                    end: Point { x: w.end.x, y: w.end.y },
-                    source: w.source.clone(),
-                })
-                .collect()
-        })
-        .unwrap_or_default();
+    // Extract walls from metadata (merged walls from hybrid vision, or raw VTracer walls)
+    let walls: Vec<WallWithSource> = match (&result.metadata.merged_walls, &result.metadata.parsed_walls) {
+        (Some(merged_walls), _) => merged_walls
+            .iter()
+            .map(|w| WallWithSource {
+                start: Point { x: w.start.x, y: w.start.y },
+                end: Point { x: w.end.x, y: w.end.y },
+                source: w.source.clone(),
+            })
+            .collect(),
+        (None, Some(parsed_walls)) => parsed_walls
+            .iter()
+            .map(|w| WallWithSource {
+                start: w.start.clone(),
+                end: w.end.clone(),
+                source: Some("vtracer".to_string()),
+            })
+            .collect(),
+        (None, None) => Vec::new(),
+    };
 
     // Convert rooms to response format
     let rooms: Vec<EnhancedRoomResponse> = result
@@ -877,6 +1745,10 @@ async fn vectorize_blueprint_handler(
         })
         .collect();
 
+    let detected: Vec<Room> = result.rooms.iter().map(|r| r.room.clone()).collect();
+    let mut summary = summarize_rooms(&detected);
+    summary.rooms_by_type = count_room_types(result.rooms.iter().map(|r| r.room_type.as_deref()));
+
     // Build metadata from detection result
     let metadata = VectorizationMetadata {
         vtracer_walls_count: result.metadata.vtracer_walls_count.unwrap_or(0),
@@ -885,11 +1757,14 @@ async fn vectorize_blueprint_handler(
         gpt5_confidence: result.metadata.gpt5_confidence.unwrap_or(0.0),
         method_used: result.method_used,
         execution_time_ms: result.execution_time_ms,
+        vtracer_cache_hit: result.metadata.vtracer_cache_hit,
+        wall_thickness: result.metadata.wall_thickness,
     };
 
     Ok(Json(VectorizeBlueprintResponse {
         walls,
         rooms,
+        summary,
         metadata,
     }))
 }
@@ -899,30 +1774,112 @@ struct ImageDetectRequest {
     image: String,  // base64 encoded image
     #[serde(default = "default_threshold")]
     threshold: u8,
+    /// Smallest room in original-image pixels, rescaled along with the image when downscaled
     #[serde(default = "default_min_area")]
     min_area: usize,
     #[serde(default = "default_max_area_ratio")]
     max_area_ratio: f32,
+    /// Flood fill neighborhood ("four" or "eight")
+    #[serde(default)]
+    connectivity: connected_components::Connectivity,
+    /// Longest side images are downscaled to before flood fill (0 disables)
+    #[serde(default = "default_max_dimension")]
+    max_dimension: u32,
+    /// "global", "adaptive_mean" or "adaptive_gaussian"; adaptive suits unevenly lit scans
+    #[serde(default)]
+    threshold_mode: connected_components::ThresholdMode,
+    /// Odd neighborhood side for adaptive thresholding
+    #[serde(default = "default_block_size")]
+    block_size: u32,
+    /// Constant subtracted from the neighborhood mean for adaptive thresholding
+    #[serde(default = "default_adaptive_c")]
+    adaptive_c: i32,
+    /// VTracer tuning for the graph-image path
+    #[serde(default)]
+    vtracer_config: Option<image_vectorizer::VTracerSettings>,
+}
+
+impl ImageDetectRequest {
+    /// Thresholding settings for this request, rejecting even adaptive block sizes
+    fn threshold_config(&self) -> Result<connected_components::ThresholdConfig, (StatusCode, Json<ErrorResponse>)> {
+        if self.threshold_mode != connected_components::ThresholdMode::Global
+            && (self.block_size < 3 || self.block_size % 2 == 0)
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "INVALID_BLOCK_SIZE".to_string(),
+                    message: format!("block_size must be odd and at least 3, got {}", self.block_size),
+                }),
+            ));
+        }
+
+        Ok(connected_components::ThresholdConfig {
+            mode: self.threshold_mode,
+            threshold: self.threshold,
+            block_size: self.block_size,
+            c: self.adaptive_c,
+        })
+    }
 }
 
-fn default_threshold() -> u8 {
+pub(crate) fn default_threshold() -> u8 {
     200
 }
 
-fn default_min_area() -> usize {
+pub(crate) fn default_min_area() -> usize {
     200  // Much smaller for blueprint detection
 }
 
-fn default_max_area_ratio() -> f32 {
+pub(crate) fn default_max_area_ratio() -> f32 {
     0.3
 }
 
+pub(crate) fn default_max_dimension() -> u32 {
+    2000
+}
+
+fn default_block_size() -> u32 {
+    connected_components::DEFAULT_ADAPTIVE_BLOCK_SIZE
+}
+
+fn default_adaptive_c() -> i32 {
+    connected_components::DEFAULT_ADAPTIVE_C
+}
+
+/// Map rooms detected on a downscaled image back to original resolution
+///
+/// Room coordinates are normalized to 0-1000, so only pixel areas need rescaling.
+fn restore_room_scale(rooms: &mut [Room], scale: f64) {
+    let area_factor = 1.0 / (scale * scale);
+    for room in rooms {
+        room.area *= area_factor;
+        room.name_hint = connected_components::generate_room_name(room.area);
+    }
+}
+
+/// `min_area`, given in original-image pixels, as an area on an image downscaled by `scale`
+fn scale_min_area(min_area: usize, scale: f64) -> usize {
+    (min_area as f64 * scale * scale).round() as usize
+}
+
 /// Detect rooms using connected components on the image
 async fn detect_rooms_connected_components_handler(
+    Query(query): Query<geojson::FormatQuery>,
+    ValidatedJson(request): ValidatedJson<ImageDetectRequest>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let result = detect_rooms_connected_components_handler_inner(Json(request)).await;
+    let result = metrics::track_detection("connected_components", result, |response| response.total_rooms);
+    geojson::format_rooms_response(query.format, result)
+}
+
+async fn detect_rooms_connected_components_handler_inner(
     Json(request): Json<ImageDetectRequest>,
 ) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
     info!("Received original connected components detection request");
 
+    let threshold = request.threshold_config()?;
+
     // Decode base64 image
     let engine = base64::engine::general_purpose::STANDARD;
     let img_bytes = engine
@@ -938,6 +1895,7 @@ async fn detect_rooms_connected_components_handler(
         })?;
 
     info!("Image decoded, size: {} bytes", img_bytes.len());
+    check_image_size(&img_bytes)?;
 
     // Load image
     let img = image::load_from_memory(&img_bytes)
@@ -949,32 +1907,77 @@ async fn detect_rooms_connected_components_handler(
                     message: format!("Failed to load image: {}", e),
                 }),
             )
-        })?
-        .to_luma8();
+        })?;
 
     info!("Image loaded: {}x{}", img.width(), img.height());
+    let source_bounds = Some([0.0, 0.0, img.width() as f64, img.height() as f64]);
+
+    let (img, scale) = image_preprocessor::downscale_to_max_dimension(&img, request.max_dimension);
+    let img = img.to_luma8();
+    if scale < 1.0 {
+        info!("Downscaled to {}x{} (scale {:.3})", img.width(), img.height(), scale);
+    }
 
     // Detect rooms using original connected components
-    let rooms = connected_components::detect_rooms_connected_components(
+    let mut rooms = connected_components::detect_rooms_connected_components(
         &img,
-        request.threshold,
-        request.min_area,
+        threshold,
+        scale_min_area(request.min_area, scale),
         request.max_area_ratio,
+        request.connectivity,
     );
+    restore_room_scale(&mut rooms, scale);
 
     info!("Detected {} rooms using original connected components", rooms.len());
 
-    Ok(Json(DetectRoomsResponse {
-        total_rooms: rooms.len(),
-        rooms,
-    }))
+    Ok(Json(DetectRoomsResponse::new(rooms, None).with_source_bounds(source_bounds)))
+}
+
+#[derive(Debug, Deserialize)]
+struct EnhancedImageDetectRequest {
+    #[serde(flatten)]
+    base: ImageDetectRequest,
+    /// Side of the square closing kernel (3, 5 or 7); larger values merge more aggressively
+    #[serde(default = "default_morph_kernel_size")]
+    morph_kernel_size: u32,
+    /// Number of closing passes
+    #[serde(default = "default_morph_iterations")]
+    morph_iterations: u32,
+}
+
+fn default_morph_kernel_size() -> u32 {
+    connected_components::DEFAULT_MORPH_KERNEL_SIZE
+}
+
+fn default_morph_iterations() -> u32 {
+    connected_components::DEFAULT_MORPH_ITERATIONS
 }
 
 async fn detect_rooms_connected_components_enhanced_handler(
-    Json(request): Json<ImageDetectRequest>,
+    Query(query): Query<geojson::FormatQuery>,
+    ValidatedJson(request): ValidatedJson<EnhancedImageDetectRequest>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    geojson::format_rooms_response(query.format, detect_rooms_connected_components_enhanced_handler_inner(Json(request)).await)
+}
+
+async fn detect_rooms_connected_components_enhanced_handler_inner(
+    Json(request): Json<EnhancedImageDetectRequest>,
 ) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
     info!("Received enhanced connected components detection request");
 
+    if ![3, 5, 7].contains(&request.morph_kernel_size) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_KERNEL_SIZE".to_string(),
+                message: format!("morph_kernel_size must be 3, 5 or 7, got {}", request.morph_kernel_size),
+            }),
+        ));
+    }
+
+    let EnhancedImageDetectRequest { base: request, morph_kernel_size, morph_iterations } = request;
+    let threshold = request.threshold_config()?;
+
     // Decode base64 image
     let engine = base64::engine::general_purpose::STANDARD;
     let img_bytes = engine
@@ -990,6 +1993,7 @@ async fn detect_rooms_connected_components_enhanced_handler(
         })?;
 
     info!("Image decoded, size: {} bytes", img_bytes.len());
+    check_image_size(&img_bytes)?;
 
     // Load image
     let img = image::load_from_memory(&img_bytes)
@@ -1001,16 +2005,24 @@ async fn detect_rooms_connected_components_enhanced_handler(
                     message: format!("Failed to load image: {}", e),
                 }),
             )
-        })?
-        .to_luma8();
+        })?;
 
     info!("Image loaded: {}x{}", img.width(), img.height());
+    let source_bounds = Some([0.0, 0.0, img.width() as f64, img.height() as f64]);
+
+    let (img, scale) = image_preprocessor::downscale_to_max_dimension(&img, request.max_dimension);
+    let img = img.to_luma8();
 
     let start_time = Instant::now();
 
     // Enhanced flood fill with morphological operations
-    let binary = connected_components::threshold_image_enhanced(&img, request.threshold);
-    let components = connected_components::find_connected_components_enhanced(&binary, request.min_area, request.max_area_ratio);
+    let binary = connected_components::threshold_image_enhanced(
+        &img,
+        threshold,
+        morph_kernel_size,
+        morph_iterations,
+    );
+    let components = connected_components::find_connected_components_enhanced(&binary, scale_min_area(request.min_area, scale), request.max_area_ratio, request.connectivity);
     
     let mut rooms = Vec::new();
     let mut room_id = 0;
@@ -1039,89 +2051,180 @@ async fn detect_rooms_connected_components_enhanced_handler(
             area: *area as f64,
             name_hint: connected_components::generate_room_name(*area as f64),
             points,
+            ..Room::default()
         });
 
         room_id += 1;
     }
-    
+    restore_room_scale(&mut rooms, scale);
+
     let execution_time = start_time.elapsed().as_millis() as u64;
     info!("Detected {} rooms using enhanced connected components in {}ms", rooms.len(), execution_time);
 
-    Ok(Json(DetectRoomsResponse {
-        total_rooms: rooms.len(),
-        rooms,
-    }))
+    Ok(Json(DetectRoomsResponse::new(rooms, None).with_source_bounds(source_bounds)))
 }
 
-async fn detect_python_cc_handler(
-    Json(request): Json<ImageDetectRequest>,
-) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
-    info!("Received Python CC detection request");
+#[derive(Debug, Clone, Deserialize)]
+struct BatchDetectConfig {
+    #[serde(default = "default_threshold")]
+    threshold: u8,
+    #[serde(default = "default_min_area")]
+    min_area: usize,
+    #[serde(default = "default_max_area_ratio")]
+    max_area_ratio: f32,
+    #[serde(default)]
+    connectivity: connected_components::Connectivity,
+}
 
-    // Create JSON input for Python script
-    let input_json = serde_json::json!({
-        "image": request.image
-    });
+impl Default for BatchDetectConfig {
+    fn default() -> Self {
+        Self {
+            threshold: default_threshold(),
+            min_area: default_min_area(),
+            max_area_ratio: default_max_area_ratio(),
+            connectivity: connected_components::Connectivity::default(),
+        }
+    }
+}
 
-    // Call Python script via subprocess
-    let python_path = ".venv/bin/python";
-    let script_path = "room_detection_image_api.py";
+#[derive(Debug, Deserialize)]
+struct BatchDetectRequest {
+    /// Base64 encoded images (PNG or JPEG)
+    images: Vec<String>,
+    /// Detection settings shared by every image in the batch
+    #[serde(default)]
+    config: BatchDetectConfig,
+}
 
-    let mut child = Command::new(python_path)
-        .arg(script_path)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "PYTHON_EXEC_ERROR".to_string(),
-                    message: format!("Failed to spawn Python process: {}", e),
-                }),
+/// One NDJSON line of the batch response
+#[derive(Debug, Serialize)]
+struct BatchImageResult {
+    index: usize,
+    total_rooms: usize,
+    execution_time_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Decode and run connected components on a single batch image
+fn process_batch_image(index: usize, image: &str, config: &BatchDetectConfig) -> BatchImageResult {
+    let start_time = Instant::now();
+
+    let result = base64::engine::general_purpose::STANDARD
+        .decode(image)
+        .map_err(|e| format!("Failed to decode base64 image: {}", e))
+        .and_then(|bytes| {
+            check_image_size(&bytes).map_err(|(_, Json(error))| error.message)?;
+            image::load_from_memory(&bytes).map_err(|e| format!("Failed to load image: {}", e))
+        })
+        .map(|img| {
+            connected_components::detect_rooms_connected_components(
+                &img.to_luma8(),
+                config.threshold,
+                config.min_area,
+                config.max_area_ratio,
+                config.connectivity,
             )
-        })?;
+            .len()
+        });
 
-    // Write JSON to stdin
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin
-            .write_all(input_json.to_string().as_bytes())
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: "PYTHON_STDIN_ERROR".to_string(),
-                        message: format!("Failed to write to Python stdin: {}", e),
-                    }),
-                )
-            })?;
+    let execution_time_ms = start_time.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(total_rooms) => BatchImageResult {
+            index,
+            total_rooms,
+            execution_time_ms,
+            error: None,
+        },
+        Err(error) => {
+            warn!("Batch image {} failed: {}", index, error);
+            BatchImageResult {
+                index,
+                total_rooms: 0,
+                execution_time_ms,
+                error: Some(error),
+            }
+        }
     }
+}
 
-    // Wait for Python script to complete
-    let output = child.wait_with_output().map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "PYTHON_WAIT_ERROR".to_string(),
-                message: format!("Failed to wait for Python process: {}", e),
-            }),
-        )
-    })?;
+/// Batch connected components detection streamed back as newline-delimited JSON
+/// Images are decoded and processed one at a time so only one image is in flight
+async fn detect_batch_handler(
+    ValidatedJson(request): ValidatedJson<BatchDetectRequest>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    info!("Received batch detection request with {} images", request.images.len());
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    if request.images.len() > MAX_BATCH_IMAGES {
         return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_REQUEST,
             Json(ErrorResponse {
-                error: "PYTHON_SCRIPT_ERROR".to_string(),
-                message: format!("Python script failed: {}", stderr),
+                error: "INPUT_TOO_LARGE".to_string(),
+                message: format!(
+                    "Too many images. Maximum allowed: {}. Received: {}",
+                    MAX_BATCH_IMAGES,
+                    request.images.len()
+                ),
             }),
         ));
     }
 
+    let config = request.config;
+    let stream = futures::stream::iter(request.images.into_iter().enumerate()).then(move |(index, image)| {
+        let config = config.clone();
+        async move {
+            let result = tokio::task::spawn_blocking(move || process_batch_image(index, &image, &config))
+                .await
+                .unwrap_or_else(|e| BatchImageResult {
+                    index,
+                    total_rooms: 0,
+                    execution_time_ms: 0,
+                    error: Some(format!("Detection task failed: {}", e)),
+                });
+
+            let mut line = serde_json::to_vec(&result).unwrap_or_default();
+            line.push(b'\n');
+            Ok::<_, std::convert::Infallible>(line)
+        }
+    });
+
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(stream))
+        .expect("static response parts are valid"))
+}
+
+async fn detect_python_cc_handler(
+    Query(query): Query<geojson::FormatQuery>,
+    ValidatedJson(request): ValidatedJson<ImageDetectRequest>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    geojson::format_rooms_response(query.format, detect_python_cc_handler_inner(Json(request)).await)
+}
+
+async fn detect_python_cc_handler_inner(
+    Json(request): Json<ImageDetectRequest>,
+) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!("Received Python CC detection request");
+
+    // Create JSON input for Python script
+    let input_json = serde_json::json!({
+        "image": request.image
+    });
+
+    // Call Python script via subprocess
+    let script_path = "room_detection_image_api.py";
+
+    let stdout = run_python_script(
+        PYTHON_VENV_PATH,
+        script_path,
+        input_json.to_string().as_bytes(),
+        python_cc_timeout(),
+    )
+    .await?;
+
     // Parse Python output
-    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stdout = String::from_utf8_lossy(&stdout);
     let python_response: serde_json::Value = serde_json::from_str(&stdout).map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -1145,19 +2248,197 @@ async fn detect_python_cc_handler(
 
     info!("Detected {} rooms using Python CC", rooms.len());
 
-    Ok(Json(DetectRoomsResponse {
-        total_rooms: rooms.len(),
-        rooms,
-    }))
+    Ok(Json(DetectRoomsResponse::new(rooms, None)))
 }
 
 
 
+/// Decoded image size limit, overridable with `MAX_IMAGE_BYTES`
+fn max_image_bytes() -> usize {
+    std::env::var("MAX_IMAGE_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_IMAGE_BYTES)
+}
+
+/// Request body limit, overridable with `MAX_BODY_BYTES`
+///
+/// Defaults to the base64 size of the largest allowed image (4 chars per 3 bytes) plus
+/// headroom, so oversized images reach `check_image_size` instead of a bare body-limit 413.
+fn max_body_bytes() -> usize {
+    std::env::var("MAX_BODY_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| max_image_bytes().div_ceil(3) * 4 + BODY_LIMIT_HEADROOM_BYTES)
+}
+
+/// Reject decoded images over `max_image_bytes()` with a 413 naming both sizes
+fn check_image_size(bytes: &[u8]) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let max = max_image_bytes();
+    if bytes.len() <= max {
+        return Ok(());
+    }
+
+    warn!("Image rejected: {} bytes > {} allowed", bytes.len(), max);
+    Err((
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(ErrorResponse {
+            error: "IMAGE_TOO_LARGE".to_string(),
+            message: format!(
+                "Image is {} bytes ({:.1} MB); the maximum allowed is {} bytes ({:.1} MB)",
+                bytes.len(),
+                bytes.len() as f64 / (1024.0 * 1024.0),
+                max,
+                max as f64 / (1024.0 * 1024.0)
+            ),
+        }),
+    ))
+}
+
+/// Python CC timeout, overridable with `PYTHON_CC_TIMEOUT_SECS`
+fn python_cc_timeout() -> Duration {
+    let secs = std::env::var("PYTHON_CC_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_PYTHON_CC_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// First `PYTHON_STDERR_LINES` lines of a script's stderr for error messages
+fn stderr_excerpt(stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    let mut excerpt = lines
+        .iter()
+        .take(PYTHON_STDERR_LINES)
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+    if lines.len() > PYTHON_STDERR_LINES {
+        excerpt.push_str(&format!("\n... ({} more lines)", lines.len() - PYTHON_STDERR_LINES));
+    }
+    excerpt
+}
+
+/// Run a Python script with `input` on stdin and return its stdout
+///
+/// The child is killed if it runs longer than `timeout`, yielding a 504.
+async fn run_python_script(
+    python_path: &str,
+    script_path: &str,
+    input: &[u8],
+    timeout: Duration,
+) -> Result<Vec<u8>, (StatusCode, Json<ErrorResponse>)> {
+    let mut child = Command::new(python_path)
+        .arg(script_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "PYTHON_EXEC_ERROR".to_string(),
+                    message: format!("Failed to spawn Python process: {}", e),
+                }),
+            )
+        })?;
+
+    // Drain both pipes concurrently so a chatty script can't block on a full buffer
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stdout_task = tokio::spawn(async move {
+        let mut buf = Vec::new();
+        let _ = stdout.read_to_end(&mut buf).await;
+        buf
+    });
+    let stderr_task = tokio::spawn(async move {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf).await;
+        buf
+    });
+
+    let mut stdin = child.stdin.take();
+    let run = async {
+        // Write JSON to stdin, then close it so the script sees EOF
+        if let Some(mut stdin) = stdin.take() {
+            stdin.write_all(input).await.map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: "PYTHON_STDIN_ERROR".to_string(),
+                        message: format!("Failed to write to Python stdin: {}", e),
+                    }),
+                )
+            })?;
+        }
+        child.wait().await.map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "PYTHON_WAIT_ERROR".to_string(),
+                    message: format!("Failed to wait for Python process: {}", e),
+                }),
+            )
+        })
+    };
+
+    let status = match tokio::time::timeout(timeout, run).await {
+        Ok(status) => status?,
+        Err(_) => {
+            warn!("Python script {} timed out after {:?}, killing it", script_path, timeout);
+            let _ = child.kill().await;
+            // Grandchildren may still hold the pipe open, so don't wait on it for long
+            let stderr = tokio::time::timeout(Duration::from_secs(1), stderr_task)
+                .await
+                .ok()
+                .and_then(Result::ok)
+                .unwrap_or_default();
+            return Err((
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(ErrorResponse {
+                    error: "PYTHON_TIMEOUT".to_string(),
+                    message: format!(
+                        "Python script timed out after {}s: {}",
+                        timeout.as_secs_f64(),
+                        stderr_excerpt(&stderr)
+                    ),
+                }),
+            ));
+        }
+    };
+
+    let stdout = stdout_task.await.unwrap_or_default();
+    let stderr = stderr_task.await.unwrap_or_default();
+
+    if !status.success() {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "PYTHON_SCRIPT_ERROR".to_string(),
+                message: format!("Python script failed: {}", stderr_excerpt(&stderr)),
+            }),
+        ));
+    }
+
+    Ok(stdout)
+}
+
 /// Detect rooms using graph-based detection on rasterized image
 async fn detect_rooms_graph_image_handler(
+    Query(query): Query<geojson::FormatQuery>,
+    ValidatedJson(request): ValidatedJson<ImageDetectRequest>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    geojson::format_rooms_response(query.format, detect_rooms_graph_image_handler_inner(Json(request)).await)
+}
+
+async fn detect_rooms_graph_image_handler_inner(
     Json(request): Json<ImageDetectRequest>,
 ) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
     info!("Received graph-image detection request");
+    let vtracer_settings = resolve_vtracer_settings(request.vtracer_config)?;
 
     // Decode base64 image
     let engine = base64::engine::general_purpose::STANDARD;
@@ -1174,6 +2455,7 @@ async fn detect_rooms_graph_image_handler(
         })?;
 
     info!("Image decoded, size: {} bytes", img_bytes.len());
+    check_image_size(&img_bytes)?;
 
     // Use VTracer to extract lines from the image
     let config = detector_orchestrator::DetectorConfig {
@@ -1183,7 +2465,13 @@ async fn detect_rooms_graph_image_handler(
         enable_yolo: false,
         strategy: detector_orchestrator::CombinationStrategy::VTracerOnly,
         confidence_threshold: 0.75,
+        min_wall_confidence: wall_merger::DEFAULT_MIN_WALL_CONFIDENCE,
         vision_model: "gpt-4o-mini".to_string(),
+        svg_parser: detector_orchestrator::SvgParser::default(),
+        vectorizer: image_vectorizer::VectorizerBackend::default(),
+        vtracer: vtracer_settings,
+        min_stroke_width: 0.0,
+        wall_thickness_tolerance: None,
     };
 
     let orchestrator = detector_orchestrator::DetectorOrchestrator::new(config);
@@ -1210,10 +2498,7 @@ async fn detect_rooms_graph_image_handler(
 
     info!("Detected {} rooms using graph-image detection", rooms.len());
 
-    Ok(Json(DetectRoomsResponse {
-        total_rooms: rooms.len(),
-        rooms,
-    }))
+    Ok(Json(DetectRoomsResponse::new(rooms, None)))
 }
 
 /// Create the Axum app with all routes and middleware
@@ -1267,52 +2552,142 @@ async fn gpt4o_validation_handler(
     }
 }
 
-use tower_http::{services::ServeDir, trace::TraceLayer};
+use tower_http::{
+    compression::{
+        predicate::{DefaultPredicate, NotForContentType, Predicate},
+        CompressionLayer,
+    },
+    services::ServeDir,
+    trace::TraceLayer,
+};
 use std::net::SocketAddr;
 
-pub fn create_app() -> Router {
-    // Configure CORS from environment or use localhost for development
-    let allowed_origins = std::env::var("ALLOWED_ORIGINS")
-        .unwrap_or_else(|_| "http://localhost:8080,http://127.0.0.1:8080,http://localhost:8081,http://127.0.0.1:8081,http://localhost:8082,http://127.0.0.1:8082,http://localhost:9090,http://127.0.0.1:9090".to_string());
+/// Methods allowed cross-origin when `ALLOWED_METHODS` is unset or has no valid entries
+const DEFAULT_CORS_METHODS: [Method; 2] = [Method::GET, Method::POST];
+
+/// Whether `origin` matches an allowlist entry; `*.example.com` (optionally with a
+/// scheme, e.g. `https://*.example.com`) matches any subdomain but not the bare domain
+fn origin_matches(pattern: &str, origin: &str) -> bool {
+    let Some((prefix, suffix)) = pattern.split_once("*.") else {
+        return pattern == origin;
+    };
 
-    let origins: Vec<_> = allowed_origins
+    let host = match prefix.strip_suffix("://") {
+        Some(scheme) => match origin.strip_prefix(scheme).and_then(|rest| rest.strip_prefix("://")) {
+            Some(host) => host,
+            None => return false,
+        },
+        None if prefix.is_empty() => origin.split_once("://").map_or(origin, |(_, host)| host),
+        None => return false,
+    };
+
+    host.strip_suffix(suffix)
+        .and_then(|subdomain| subdomain.strip_suffix('.'))
+        .is_some_and(|subdomain| !subdomain.is_empty())
+}
+
+/// CORS layer for comma-separated origin patterns and methods
+///
+/// Exact origins are matched as a list; any `*.` wildcard switches to a predicate that
+/// checks every pattern. No valid origins falls back to allowing any origin.
+fn cors_layer(allowed_origins: &str, allowed_methods: &str) -> CorsLayer {
+    let patterns: Vec<String> = allowed_origins
         .split(',')
-        .filter_map(|s| s.trim().parse().ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
         .collect();
 
-    let cors = if origins.is_empty() {
-        // Fallback to Any only if no valid origins configured (not recommended for production)
-        CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods([Method::GET, Method::POST])
-            .allow_headers([header::CONTENT_TYPE])
+    let allow_origin = if patterns.iter().any(|p| p.contains("*.")) {
+        AllowOrigin::predicate(move |origin: &HeaderValue, _: &axum::http::request::Parts| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| patterns.iter().any(|pattern| origin_matches(pattern, origin)))
+        })
     } else {
-        CorsLayer::new()
-            .allow_origin(origins)
-            .allow_methods([Method::GET, Method::POST])
-            .allow_headers([header::CONTENT_TYPE])
+        let origins: Vec<HeaderValue> = patterns.iter().filter_map(|s| s.parse().ok()).collect();
+        if origins.is_empty() {
+            // Fallback to Any only if no valid origins configured (not recommended for production)
+            AllowOrigin::any()
+        } else {
+            AllowOrigin::list(origins)
+        }
     };
 
+    let mut methods: Vec<Method> = allowed_methods
+        .split(',')
+        .filter_map(|m| Method::from_bytes(m.trim().to_ascii_uppercase().as_bytes()).ok())
+        .collect();
+    if methods.is_empty() {
+        methods = DEFAULT_CORS_METHODS.to_vec();
+    }
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(methods)
+        .allow_headers([header::CONTENT_TYPE, request_id::X_REQUEST_ID, idempotency::IDEMPOTENCY_KEY])
+        .expose_headers([request_id::X_REQUEST_ID, idempotency::IDEMPOTENT_REPLAYED])
+}
+
+pub fn create_app() -> Router {
+    create_app_with_rate_limit(
+        rate_limit::RateLimiter::new(rate_limit::rate_limit_per_minute())
+            .trusting_proxy(rate_limit::trust_proxy_headers()),
+    )
+}
+
+fn create_app_with_rate_limit(limiter: rate_limit::RateLimiter) -> Router {
+    // Configure CORS from environment or use localhost for development
+    let allowed_origins = std::env::var("ALLOWED_ORIGINS")
+        .unwrap_or_else(|_| "http://localhost:8080,http://127.0.0.1:8080,http://localhost:8081,http://127.0.0.1:8081,http://localhost:8082,http://127.0.0.1:8082,http://localhost:9090,http://127.0.0.1:9090".to_string());
+    let allowed_methods = std::env::var("ALLOWED_METHODS").unwrap_or_default();
+    let cors = cors_layer(&allowed_origins, &allowed_methods);
+
     let api_router = Router::new()
         .route("/health", get(health_check))
+        .route("/health/deep", get(deep_health::deep_health_handler))
         .route("/detect", post(detect_rooms_handler))
+        .route("/detect/auto", post(detect_auto_handler))
+        .route("/detect/compare", post(compare::compare_handler))
         .route("/detect/simple", post(detect_rooms_simple_handler))
+        .route("/detect/cycles", post(detect_cycles_handler))
+        .route("/detect/adjacency", post(detect_adjacency_handler))
         .route("/detect/enhanced", post(enhanced_detect_handler))
         .route("/detect/svg", post(svg_detect_handler))
         .route("/detect/connected-components", post(detect_rooms_connected_components_handler))
+        .route("/detect/connected-components-enhanced", post(detect_rooms_connected_components_enhanced_handler))
         .route("/detect/rust-floodfill", post(detect_rust_floodfill_handler))
         .route("/detect/vector-graph", post(detect_vector_graph_handler))
         .route("/detect/graph-image", post(detect_rooms_graph_image_handler))
         .route("/detect/python-cc", post(detect_python_cc_handler))
+        .route("/detect/batch", post(detect_batch_handler))
+        .route("/detect/pdf", post(pdf_input::detect_pdf_handler))
+        .route("/detect/async", post(progress::detect_async_handler))
+        .route("/detect/progress/:job_id", get(progress::detect_progress_handler))
+        .route("/ws/detect", get(live_detect::live_detect_handler))
+        .route("/render/svg", post(render::render_svg_handler))
         .route("/upload-image", post(upload_image_handler))
         .route("/vectorize-blueprint", post(vectorize_blueprint_handler))
         .route("/validate/gpt4o", post(gpt4o_validation_handler))
         .route("/test", get(test_handler))
+        .route_layer(axum::middleware::from_fn_with_state(
+            idempotency::IdempotencyCache::new(idempotency::idempotency_ttl())
+                .trusting_proxy(rate_limit::trust_proxy_headers()),
+            idempotency::replay_idempotent,
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(limiter, rate_limit::limit_requests))
+        .route_layer(axum::middleware::from_fn(metrics::track_requests))
+        .layer(axum::middleware::from_fn(request_id::propagate_request_id))
         .layer(TraceLayer::new_for_http())
-        .layer(DefaultBodyLimit::max(10 * 1024 * 1024)); // 10MB max for images
+        // gzip/deflate by `Accept-Encoding`. Streams are left uncompressed so each SSE event
+        // and NDJSON batch line reaches the client as soon as it is written.
+        .layer(CompressionLayer::new().compress_when(
+            DefaultPredicate::new().and(NotForContentType::const_new("application/x-ndjson")),
+        ))
+        .layer(DefaultBodyLimit::max(max_body_bytes()));
 
     // Create main router with API routes
     let app = Router::new()
+        .route("/metrics", get(metrics::metrics_handler))
         .nest("/api", api_router)
         .layer(cors);
 
@@ -1340,5 +2715,1085 @@ async fn main() {
     info!("Server listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    shutdown::serve_with_drain(listener, app, shutdown::shutdown_signal(), shutdown::drain_timeout())
+        .await
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_counts_detect_requests() {
+        let app = create_app();
+
+        let body = serde_json::json!({
+            "lines": [
+                {"start": {"x": 0.0, "y": 0.0}, "end": {"x": 100.0, "y": 0.0}},
+                {"start": {"x": 100.0, "y": 0.0}, "end": {"x": 100.0, "y": 100.0}},
+                {"start": {"x": 100.0, "y": 100.0}, "end": {"x": 0.0, "y": 100.0}},
+                {"start": {"x": 0.0, "y": 100.0}, "end": {"x": 0.0, "y": 0.0}}
+            ]
+        });
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post("/api/detect")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let content_type = response.headers()[header::CONTENT_TYPE].to_str().unwrap().to_string();
+        assert!(content_type.starts_with("text/plain"));
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(text.contains(r#"http_requests_total{method="POST",path="/api/detect",status="200"}"#));
+        assert!(text.contains(r#"detections_total{endpoint="detect"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_rejects_request_over_limit_per_ip() {
+        let limit = 3;
+        let app = create_app_with_rate_limit(rate_limit::RateLimiter::new(limit).trusting_proxy(true));
+        let body = serde_json::json!({
+            "lines": [
+                {"start": {"x": 0.0, "y": 0.0}, "end": {"x": 100.0, "y": 0.0}},
+                {"start": {"x": 100.0, "y": 0.0}, "end": {"x": 100.0, "y": 100.0}},
+                {"start": {"x": 100.0, "y": 100.0}, "end": {"x": 0.0, "y": 100.0}},
+                {"start": {"x": 0.0, "y": 100.0}, "end": {"x": 0.0, "y": 0.0}}
+            ]
+        });
+        let detect_from = |ip: &str| {
+            Request::post("/api/detect")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header("x-forwarded-for", ip)
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        for _ in 0..limit {
+            let response = app.clone().oneshot(detect_from("203.0.113.7")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app.clone().oneshot(detect_from("203.0.113.7")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert!(retry_after >= 1);
+
+        // Other clients and health checks are unaffected
+        let response = app.clone().oneshot(detect_from("198.51.100.1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let health = Request::get("/api/health")
+            .header("x-forwarded-for", "203.0.113.7")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(app.oneshot(health).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_detect_classifies_rooms_by_geometry() {
+        // Living room and bedroom over a full-width hallway
+        let lines = vec![
+            wall(0.0, 0.0, 1000.0, 0.0),
+            wall(1000.0, 0.0, 1000.0, 500.0),
+            wall(1000.0, 500.0, 0.0, 500.0),
+            wall(0.0, 500.0, 0.0, 0.0),
+            wall(0.0, 400.0, 1000.0, 400.0),
+            wall(600.0, 0.0, 600.0, 400.0),
+        ];
+        let request = |classify: bool| {
+            Request::post("/api/detect")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "lines": lines, "door_threshold": 0.0, "classify": classify }).to_string(),
+                ))
+                .unwrap()
+        };
+
+        let response = create_app().oneshot(request(true)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        let classifications = json["classifications"].as_array().unwrap();
+        assert_eq!(classifications.len(), 3);
+        let hallway = json["rooms"].as_array().unwrap().iter().find(|r| r["area"] == 100000.0).unwrap();
+        let hallway_type = classifications.iter().find(|c| c["room_id"] == hallway["id"]).unwrap();
+        assert_eq!(hallway_type["room_type"], "hallway");
+        assert_eq!(json["summary"]["rooms_by_type"]["hallway"], 1);
+        assert_eq!(json["summary"]["rooms_by_type"]["living_room"], 1);
+
+        let response = create_app().oneshot(request(false)).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(json.get("classifications").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_large_detection_gzip_compressed_when_accepted() {
+        // 15x15 grid of rooms, each with its own point list
+        let mut lines = Vec::new();
+        for i in 0..=15 {
+            for j in 0..15 {
+                let (i, j) = (i as f64 * 100.0, j as f64 * 100.0);
+                lines.push(serde_json::json!({"start": {"x": j, "y": i}, "end": {"x": j + 100.0, "y": i}}));
+                lines.push(serde_json::json!({"start": {"x": i, "y": j}, "end": {"x": i, "y": j + 100.0}}));
+            }
+        }
+        let body = serde_json::json!({ "lines": lines, "door_threshold": 0.0 }).to_string();
+        let detect = |encoding: Option<&str>| {
+            let mut request = Request::post("/api/detect").header(header::CONTENT_TYPE, "application/json");
+            if let Some(encoding) = encoding {
+                request = request.header(header::ACCEPT_ENCODING, encoding);
+            }
+            request.body(Body::from(body.clone())).unwrap()
+        };
+        let app = create_app();
+
+        let response = app.clone().oneshot(detect(Some("gzip"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        let compressed = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut json = String::new();
+        std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(&compressed[..]), &mut json).unwrap();
+        let detection: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(detection["total_rooms"], 225);
+        assert!(compressed.len() * 4 < json.len(), "{} bytes compressed from {}", compressed.len(), json.len());
+
+        let response = app.clone().oneshot(detect(Some("deflate"))).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "deflate");
+
+        let response = app.oneshot(detect(None)).await.unwrap();
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_streamed_responses_not_compressed() {
+        let app = create_app();
+        let post = |uri: &str, body: serde_json::Value| {
+            Request::post(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::ACCEPT_ENCODING, "gzip")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let image = base64::engine::general_purpose::STANDARD.encode(four_room_png());
+
+        let response = app
+            .clone()
+            .oneshot(post("/api/detect/batch", serde_json::json!({ "images": [image.clone(), image.clone()] })))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/x-ndjson");
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(String::from_utf8(bytes.to_vec()).unwrap().lines().count(), 2);
+
+        let submit = Request::post("/api/detect/async")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::json!({ "image": image }).to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(submit).await.unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let job: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let progress = Request::get(format!("/api{}", job["progress_url"].as_str().unwrap()))
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(progress).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_malformed_detect_body_reports_offending_field() {
+        let app = create_app();
+        let post = |body: serde_json::Value| {
+            Request::post("/api/detect")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let error_of = |response: Response| async move {
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+        };
+
+        let response = app.clone().oneshot(post(serde_json::json!({ "lines": "walls" }))).await.unwrap();
+        let error = error_of(response).await;
+        assert_eq!(error["error"], "VALIDATION_ERROR");
+        let message = error["message"].as_str().unwrap();
+        assert!(message.contains("`lines`") && message.contains("expected a sequence"), "{}", message);
+
+        let body = serde_json::json!({ "lines": [{ "start": { "x": 0.0, "y": 0.0 } }] });
+        let error = error_of(app.clone().oneshot(post(body)).await.unwrap()).await;
+        assert_eq!(error["error"], "VALIDATION_ERROR");
+        assert_eq!(error["message"], "Invalid value at `lines[0]`: missing field `end`");
+
+        let error = error_of(app.clone().oneshot(post(serde_json::json!({}))).await.unwrap()).await;
+        assert_eq!(error["message"], "Invalid request body: missing field `lines`");
+
+        let response = app
+            .oneshot(
+                Request::post("/api/detect")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"lines": ["#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let error = error_of(response).await;
+        assert_eq!(error["error"], "VALIDATION_ERROR");
+        assert!(error["message"].as_str().unwrap().starts_with("Malformed JSON"));
+    }
+
+    #[test]
+    fn test_summarize_rooms() {
+        let room = |id: usize, area: f64| Room {
+            id,
+            bounding_box: [0.0, 0.0, 10.0, 10.0],
+            area,
+            name_hint: String::new(),
+            ..Room::default()
+        };
+        let rooms = vec![room(0, 100.0), room(1, 250.0), room(2, 50.0)];
+
+        let mut summary = summarize_rooms(&rooms);
+        summary.rooms_by_type = count_room_types([Some("bedroom"), None, Some("bedroom")]);
+
+        assert_eq!(summary.total_area, 400.0);
+        assert!((summary.average_area - 400.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary.largest_area, 250.0);
+        assert_eq!(summary.smallest_area, 50.0);
+        assert_eq!(summary.rooms_by_type.len(), 1);
+        assert_eq!(summary.rooms_by_type["bedroom"], 2);
+        assert_eq!(summarize_rooms(&[]), RoomSummary::default());
+    }
+
+    #[test]
+    fn test_origin_wildcard_patterns() {
+        assert!(origin_matches("https://*.vercel.app", "https://my-app-git-main.vercel.app"));
+        assert!(origin_matches("*.example.com", "http://preview.example.com"));
+        assert!(!origin_matches("https://*.vercel.app", "http://preview.vercel.app"));
+        assert!(!origin_matches("https://*.vercel.app", "https://vercel.app"));
+        assert!(!origin_matches("https://*.vercel.app", "https://evilvercel.app"));
+        assert!(!origin_matches("https://*.vercel.app", "https://preview.vercel.app.evil.com"));
+        assert!(origin_matches("http://localhost:8080", "http://localhost:8080"));
+        assert!(!origin_matches("http://localhost:8080", "http://localhost:8081"));
+    }
+
+    async fn cors_response(cors: CorsLayer, request: Request<Body>) -> Response {
+        Router::new()
+            .route("/ping", get(|| async { "pong" }))
+            .layer(cors)
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cors_wildcard_origin_predicate() {
+        let cors = || cors_layer("http://localhost:8080, https://*.example.com", "");
+        let get_from = |origin: &str| Request::get("/ping").header(header::ORIGIN, origin).body(Body::empty()).unwrap();
+
+        let response = cors_response(cors(), get_from("https://pr-42.example.com")).await;
+        assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://pr-42.example.com");
+
+        let response = cors_response(cors(), get_from("http://localhost:8080")).await;
+        assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "http://localhost:8080");
+
+        let response = cors_response(cors(), get_from("https://example.org")).await;
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cors_methods_from_config() {
+        let preflight = || {
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri("/ping")
+                .header(header::ORIGIN, "http://localhost:8080")
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let allowed_methods = |response: &Response| {
+            response.headers()[header::ACCESS_CONTROL_ALLOW_METHODS].to_str().unwrap().to_string()
+        };
+
+        let response = cors_response(cors_layer("http://localhost:8080", "get, put,DELETE"), preflight()).await;
+        assert_eq!(allowed_methods(&response), "GET,PUT,DELETE");
+
+        let response = cors_response(cors_layer("http://localhost:8080", ""), preflight()).await;
+        assert_eq!(allowed_methods(&response), "GET,POST");
+    }
+
+    #[tokio::test]
+    async fn test_oversized_image_rejected_with_structured_413() {
+        // Just over the default limit; its base64 still fits the default body limit
+        let image = base64::engine::general_purpose::STANDARD.encode(vec![0u8; DEFAULT_MAX_IMAGE_BYTES + 1]);
+        let body = serde_json::json!({ "image": image });
+
+        let response = create_app()
+            .oneshot(
+                Request::post("/api/detect/connected-components")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(error["error"], "IMAGE_TOO_LARGE");
+        let message = error["message"].as_str().unwrap();
+        assert!(message.contains(&(DEFAULT_MAX_IMAGE_BYTES + 1).to_string()));
+        assert!(message.contains(&DEFAULT_MAX_IMAGE_BYTES.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_request_id_echoed() {
+        let response = create_app()
+            .oneshot(
+                Request::get("/api/health")
+                    .header("x-request-id", "test-request-123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()["x-request-id"], "test-request-123");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_generated_when_missing() {
+        let response = create_app()
+            .oneshot(Request::get("/api/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let id = response.headers()["x-request-id"].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(id).is_ok());
+    }
+
+    fn write_fake_script(name: &str, body: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("{}_{}.sh", name, uuid::Uuid::new_v4()));
+        std::fs::write(&path, body).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_python_script_killed_on_timeout() {
+        let script = write_fake_script("sleepy", "sleep 10\n");
+        let start = Instant::now();
+
+        let result = run_python_script("sh", script.to_str().unwrap(), b"{}", Duration::from_millis(200)).await;
+        let _ = std::fs::remove_file(&script);
+
+        let (status, Json(error)) = result.unwrap_err();
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(error.error, "PYTHON_TIMEOUT");
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_python_script_failure_includes_stderr_excerpt() {
+        let script = write_fake_script(
+            "failing",
+            "cat > /dev/null\nfor i in $(seq 1 30); do echo \"line $i\" >&2; done\nexit 1\n",
+        );
+
+        let result = run_python_script("sh", script.to_str().unwrap(), b"{}", Duration::from_secs(10)).await;
+        let _ = std::fs::remove_file(&script);
+
+        let (status, Json(error)) = result.unwrap_err();
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.error, "PYTHON_SCRIPT_ERROR");
+        assert!(error.message.contains("line 20\n"));
+        assert!(!error.message.contains("line 21"));
+        assert!(error.message.ends_with("(10 more lines)"));
+    }
+
+    fn wall(x1: f64, y1: f64, x2: f64, y2: f64) -> serde_json::Value {
+        serde_json::json!({"start": {"x": x1, "y": y1}, "end": {"x": x2, "y": y2}})
+    }
+
+    fn square_lines() -> serde_json::Value {
+        serde_json::json!([
+            {"start": {"x": 0.0, "y": 0.0}, "end": {"x": 100.0, "y": 0.0}},
+            {"start": {"x": 100.0, "y": 0.0}, "end": {"x": 100.0, "y": 100.0}},
+            {"start": {"x": 100.0, "y": 100.0}, "end": {"x": 0.0, "y": 100.0}},
+            {"start": {"x": 0.0, "y": 100.0}, "end": {"x": 0.0, "y": 0.0}}
+        ])
+    }
+
+    /// 400x300 PNG with a 2x2 grid of walled rooms
+    fn four_room_png() -> Vec<u8> {
+        let mut img = image::GrayImage::from_pixel(400, 300, image::Luma([255]));
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            let is_wall = !(10..390).contains(&x) || !(10..290).contains(&y);
+            if is_wall || (195..205).contains(&x) || (145..155).contains(&y) {
+                *pixel = image::Luma([0]);
+            }
+        }
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+        png
+    }
+
+    async fn detect_auto(body: serde_json::Value) -> AutoDetectResponse {
+        let request: AutoDetectRequest = serde_json::from_value(body).unwrap();
+        detect_auto_handler_inner(Json(request)).await.unwrap().0
+    }
+
+    #[tokio::test]
+    async fn test_detect_auto_lines_use_graph() {
+        let response = detect_auto(serde_json::json!({ "lines": square_lines() })).await;
+
+        assert_eq!(response.method_used, AutoDetectMethod::Graph);
+        assert!(!response.fell_back);
+        assert!(response.detection.total_rooms > 0);
+    }
+
+    async fn detect(body: serde_json::Value) -> Result<DetectRoomsResponse, (StatusCode, Json<ErrorResponse>)> {
+        let request: DetectRoomsRequest = serde_json::from_value(body).unwrap();
+        detect_rooms_handler_inner(Json(request)).await.map(|Json(response)| response)
+    }
+
+    #[tokio::test]
+    async fn test_dfs_reports_whether_search_was_truncated() {
+        let dfs = detect(serde_json::json!({ "lines": square_lines(), "cycle_method": "dfs" })).await.unwrap();
+        assert_eq!(dfs.metadata.unwrap().truncated, Some(false));
+
+        // Face tracing has no deadline to report
+        let faces = detect(serde_json::json!({ "lines": square_lines() })).await.unwrap();
+        assert_eq!(faces.metadata.unwrap().truncated, None);
+    }
+
+    #[tokio::test]
+    async fn test_exclude_courtyards_keeps_interior_closet() {
+        // Two pinwheel plans side by side: the left center has a doorway (a closet), the
+        // right center is walled off on every side (an open-air courtyard)
+        let mut lines = vec![
+            wall(0.0, 0.0, 600.0, 0.0),
+            wall(600.0, 0.0, 600.0, 300.0),
+            wall(600.0, 300.0, 0.0, 300.0),
+            wall(0.0, 300.0, 0.0, 0.0),
+            wall(300.0, 0.0, 300.0, 300.0),
+        ];
+        for dx in [0.0, 300.0] {
+            lines.extend([
+                wall(dx + 200.0, 100.0, dx + 200.0, 200.0),
+                wall(dx + 200.0, 200.0, dx + 100.0, 200.0),
+                wall(dx + 100.0, 200.0, dx + 100.0, 100.0),
+                wall(dx + 100.0, 0.0, dx + 100.0, 100.0),
+                wall(dx + 200.0, 100.0, dx + 300.0, 100.0),
+                wall(dx + 200.0, 200.0, dx + 200.0, 300.0),
+                wall(dx, 200.0, dx + 100.0, 200.0),
+            ]);
+        }
+        lines.extend([
+            wall(100.0, 100.0, 130.0, 100.0),
+            wall(160.0, 100.0, 200.0, 100.0),
+            wall(400.0, 100.0, 500.0, 100.0),
+        ]);
+        let body = |exclude_courtyards: bool| {
+            serde_json::json!({
+                "lines": lines,
+                "area_threshold": 100.0,
+                "door_threshold": 40.0,
+                "exclude_courtyards": exclude_courtyards,
+            })
+        };
+
+        let all = detect(body(false)).await.unwrap();
+        assert_eq!(all.rooms.iter().filter(|r| r.kind == RoomKind::Courtyard).count(), 1);
+
+        let rooms = detect(body(true)).await.unwrap().rooms;
+        assert_eq!(rooms.len(), all.rooms.len() - 1);
+        assert!(rooms.iter().all(|r| r.kind == RoomKind::Enclosed));
+        assert_eq!(rooms.iter().filter(|r| (r.area - 10_000.0).abs() < 1e-6).count(), 1, "closet survives");
+    }
+
+    #[test]
+    fn test_scaled_areas_for_100_unit_square() {
+        let room = Room {
+            id: 0,
+            bounding_box: [0.0, 0.0, 100.0, 100.0],
+            area: 10_000.0,
+            name_hint: String::new(),
+            ..Room::default()
+        };
+
+        let response = DetectRoomsResponse::new(vec![room], None).with_units_per_meter(Some(100.0));
+
+        assert!((response.rooms[0].area_sq_meters.unwrap() - 1.0).abs() < 1e-12);
+        assert!((response.rooms[0].area_sq_feet.unwrap() - 10.763_910_4).abs() < 1e-6);
+        assert!((response.summary.total_area_sq_meters.unwrap() - 1.0).abs() < 1e-12);
+        assert!((response.summary.total_area_sq_feet.unwrap() - 10.763_910_4).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_detect_applies_request_scale() {
+        // 100 drawing units to the meter, given directly or as a two-point calibration
+        for scale in [
+            serde_json::json!({ "units_per_meter": 100.0 }),
+            serde_json::json!({ "point_a": {"x": 0.0, "y": 0.0}, "point_b": {"x": 100.0, "y": 0.0}, "distance": 1.0 }),
+        ] {
+            let response = detect(serde_json::json!({ "lines": square_lines(), "scale": scale })).await.unwrap();
+
+            assert!(response.total_rooms > 0);
+            for room in &response.rooms {
+                assert!((room.area_sq_meters.unwrap() - room.area / 10_000.0).abs() < 1e-9);
+            }
+            assert!(response.summary.total_area_sq_meters.is_some());
+        }
+
+        let unscaled = detect(serde_json::json!({ "lines": square_lines() })).await.unwrap();
+        assert!(unscaled.rooms.iter().all(|room| room.area_sq_meters.is_none()));
+        assert_eq!(unscaled.summary.total_area_sq_meters, None);
+    }
+
+    #[tokio::test]
+    async fn test_diagnostics_count_dangling_wall() {
+        let mut lines = square_lines();
+        // A wall off the top-right corner that closes nothing
+        lines.as_array_mut().unwrap().push(serde_json::json!(
+            {"start": {"x": 100.0, "y": 100.0}, "end": {"x": 150.0, "y": 150.0}}
+        ));
+
+        let response = detect(serde_json::json!({ "lines": lines, "door_threshold": 0.0, "diagnostics": true }))
+            .await
+            .unwrap();
+        let diagnostics = response.diagnostics.unwrap();
+
+        assert_eq!(diagnostics.node_count, 5);
+        assert_eq!(diagnostics.edge_count, 5);
+        assert_eq!(diagnostics.connected_components, 1);
+        assert_eq!(diagnostics.dangling_nodes, 1);
+        assert_eq!(diagnostics.door_gaps_bridged, 0);
+
+        let response = detect(serde_json::json!({ "lines": square_lines() })).await.unwrap();
+        assert!(response.diagnostics.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_empty_detection_reports_area_threshold_reason() {
+        let response = detect(serde_json::json!({ "lines": square_lines(), "area_threshold": 20000.0 }))
+            .await
+            .unwrap();
+        assert_eq!(response.total_rooms, 0);
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["reason"], "below_area_threshold");
+
+        let response = detect(serde_json::json!({ "lines": square_lines() })).await.unwrap();
+        assert_eq!(response.total_rooms, 1);
+        assert!(serde_json::to_value(&response).unwrap().get("reason").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_summary_totals_wall_length() {
+        let response = detect(serde_json::json!({ "lines": square_lines() })).await.unwrap();
+
+        assert_eq!(response.rooms[0].perimeter, Some(400.0));
+        assert_eq!(response.summary.total_wall_length, Some(400.0));
+    }
+
+    #[tokio::test]
+    async fn test_graph_rooms_normalized_to_1000_per_axis() {
+        // 400x300 plan offset from the origin, split into two 200x300 rooms
+        let lines = vec![
+            wall(50.0, 20.0, 450.0, 20.0),
+            wall(450.0, 20.0, 450.0, 320.0),
+            wall(450.0, 320.0, 50.0, 320.0),
+            wall(50.0, 320.0, 50.0, 20.0),
+            wall(250.0, 20.0, 250.0, 320.0),
+        ];
+
+        let response = detect(serde_json::json!({ "lines": lines, "door_threshold": 0.0 })).await.unwrap();
+
+        let boxes: Vec<[f64; 4]> = response.rooms.iter().map(|r| r.bounding_box).collect();
+        assert_eq!(boxes, vec![[0.0, 0.0, 500.0, 1000.0], [500.0, 0.0, 1000.0, 1000.0]]);
+        assert!(response.rooms[0].points.iter().all(|p| p.x <= 500.0 && p.y <= 1000.0));
+        // Areas stay in input units
+        assert_eq!(response.rooms[0].area, 60000.0);
+        assert_eq!(response.metadata.unwrap().source_bounds, Some([50.0, 20.0, 450.0, 320.0]));
+    }
+
+    #[tokio::test]
+    async fn test_graph_and_connected_components_agree_on_non_square_plan() {
+        // `four_room_png`'s walls as lines in its 400x300 pixel frame
+        let lines = vec![
+            wall(0.0, 0.0, 400.0, 0.0),
+            wall(400.0, 0.0, 400.0, 300.0),
+            wall(400.0, 300.0, 0.0, 300.0),
+            wall(0.0, 300.0, 0.0, 0.0),
+            wall(200.0, 0.0, 200.0, 300.0),
+            wall(0.0, 150.0, 400.0, 150.0),
+        ];
+        let centers = |boxes: Vec<[f64; 4]>| {
+            let mut centers: Vec<(f64, f64)> = boxes.iter().map(|b| ((b[0] + b[2]) / 2.0, (b[1] + b[3]) / 2.0)).collect();
+            centers.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0)));
+            centers
+        };
+
+        let graph = detect(serde_json::json!({ "lines": lines, "door_threshold": 0.0 })).await.unwrap();
+        let graph = centers(graph.rooms.iter().map(|r| r.bounding_box).collect());
+
+        let image = base64::engine::general_purpose::STANDARD.encode(four_room_png());
+        let response = create_app()
+            .oneshot(
+                Request::post("/api/detect/connected-components")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(serde_json::json!({ "image": image }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let cc = centers(
+            json["rooms"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|room| serde_json::from_value(room["bounding_box"].clone()).unwrap())
+                .collect(),
+        );
+
+        assert_eq!(graph.len(), 4);
+        assert_eq!(cc.len(), 4);
+        // Same room, same place, up to how wall thickness shifts the image detector's boxes
+        for ((gx, gy), (cx, cy)) in graph.iter().zip(&cc) {
+            assert!((gx - cx).abs() < 15.0 && (gy - cy).abs() < 15.0, "graph ({}, {}) vs CC ({}, {})", gx, gy, cx, cy);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_room_ids_follow_position_not_line_order() {
+        // 2x2 grid of 100x100 rooms
+        let mut lines = Vec::new();
+        for i in 0..3 {
+            let at = i as f64 * 100.0;
+            lines.push(wall(0.0, at, 200.0, at));
+            lines.push(wall(at, 0.0, at, 200.0));
+        }
+        let id_to_bbox = |response: DetectRoomsResponse| -> Vec<(usize, [f64; 4])> {
+            response.rooms.iter().map(|r| (r.id, r.bounding_box)).collect()
+        };
+
+        let first = detect(serde_json::json!({ "lines": lines, "door_threshold": 0.0 })).await.unwrap();
+        lines.reverse();
+        let second = detect(serde_json::json!({ "lines": lines, "door_threshold": 0.0 })).await.unwrap();
+
+        let first = id_to_bbox(first);
+        assert_eq!(first, id_to_bbox(second));
+        assert_eq!(
+            first,
+            vec![
+                (0, [0.0, 0.0, 500.0, 500.0]),
+                (1, [500.0, 0.0, 1000.0, 500.0]),
+                (2, [0.0, 500.0, 500.0, 1000.0]),
+                (3, [500.0, 500.0, 1000.0, 1000.0]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_min_wall_length_drops_noise_before_graph_building() {
+        let mut lines = square_lines();
+        lines.as_array_mut().unwrap().push(serde_json::json!(
+            {"start": {"x": 50.0, "y": 0.0}, "end": {"x": 50.0, "y": 2.0}}
+        ));
+        let body = |min_wall_length: f64| {
+            serde_json::json!({
+                "lines": lines, "door_threshold": 0.0, "diagnostics": true, "min_wall_length": min_wall_length
+            })
+        };
+
+        let noisy = detect(body(0.0)).await.unwrap();
+        assert_eq!(noisy.diagnostics.unwrap().dangling_nodes, 1);
+
+        let cleaned = detect(body(5.0)).await.unwrap();
+        assert_eq!(cleaned.total_rooms, 1);
+        assert_eq!(cleaned.diagnostics.unwrap().node_count, 4);
+
+        let (status, Json(error)) = detect(body(-1.0)).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error, "INVALID_MIN_WALL_LENGTH");
+    }
+
+    #[tokio::test]
+    async fn test_degenerate_scale_rejected() {
+        let scale = serde_json::json!({ "units_per_meter": -1.0 });
+        let (status, Json(error)) = detect(serde_json::json!({ "lines": square_lines(), "scale": scale }))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error, "INVALID_SCALE");
+    }
+
+    #[tokio::test]
+    async fn test_detect_auto_svg_content_uses_svg() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><rect x="0" y="0" width="100" height="100"/></svg>"#;
+
+        let response = detect_auto(serde_json::json!({ "svg_content": svg })).await;
+
+        assert_eq!(response.method_used, AutoDetectMethod::Svg);
+        assert!(response.detection.total_rooms > 0);
+
+        // An SVG sent as a base64 image is sniffed and takes the same path
+        let encoded = base64::engine::general_purpose::STANDARD.encode(svg);
+        let response = detect_auto(serde_json::json!({ "image": encoded })).await;
+        assert_eq!(response.method_used, AutoDetectMethod::Svg);
+    }
+
+    #[tokio::test]
+    async fn test_detect_auto_raster_uses_connected_components() {
+        let image = base64::engine::general_purpose::STANDARD.encode(four_room_png());
+
+        let response = detect_auto(serde_json::json!({ "image": image })).await;
+
+        assert_eq!(response.method_used, AutoDetectMethod::ConnectedComponents);
+        assert!(!response.fell_back);
+        assert_eq!(response.detection.total_rooms, 4);
+    }
+
+    #[tokio::test]
+    async fn test_detect_auto_falls_back_from_graph_to_image() {
+        // A single wall encloses nothing
+        let lines = serde_json::json!([{"start": {"x": 0.0, "y": 0.0}, "end": {"x": 100.0, "y": 0.0}}]);
+        let image = base64::engine::general_purpose::STANDARD.encode(four_room_png());
+
+        let response = detect_auto(serde_json::json!({ "lines": lines, "image": image })).await;
+
+        assert_eq!(response.method_used, AutoDetectMethod::ConnectedComponents);
+        assert!(response.fell_back);
+        assert_eq!(response.detection.total_rooms, 4);
+
+        let response = detect_auto(serde_json::json!({ "lines": lines })).await;
+        assert_eq!(response.method_used, AutoDetectMethod::Graph);
+        assert_eq!(response.detection.total_rooms, 0);
+    }
+
+    #[tokio::test]
+    async fn test_vectorize_blueprint_walls_only_skips_rooms() {
+        let image = base64::engine::general_purpose::STANDARD.encode(four_room_png());
+        let vectorize = |detect_rooms: bool| {
+            let request: VectorizeBlueprintRequest = serde_json::from_value(serde_json::json!({
+                "image": image,
+                "strategy": "vtracer_only",
+                "detect_rooms": detect_rooms,
+            }))
+            .unwrap();
+            vectorize_blueprint_handler_inner(Json(request))
+        };
+
+        let Json(full) = vectorize(true).await.unwrap();
+        let Json(walls_only) = vectorize(false).await.unwrap();
+
+        assert!(!full.rooms.is_empty());
+        assert_eq!(walls_only.walls.len(), walls_only.metadata.vtracer_walls_count);
+        assert!(walls_only.rooms.is_empty());
+        assert_eq!(walls_only.metadata.method_used, "vtracer_walls");
+    }
+
+    #[tokio::test]
+    async fn test_vectorize_blueprint_rejects_out_of_range_vtracer_config() {
+        let image = base64::engine::general_purpose::STANDARD.encode(four_room_png());
+        let request: VectorizeBlueprintRequest = serde_json::from_value(serde_json::json!({
+            "image": image,
+            "vtracer_config": { "corner_threshold": 270 },
+        }))
+        .unwrap();
+
+        let (status, Json(error)) = vectorize_blueprint_handler_inner(Json(request)).await.unwrap_err();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error, "INVALID_VTRACER_CONFIG");
+        assert!(error.message.contains("corner_threshold"));
+    }
+
+    #[tokio::test]
+    async fn test_vectorize_blueprint_honors_vectorizer_backend() {
+        let image = base64::engine::general_purpose::STANDARD.encode(four_room_png());
+        let request: VectorizeBlueprintRequest = serde_json::from_value(serde_json::json!({
+            "image": image,
+            "strategy": "vtracer_only",
+            "detect_rooms": false,
+            "vectorizer": "potrace",
+        }))
+        .unwrap();
+
+        let Json(response) = vectorize_blueprint_handler_inner(Json(request)).await.unwrap();
+
+        // Only the VTracer backend goes through the vectorization cache
+        assert!(response.metadata.vtracer_cache_hit.is_none());
+        assert!(!response.walls.is_empty());
+        assert_eq!(response.metadata.method_used, "potrace_walls");
+    }
+
+    #[tokio::test]
+    async fn test_detect_auto_rejects_unknown_input() {
+        let request: AutoDetectRequest = serde_json::from_value(serde_json::json!({})).unwrap();
+        let (status, Json(error)) = detect_auto_handler_inner(Json(request)).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error, "MISSING_INPUT");
+
+        let image = base64::engine::general_purpose::STANDARD.encode(b"not an image");
+        let request: AutoDetectRequest = serde_json::from_value(serde_json::json!({ "image": image })).unwrap();
+        let (_, Json(error)) = detect_auto_handler_inner(Json(request)).await.unwrap_err();
+        assert_eq!(error.error, "UNSUPPORTED_FORMAT");
+    }
+
+    fn svg_request(svg_content: &str) -> SvgDetectRequest {
+        serde_json::from_value(serde_json::json!({ "svg_content": svg_content })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_min_stroke_width_rejected_with_ai_svg_parser() {
+        let request: SvgDetectRequest = serde_json::from_value(serde_json::json!({
+            "svg_content": r#"<svg xmlns="http://www.w3.org/2000/svg"><rect x="0" y="0" width="100" height="100"/></svg>"#,
+            "svg_parser": "ai",
+            "min_stroke_width": 2.0,
+        }))
+        .unwrap();
+
+        let (status, Json(error)) = svg_detect_handler(ValidatedJson(request)).await.unwrap_err();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error, "INVALID_MIN_STROKE_WIDTH");
+    }
+
+    #[tokio::test]
+    async fn test_svg_with_entity_declaration_rejected() {
+        let svg = r#"<?xml version="1.0"?>
+<!DOCTYPE svg [<!ENTITY xxe SYSTEM "file:///etc/passwd">]>
+<svg xmlns="http://www.w3.org/2000/svg"><text>&xxe;</text></svg>"#;
+
+        let (status, Json(error)) = svg_detect_handler(ValidatedJson(svg_request(svg))).await.unwrap_err();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error, "UNSAFE_SVG");
+
+        let external = r#"<svg xmlns:xlink="http://www.w3.org/1999/xlink"><image xlink:href="file:///etc/passwd"/></svg>"#;
+        let (_, Json(error)) = svg_detect_handler(ValidatedJson(svg_request(external))).await.unwrap_err();
+        assert_eq!(error.error, "UNSAFE_SVG");
+    }
+
+    #[tokio::test]
+    async fn test_svg_detect_returns_parsed_walls_when_requested() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><rect x="0" y="0" width="100" height="50"/></svg>"#;
+        let mut request = svg_request(svg);
+        request.include_walls = true;
+
+        let Json(result) = svg_detect_handler(ValidatedJson(request)).await.unwrap();
+
+        // Walls share the rooms' 0-1000 space
+        let walls = result.metadata.parsed_walls.expect("walls requested");
+        assert_eq!(walls.len(), 4);
+        let endpoints: Vec<((f64, f64), (f64, f64))> = walls
+            .iter()
+            .map(|w| ((w.start.x, w.start.y), (w.end.x, w.end.y)))
+            .collect();
+        assert_eq!(
+            endpoints,
+            [
+                ((0.0, 0.0), (1000.0, 0.0)),
+                ((1000.0, 0.0), (1000.0, 1000.0)),
+                ((1000.0, 1000.0), (0.0, 1000.0)),
+                ((0.0, 1000.0), (0.0, 0.0)),
+            ]
+        );
+        assert_eq!(result.metadata.source_bounds, Some([0.0, 0.0, 100.0, 50.0]));
+
+        // Off by default
+        let Json(result) = svg_detect_handler(ValidatedJson(svg_request(svg))).await.unwrap();
+        assert!(result.metadata.parsed_walls.is_none());
+        assert!(serde_json::to_value(&result).unwrap()["metadata"].get("parsed_walls").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_svg_detect_groups_rooms_by_layer() {
+        // Two floors drawn over the same footprint; the first floor is split in two
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+  <g id="floor-1">
+    <rect x="0" y="0" width="100" height="100"/>
+    <rect x="100" y="0" width="100" height="100"/>
+  </g>
+  <!-- upper <g> floor -->
+  <g id="floor-2"><g><rect x="0" y="0" width="200" height="100"/></g></g>
+</svg>"#;
+        let mut request = svg_request(svg);
+        request.group_by_layer = true;
+
+        let Json(result) = svg_detect_handler(ValidatedJson(request)).await.unwrap();
+
+        let layers = result.layers.expect("grouped by layer");
+        assert_eq!(layers.keys().collect::<Vec<_>>(), ["floor-1", "floor-2"]);
+        assert_eq!(layers["floor-1"].len(), 2);
+        assert_eq!(layers["floor-2"].len(), 1);
+        assert!((layers["floor-2"][0].room.area - 20_000.0).abs() < 1.0);
+        assert!(layers["floor-1"].iter().all(|r| (r.room.area - 10_000.0).abs() < 1.0));
+
+        // The flat list carries every layer's rooms with ids unique across layers
+        let ids: Vec<usize> = result.rooms.iter().map(|r| r.room.id).collect();
+        assert_eq!(ids, [0, 1, 2]);
+        assert_eq!(layers["floor-2"][0].room.id, 2);
+
+        // Ungrouped detection keeps the flat shape
+        let Json(result) = svg_detect_handler(ValidatedJson(svg_request(svg))).await.unwrap();
+        assert!(result.layers.is_none());
+
+        let mut request = svg_request(r#"<svg><rect x="0" y="0" width="10" height="10"/></svg>"#);
+        request.group_by_layer = true;
+        let (status, Json(error)) = svg_detect_handler(ValidatedJson(request)).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error, "NO_LAYERS");
+    }
+
+    #[tokio::test]
+    async fn test_benign_inline_svg_accepted() {
+        // Namespace URIs are identifiers, not references to fetch
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+<rect x="0" y="0" width="100" height="100"/><use xlink:href="#wall"/></svg>"##;
+
+        let Json(result) = svg_detect_handler(ValidatedJson(svg_request(svg))).await.unwrap();
+
+        assert!(!result.rooms.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_large_image_downscaled_rooms_in_original_space() {
+        // 4000x3000 plan with one walled room spanning the middle half
+        let mut img = image::GrayImage::from_pixel(4000, 3000, image::Luma([255]));
+        for y in 750..2250 {
+            for x in 1000..3000 {
+                if !(1010..2990).contains(&x) || !(760..2240).contains(&y) {
+                    img.put_pixel(x, y, image::Luma([0]));
+                }
+            }
+        }
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+
+        let request = ImageDetectRequest {
+            image: base64::engine::general_purpose::STANDARD.encode(&png),
+            threshold: default_threshold(),
+            min_area: default_min_area(),
+            max_area_ratio: default_max_area_ratio(),
+            connectivity: connected_components::Connectivity::Eight,
+            max_dimension: 2000,
+            threshold_mode: connected_components::ThresholdMode::Global,
+            block_size: default_block_size(),
+            adaptive_c: default_adaptive_c(),
+            vtracer_config: None,
+        };
+        let Json(response) = detect_rooms_connected_components_handler_inner(Json(request)).await.unwrap();
+
+        assert_eq!(response.total_rooms, 1);
+        let room = &response.rooms[0];
+        // Interior spans x 1010..2990, y 760..2240 of the original image
+        let expected = [252.5, 253.3, 747.5, 746.7];
+        for (actual, expected) in room.bounding_box.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1.5, "bbox {:?}", room.bounding_box);
+        }
+        let expected_area = 1980.0 * 1480.0;
+        assert!((room.area - expected_area).abs() / expected_area < 0.02, "area {}", room.area);
+    }
+
+    #[tokio::test]
+    async fn test_small_room_survives_downscaling() {
+        // 4000x3000 plan with a 150x150 room and a 40x40 closet, whose 1600 px shrink to
+        // about 400 px at the 0.5 downscale: still above the requested 200 px min_area
+        let mut img = image::GrayImage::new(4000, 3000);
+        for (x0, y0, side) in [(1000, 1000, 150), (2000, 1000, 40)] {
+            for y in y0..y0 + side {
+                for x in x0..x0 + side {
+                    img.put_pixel(x, y, image::Luma([255]));
+                }
+            }
+        }
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+
+        let request = ImageDetectRequest {
+            image: base64::engine::general_purpose::STANDARD.encode(&png),
+            threshold: default_threshold(),
+            min_area: default_min_area(),
+            max_area_ratio: default_max_area_ratio(),
+            connectivity: connected_components::Connectivity::Eight,
+            max_dimension: 2000,
+            threshold_mode: connected_components::ThresholdMode::Global,
+            block_size: default_block_size(),
+            adaptive_c: default_adaptive_c(),
+            vtracer_config: None,
+        };
+        let Json(response) = detect_rooms_connected_components_handler_inner(Json(request)).await.unwrap();
+
+        assert_eq!(response.total_rooms, 2);
+        let closet = response.rooms.iter().map(|r| r.area).fold(f64::INFINITY, f64::min);
+        assert!((closet - 1600.0).abs() / 1600.0 < 0.2, "closet area {}", closet);
+    }
+
+    #[test]
+    fn test_min_area_scales_with_downscaled_image() {
+        assert_eq!(scale_min_area(1000, 1.0), 1000);
+        assert_eq!(scale_min_area(1000, 0.5), 250);
+    }
+
+    #[tokio::test]
+    async fn test_detect_geojson_format() {
+        let body = serde_json::json!({
+            "lines": [
+                {"start": {"x": 0.0, "y": 0.0}, "end": {"x": 100.0, "y": 0.0}},
+                {"start": {"x": 100.0, "y": 0.0}, "end": {"x": 100.0, "y": 100.0}},
+                {"start": {"x": 100.0, "y": 100.0}, "end": {"x": 0.0, "y": 100.0}},
+                {"start": {"x": 0.0, "y": 100.0}, "end": {"x": 0.0, "y": 0.0}}
+            ]
+        });
+        let response = create_app()
+            .oneshot(
+                Request::post("/api/detect?format=geojson")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/geo+json");
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let collection: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(collection["type"], "FeatureCollection");
+        let features = collection["features"].as_array().unwrap();
+        assert!(!features.is_empty());
+        for feature in features {
+            let ring = feature["geometry"]["coordinates"][0].as_array().unwrap();
+            assert_eq!(ring.first(), ring.last());
+        }
+    }
 }
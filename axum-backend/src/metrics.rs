@@ -0,0 +1,176 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::ErrorResponse;
+
+/// Latency histogram bucket bounds in seconds (1ms to 120s; vision strategies are slow)
+const LATENCY_BUCKETS: [f64; 16] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0,
+];
+
+#[derive(Default)]
+struct Histogram {
+    /// Non-cumulative count per bucket; the last slot is +Inf
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        let idx = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[idx] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// In-process metrics, rendered in Prometheus text format on scrape
+#[derive(Default)]
+struct Registry {
+    /// (method, path, status) -> count
+    requests: BTreeMap<(String, String, u16), u64>,
+    /// (method, path) -> latency
+    latency: BTreeMap<(String, String), Histogram>,
+    /// endpoint -> rooms detected
+    rooms_detected: BTreeMap<&'static str, u64>,
+    /// endpoint -> successful detections
+    detections: BTreeMap<&'static str, u64>,
+    /// (endpoint, error code) -> failures
+    failures: BTreeMap<(&'static str, String), u64>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+/// Middleware recording method, route, status and duration of every matched request
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = start.elapsed().as_secs_f64();
+    let status = response.status().as_u16();
+
+    let mut registry = registry().lock().unwrap();
+    *registry.requests.entry((method.clone(), path.clone(), status)).or_default() += 1;
+    registry.latency.entry((method, path)).or_default().observe(elapsed);
+
+    response
+}
+
+/// Count a detection handler's outcome: rooms on success, error code on failure
+pub fn track_detection<T>(
+    endpoint: &'static str,
+    result: Result<Json<T>, (StatusCode, Json<ErrorResponse>)>,
+    room_count: impl FnOnce(&T) -> usize,
+) -> Result<Json<T>, (StatusCode, Json<ErrorResponse>)> {
+    let mut registry = registry().lock().unwrap();
+    match &result {
+        Ok(Json(response)) => {
+            *registry.detections.entry(endpoint).or_default() += 1;
+            *registry.rooms_detected.entry(endpoint).or_default() += room_count(response) as u64;
+        }
+        Err((_, Json(error))) => {
+            *registry.failures.entry((endpoint, error.error.clone())).or_default() += 1;
+        }
+    }
+    result
+}
+
+/// `GET /metrics` scrape endpoint
+pub async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        render(&registry().lock().unwrap()),
+    )
+}
+
+fn render(registry: &Registry) -> String {
+    let mut out = String::new();
+
+    out.push_str("# HELP http_requests_total Total HTTP requests by method, route and status.\n");
+    out.push_str("# TYPE http_requests_total counter\n");
+    for ((method, path, status), count) in &registry.requests {
+        let _ = writeln!(
+            out,
+            "http_requests_total{{method=\"{}\",path=\"{}\",status=\"{}\"}} {}",
+            method, path, status, count
+        );
+    }
+
+    out.push_str("# HELP http_request_duration_seconds HTTP request latency by method and route.\n");
+    out.push_str("# TYPE http_request_duration_seconds histogram\n");
+    for ((method, path), histogram) in &registry.latency {
+        let labels = format!("method=\"{}\",path=\"{}\"", method, path);
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, cumulative);
+        }
+        let _ = writeln!(out, "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, histogram.count);
+        let _ = writeln!(out, "http_request_duration_seconds_sum{{{}}} {}", labels, histogram.sum);
+        let _ = writeln!(out, "http_request_duration_seconds_count{{{}}} {}", labels, histogram.count);
+    }
+
+    out.push_str("# HELP detections_total Successful room detections by endpoint.\n");
+    out.push_str("# TYPE detections_total counter\n");
+    for (endpoint, count) in &registry.detections {
+        let _ = writeln!(out, "detections_total{{endpoint=\"{}\"}} {}", endpoint, count);
+    }
+
+    out.push_str("# HELP rooms_detected_total Rooms detected by endpoint.\n");
+    out.push_str("# TYPE rooms_detected_total counter\n");
+    for (endpoint, count) in &registry.rooms_detected {
+        let _ = writeln!(out, "rooms_detected_total{{endpoint=\"{}\"}} {}", endpoint, count);
+    }
+
+    out.push_str("# HELP detection_failures_total Failed detections by endpoint and error code.\n");
+    out.push_str("# TYPE detection_failures_total counter\n");
+    for ((endpoint, error), count) in &registry.failures {
+        let _ = writeln!(out, "detection_failures_total{{endpoint=\"{}\",error=\"{}\"}} {}", endpoint, error, count);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_cumulative() {
+        let mut registry = Registry::default();
+        let hist = registry.latency.entry(("GET".to_string(), "/x".to_string())).or_default();
+        hist.observe(0.0005);
+        hist.observe(0.3);
+        hist.observe(500.0);
+
+        let text = render(&registry);
+
+        assert!(text.contains("http_request_duration_seconds_bucket{method=\"GET\",path=\"/x\",le=\"0.001\"} 1"));
+        assert!(text.contains("http_request_duration_seconds_bucket{method=\"GET\",path=\"/x\",le=\"0.5\"} 2"));
+        assert!(text.contains("http_request_duration_seconds_bucket{method=\"GET\",path=\"/x\",le=\"120\"} 2"));
+        assert!(text.contains("http_request_duration_seconds_bucket{method=\"GET\",path=\"/x\",le=\"+Inf\"} 3"));
+        assert!(text.contains("http_request_duration_seconds_count{method=\"GET\",path=\"/x\"} 3"));
+    }
+}
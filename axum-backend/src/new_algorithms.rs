@@ -6,6 +6,9 @@ use std::collections::VecDeque;
 use std::time::Instant;
 use tracing::info;
 
+use crate::connected_components::Connectivity;
+use crate::room_detector::assign_spatial_ids;
+use crate::validated_json::ValidatedJson;
 use crate::{ErrorResponse, Point, Room};
 
 #[derive(Debug, Deserialize)]
@@ -14,6 +17,8 @@ pub struct ImageDetectRequest {
     pub threshold: u8,
     pub min_area: usize,
     pub max_area_ratio: f32,
+    #[serde(default)]
+    pub connectivity: Connectivity,
 }
 
 #[derive(Debug, Serialize)]
@@ -34,7 +39,7 @@ struct FloodFillRoom {
 // Morphological operations not needed for this simpler approach
 
 pub async fn detect_rust_floodfill_handler(
-    Json(request): Json<ImageDetectRequest>,
+    ValidatedJson(request): ValidatedJson<ImageDetectRequest>,
 ) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
     info!("Received Rust flood fill detection request");
 
@@ -53,6 +58,7 @@ pub async fn detect_rust_floodfill_handler(
         })?;
 
     info!("Image decoded, size: {} bytes", img_bytes.len());
+    crate::check_image_size(&img_bytes)?;
 
     // Load image
     let img = image::load_from_memory(&img_bytes)
@@ -75,7 +81,7 @@ pub async fn detect_rust_floodfill_handler(
     let binary = threshold_image_floodfill(&img, request.threshold);
 
     // Find components
-    let components = find_connected_components_floodfill(&binary);
+    let components = find_connected_components_floodfill(&binary, request.connectivity);
 
     let (width, height) = img.dimensions();
 
@@ -130,15 +136,19 @@ pub async fn detect_rust_floodfill_handler(
     let execution_time = start_time.elapsed().as_millis() as u64;
     info!("Detected {} rooms using Rust flood fill in {}ms", rooms.len(), execution_time);
 
-    Ok(Json(DetectRoomsResponse {
-        total_rooms: rooms.len(),
-        rooms: rooms.into_iter().map(|r| Room {
+    let mut rooms: Vec<Room> = rooms.into_iter().map(|r| Room {
             id: r.id,
             bounding_box: r.bounding_box,
             area: r.area,
             name_hint: r.name_hint,
             points: r.points,
-        }).collect(),
+            ..Room::default()
+        }).collect();
+    assign_spatial_ids(&mut rooms);
+
+    Ok(Json(DetectRoomsResponse {
+        total_rooms: rooms.len(),
+        rooms,
     }))
 }
 
@@ -152,7 +162,7 @@ fn threshold_image_floodfill(img: &GrayImage, threshold: u8) -> GrayImage {
     binary
 }
 
-fn find_connected_components_floodfill(img: &GrayImage) -> Vec<(usize, (u32, u32, u32, u32))> {
+fn find_connected_components_floodfill(img: &GrayImage, connectivity: Connectivity) -> Vec<(usize, (u32, u32, u32, u32))> {
     let (width, height) = img.dimensions();
     let mut visited = vec![false; width as usize * height as usize];
     let mut components = Vec::new();
@@ -165,7 +175,7 @@ fn find_connected_components_floodfill(img: &GrayImage) -> Vec<(usize, (u32, u32
         for x in 0..width {
             let idx = (y as usize * width as usize) + x as usize;
             if img.get_pixel(x, y)[0] == 255 && !visited[idx] {
-                let (area, bbox) = flood_fill_internal(img, x, y, &mut visited, width, height);
+                let (area, bbox) = flood_fill_internal(img, x, y, &mut visited, width, height, connectivity);
                 let (min_x, min_y, max_x, max_y) = bbox;
 
                 // Calculate dimensions
@@ -196,6 +206,7 @@ fn flood_fill_internal(
     visited: &mut Vec<bool>,
     width: u32,
     height: u32,
+    connectivity: Connectivity,
 ) -> (usize, (u32, u32, u32, u32)) {
     let mut queue = VecDeque::new();
     let mut area = 0;
@@ -215,22 +226,16 @@ fn flood_fill_internal(
         max_x = max_x.max(x);
         max_y = max_y.max(y);
 
-        // 8-connectivity neighbors
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                let nx = x as i32 + dx;
-                let ny = y as i32 + dy;
-                if nx >= 0 && nx < width as i32 && ny >= 0 && ny < height as i32 {
-                    let px = nx as u32;
-                    let py = ny as u32;
-                    let nidx = (py as usize * width as usize) + px as usize;
-                    if !visited[nidx] && img.get_pixel(px, py)[0] == 255 {
-                        visited[nidx] = true;
-                        queue.push_back((px, py));
-                    }
+        for &(dx, dy) in connectivity.offsets() {
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            if nx >= 0 && nx < width as i32 && ny >= 0 && ny < height as i32 {
+                let px = nx as u32;
+                let py = ny as u32;
+                let nidx = (py as usize * width as usize) + px as usize;
+                if !visited[nidx] && img.get_pixel(px, py)[0] == 255 {
+                    visited[nidx] = true;
+                    queue.push_back((px, py));
                 }
             }
         }
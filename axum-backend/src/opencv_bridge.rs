@@ -134,6 +134,7 @@ pub async fn detect_opencv_cc_handler(
             area: r.area,
             name_hint: r.name_hint,
             points: r.points,
+            ..Room::default()
         }).collect(),
     }))
 }
@@ -0,0 +1,139 @@
+use crate::Point;
+use image::GrayImage;
+use std::collections::HashMap;
+
+type Vertex = (i32, i32);
+
+/// Trace the outlines of dark regions, potrace-style
+///
+/// Pixels darker than `threshold` are ink. Every ink/background pixel boundary becomes a
+/// directed unit edge running clockwise around the ink, and the edges are chained into
+/// closed rings on the pixel-corner lattice. Rings are returned with collinear vertices
+/// removed and the first vertex repeated at the end.
+pub fn trace_outlines(image: &GrayImage, threshold: u8) -> Vec<Vec<Point>> {
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    let is_ink = |x: i32, y: i32| {
+        x >= 0 && y >= 0 && x < width && y < height && image.get_pixel(x as u32, y as u32)[0] < threshold
+    };
+
+    let mut edges: Vec<(Vertex, Vertex)> = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if !is_ink(x, y) {
+                continue;
+            }
+            if !is_ink(x, y - 1) {
+                edges.push(((x, y), (x + 1, y)));
+            }
+            if !is_ink(x + 1, y) {
+                edges.push(((x + 1, y), (x + 1, y + 1)));
+            }
+            if !is_ink(x, y + 1) {
+                edges.push(((x + 1, y + 1), (x, y + 1)));
+            }
+            if !is_ink(x - 1, y) {
+                edges.push(((x, y + 1), (x, y)));
+            }
+        }
+    }
+
+    let mut outgoing: HashMap<Vertex, Vec<usize>> = HashMap::new();
+    for (i, (from, _)) in edges.iter().enumerate() {
+        outgoing.entry(*from).or_default().push(i);
+    }
+
+    // Every vertex has as many edges in as out, so a walk can only get stuck where it started
+    let mut used = vec![false; edges.len()];
+    let mut rings = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let start = edges[first].0;
+        let mut ring = vec![start];
+        let mut current = first;
+
+        loop {
+            let (from, to) = edges[current];
+            ring.push(to);
+            let direction = (to.0 - from.0, to.1 - from.1);
+            // Where two ink pixels touch only at a corner, keep turning the same way
+            let next = outgoing
+                .get(&to)
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|&e| !used[e])
+                .max_by_key(|&e| {
+                    let (a, b) = edges[e];
+                    direction.0 * (b.1 - a.1) - direction.1 * (b.0 - a.0)
+                });
+            let Some(next) = next else { break };
+            used[next] = true;
+            current = next;
+        }
+
+        rings.push(remove_collinear(&ring));
+    }
+
+    rings
+}
+
+/// Drop ring vertices where the outline runs straight through; `ring` repeats its first vertex
+fn remove_collinear(ring: &[Vertex]) -> Vec<Point> {
+    let open = &ring[..ring.len() - 1];
+    let n = open.len();
+    let corners: Vec<Vertex> = (0..n)
+        .filter(|&i| {
+            let (prev, here, next) = (open[(i + n - 1) % n], open[i], open[(i + 1) % n]);
+            (here.0 - prev.0) * (next.1 - here.1) != (here.1 - prev.1) * (next.0 - here.0)
+        })
+        .map(|i| open[i])
+        .collect();
+
+    corners
+        .iter()
+        .chain(corners.first())
+        .map(|&(x, y)| Point { x: x as f64, y: y as f64 })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filled_square_traces_one_four_corner_ring() {
+        let mut image = GrayImage::from_pixel(10, 10, image::Luma([255]));
+        for y in 2..6 {
+            for x in 3..8 {
+                image.put_pixel(x, y, image::Luma([0]));
+            }
+        }
+
+        let rings = trace_outlines(&image, 128);
+
+        assert_eq!(rings.len(), 1);
+        let corners: Vec<(f64, f64)> = rings[0].iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(corners, vec![(3.0, 2.0), (8.0, 2.0), (8.0, 6.0), (3.0, 6.0), (3.0, 2.0)]);
+    }
+
+    #[test]
+    fn test_hollow_frame_traces_outer_and_inner_rings() {
+        let mut image = GrayImage::from_pixel(12, 12, image::Luma([255]));
+        for y in 1..11 {
+            for x in 1..11 {
+                let on_frame = !(3..9).contains(&x) || !(3..9).contains(&y);
+                if on_frame {
+                    image.put_pixel(x, y, image::Luma([0]));
+                }
+            }
+        }
+
+        let rings = trace_outlines(&image, 128);
+
+        assert_eq!(rings.len(), 2);
+        assert!(rings.iter().all(|ring| ring.len() == 5));
+    }
+}
@@ -0,0 +1,340 @@
+use axum::{
+    extract::{Json, Query},
+    http::StatusCode,
+    response::Response,
+};
+use base64::Engine;
+use image::DynamicImage;
+use pdfium_render::prelude::*;
+use serde::Deserialize;
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::validated_json::ValidatedJson;
+use crate::{connected_components, geojson, image_preprocessor, DetectRoomsResponse, ErrorResponse};
+
+/// Default render resolution; 200+ keeps thin vector walls crisp
+const DEFAULT_PDF_DPI: f32 = 200.0;
+const MIN_PDF_DPI: f32 = 72.0;
+const MAX_PDF_DPI: f32 = 600.0;
+
+/// PDF points per inch
+const POINTS_PER_INCH: f32 = 72.0;
+
+#[derive(Debug, Deserialize)]
+pub struct PdfDetectRequest {
+    /// Base64-encoded PDF
+    pdf: String,
+    /// 1-based page number to rasterize
+    #[serde(default = "default_page")]
+    page: u16,
+    /// Render resolution (72-600)
+    #[serde(default = "default_dpi")]
+    dpi: f32,
+    #[serde(default = "crate::default_threshold")]
+    threshold: u8,
+    /// Smallest room in rendered-page pixels, rescaled along with the page when downscaled
+    #[serde(default = "crate::default_min_area")]
+    min_area: usize,
+    #[serde(default = "crate::default_max_area_ratio")]
+    max_area_ratio: f32,
+    /// Flood fill neighborhood ("four" or "eight")
+    #[serde(default)]
+    connectivity: connected_components::Connectivity,
+    /// Longest side the rendered page is downscaled to before flood fill (0 disables)
+    #[serde(default = "crate::default_max_dimension")]
+    max_dimension: u32,
+}
+
+fn default_page() -> u16 {
+    1
+}
+
+fn default_dpi() -> f32 {
+    DEFAULT_PDF_DPI
+}
+
+/// Why a PDF page couldn't be rasterized, with the API error code for each
+#[derive(Debug)]
+pub enum PdfRasterError {
+    /// The Pdfium library couldn't be loaded; a server problem, not a bad request
+    Unavailable(PdfiumError),
+    /// The document couldn't be parsed or rendered
+    Pdfium(PdfiumError),
+    NoPages,
+    PageOutOfRange { page: u16, page_count: u16 },
+    /// The page would render to more pixels than an uploaded image may have bytes
+    PageTooLarge { width: u32, height: u32, max_pixels: usize },
+}
+
+impl PdfRasterError {
+    fn into_response(self) -> (StatusCode, Json<ErrorResponse>) {
+        let (status, error, message) = match self {
+            PdfRasterError::Unavailable(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "PDF_RENDERER_UNAVAILABLE",
+                format!("PDF rendering is unavailable: {}", e),
+            ),
+            PdfRasterError::Pdfium(e) => (StatusCode::BAD_REQUEST, "INVALID_PDF", format!("Failed to render PDF: {}", e)),
+            PdfRasterError::NoPages => (StatusCode::BAD_REQUEST, "EMPTY_PDF", "PDF has no pages".to_string()),
+            PdfRasterError::PageOutOfRange { page, page_count } => (
+                StatusCode::BAD_REQUEST,
+                "PAGE_OUT_OF_RANGE",
+                format!("Page {} requested but PDF has {} page(s)", page, page_count),
+            ),
+            PdfRasterError::PageTooLarge { width, height, max_pixels } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "IMAGE_TOO_LARGE",
+                format!(
+                    "Page renders to {}x{} pixels; the maximum is {} pixels, so lower the dpi",
+                    width, height, max_pixels
+                ),
+            ),
+        };
+        (
+            status,
+            Json(ErrorResponse {
+                error: error.to_string(),
+                message,
+            }),
+        )
+    }
+}
+
+impl From<PdfiumError> for PdfRasterError {
+    fn from(err: PdfiumError) -> Self {
+        PdfRasterError::Pdfium(err)
+    }
+}
+
+/// Map a 1-based page number to a 0-based page index, bounds-checked against the page count
+fn page_index(page: u16, page_count: u16) -> Result<u16, PdfRasterError> {
+    if page_count == 0 {
+        return Err(PdfRasterError::NoPages);
+    }
+    if page == 0 || page > page_count {
+        return Err(PdfRasterError::PageOutOfRange { page, page_count });
+    }
+    Ok(page - 1)
+}
+
+/// Bind Pdfium from `PDFIUM_LIB_PATH` (a directory) if set, else the system library
+fn bind_pdfium() -> Result<Pdfium, PdfiumError> {
+    let bindings = match std::env::var("PDFIUM_LIB_PATH") {
+        Ok(dir) => Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(&dir))?,
+        Err(_) => Pdfium::bind_to_system_library()?,
+    };
+    Ok(Pdfium::new(bindings))
+}
+
+/// Pixel size of a `width_pt` x `height_pt` page rendered at `dpi`, refused when it would
+/// exceed `max_pixels` (the same budget as an uploaded image's bytes)
+fn rendered_size(width_pt: f32, height_pt: f32, dpi: f32, max_pixels: usize) -> Result<(u32, u32), PdfRasterError> {
+    let scale = dpi / POINTS_PER_INCH;
+    let width = (width_pt * scale).round() as u32;
+    let height = (height_pt * scale).round() as u32;
+    if width as usize * height as usize > max_pixels {
+        return Err(PdfRasterError::PageTooLarge { width, height, max_pixels });
+    }
+    Ok((width, height))
+}
+
+/// Rasterize one page (1-based) of a PDF at `dpi` onto a white background, refusing pages
+/// that would render to more than `max_pixels`
+pub fn rasterize_pdf_page(pdf_bytes: &[u8], page: u16, dpi: f32, max_pixels: usize) -> Result<DynamicImage, PdfRasterError> {
+    let pdfium = bind_pdfium().map_err(PdfRasterError::Unavailable)?;
+    let document = pdfium.load_pdf_from_byte_slice(pdf_bytes, None)?;
+    let pages = document.pages();
+    let index = page_index(page, pages.len())?;
+    let page = pages.get(index)?;
+    rendered_size(page.width().value, page.height().value, dpi, max_pixels)?;
+
+    let config = PdfRenderConfig::new()
+        .scale_page_by_factor(dpi / POINTS_PER_INCH)
+        .set_clear_color(PdfColor::WHITE);
+    let bitmap = page.render_with_config(&config)?;
+
+    Ok(bitmap.as_image())
+}
+
+/// Detect rooms on one page of a PDF using connected components
+pub async fn detect_pdf_handler(
+    Query(query): Query<geojson::FormatQuery>,
+    ValidatedJson(request): ValidatedJson<PdfDetectRequest>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    geojson::format_rooms_response(query.format, detect_pdf_handler_inner(Json(request)).await)
+}
+
+async fn detect_pdf_handler_inner(
+    Json(request): Json<PdfDetectRequest>,
+) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    info!("Received PDF detection request (page {}, {} dpi)", request.page, request.dpi);
+
+    if !(MIN_PDF_DPI..=MAX_PDF_DPI).contains(&request.dpi) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_DPI".to_string(),
+                message: format!("dpi must be between {} and {}, got {}", MIN_PDF_DPI, MAX_PDF_DPI, request.dpi),
+            }),
+        ));
+    }
+
+    let pdf_bytes = base64::engine::general_purpose::STANDARD
+        .decode(&request.pdf)
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "INVALID_BASE64".to_string(),
+                    message: format!("Failed to decode base64 PDF: {}", e),
+                }),
+            )
+        })?;
+
+    // Rendering and flood fill are CPU bound; keep them off the async workers
+    tokio::task::spawn_blocking(move || detect_pdf(&request, &pdf_bytes))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "DETECTION_FAILED".to_string(),
+                    message: format!("PDF detection task failed: {}", e),
+                }),
+            )
+        })?
+}
+
+fn detect_pdf(
+    request: &PdfDetectRequest,
+    pdf_bytes: &[u8],
+) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let start_time = Instant::now();
+
+    let img = rasterize_pdf_page(pdf_bytes, request.page, request.dpi, crate::max_image_bytes())
+        .map_err(|e| {
+            warn!("PDF rasterization failed: {:?}", e);
+            e.into_response()
+        })?;
+
+    info!(
+        "Rasterized page {} to {}x{} in {}ms",
+        request.page,
+        img.width(),
+        img.height(),
+        start_time.elapsed().as_millis()
+    );
+
+    Ok(Json(detect_rendered_page(&img, request)))
+}
+
+/// Flood-fill rooms on a rendered page, downscaled like an uploaded image; areas are
+/// reported in rendered-page pixels
+fn detect_rendered_page(img: &DynamicImage, request: &PdfDetectRequest) -> DetectRoomsResponse {
+    let source_bounds = [0.0, 0.0, img.width() as f64, img.height() as f64];
+
+    let (img, scale) = image_preprocessor::downscale_to_max_dimension(img, request.max_dimension);
+    let img = img.to_luma8();
+    if scale < 1.0 {
+        info!("Downscaled page to {}x{} (scale {:.3})", img.width(), img.height(), scale);
+    }
+
+    let mut rooms = connected_components::detect_rooms_connected_components(
+        &img,
+        request.threshold,
+        crate::scale_min_area(request.min_area, scale),
+        request.max_area_ratio,
+        request.connectivity,
+    );
+    crate::restore_room_scale(&mut rooms, scale);
+
+    info!("Detected {} rooms from PDF", rooms.len());
+
+    DetectRoomsResponse::new(rooms, None).with_source_bounds(Some(source_bounds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_index_bounds_checked() {
+        assert_eq!(page_index(1, 3).unwrap(), 0);
+        assert_eq!(page_index(3, 3).unwrap(), 2);
+        assert!(matches!(page_index(0, 3), Err(PdfRasterError::PageOutOfRange { .. })));
+        assert!(matches!(
+            page_index(4, 3),
+            Err(PdfRasterError::PageOutOfRange { page: 4, page_count: 3 })
+        ));
+        assert!(matches!(page_index(1, 0), Err(PdfRasterError::NoPages)));
+    }
+
+    #[test]
+    fn test_missing_pdfium_is_a_server_error() {
+        let (status, Json(error)) = PdfRasterError::Unavailable(PdfiumError::UnrecognizedPath).into_response();
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.error, "PDF_RENDERER_UNAVAILABLE");
+
+        let (status, Json(error)) = PdfRasterError::Pdfium(PdfiumError::UnknownBitmapFormat).into_response();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.error, "INVALID_PDF");
+    }
+
+    #[test]
+    fn test_oversized_page_rejected_before_rendering() {
+        // US Letter at 200 dpi is 1700x2200
+        assert_eq!(rendered_size(612.0, 792.0, 200.0, 4_000_000).unwrap(), (1700, 2200));
+
+        let err = rendered_size(612.0, 792.0, 600.0, 4_000_000).unwrap_err();
+        assert!(matches!(err, PdfRasterError::PageTooLarge { width: 5100, height: 6600, .. }));
+        assert_eq!(err.into_response().0, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_rendered_page_downscaled_and_mapped_back() {
+        // A 400x300pt four-room page rendered at 600 dpi
+        let mut img = image::GrayImage::from_pixel(3332, 2500, image::Luma([255]));
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            let is_wall = !(66..3266).contains(&x) || !(66..2434).contains(&y);
+            if is_wall || (1633..1699).contains(&x) || (1217..1283).contains(&y) {
+                *pixel = image::Luma([0]);
+            }
+        }
+        let request: PdfDetectRequest = serde_json::from_value(serde_json::json!({ "pdf": "" })).unwrap();
+
+        let response = detect_rendered_page(&DynamicImage::ImageLuma8(img), &request);
+
+        assert_eq!(response.total_rooms, 4);
+        assert_eq!(response.metadata.unwrap().source_bounds, Some([0.0, 0.0, 3332.0, 2500.0]));
+        // Areas come back in rendered-page pixels, not the downscaled ones
+        for room in &response.rooms {
+            assert!(room.area > 1_700_000.0, "area {}", room.area);
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires the Pdfium library (set PDFIUM_LIB_PATH)"]
+    async fn test_detect_rooms_from_one_page_pdf() {
+        // 400x300pt page split into four rooms by 8pt walls
+        let pdf = include_bytes!("../../test-data/test_floorplan_one_page.pdf");
+        let request: PdfDetectRequest = serde_json::from_value(serde_json::json!({
+            "pdf": base64::engine::general_purpose::STANDARD.encode(pdf),
+        }))
+        .unwrap();
+
+        let Json(response) = detect_pdf_handler_inner(Json(request)).await.unwrap();
+
+        assert_eq!(response.total_rooms, 4);
+    }
+
+    #[tokio::test]
+    #[ignore = "requires the Pdfium library (set PDFIUM_LIB_PATH)"]
+    async fn test_pdf_page_out_of_range() {
+        let pdf = include_bytes!("../../test-data/test_floorplan_one_page.pdf");
+
+        let err = rasterize_pdf_page(pdf, 2, DEFAULT_PDF_DPI, usize::MAX).unwrap_err();
+
+        assert!(matches!(err, PdfRasterError::PageOutOfRange { page: 2, page_count: 1 }));
+    }
+}
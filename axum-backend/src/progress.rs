@@ -0,0 +1,303 @@
+use axum::{
+    extract::{Json, Path},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use base64::Engine;
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::validated_json::ValidatedJson;
+use crate::{connected_components, image_preprocessor, restore_room_scale, ErrorResponse, ImageDetectRequest, Room};
+
+/// Interval between heartbeat events on idle progress streams
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long a finished job's events stay available to late subscribers
+const FINISHED_JOB_TTL: Duration = Duration::from_secs(300);
+
+/// Buffered events per job for live subscribers
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+/// One progress update for an async detection job
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    Progress {
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        percent: Option<u8>,
+    },
+    Done {
+        message: String,
+        total_rooms: usize,
+        rooms: Vec<Room>,
+        execution_time_ms: u64,
+    },
+    Failed {
+        error: String,
+        message: String,
+    },
+}
+
+impl ProgressEvent {
+    fn progress(message: impl Into<String>, percent: Option<u8>) -> Self {
+        ProgressEvent::Progress {
+            message: message.into(),
+            percent,
+        }
+    }
+
+    fn failed(error: &str, message: String) -> Self {
+        ProgressEvent::Failed {
+            error: error.to_string(),
+            message,
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        !matches!(self, ProgressEvent::Progress { .. })
+    }
+
+    fn event_name(&self) -> &'static str {
+        match self {
+            ProgressEvent::Progress { .. } => "progress",
+            ProgressEvent::Done { .. } => "done",
+            ProgressEvent::Failed { .. } => "error",
+        }
+    }
+
+    fn to_sse(&self) -> Result<Event, axum::Error> {
+        Event::default().event(self.event_name()).json_data(self)
+    }
+}
+
+/// Event history (for replay to late subscribers) plus a live channel
+struct Job {
+    history: Vec<ProgressEvent>,
+    sender: broadcast::Sender<ProgressEvent>,
+}
+
+fn jobs() -> &'static Mutex<HashMap<Uuid, Job>> {
+    static JOBS: OnceLock<Mutex<HashMap<Uuid, Job>>> = OnceLock::new();
+    JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn create_job() -> Uuid {
+    let job_id = Uuid::new_v4();
+    let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    jobs().lock().unwrap().insert(
+        job_id,
+        Job {
+            history: Vec::new(),
+            sender,
+        },
+    );
+    job_id
+}
+
+/// Record an event and forward it to live subscribers
+fn emit(job_id: Uuid, event: ProgressEvent) {
+    let terminal = event.is_terminal();
+
+    if let Some(job) = jobs().lock().unwrap().get_mut(&job_id) {
+        job.history.push(event.clone());
+        // No subscribers is fine: the history replays on connect
+        let _ = job.sender.send(event);
+    }
+
+    if terminal {
+        tokio::spawn(async move {
+            tokio::time::sleep(FINISHED_JOB_TTL).await;
+            jobs().lock().unwrap().remove(&job_id);
+        });
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AsyncDetectResponse {
+    pub job_id: Uuid,
+    pub progress_url: String,
+}
+
+/// Submit a connected components detection job; progress streams from `/detect/progress/:job_id`
+pub async fn detect_async_handler(
+    ValidatedJson(request): ValidatedJson<ImageDetectRequest>,
+) -> (StatusCode, Json<AsyncDetectResponse>) {
+    let job_id = create_job();
+    info!("Queued async detection job {}", job_id);
+
+    tokio::task::spawn_blocking(move || run_detection_job(job_id, &request));
+
+    (
+        StatusCode::ACCEPTED,
+        Json(AsyncDetectResponse {
+            job_id,
+            progress_url: format!("/detect/progress/{}", job_id),
+        }),
+    )
+}
+
+fn run_detection_job(job_id: Uuid, request: &ImageDetectRequest) {
+    let start_time = Instant::now();
+
+    let threshold = match request.threshold_config() {
+        Ok(threshold) => threshold,
+        Err((_, Json(error))) => {
+            emit(job_id, ProgressEvent::failed(&error.error, error.message));
+            return;
+        }
+    };
+
+    let img_bytes = match base64::engine::general_purpose::STANDARD.decode(&request.image) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            emit(job_id, ProgressEvent::failed("INVALID_BASE64", format!("Failed to decode base64 image: {}", e)));
+            return;
+        }
+    };
+
+    if let Err((_, Json(error))) = crate::check_image_size(&img_bytes) {
+        emit(job_id, ProgressEvent::failed(&error.error, error.message));
+        return;
+    }
+
+    let img = match image::load_from_memory(&img_bytes) {
+        Ok(img) => img,
+        Err(e) => {
+            emit(job_id, ProgressEvent::failed("INVALID_IMAGE", format!("Failed to load image: {}", e)));
+            return;
+        }
+    };
+    let (img, scale) = image_preprocessor::downscale_to_max_dimension(&img, request.max_dimension);
+    let img = img.to_luma8();
+    emit(
+        job_id,
+        ProgressEvent::progress(format!("decoded image ({}x{})", img.width(), img.height()), Some(0)),
+    );
+
+    let mut rooms = connected_components::detect_rooms_connected_components_with_progress(
+        &img,
+        threshold,
+        crate::scale_min_area(request.min_area, scale),
+        request.max_area_ratio,
+        request.connectivity,
+        |percent| emit(job_id, ProgressEvent::progress(format!("flood fill {}%", percent), Some(percent))),
+    );
+    restore_room_scale(&mut rooms, scale);
+
+    info!("Async job {} detected {} rooms", job_id, rooms.len());
+    emit(
+        job_id,
+        ProgressEvent::Done {
+            message: format!("done, {} rooms", rooms.len()),
+            total_rooms: rooms.len(),
+            rooms,
+            execution_time_ms: start_time.elapsed().as_millis() as u64,
+        },
+    );
+}
+
+/// Server-Sent Events stream of a job's progress, ending after the done/error event
+pub async fn detect_progress_handler(
+    Path(job_id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, (StatusCode, Json<ErrorResponse>)> {
+    let (history, receiver) = {
+        let jobs = jobs().lock().unwrap();
+        let job = jobs.get(&job_id).ok_or_else(|| {
+            warn!("Progress requested for unknown job {}", job_id);
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "JOB_NOT_FOUND".to_string(),
+                    message: format!("No detection job with id {}", job_id),
+                }),
+            )
+        })?;
+        // Subscribe under the lock so no event falls between history and channel
+        (job.history.clone(), job.sender.subscribe())
+    };
+
+    let finished = history.iter().any(ProgressEvent::is_terminal);
+    let live = stream::unfold((receiver, finished), |(mut receiver, finished)| async move {
+        if finished {
+            return None;
+        }
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let terminal = event.is_terminal();
+                    return Some((event, (receiver, terminal)));
+                }
+                // Slow subscriber: skip the dropped events, keep streaming
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    let events = stream::iter(history).chain(live).map(|event| event.to_sse());
+
+    Ok(Sse::new(events).keep_alive(
+        KeepAlive::new()
+            .interval(HEARTBEAT_INTERVAL)
+            .event(Event::default().event("heartbeat")),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_job_history_replayed_to_late_subscriber() {
+        let job_id = create_job();
+        emit(job_id, ProgressEvent::progress("decoded image", Some(0)));
+        emit(
+            job_id,
+            ProgressEvent::Done {
+                message: "done, 0 rooms".to_string(),
+                total_rooms: 0,
+                rooms: Vec::new(),
+                execution_time_ms: 1,
+            },
+        );
+
+        let jobs = jobs().lock().unwrap();
+        let history = &jobs.get(&job_id).unwrap().history;
+        assert_eq!(history.len(), 2);
+        assert!(!history[0].is_terminal());
+        assert!(history[1].is_terminal());
+        assert_eq!(history[1].event_name(), "done");
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_emits_error_event() {
+        let job_id = create_job();
+        let request = ImageDetectRequest {
+            image: "not base64!".to_string(),
+            threshold: 200,
+            min_area: 200,
+            max_area_ratio: 0.3,
+            connectivity: connected_components::Connectivity::Eight,
+            max_dimension: 2000,
+            threshold_mode: connected_components::ThresholdMode::Global,
+            block_size: 31,
+            adaptive_c: 10,
+            vtracer_config: None,
+        };
+
+        run_detection_job(job_id, &request);
+
+        let jobs = jobs().lock().unwrap();
+        let history = &jobs.get(&job_id).unwrap().history;
+        assert_eq!(history.len(), 1);
+        assert!(matches!(&history[0], ProgressEvent::Failed { error, .. } if error == "INVALID_BASE64"));
+    }
+}
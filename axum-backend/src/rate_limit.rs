@@ -0,0 +1,185 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::ErrorResponse;
+
+/// Requests per minute per client unless `RATE_LIMIT_PER_MINUTE` overrides it (0 disables)
+const DEFAULT_RATE_LIMIT_PER_MINUTE: u32 = 120;
+/// Tracked clients before full (idle) buckets are pruned
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+/// Header Fly's edge proxy sets to the address it accepted the connection from
+const FLY_CLIENT_IP: &str = "fly-client-ip";
+
+/// Requests per minute, overridable with `RATE_LIMIT_PER_MINUTE`
+pub fn rate_limit_per_minute() -> u32 {
+    std::env::var("RATE_LIMIT_PER_MINUTE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RATE_LIMIT_PER_MINUTE)
+}
+
+/// Whether a reverse proxy in front of the server sets the client address headers, from
+/// `TRUST_PROXY_HEADERS`. Without one, those headers are client-controlled and ignored.
+pub fn trust_proxy_headers() -> bool {
+    std::env::var("TRUST_PROXY_HEADERS").is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token bucket per client IP: bursts up to the per-minute limit, refilling continuously
+#[derive(Clone)]
+pub struct RateLimiter {
+    per_minute: u32,
+    trust_proxy: bool,
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            trust_proxy: false,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Key clients by the address the proxy reports instead of the peer address
+    pub fn trusting_proxy(mut self, trust_proxy: bool) -> Self {
+        self.trust_proxy = trust_proxy;
+        self
+    }
+
+    /// Take a token for `client`, or the whole seconds until one is available
+    fn acquire(&self, client: &str) -> Result<(), u64> {
+        let capacity = self.per_minute as f64;
+        let per_second = capacity / 60.0;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(client) {
+            buckets.retain(|_, b| b.tokens + now.duration_since(b.refilled_at).as_secs_f64() * per_second < capacity);
+        }
+
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / per_second).ceil().max(1.0) as u64)
+        }
+    }
+}
+
+/// Client IP. Behind a trusted proxy this is `Fly-Client-IP`, else the last `X-Forwarded-For`
+/// entry, which the proxy appended; earlier entries come from the client and are ignored.
+/// Otherwise it is the peer address.
+pub(crate) fn client_ip(request: &Request, trust_proxy: bool) -> String {
+    let header = |name: &str| request.headers().get(name).and_then(|value| value.to_str().ok());
+    trust_proxy
+        .then(|| {
+            header(FLY_CLIENT_IP).or_else(|| header("x-forwarded-for").and_then(|value| value.rsplit(',').next()))
+        })
+        .flatten()
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .map(str::to_string)
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Middleware rejecting clients over their limit with 429 and `Retry-After`; health checks are exempt
+pub async fn limit_requests(State(limiter): State<RateLimiter>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    let path = path.strip_prefix("/api").unwrap_or(path);
+    if limiter.per_minute == 0 || path == "/health" || path.starts_with("/health/") {
+        return next.run(request).await;
+    }
+
+    match limiter.acquire(&client_ip(&request, limiter.trust_proxy)) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(ErrorResponse {
+                    error: "RATE_LIMITED".to_string(),
+                    message: format!(
+                        "Rate limit of {} requests per minute exceeded; retry in {}s",
+                        limiter.per_minute, retry_after
+                    ),
+                }),
+            )
+                .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_is_per_client_and_reports_retry_after() {
+        let limiter = RateLimiter::new(2);
+
+        assert!(limiter.acquire("10.0.0.1").is_ok());
+        assert!(limiter.acquire("10.0.0.1").is_ok());
+        // One token refills every 30s at 2/min
+        let retry_after = limiter.acquire("10.0.0.1").unwrap_err();
+        assert!((29..=30).contains(&retry_after), "retry after {}", retry_after);
+
+        assert!(limiter.acquire("10.0.0.2").is_ok());
+    }
+
+    fn request_from(peer: &str, headers: &[(&str, &str)]) -> Request {
+        let mut request = Request::get("/api/detect");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let mut request = request.body(axum::body::Body::empty()).unwrap();
+        request.extensions_mut().insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+        request
+    }
+
+    #[test]
+    fn test_client_ip_ignores_client_supplied_forwarding_entries() {
+        let spoofed = request_from("10.0.0.9:4000", &[("x-forwarded-for", "1.2.3.4, 203.0.113.7")]);
+        assert_eq!(client_ip(&spoofed, true), "203.0.113.7");
+        // Without a trusted proxy, the whole header is client-controlled
+        assert_eq!(client_ip(&spoofed, false), "10.0.0.9");
+
+        let fly = request_from(
+            "10.0.0.9:4000",
+            &[("fly-client-ip", "198.51.100.1"), ("x-forwarded-for", "1.2.3.4, 198.51.100.1")],
+        );
+        assert_eq!(client_ip(&fly, true), "198.51.100.1");
+        assert_eq!(client_ip(&fly, false), "10.0.0.9");
+    }
+}
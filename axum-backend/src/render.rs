@@ -0,0 +1,190 @@
+use axum::{
+    extract::Json,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use std::fmt::Write;
+use tracing::info;
+
+use crate::room_detector::NORMALIZED_SIZE;
+use crate::{ErrorResponse, Line, Room};
+
+/// Room fill colors, matching the frontend's canvas renderer
+const ROOM_COLORS: [&str; 10] = [
+    "#FF6B6B", "#4ECDC4", "#45B7D1", "#FFA07A", "#98D8C8", "#F7DC6F", "#BB8FCE", "#85C1E2", "#F8B88B", "#AAB7B8",
+];
+
+const MAX_RENDER_DIMENSION: u32 = 10_000;
+
+#[derive(Debug, Deserialize)]
+pub struct RenderSvgRequest {
+    rooms: Vec<Room>,
+    /// Walls drawn over the rooms, in the same 0-1000 space
+    #[serde(default)]
+    walls: Vec<Line>,
+    #[serde(default = "default_render_size")]
+    width: u32,
+    #[serde(default = "default_render_size")]
+    height: u32,
+}
+
+fn default_render_size() -> u32 {
+    1000
+}
+
+/// Escape text for use in SVG content and attribute values
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Draw rooms (and optional walls) as an SVG; rooms with fewer than 3 points use their bounding box
+pub fn render_rooms_svg(rooms: &[Room], walls: &[Line], width: u32, height: u32) -> String {
+    let sx = width as f64 / NORMALIZED_SIZE;
+    let sy = height as f64 / NORMALIZED_SIZE;
+    let mut svg = String::new();
+
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(svg, r#"<rect x="0" y="0" width="{}" height="{}" fill="white"/>"#, width, height);
+
+    for (i, room) in rooms.iter().enumerate() {
+        let color = ROOM_COLORS[i % ROOM_COLORS.len()];
+        let [x1, y1, x2, y2] = room.bounding_box;
+        let (x1, y1, x2, y2) = (x1 * sx, y1 * sy, x2 * sx, y2 * sy);
+
+        if room.points.len() >= 3 {
+            let points: Vec<String> = room
+                .points
+                .iter()
+                .map(|p| format!("{:.2},{:.2}", p.x * sx, p.y * sy))
+                .collect();
+            let _ = writeln!(
+                svg,
+                r##"<polygon points="{}" fill="{}" fill-opacity="0.6" stroke="#333" stroke-width="2"/>"##,
+                points.join(" "),
+                color
+            );
+        } else {
+            let _ = writeln!(
+                svg,
+                r##"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}" fill-opacity="0.6" stroke="#333" stroke-width="2"/>"##,
+                x1,
+                y1,
+                x2 - x1,
+                y2 - y1,
+                color
+            );
+        }
+
+        let _ = writeln!(
+            svg,
+            r##"<text x="{:.2}" y="{:.2}" font-family="Arial" font-size="14" fill="#000">Room {}: {}</text>"##,
+            x1 + 10.0,
+            y1 + 25.0,
+            room.id,
+            escape_xml(&room.name_hint)
+        );
+    }
+
+    for wall in walls {
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="#000" stroke-width="3"/>"##,
+            wall.start.x * sx,
+            wall.start.y * sy,
+            wall.end.x * sx,
+            wall.end.y * sy
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// `POST /render/svg`: detected rooms as a colored SVG overlay
+pub async fn render_svg_handler(
+    Json(request): Json<RenderSvgRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    info!(
+        "Rendering {} rooms and {} walls to {}x{} SVG",
+        request.rooms.len(),
+        request.walls.len(),
+        request.width,
+        request.height
+    );
+
+    let valid = 1..=MAX_RENDER_DIMENSION;
+    if !valid.contains(&request.width) || !valid.contains(&request.height) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "INVALID_DIMENSIONS".to_string(),
+                message: format!(
+                    "width and height must be between 1 and {}, got {}x{}",
+                    MAX_RENDER_DIMENSION, request.width, request.height
+                ),
+            }),
+        ));
+    }
+
+    let svg = render_rooms_svg(&request.rooms, &request.walls, request.width, request.height);
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+
+    fn room(id: usize, name_hint: &str, points: Vec<Point>) -> Room {
+        Room {
+            id,
+            bounding_box: [100.0, 100.0, 400.0, 300.0],
+            area: 60000.0,
+            name_hint: name_hint.to_string(),
+            points,
+            ..Room::default()
+        }
+    }
+
+    #[test]
+    fn test_render_one_shape_and_label_per_room() {
+        let square = vec![
+            Point { x: 100.0, y: 100.0 },
+            Point { x: 400.0, y: 100.0 },
+            Point { x: 400.0, y: 300.0 },
+            Point { x: 100.0, y: 300.0 },
+        ];
+        let rooms = vec![
+            room(1, "Kitchen", square),
+            room(2, "Bed & Bath", Vec::new()),
+            room(3, "Hall", Vec::new()),
+        ];
+        let walls = vec![Line {
+            start: Point { x: 0.0, y: 0.0 },
+            end: Point { x: 1000.0, y: 0.0 },
+            is_load_bearing: false,
+        }];
+
+        let svg = render_rooms_svg(&rooms, &walls, 500, 400);
+
+        // One background rect plus one shape per room
+        assert_eq!(svg.matches("<polygon").count(), 1);
+        assert_eq!(svg.matches("<rect").count(), 1 + 2);
+        assert_eq!(svg.matches("<text").count(), rooms.len());
+        assert_eq!(svg.matches("<line").count(), 1);
+        assert!(svg.contains("Room 2: Bed &amp; Bath"));
+        // 0-1000 space scaled to 500x400
+        assert!(svg.contains(r#"points="50.00,40.00 200.00,40.00 200.00,120.00 50.00,120.00""#));
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="500" height="400""#));
+    }
+}
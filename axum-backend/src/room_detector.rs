@@ -1,18 +1,95 @@
-use crate::{graph_builder::FloorplanGraph, Point, Room};
-use geo::{Area, Coord, LineString, Polygon as GeoPolygon};
+use crate::{graph_builder::FloorplanGraph, Line, Point, Room};
+use geo::{Area, Contains, ConvexHull, Coord, LineString, Polygon as GeoPolygon};
 use petgraph::graph::NodeIndex;
 use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 use tracing::{warn, debug};
 
 // Maximum number of cycles to detect (prevent DoS)
-const MAX_CYCLES: usize = 1000;
+pub const MAX_CYCLES: usize = 1000;
 // Maximum cycle length to consider (prevent exponential blowup)
-const MAX_CYCLE_LENGTH: usize = 100;
+pub const MAX_CYCLE_LENGTH: usize = 100;
+// Wall-clock budget for cycle enumeration, overridable with `CYCLE_SEARCH_TIMEOUT_MS`
+pub const DEFAULT_CYCLE_SEARCH_TIMEOUT_MS: u64 = 5000;
+const CYCLE_SEARCH_TIMEOUT_ENV: &str = "CYCLE_SEARCH_TIMEOUT_MS";
+// DFS steps between deadline checks, so the clock is not read on every node
+const DEADLINE_CHECK_INTERVAL: u32 = 1024;
 
 /// Detect rooms in a floorplan graph by finding cycles
 pub fn detect_rooms(graph: &FloorplanGraph, area_threshold: f64, outer_boundary_ratio: f64) -> Vec<Room> {
-    let cycles = find_room_cycles(graph, outer_boundary_ratio);
+    detect_rooms_with_naming(graph, area_threshold, outer_boundary_ratio, &NamingRules::default(), false)
+}
+
+/// Like `detect_rooms`, naming rooms with custom `naming_rules`. With `include_outer_boundary`,
+/// the building footprint is appended as a `RoomKind::Footprint` room instead of being dropped.
+pub fn detect_rooms_with_naming(
+    graph: &FloorplanGraph,
+    area_threshold: f64,
+    outer_boundary_ratio: f64,
+    naming_rules: &NamingRules,
+    include_outer_boundary: bool,
+) -> Vec<Room> {
+    detect_rooms_explained(
+        graph,
+        area_threshold,
+        outer_boundary_ratio,
+        naming_rules,
+        include_outer_boundary,
+        CycleMethod::default(),
+    )
+    .rooms
+}
+
+/// How candidate room cycles are found in the wall graph
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CycleMethod {
+    /// Trace the faces of the planar wall graph, in near-linear time and without caps
+    #[default]
+    Faces,
+    /// Enumerate every simple cycle by DFS, bounded by MAX_CYCLES and the search deadline
+    Dfs,
+}
+
+/// Why detection produced no rooms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyReason {
+    /// The request had no lines to build a graph from
+    NoLines,
+    /// The walls form no closed cycle
+    NoCycles,
+    /// The walls never meet up, so no cycle can close
+    GraphDisconnected,
+    /// Every cycle was smaller than `area_threshold`
+    BelowAreaThreshold,
+    /// Only the building's outer boundary passed `area_threshold`, and it is not returned as a room
+    OnlyOuterBoundary,
+}
+
+/// Rooms from `detect_rooms_explained`, with the reason when there are none
+#[derive(Debug, Clone)]
+pub struct RoomDetection {
+    pub rooms: Vec<Room>,
+    pub empty_reason: Option<EmptyReason>,
+    /// The DFS search deadline passed, so some rooms may be missing (`CycleMethod::Dfs` only)
+    pub truncated: bool,
+}
+
+/// Like `detect_rooms_with_naming`, also reporting why no rooms were found
+pub fn detect_rooms_explained(
+    graph: &FloorplanGraph,
+    area_threshold: f64,
+    outer_boundary_ratio: f64,
+    naming_rules: &NamingRules,
+    include_outer_boundary: bool,
+    cycle_method: CycleMethod,
+) -> RoomDetection {
+    let RoomCycles { rooms: cycles, outer_boundary, truncated } =
+        find_room_cycles(graph, outer_boundary_ratio, cycle_method);
+    let courtyards = find_courtyard_cycles(graph, &cycles, outer_boundary.as_deref());
     let mut rooms = Vec::new();
 
     for (id, cycle_nodes) in cycles.iter().enumerate() {
@@ -38,7 +115,9 @@ pub fn detect_rooms(graph: &FloorplanGraph, area_threshold: f64, outer_boundary_
         let bbox = calculate_bounding_box(&points);
 
         // Generate heuristic name
-        let name_hint = generate_room_name(area, &bbox);
+        let name_hint = generate_room_name(area, &bbox, naming_rules);
+        let confidence = cycle_confidence(graph, cycle_nodes, &points, area, &bbox);
+        let wall_lengths = wall_lengths(&points);
 
         rooms.push(Room {
             id,
@@ -46,39 +125,522 @@ pub fn detect_rooms(graph: &FloorplanGraph, area_threshold: f64, outer_boundary_
             area,
             name_hint,
             points,
+            kind: if courtyards.contains(&id) { RoomKind::Courtyard } else { RoomKind::Enclosed },
+            confidence: Some(confidence),
+            load_bearing_edges: load_bearing_edges(graph, cycle_nodes),
+            perimeter: Some(wall_lengths.iter().sum()),
+            wall_lengths,
+            ..Room::default()
         });
     }
 
-    rooms
+    if let Some(cycle_nodes) = outer_boundary.as_ref().filter(|_| include_outer_boundary) {
+        let points = cycle_points(graph, cycle_nodes);
+        let area = calculate_polygon_area(&points);
+        let bbox = calculate_bounding_box(&points);
+        let wall_lengths = wall_lengths(&points);
+        rooms.push(Room {
+            id: cycles.len(),
+            bounding_box: bbox,
+            area,
+            name_hint: FOOTPRINT_NAME.to_string(),
+            confidence: Some(cycle_confidence(graph, cycle_nodes, &points, area, &bbox)),
+            points,
+            kind: RoomKind::Footprint,
+            load_bearing_edges: load_bearing_edges(graph, cycle_nodes),
+            perimeter: Some(wall_lengths.iter().sum()),
+            wall_lengths,
+            ..Room::default()
+        });
+    }
+
+    let empty_reason = rooms.is_empty().then(|| {
+        let outer_area = outer_boundary
+            .as_ref()
+            .map(|cycle_nodes| calculate_polygon_area(&cycle_points(graph, cycle_nodes)));
+        if outer_area.is_some_and(|area| area >= area_threshold) {
+            EmptyReason::OnlyOuterBoundary
+        } else if !cycles.is_empty() || outer_area.is_some() {
+            EmptyReason::BelowAreaThreshold
+        } else if petgraph::algo::connected_components(graph) > 1 {
+            EmptyReason::GraphDisconnected
+        } else {
+            EmptyReason::NoCycles
+        }
+    });
+
+    assign_spatial_ids(&mut rooms);
+    RoomDetection { rooms, empty_reason, truncated }
+}
+
+/// Index pairs of consecutive cycle nodes joined by a load-bearing wall
+fn load_bearing_edges(graph: &FloorplanGraph, cycle: &[NodeIndex]) -> Vec<[usize; 2]> {
+    cycle
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| {
+            graph
+                .edges_connecting(pair[0], pair[1])
+                .any(|edge| !edge.weight().is_virtual() && edge.weight().line().is_load_bearing)
+        })
+        .map(|(i, _)| [i, i + 1])
+        .collect()
+}
+
+/// Reading order for rooms: top-to-bottom, then left-to-right by bounding-box min corner,
+/// with the max corner breaking ties
+pub fn spatial_order(a: &[f64; 4], b: &[f64; 4]) -> std::cmp::Ordering {
+    a[1].total_cmp(&b[1])
+        .then(a[0].total_cmp(&b[0]))
+        .then(a[3].total_cmp(&b[3]))
+        .then(a[2].total_cmp(&b[2]))
+}
+
+/// Sort rooms into `spatial_order` (footprint last) and renumber ids to match, so the same
+/// plan always gets the same ids regardless of cycle enumeration order
+pub fn assign_spatial_ids(rooms: &mut [Room]) {
+    rooms.sort_by(|a, b| {
+        (a.kind == RoomKind::Footprint)
+            .cmp(&(b.kind == RoomKind::Footprint))
+            .then_with(|| spatial_order(&a.bounding_box, &b.bounding_box))
+    });
+    for (id, room) in rooms.iter_mut().enumerate() {
+        room.id = id;
+    }
+}
+
+/// Length of each edge between consecutive `points` (a closed outline repeats its first point)
+pub fn wall_lengths(points: &[Point]) -> Vec<f64> {
+    points
+        .windows(2)
+        .map(|pair| squared_distance(&pair[0], &pair[1]).sqrt())
+        .collect()
+}
+
+/// Side of the normalized coordinate space every detector reports rooms in
+pub const NORMALIZED_SIZE: f64 = 1000.0;
+
+/// `[min_x, min_y, max_x, max_y]` over every line endpoint; `None` without lines
+pub fn lines_bounds(lines: &[Line]) -> Option<[f64; 4]> {
+    lines
+        .iter()
+        .flat_map(|line| [&line.start, &line.end])
+        .fold(None, |bounds, p| {
+            let [min_x, min_y, max_x, max_y] = bounds.unwrap_or([p.x, p.y, p.x, p.y]);
+            Some([min_x.min(p.x), min_y.min(p.y), max_x.max(p.x), max_y.max(p.y)])
+        })
+}
+
+/// Map room outlines and bounding boxes from input units into the 0-1000 space the image
+/// detectors use: each axis of `bounds` stretches to 0-1000 on its own, as an image's width
+/// and height do. Areas and wall lengths stay in input units, like image detectors' pixel
+/// areas. A degenerate axis is only translated.
+pub fn normalize_rooms_to_1000(rooms: &mut [Room], bounds: [f64; 4]) {
+    map_room_coordinates(rooms, point_to_1000(bounds));
+}
+
+/// Undo [`normalize_rooms_to_1000`], mapping rooms back into the input units of `bounds`
+pub fn denormalize_rooms_from_1000(rooms: &mut [Room], bounds: [f64; 4]) {
+    let (scale_x, scale_y) = scale_to_1000(bounds);
+    map_room_coordinates(rooms, |x, y| (x / scale_x + bounds[0], y / scale_y + bounds[1]));
+}
+
+/// Map wall endpoints into 0-1000 the same way as [`normalize_rooms_to_1000`]
+pub fn normalize_lines_to_1000(lines: &mut [Line], bounds: [f64; 4]) {
+    let map = point_to_1000(bounds);
+    for point in lines.iter_mut().flat_map(|line| [&mut line.start, &mut line.end]) {
+        (point.x, point.y) = map(point.x, point.y);
+    }
+}
+
+/// The per-point map [`normalize_rooms_to_1000`] applies, for other geometry in the same space
+pub fn point_to_1000(bounds: [f64; 4]) -> impl Fn(f64, f64) -> (f64, f64) {
+    let (scale_x, scale_y) = scale_to_1000(bounds);
+    move |x, y| ((x - bounds[0]) * scale_x, (y - bounds[1]) * scale_y)
+}
+
+/// Per-axis factors taking `bounds` onto 0-1000
+fn scale_to_1000(bounds: [f64; 4]) -> (f64, f64) {
+    let [min_x, min_y, max_x, max_y] = bounds;
+    let scale = |extent: f64| if extent > 0.0 { NORMALIZED_SIZE / extent } else { 1.0 };
+    (scale(max_x - min_x), scale(max_y - min_y))
+}
+
+fn map_room_coordinates(rooms: &mut [Room], map: impl Fn(f64, f64) -> (f64, f64)) {
+    for room in rooms {
+        let [x1, y1, x2, y2] = room.bounding_box;
+        let ((x1, y1), (x2, y2)) = (map(x1, y1), map(x2, y2));
+        room.bounding_box = [x1, y1, x2, y2];
+        for point in &mut room.points {
+            (point.x, point.y) = map(point.x, point.y);
+        }
+    }
+}
+
+/// Simplify a room's outline, keeping `load_bearing_edges` pointing at the surviving vertices.
+/// A merged edge stays load-bearing only if every wall it replaces was.
+pub fn simplify_room(room: &mut Room, epsilon: f64) {
+    let simplified = simplify_polygon(&room.points, epsilon);
+    if simplified.len() == room.points.len() {
+        return;
+    }
+
+    // Simplification keeps a subsequence of the original vertices
+    let mut kept = Vec::with_capacity(simplified.len());
+    let mut next = 0;
+    for point in &simplified {
+        let Some(offset) = room.points[next..].iter().position(|p| p == point) else {
+            kept.clear();
+            break;
+        };
+        kept.push(next + offset);
+        next += offset + 1;
+    }
+
+    let flagged: HashSet<usize> = room.load_bearing_edges.iter().map(|edge| edge[0]).collect();
+    room.load_bearing_edges = kept
+        .windows(2)
+        .enumerate()
+        .filter(|(_, span)| (span[0]..span[1]).all(|i| flagged.contains(&i)))
+        .map(|(i, _)| [i, i + 1])
+        .collect();
+    room.points = simplified;
+    if room.perimeter.is_some() {
+        room.wall_lengths = wall_lengths(&room.points);
+        room.perimeter = Some(room.wall_lengths.iter().sum());
+    }
+}
+
+/// Name given to the outer boundary when it is returned as a room
+const FOOTPRINT_NAME: &str = "Building Footprint";
+
+/// Confidence lost for each door-bridged edge in a cycle
+const BRIDGED_EDGE_PENALTY: f64 = 0.15;
+
+/// Geometric confidence in 0..1 that a cycle is a real room. Rectangular, convex outlines
+/// closed entirely by walls score highest; door-bridged edges lower it, both per edge and by
+/// the share of the perimeter they span. There is no separate closure-gap factor: endpoints
+/// are snapped and merged into shared nodes before the graph is built, so every cycle closes
+/// exactly, and a gap too wide to snap only closes through a door bridge, which is scored.
+fn cycle_confidence(graph: &FloorplanGraph, cycle: &[NodeIndex], points: &[Point], area: f64, bbox: &[f64; 4]) -> f64 {
+    let mut perimeter = 0.0;
+    let mut bridged_length = 0.0;
+    let mut bridged_edges = 0;
+    for pair in cycle.windows(2) {
+        let length = graph[pair[0]].distance_to(&graph[pair[1]]);
+        perimeter += length;
+        if !graph.edges_connecting(pair[0], pair[1]).any(|edge| !edge.weight().is_virtual()) {
+            bridged_length += length;
+            bridged_edges += 1;
+        }
+    }
+    if perimeter <= 0.0 || area <= 0.0 {
+        return 0.0;
+    }
+
+    let bbox_area = (bbox[2] - bbox[0]) * (bbox[3] - bbox[1]);
+    let rectangularity = if bbox_area > 0.0 { area / bbox_area } else { 0.0 };
+    let hull_area = geo_polygon(points).convex_hull().unsigned_area();
+    let convexity = if hull_area > 0.0 { area / hull_area } else { 0.0 };
+
+    let shape = (rectangularity.min(1.0) + convexity.min(1.0)) / 2.0;
+    let closure = 1.0 - bridged_length / perimeter;
+    (shape * closure * (1.0 - BRIDGED_EDGE_PENALTY).powi(bridged_edges)).clamp(0.0, 1.0)
+}
+
+/// Whether a detected cycle is a roofed room or a hole in the building footprint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoomKind {
+    #[default]
+    Enclosed,
+    /// Open space inside the footprint, ringed on every side by other rooms with no doorway onto it
+    Courtyard,
+    /// The building's outer boundary, returned only on request
+    Footprint,
+}
+
+/// Indices of room cycles that are open-air holes in the footprint: the footprint contains
+/// the cycle without it touching an exterior wall, every wall is shared with a room around
+/// it, and no doorway opens onto it. Interior closets and halls are reached through a door,
+/// so they stay enclosed rooms.
+fn find_courtyard_cycles(
+    graph: &FloorplanGraph,
+    cycles: &[Vec<NodeIndex>],
+    outer_boundary: Option<&[NodeIndex]>,
+) -> HashSet<usize> {
+    let Some(outer_boundary) = outer_boundary else {
+        return HashSet::new();
+    };
+    let exterior = cycle_edges(outer_boundary);
+    let footprint = geo_polygon(&cycle_points(graph, outer_boundary));
+
+    let edges: Vec<HashSet<(NodeIndex, NodeIndex)>> = cycles.iter().map(|cycle| cycle_edges(cycle)).collect();
+    let mut edge_counts: HashMap<(NodeIndex, NodeIndex), usize> = HashMap::new();
+    for edge in edges.iter().flatten() {
+        *edge_counts.entry(*edge).or_default() += 1;
+    }
+    let is_doorway = |&(a, b): &(NodeIndex, NodeIndex)| !graph.edges_connecting(a, b).any(|edge| !edge.weight().is_virtual());
+
+    edges
+        .iter()
+        .enumerate()
+        .filter(|(idx, cycle_edges)| {
+            !cycle_edges.is_empty()
+                && cycle_edges.is_disjoint(&exterior)
+                && cycle_edges.iter().all(|edge| edge_counts[edge] > 1 && !is_doorway(edge))
+                && footprint.contains(&geo_polygon(&cycle_points(graph, &cycles[*idx])))
+        })
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+fn geo_polygon(points: &[Point]) -> GeoPolygon<f64> {
+    let coords: Vec<Coord> = points.iter().map(|p| Coord { x: p.x, y: p.y }).collect();
+    GeoPolygon::new(LineString::from(coords), vec![])
+}
+
+/// Undirected edges of a cycle, whether or not its closing node is repeated
+fn cycle_edges(cycle: &[NodeIndex]) -> HashSet<(NodeIndex, NodeIndex)> {
+    let mut nodes = cycle.to_vec();
+    if nodes.len() > 1 && nodes.first() != nodes.last() {
+        nodes.push(nodes[0]);
+    }
+    nodes
+        .windows(2)
+        .map(|pair| (pair[0].min(pair[1]), pair[0].max(pair[1])))
+        .collect()
+}
+
+/// Raw cycle enumeration result, including what the DoS guards cut off
+#[derive(Debug, Clone, Default)]
+pub struct CycleEnumeration {
+    /// Deduplicated cycles (closed: last node repeats the first)
+    pub cycles: Vec<Vec<NodeIndex>>,
+    /// Whether MAX_CYCLES stopped the search early
+    pub cycle_limit_reached: bool,
+    /// Number of start nodes never explored because MAX_CYCLES was hit
+    pub unexplored_start_nodes: usize,
+    /// Number of distinct cycles dropped for exceeding MAX_CYCLE_LENGTH
+    pub truncated_by_length: usize,
+    /// Whether the wall-clock deadline stopped the search early
+    pub truncated: bool,
+}
+
+/// Cycle search deadline, overridable with `CYCLE_SEARCH_TIMEOUT_MS`
+pub fn cycle_search_timeout() -> Duration {
+    let millis = std::env::var(CYCLE_SEARCH_TIMEOUT_ENV)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_CYCLE_SEARCH_TIMEOUT_MS);
+    Duration::from_millis(millis)
+}
+
+/// Wall-clock deadline shared by every DFS of one enumeration
+struct SearchDeadline {
+    deadline: Instant,
+    steps: u32,
+    expired: bool,
+}
+
+impl SearchDeadline {
+    fn new(max_duration: Duration) -> Self {
+        Self { deadline: Instant::now() + max_duration, steps: 0, expired: false }
+    }
+
+    /// Count a DFS step and report whether the deadline has passed, reading the clock every
+    /// `DEADLINE_CHECK_INTERVAL` steps
+    fn tick(&mut self) -> bool {
+        if !self.expired {
+            self.steps += 1;
+            if self.steps >= DEADLINE_CHECK_INTERVAL {
+                self.steps = 0;
+                self.expired = Instant::now() >= self.deadline;
+            }
+        }
+        self.expired
+    }
 }
 
 /// Find all simple cycles in the undirected graph using DFS-based cycle enumeration
 /// Returns all valid cycles without filtering
+#[cfg(test)]
 pub fn find_all_cycles(graph: &FloorplanGraph) -> Vec<Vec<NodeIndex>> {
+    enumerate_cycles(graph).cycles
+}
+
+/// Enumerate all simple cycles and report how many were lost to the search limits
+pub fn enumerate_cycles(graph: &FloorplanGraph) -> CycleEnumeration {
+    enumerate_cycles_within(graph, cycle_search_timeout())
+}
+
+/// Like `enumerate_cycles`, giving up after `max_duration` with the cycles found so far
+pub fn enumerate_cycles_within(graph: &FloorplanGraph, max_duration: Duration) -> CycleEnumeration {
     let mut all_cycles = Vec::new();
+    let mut too_long = HashSet::new();
+    let mut result = CycleEnumeration::default();
+    let mut deadline = SearchDeadline::new(max_duration);
+    let start_nodes: Vec<NodeIndex> = graph.node_indices().collect();
 
     // For each node, start DFS to find cycles
-    for start_node in graph.node_indices() {
+    for (explored, &start_node) in start_nodes.iter().enumerate() {
         if all_cycles.len() >= MAX_CYCLES {
             debug!("Reached maximum cycle limit ({})", MAX_CYCLES);
+            result.cycle_limit_reached = true;
+            result.unexplored_start_nodes = start_nodes.len() - explored;
             break;
         }
 
         debug!("Starting cycle detection from node {:?}", start_node);
-        let cycles = find_cycles_from_node_dfs(graph, start_node);
+        let cycles = find_cycles_from_node_dfs(graph, start_node, &mut too_long, &mut deadline);
         all_cycles.extend(cycles);
+
+        if deadline.expired {
+            warn!("Cycle search hit its {:?} deadline; returning the cycles found so far", max_duration);
+            result.truncated = true;
+            result.unexplored_start_nodes = start_nodes.len() - explored - 1;
+            break;
+        }
     }
 
     // Deduplicate cycles
-    deduplicate_cycles(all_cycles)
+    result.cycles = deduplicate_cycles(all_cycles);
+    result.truncated_by_length = too_long.len();
+    result
+}
+
+/// Room cycles, plus the outer boundary that was filtered out of them
+#[derive(Debug, Default)]
+struct RoomCycles {
+    rooms: Vec<Vec<NodeIndex>>,
+    /// Largest enclosing cycle, i.e. the building footprint
+    outer_boundary: Option<Vec<NodeIndex>>,
+    /// The DFS search deadline cut the enumeration short
+    truncated: bool,
 }
 
 /// Find cycles that could represent room boundaries (filtered version)
-fn find_room_cycles(graph: &FloorplanGraph, outer_boundary_ratio: f64) -> Vec<Vec<NodeIndex>> {
-    let all_cycles = find_all_cycles(graph);
+fn find_room_cycles(graph: &FloorplanGraph, outer_boundary_ratio: f64, method: CycleMethod) -> RoomCycles {
+    match method {
+        CycleMethod::Faces => face_room_cycles(graph, outer_boundary_ratio),
+        // Filter to only include cycles that are large enough to be rooms (3+ sides)
+        CycleMethod::Dfs => {
+            let enumeration = enumerate_cycles(graph);
+            RoomCycles {
+                truncated: enumeration.truncated,
+                ..filter_room_cycles(enumeration.cycles, graph, outer_boundary_ratio)
+            }
+        }
+    }
+}
+
+/// Split traced faces by orientation: counterclockwise faces are rooms, and the largest
+/// clockwise face is the building footprint. Rooms enclosing a separate wall loop (a hole
+/// drawn inside them) are dropped as before. Falls back to `filter_room_cycles` and its
+/// `outer_boundary_ratio` heuristic when no face is clockwise.
+fn face_room_cycles(graph: &FloorplanGraph, outer_boundary_ratio: f64) -> RoomCycles {
+    let faces: Vec<Vec<NodeIndex>> = trace_faces(graph)
+        .into_iter()
+        .filter(|face| face.len() >= 4 && is_valid_cycle(face, graph))
+        .collect();
+    let signed_areas: Vec<f64> = faces
+        .iter()
+        .map(|face| signed_polygon_area(&cycle_points(graph, face)))
+        .collect();
 
-    // Filter to only include cycles that are large enough to be rooms (3+ sides)
-    filter_room_cycles(all_cycles, graph, outer_boundary_ratio)
+    let outer_boundary = signed_areas
+        .iter()
+        .enumerate()
+        .filter(|(_, &area)| area < 0.0)
+        .min_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(idx, _)| idx);
+    let Some(outer_boundary) = outer_boundary else {
+        return filter_room_cycles(deduplicate_cycles(faces), graph, outer_boundary_ratio);
+    };
+
+    let bounded: Vec<usize> = (0..faces.len()).filter(|&idx| signed_areas[idx] > 0.0).collect();
+    let polygons: Vec<Vec<Point>> = faces.iter().map(|face| cycle_points(graph, face)).collect();
+    let cycle_areas: Vec<(usize, f64)> = bounded.iter().map(|&idx| (idx, signed_areas[idx])).collect();
+    let containers = find_container_cycles(&polygons, &cycle_areas);
+
+    RoomCycles {
+        rooms: bounded
+            .into_iter()
+            .filter(|idx| !containers.contains(idx))
+            .map(|idx| faces[idx].clone())
+            .collect(),
+        outer_boundary: Some(faces[outer_boundary].clone()),
+        truncated: false,
+    }
+}
+
+/// Every face of the planar wall graph as closed cycles (last node repeats the first),
+/// including each component's outer face
+///
+/// Each directed edge is walked once, always turning onto the next edge clockwise around the
+/// node just reached. That traces bounded faces counterclockwise (positive signed area) and
+/// each component's outer face clockwise, so every room is found exactly once. Dangling
+/// walls are pruned first so faces stay simple.
+fn trace_faces(graph: &FloorplanGraph) -> Vec<Vec<NodeIndex>> {
+    let mut neighbors: HashMap<NodeIndex, Vec<NodeIndex>> = graph
+        .node_indices()
+        .map(|node| {
+            let mut adjacent: Vec<NodeIndex> = graph.neighbors(node).filter(|&n| n != node).collect();
+            adjacent.sort();
+            adjacent.dedup();
+            (node, adjacent)
+        })
+        .collect();
+
+    // Peel off dead ends until every remaining node closes at least one face
+    let mut dead_ends: Vec<NodeIndex> = neighbors
+        .iter()
+        .filter(|(_, adjacent)| adjacent.len() < 2)
+        .map(|(&node, _)| node)
+        .collect();
+    while let Some(node) = dead_ends.pop() {
+        let Some(adjacent) = neighbors.remove(&node) else {
+            continue;
+        };
+        for other in adjacent {
+            if let Some(others) = neighbors.get_mut(&other) {
+                others.retain(|&n| n != node);
+                if others.len() < 2 {
+                    dead_ends.push(other);
+                }
+            }
+        }
+    }
+
+    // Counterclockwise order around each node
+    for (&node, adjacent) in neighbors.iter_mut() {
+        let origin = &graph[node];
+        let angle = |n: &NodeIndex| (graph[*n].y - origin.y).atan2(graph[*n].x - origin.x);
+        adjacent.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+    }
+
+    let mut visited: HashSet<(NodeIndex, NodeIndex)> = HashSet::new();
+    let mut faces = Vec::new();
+    for start in graph.node_indices() {
+        let Some(adjacent) = neighbors.get(&start) else {
+            continue;
+        };
+        for &first in adjacent {
+            let mut face = vec![start];
+            let (mut from, mut to) = (start, first);
+            while visited.insert((from, to)) {
+                face.push(to);
+                let around = &neighbors[&to];
+                let back = around.iter().position(|&n| n == from).unwrap_or(0);
+                (from, to) = (to, around[(back + around.len() - 1) % around.len()]);
+            }
+            if face.len() > 1 {
+                faces.push(face);
+            }
+        }
+    }
+
+    faces
 }
 
 /// Find all cycles starting from a given node using DFS
@@ -86,13 +648,15 @@ fn find_room_cycles(graph: &FloorplanGraph, outer_boundary_ratio: f64) -> Vec<Ve
 fn find_cycles_from_node_dfs(
     graph: &FloorplanGraph,
     start: NodeIndex,
+    too_long: &mut HashSet<Vec<u32>>,
+    deadline: &mut SearchDeadline,
 ) -> Vec<Vec<NodeIndex>> {
     let mut cycles = Vec::new();
     let mut path = Vec::new();
     let mut path_set = HashSet::new();
 
     // Start DFS from the start node
-    dfs_cycle_find(graph, start, &mut path, &mut path_set, &mut cycles);
+    dfs_cycle_find(graph, start, &mut path, &mut path_set, &mut cycles, too_long, deadline);
 
     cycles
 }
@@ -105,13 +669,23 @@ fn dfs_cycle_find(
     path: &mut Vec<NodeIndex>,
     path_set: &mut HashSet<NodeIndex>,
     cycles: &mut Vec<Vec<NodeIndex>>,
+    too_long: &mut HashSet<Vec<u32>>,
+    deadline: &mut SearchDeadline,
 ) {
+    if deadline.tick() {
+        return;
+    }
+
     // Add current node to path
     path.push(current);
     path_set.insert(current);
 
     // Explore neighbors
     for edge in graph.edges(current) {
+        if deadline.expired {
+            break;
+        }
+
         // For undirected graph, get the "other" node (not current)
         let neighbor = if edge.source() == current {
             edge.target()
@@ -121,12 +695,14 @@ fn dfs_cycle_find(
 
         if !path_set.contains(&neighbor) {
             // Neighbor not in current path, continue DFS
-            dfs_cycle_find(graph, neighbor, path, path_set, cycles);
+            dfs_cycle_find(graph, neighbor, path, path_set, cycles, too_long, deadline);
         } else if path.len() >= 3 && neighbor == path[0] {
             // Found cycle back to start node - validate it's a proper cycle
             let mut cycle = path.clone();
             cycle.push(path[0]); // Close the cycle
-            if cycle.len() <= MAX_CYCLE_LENGTH && is_valid_cycle(&cycle, graph) {
+            if cycle.len() > MAX_CYCLE_LENGTH {
+                too_long.insert(cycle_signature(&cycle));
+            } else if is_valid_cycle(&cycle, graph) {
                 cycles.push(cycle);
             }
         }
@@ -200,8 +776,9 @@ fn is_valid_cycle(cycle: &[NodeIndex], graph: &FloorplanGraph) -> bool {
 /// Filter cycles to only include those that represent potential room boundaries
 /// - Must have at least 3 sides (minimum polygon)
 /// - Must be valid (all edges exist)
-/// - Filters out the outer boundary (largest area cycle)
-fn filter_room_cycles(cycles: Vec<Vec<NodeIndex>>, graph: &FloorplanGraph, outer_boundary_ratio: f64) -> Vec<Vec<NodeIndex>> {
+/// - Filters out every cycle that encloses another cycle (outer boundaries, courtyard rims)
+/// - Falls back to dropping the largest cycle by `outer_boundary_ratio` when nothing is nested
+fn filter_room_cycles(cycles: Vec<Vec<NodeIndex>>, graph: &FloorplanGraph, outer_boundary_ratio: f64) -> RoomCycles {
     let mut valid_cycles: Vec<Vec<NodeIndex>> = Vec::new();
 
     for cycle in cycles {
@@ -224,51 +801,180 @@ fn filter_room_cycles(cycles: Vec<Vec<NodeIndex>>, graph: &FloorplanGraph, outer
 
     // Filter out the outer boundary (largest area)
     if valid_cycles.len() <= 1 {
-        return valid_cycles; // If only one cycle, it's the only room
+        // If only one cycle, it's the only room
+        return RoomCycles {
+            rooms: valid_cycles,
+            outer_boundary: None,
+            truncated: false,
+        };
     }
 
-    // Calculate areas for all cycles
-    let mut cycle_areas: Vec<(usize, f64)> = valid_cycles
+    // Calculate polygons and areas for all cycles
+    let polygons: Vec<Vec<Point>> = valid_cycles
+        .iter()
+        .map(|cycle| cycle_points(graph, cycle))
+        .collect();
+
+    let mut cycle_areas: Vec<(usize, f64)> = polygons
         .iter()
         .enumerate()
-        .map(|(idx, cycle)| {
-            let points: Vec<Point> = cycle
-                .iter()
-                .filter_map(|&node_idx| {
-                    if node_idx.index() < graph.node_count() {
-                        Some(graph[node_idx].clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            let area = calculate_polygon_area(&points);
-            (idx, area)
-        })
+        .map(|(idx, points)| (idx, calculate_polygon_area(points)))
         .collect();
 
+    // Remove every cycle that geometrically contains the centroid of a smaller cycle
+    let containers = find_container_cycles(&polygons, &cycle_areas);
+
+    let outer_boundary = if !containers.is_empty() {
+        debug!("Removing {} cycles that enclose other cycles", containers.len());
+        let outermost = cycle_areas
+            .iter()
+            .filter(|(idx, _)| containers.contains(idx))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|&(idx, _)| idx);
+        cycle_areas.retain(|(idx, _)| !containers.contains(idx));
+        outermost
+    } else {
+        // Remove the largest cycle (outer boundary) if it's significantly larger
+        // Keep it if there are multiple cycles with similar area (indicates no clear outer boundary)
+        let areas: Vec<f64> = cycle_areas.iter().map(|(_, area)| *area).collect();
+        outer_boundary_index(&areas, outer_boundary_ratio).map(|outer| cycle_areas.remove(outer).0)
+    };
+
     // Sort by area descending
     cycle_areas.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
 
-    // Find the largest area
-    let largest_area = cycle_areas[0].1;
+    // Return filtered cycles in original order
+    RoomCycles {
+        rooms: cycle_areas
+            .into_iter()
+            .map(|(idx, _)| valid_cycles[idx].clone())
+            .collect(),
+        outer_boundary: outer_boundary.map(|idx| valid_cycles[idx].clone()),
+        truncated: false,
+    }
+}
 
-    // Remove the largest cycle (outer boundary) if it's significantly larger
-    // Keep it if there are multiple cycles with similar area (indicates no clear outer boundary)
+/// Indices of cycles that enclose at least one smaller cycle's centroid
+fn find_container_cycles(polygons: &[Vec<Point>], cycle_areas: &[(usize, f64)]) -> HashSet<usize> {
+    let centroids: Vec<Option<Point>> = polygons.iter().map(|points| polygon_centroid(points)).collect();
+    let mut containers = HashSet::new();
 
-    if cycle_areas.len() >= 2 && largest_area > cycle_areas[1].1 * outer_boundary_ratio {
-        // Remove the largest (outer boundary)
-        cycle_areas.remove(0);
+    for &(outer_idx, outer_area) in cycle_areas {
+        if polygons[outer_idx].len() < 3 {
+            continue;
+        }
+
+        let encloses_other = cycle_areas.iter().any(|&(inner_idx, inner_area)| {
+            inner_idx != outer_idx
+                && inner_area < outer_area
+                && centroids[inner_idx]
+                    .as_ref()
+                    .is_some_and(|c| point_in_polygon(c, &polygons[outer_idx]))
+        });
+
+        if encloses_other {
+            containers.insert(outer_idx);
+        }
     }
 
-    // Return filtered cycles in original order
-    cycle_areas
-        .into_iter()
-        .map(|(idx, _)| valid_cycles[idx].clone())
-        .collect()
+    containers
+}
+
+/// Area-weighted centroid of a polygon (Shoelace formula)
+/// Falls back to the vertex average for degenerate (zero-area) polygons
+fn polygon_centroid(points: &[Point]) -> Option<Point> {
+    if points.is_empty() {
+        return None;
+    }
+
+    let n = points.len();
+    let mut signed_area = 0.0;
+    let mut cx = 0.0;
+    let mut cy = 0.0;
+
+    for i in 0..n {
+        let p1 = &points[i];
+        let p2 = &points[(i + 1) % n];
+        let cross = p1.x * p2.y - p2.x * p1.y;
+        signed_area += cross;
+        cx += (p1.x + p2.x) * cross;
+        cy += (p1.y + p2.y) * cross;
+    }
+
+    signed_area *= 0.5;
+
+    if signed_area.abs() < 1e-9 {
+        let sum_x: f64 = points.iter().map(|p| p.x).sum();
+        let sum_y: f64 = points.iter().map(|p| p.y).sum();
+        return Some(Point { x: sum_x / n as f64, y: sum_y / n as f64 });
+    }
+
+    Some(Point {
+        x: cx / (6.0 * signed_area),
+        y: cy / (6.0 * signed_area),
+    })
 }
 
+/// Ray casting point-in-polygon test (ported from python-graph-rust)
+fn point_in_polygon(point: &Point, poly: &[Point]) -> bool {
+    if poly.is_empty() {
+        return false;
+    }
+
+    let (x, y) = (point.x, point.y);
+    let n = poly.len();
+    let mut inside = false;
+    let mut p1x = poly[0].x;
+    let mut p1y = poly[0].y;
+
+    for i in 0..n {
+        let p2x = poly[(i + 1) % n].x;
+        let p2y = poly[(i + 1) % n].y;
+
+        if y > p1y.min(p2y) && y <= p1y.max(p2y) && x <= p1x.max(p2x) && p1y != p2y {
+            let xinters = (y - p1y) * (p2x - p1x) / (p2y - p1y) + p1x;
+            if p1x == p2x || x <= xinters {
+                inside = !inside;
+            }
+        }
+
+        p1x = p2x;
+        p1y = p2y;
+    }
+
+    inside
+}
 
+/// Index of the cycle that would be discarded as the outer boundary, if any
+///
+/// The largest cycle is treated as the outer boundary only when its area exceeds
+/// the second-largest by more than `outer_boundary_ratio`.
+pub fn outer_boundary_index(areas: &[f64], outer_boundary_ratio: f64) -> Option<usize> {
+    if areas.len() < 2 {
+        return None;
+    }
+
+    let mut order: Vec<usize> = (0..areas.len()).collect();
+    order.sort_by(|&a, &b| areas[b].partial_cmp(&areas[a]).unwrap_or(std::cmp::Ordering::Equal));
+
+    let largest = order[0];
+    let second = order[1];
+
+    if areas[largest] > areas[second] * outer_boundary_ratio {
+        Some(largest)
+    } else {
+        None
+    }
+}
+
+/// Ordered points of a cycle (closing node included)
+pub fn cycle_points(graph: &FloorplanGraph, cycle: &[NodeIndex]) -> Vec<Point> {
+    cycle
+        .iter()
+        .filter(|node_idx| node_idx.index() < graph.node_count())
+        .map(|&node_idx| graph[node_idx].clone())
+        .collect()
+}
 
 /// Deduplicate cycles that represent the same room
 /// Handles cycles with different starting points and reverse traversals
@@ -329,8 +1035,18 @@ fn cycle_signature(cycle: &[NodeIndex]) -> Vec<u32> {
     candidates.into_iter().min().unwrap()
 }
 
+/// Shoelace area keeping the winding: positive when the points run counterclockwise
+/// (x right, y up), negative when they run clockwise
+pub fn signed_polygon_area(points: &[Point]) -> f64 {
+    if points.len() < 3 {
+        return 0.0;
+    }
+    let coords: Vec<Coord> = points.iter().map(|p| Coord { x: p.x, y: p.y }).collect();
+    GeoPolygon::new(LineString::from(coords), vec![]).signed_area()
+}
+
 /// Calculate the area of a polygon using the Shoelace formula
-fn calculate_polygon_area(points: &[Point]) -> f64 {
+pub fn calculate_polygon_area(points: &[Point]) -> f64 {
     if points.len() < 3 {
         return 0.0;
     }
@@ -364,30 +1080,162 @@ fn calculate_bounding_box(points: &[Point]) -> [f64; 4] {
     [min_x, min_y, max_x, max_y]
 }
 
-/// Generate a heuristic name for a room based on its properties
-fn generate_room_name(area: f64, bbox: &[f64; 4]) -> String {
+/// Douglas-Peucker simplification of a polygon ring, dropping vertices closer than `epsilon`
+/// to the outline without them. The first vertex, and the closing repeat of it, are kept;
+/// the original points come back unchanged if fewer than 3 would remain.
+pub fn simplify_polygon(points: &[Point], epsilon: f64) -> Vec<Point> {
+    if epsilon <= 0.0 || points.len() < 4 {
+        return points.to_vec();
+    }
+
+    let closed = points.first() == points.last();
+    let ring = if closed { &points[..points.len() - 1] } else { points };
+    if ring.len() < 4 {
+        return points.to_vec();
+    }
+
+    // Split the ring at the vertex farthest from the start, then simplify each half as a chain
+    let start = &ring[0];
+    let far = (1..ring.len())
+        .max_by(|&a, &b| squared_distance(start, &ring[a]).total_cmp(&squared_distance(start, &ring[b])))
+        .unwrap_or(1);
+    let mut keep = vec![false; ring.len() + 1];
+    keep[0] = true;
+    keep[far] = true;
+    keep[ring.len()] = true;
+    let chain: Vec<&Point> = ring.iter().chain(std::iter::once(start)).collect();
+    mark_douglas_peucker(&chain, 0, far, epsilon, &mut keep);
+    mark_douglas_peucker(&chain, far, ring.len(), epsilon, &mut keep);
+
+    let mut simplified: Vec<Point> = ring
+        .iter()
+        .zip(&keep)
+        .filter(|(_, &kept)| kept)
+        .map(|(p, _)| p.clone())
+        .collect();
+    if simplified.len() < 3 {
+        return points.to_vec();
+    }
+    if closed {
+        simplified.push(start.clone());
+    }
+    simplified
+}
+
+/// Mark the vertices strictly between `first` and `last` that Douglas-Peucker keeps
+fn mark_douglas_peucker(chain: &[&Point], first: usize, last: usize, epsilon: f64, keep: &mut [bool]) {
+    if last <= first + 1 {
+        return;
+    }
+
+    let (index, distance) = (first + 1..last)
+        .map(|i| (i, distance_to_segment(chain[i], chain[first], chain[last])))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((first, 0.0));
+
+    if distance > epsilon {
+        keep[index] = true;
+        mark_douglas_peucker(chain, first, index, epsilon, keep);
+        mark_douglas_peucker(chain, index, last, epsilon, keep);
+    }
+}
+
+fn squared_distance(a: &Point, b: &Point) -> f64 {
+    (b.x - a.x).powi(2) + (b.y - a.y).powi(2)
+}
+
+fn distance_to_segment(point: &Point, start: &Point, end: &Point) -> f64 {
+    let length_sq = squared_distance(start, end);
+    if length_sq == 0.0 {
+        return squared_distance(point, start).sqrt();
+    }
+
+    let t = (((point.x - start.x) * (end.x - start.x) + (point.y - start.y) * (end.y - start.y)) / length_sq)
+        .clamp(0.0, 1.0);
+    let projection = Point {
+        x: start.x + t * (end.x - start.x),
+        y: start.y + t * (end.y - start.y),
+    };
+    squared_distance(point, &projection).sqrt()
+}
+
+/// Name used when no naming rule matches
+const UNNAMED_ROOM: &str = "Room";
+
+/// One room-naming rule; omitted bounds don't constrain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamingRule {
+    /// Matches rooms with area strictly below this
+    #[serde(default)]
+    pub max_area: Option<f64>,
+    /// Inclusive lower bound on bounding-box width / height
+    #[serde(default)]
+    pub min_aspect: Option<f64>,
+    /// Inclusive upper bound on bounding-box width / height
+    #[serde(default)]
+    pub max_aspect: Option<f64>,
+    pub name: String,
+}
+
+impl NamingRule {
+    fn new(max_area: Option<f64>, aspect: Option<(f64, f64)>, name: &str) -> Self {
+        Self {
+            max_area,
+            min_aspect: aspect.map(|(min, _)| min),
+            max_aspect: aspect.map(|(_, max)| max),
+            name: name.to_string(),
+        }
+    }
+
+    fn matches(&self, area: f64, aspect_ratio: f64) -> bool {
+        self.max_area.map_or(true, |max| area < max)
+            && self.min_aspect.map_or(true, |min| aspect_ratio >= min)
+            && self.max_aspect.map_or(true, |max| aspect_ratio <= max)
+    }
+}
+
+/// Ordered room-naming rules; the first matching rule names the room
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NamingRules {
+    pub rules: Vec<NamingRule>,
+}
+
+impl Default for NamingRules {
+    /// Residential heuristics: small rooms, bedrooms vs. corridors by aspect, then by size
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                NamingRule::new(Some(500.0), None, "Small Room"),
+                NamingRule::new(Some(2000.0), Some((0.67, 1.5)), "Bedroom"),
+                NamingRule::new(Some(2000.0), None, "Corridor"),
+                NamingRule::new(Some(5000.0), None, "Living Room"),
+                NamingRule::new(None, None, "Large Room"),
+            ],
+        }
+    }
+}
+
+/// Generate a heuristic name for a room from the first matching rule
+fn generate_room_name(area: f64, bbox: &[f64; 4], rules: &NamingRules) -> String {
     let width = bbox[2] - bbox[0];
     let height = bbox[3] - bbox[1];
     let aspect_ratio = width / height;
 
-    // Simple heuristics based on size
-    if area < 500.0 {
-        "Small Room".to_string()
-    } else if area < 2000.0 {
-        if aspect_ratio > 1.5 || aspect_ratio < 0.67 {
-            "Corridor".to_string()
-        } else {
-            "Bedroom".to_string()
-        }
-    } else if area < 5000.0 {
-        "Living Room".to_string()
-    } else {
-        "Large Room".to_string()
-    }
+    rules
+        .rules
+        .iter()
+        .find(|rule| rule.matches(area, aspect_ratio))
+        .map_or(UNNAMED_ROOM, |rule| rule.name.as_str())
+        .to_string()
 }
 
-/// Simplified room detection for rectangular floorplans with vertical dividers
+/// Simplified room detection for rectangular floorplans split by axis-aligned dividers
 /// This algorithm works well for simple cases like test-floorplan.json
+///
+/// Vertical (constant-x) and horizontal (constant-y) dividers are collected and the
+/// bounding box is cut into a grid of cells, each filtered by `area_threshold`.
+/// Segments that are not perfectly axis-aligned are classified by their dominant axis.
 pub fn detect_rooms_simple(lines: &[crate::Line], area_threshold: f64, coverage_threshold: f64) -> Vec<Room> {
     if lines.is_empty() {
         return vec![];
@@ -406,69 +1254,84 @@ pub fn detect_rooms_simple(lines: &[crate::Line], area_threshold: f64, coverage_
         max_y = max_y.max(line.start.y).max(line.end.y);
     }
 
-    // Identify vertical dividers (internal walls with constant x)
-    let mut dividers: Vec<f64> = Vec::new();
+    // Identify internal dividers on both axes
+    let mut x_dividers: Vec<f64> = Vec::new();
+    let mut y_dividers: Vec<f64> = Vec::new();
     const EPSILON: f64 = 0.1;
 
     for line in lines {
-        // Check if line is vertical (constant x)
-        if (line.start.x - line.end.x).abs() < EPSILON {
-            let x = line.start.x;
-            // Check if it's internal (not at boundaries)
-            if x > min_x + EPSILON && x < max_x - EPSILON {
-                // Check if it spans a significant portion of the height
-                let line_min_y = line.start.y.min(line.end.y);
-                let line_max_y = line.start.y.max(line.end.y);
-                let coverage = (line_max_y - line_min_y) / (max_y - min_y);
-
-                if coverage > coverage_threshold {
-                    if !dividers.iter().any(|&d| (d - x).abs() < EPSILON) {
-                        dividers.push(x);
-                    }
-                }
+        let dx = (line.end.x - line.start.x).abs();
+        let dy = (line.end.y - line.start.y).abs();
+
+        if dx < EPSILON && dy < EPSILON {
+            continue; // Degenerate segment
+        }
+
+        if dy >= dx {
+            // Vertical (or mostly vertical): divider position is the mean x
+            let x = (line.start.x + line.end.x) / 2.0;
+            let coverage = dy / (max_y - min_y);
+            if x > min_x + EPSILON && x < max_x - EPSILON && coverage > coverage_threshold {
+                push_divider(&mut x_dividers, x, EPSILON);
+            }
+        } else {
+            // Horizontal (or mostly horizontal): divider position is the mean y
+            let y = (line.start.y + line.end.y) / 2.0;
+            let coverage = dx / (max_x - min_x);
+            if y > min_y + EPSILON && y < max_y - EPSILON && coverage > coverage_threshold {
+                push_divider(&mut y_dividers, y, EPSILON);
             }
         }
     }
 
-    // Sort dividers by x-coordinate
-    dividers.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    // Sort dividers along each axis
+    x_dividers.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    y_dividers.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
-    // Create rooms between dividers
-    let mut rooms = Vec::new();
     let mut x_boundaries = vec![min_x];
-    x_boundaries.extend(dividers);
+    x_boundaries.extend(x_dividers);
     x_boundaries.push(max_x);
 
-    for i in 0..x_boundaries.len() - 1 {
-        let x1 = x_boundaries[i];
-        let x2 = x_boundaries[i + 1];
+    let mut y_boundaries = vec![min_y];
+    y_boundaries.extend(y_dividers);
+    y_boundaries.push(max_y);
 
-        // Create rectangular room
-        let points = vec![
-            Point { x: x1, y: min_y },
-            Point { x: x2, y: min_y },
-            Point { x: x2, y: max_y },
-            Point { x: x1, y: max_y },
-        ];
+    let columns = x_boundaries.len() - 1;
+    let rows = y_boundaries.len() - 1;
+
+    // Create a rectangular room for every grid cell
+    let mut rooms = Vec::new();
 
-        let area = (x2 - x1) * (max_y - min_y);
+    for row in 0..rows {
+        let y1 = y_boundaries[row];
+        let y2 = y_boundaries[row + 1];
 
-        if area >= area_threshold {
-            let bbox = [x1, min_y, x2, max_y];
-            let name_hint = if i == 0 {
-                "Left Room".to_string()
-            } else if i == x_boundaries.len() - 2 {
-                "Right Room".to_string()
-            } else {
-                format!("Room {}", i + 1)
-            };
+        for col in 0..columns {
+            let x1 = x_boundaries[col];
+            let x2 = x_boundaries[col + 1];
+
+            let area = (x2 - x1) * (y2 - y1);
+            if area < area_threshold {
+                continue;
+            }
+
+            let points = vec![
+                Point { x: x1, y: y1 },
+                Point { x: x2, y: y1 },
+                Point { x: x2, y: y2 },
+                Point { x: x1, y: y2 },
+            ];
+
+            let id = row * columns + col;
+            let name_hint = simple_room_name(row, col, rows, columns, id);
 
             rooms.push(Room {
-                id: i,
-                bounding_box: bbox,
+                id,
+                bounding_box: [x1, y1, x2, y2],
                 area,
                 name_hint,
                 points,
+                ..Room::default()
             });
         }
     }
@@ -476,10 +1339,111 @@ pub fn detect_rooms_simple(lines: &[crate::Line], area_threshold: f64, coverage_
     rooms
 }
 
+/// Add a divider position unless one already exists within `epsilon`
+fn push_divider(dividers: &mut Vec<f64>, value: f64, epsilon: f64) {
+    if !dividers.iter().any(|&d| (d - value).abs() < epsilon) {
+        dividers.push(value);
+    }
+}
+
+/// Positional name for a cell of the simple divider grid
+fn simple_room_name(row: usize, col: usize, rows: usize, columns: usize, id: usize) -> String {
+    if rows == 1 && columns > 1 {
+        if col == 0 {
+            return "Left Room".to_string();
+        } else if col == columns - 1 {
+            return "Right Room".to_string();
+        }
+    } else if columns == 1 && rows > 1 {
+        if row == 0 {
+            return "Top Room".to_string();
+        } else if row == rows - 1 {
+            return "Bottom Room".to_string();
+        }
+    }
+
+    format!("Room {}", id + 1)
+}
+
+/// Distance within which room bounding boxes count as touching
+pub const ADJACENCY_EPSILON: f64 = 1.0;
+
+/// Pairs of room ids `(a, b)` with `a < b` that share a wall or connect through a door gap
+///
+/// Rooms share a wall when their bounding boxes touch (within `ADJACENCY_EPSILON`) along
+/// an edge with positive overlap; touching only at a corner doesn't count. A door gap
+/// connects every room whose bounding box contains one of its endpoints or its midpoint.
+pub fn compute_adjacency(rooms: &[Room], door_gaps: &[Line]) -> Vec<(usize, usize)> {
+    let mut edges = HashSet::new();
+    let mut add_edge = |a: usize, b: usize| {
+        if a != b {
+            edges.insert((a.min(b), a.max(b)));
+        }
+    };
+
+    for (i, a) in rooms.iter().enumerate() {
+        for b in &rooms[i + 1..] {
+            if bounding_boxes_share_edge(&a.bounding_box, &b.bounding_box, ADJACENCY_EPSILON) {
+                add_edge(a.id, b.id);
+            }
+        }
+    }
+
+    for gap in door_gaps {
+        let mid = Point {
+            x: (gap.start.x + gap.end.x) / 2.0,
+            y: (gap.start.y + gap.end.y) / 2.0,
+        };
+        let connected: Vec<usize> = rooms
+            .iter()
+            .filter(|room| {
+                [&gap.start, &gap.end, &mid]
+                    .iter()
+                    .any(|p| bounding_box_contains(&room.bounding_box, p))
+            })
+            .map(|room| room.id)
+            .collect();
+        for (i, &a) in connected.iter().enumerate() {
+            for &b in &connected[i + 1..] {
+                add_edge(a, b);
+            }
+        }
+    }
+
+    let mut edges: Vec<(usize, usize)> = edges.into_iter().collect();
+    edges.sort_unstable();
+    edges
+}
+
+/// Whether a point lies inside a `[min_x, min_y, max_x, max_y]` box grown by `ADJACENCY_EPSILON`
+fn bounding_box_contains(bbox: &[f64; 4], point: &Point) -> bool {
+    let [min_x, min_y, max_x, max_y] = *bbox;
+    point.x >= min_x - ADJACENCY_EPSILON
+        && point.x <= max_x + ADJACENCY_EPSILON
+        && point.y >= min_y - ADJACENCY_EPSILON
+        && point.y <= max_y + ADJACENCY_EPSILON
+}
+
+/// Whether two `[min_x, min_y, max_x, max_y]` boxes touch along an edge (not just a corner)
+fn bounding_boxes_share_edge(a: &[f64; 4], b: &[f64; 4], epsilon: f64) -> bool {
+    let overlap = |a_min: f64, a_max: f64, b_min: f64, b_max: f64| a_max.min(b_max) - a_min.max(b_min);
+    let touches = |a_min: f64, a_max: f64, b_min: f64, b_max: f64| {
+        (a_max - b_min).abs() <= epsilon || (b_max - a_min).abs() <= epsilon
+    };
+
+    let x_overlap = overlap(a[0], a[2], b[0], b[2]);
+    let y_overlap = overlap(a[1], a[3], b[1], b[3]);
+
+    // Side by side: vertical edges meet and the y ranges overlap by more than a corner
+    (touches(a[0], a[2], b[0], b[2]) && y_overlap > epsilon)
+        // Stacked: horizontal edges meet and the x ranges overlap by more than a corner
+        || (touches(a[1], a[3], b[1], b[3]) && x_overlap > epsilon)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{graph_builder::build_graph, Line};
+    use crate::{graph_builder::{build_graph, build_graph_with_door_threshold}, Line};
 
     #[test]
     fn test_bounding_box_calculation() {
@@ -510,6 +1474,26 @@ mod tests {
         assert!((area - 100.0).abs() < 1e-6);
     }
 
+    #[test]
+    fn test_simplify_polygon_drops_collinear_midpoint() {
+        let p = |x: f64, y: f64| Point { x, y };
+        // Closed square with a redundant vertex halfway along the bottom edge
+        let points = vec![p(0.0, 0.0), p(50.0, 0.0), p(100.0, 0.0), p(100.0, 100.0), p(0.0, 100.0), p(0.0, 0.0)];
+
+        let simplified = simplify_polygon(&points, 1.0);
+
+        assert_eq!(simplified, vec![p(0.0, 0.0), p(100.0, 0.0), p(100.0, 100.0), p(0.0, 100.0), p(0.0, 0.0)]);
+        assert_eq!(simplify_polygon(&points, 0.0), points);
+    }
+
+    #[test]
+    fn test_simplify_polygon_keeps_at_least_a_triangle() {
+        let p = |x: f64, y: f64| Point { x, y };
+        let sliver = vec![p(0.0, 0.0), p(10.0, 0.0), p(20.0, 0.5), p(10.0, 1.0), p(0.0, 0.0)];
+
+        assert_eq!(simplify_polygon(&sliver, 5.0), sliver);
+    }
+
     #[test]
     fn test_room_detection_simple_square() {
         // Create a simple square
@@ -548,17 +1532,36 @@ mod tests {
 
     #[test]
     fn test_room_name_generation() {
+        let rules = NamingRules::default();
+
         let small_area = 300.0;
         let small_bbox = [0.0, 0.0, 10.0, 30.0];
-        assert_eq!(generate_room_name(small_area, &small_bbox), "Small Room");
+        assert_eq!(generate_room_name(small_area, &small_bbox, &rules), "Small Room");
 
         let medium_area = 1000.0;
         let square_bbox = [0.0, 0.0, 30.0, 30.0];
-        assert_eq!(generate_room_name(medium_area, &square_bbox), "Bedroom");
+        assert_eq!(generate_room_name(medium_area, &square_bbox, &rules), "Bedroom");
 
         let corridor_area = 800.0;
         let corridor_bbox = [0.0, 0.0, 50.0, 10.0];
-        assert_eq!(generate_room_name(corridor_area, &corridor_bbox), "Corridor");
+        assert_eq!(generate_room_name(corridor_area, &corridor_bbox, &rules), "Corridor");
+
+        assert_eq!(generate_room_name(3000.0, &square_bbox, &rules), "Living Room");
+        assert_eq!(generate_room_name(9000.0, &square_bbox, &rules), "Large Room");
+    }
+
+    #[test]
+    fn test_custom_naming_rules() {
+        let rules: NamingRules = serde_json::from_value(serde_json::json!([
+            {"max_area": 8000.0, "name": "Office"},
+            {"name": "Warehouse Bay"}
+        ]))
+        .unwrap();
+        let square_bbox = [0.0, 0.0, 95.0, 95.0];
+
+        assert_eq!(generate_room_name(9000.0, &square_bbox, &rules), "Warehouse Bay");
+        assert_eq!(generate_room_name(1000.0, &square_bbox, &rules), "Office");
+        assert_eq!(generate_room_name(1000.0, &square_bbox, &NamingRules { rules: vec![] }), "Room");
     }
 
     #[test]
@@ -624,9 +1627,13 @@ mod tests {
         let graph = build_graph(&lines);
         let cycles = find_all_cycles(&graph);
 
-        // Should find the outer boundary cycle
-        assert_eq!(cycles.len(), 1, "Should detect the outer boundary cycle");
-        assert_eq!(cycles[0].len(), 7, "Outer boundary cycle should have 7 nodes (including closing)");
+        // The internal wall ends mid-way along the top wall (T-junction), which splits it,
+        // so both rooms and the outer boundary are found
+        assert_eq!(cycles.len(), 3, "Should detect both rooms and the outer boundary");
+        let cycle_lengths: Vec<usize> = cycles.iter().map(|c| c.len()).collect();
+        assert!(cycle_lengths.contains(&5), "Right room should have 5 nodes (including closing)");
+        assert!(cycle_lengths.contains(&6), "Left room should have 6 nodes (including closing)");
+        assert!(cycle_lengths.contains(&8), "Outer boundary should have 8 nodes (including closing)");
     }
 
     #[test]
@@ -664,6 +1671,157 @@ mod tests {
         assert_eq!(cycles[0].len(), 4, "Triangle cycle should have 4 nodes (including closing)");
     }
 
+    #[test]
+    fn test_cycle_search_stops_at_deadline() {
+        // A 10x10 grid of unit cells has far too many simple cycles to enumerate
+        let mut lines = Vec::new();
+        for i in 0..=10 {
+            for j in 0..10 {
+                let (i, j) = (i as f64 * 10.0, j as f64 * 10.0);
+                lines.push(Line { start: Point { x: j, y: i }, end: Point { x: j + 10.0, y: i }, is_load_bearing: false });
+                lines.push(Line { start: Point { x: i, y: j }, end: Point { x: i, y: j + 10.0 }, is_load_bearing: false });
+            }
+        }
+        let graph = build_graph(&lines);
+
+        let max_duration = Duration::from_millis(50);
+        let start = Instant::now();
+        let enumeration = enumerate_cycles_within(&graph, max_duration);
+
+        assert!(enumeration.truncated, "Dense grid should hit the deadline");
+        assert!(start.elapsed() < max_duration * 10, "Search ran for {:?}", start.elapsed());
+        assert!(enumeration.unexplored_start_nodes > 0);
+
+        // A small plan finishes well within the deadline
+        let square = vec![
+            Line { start: Point { x: 0.0, y: 0.0 }, end: Point { x: 100.0, y: 0.0 }, is_load_bearing: false },
+            Line { start: Point { x: 100.0, y: 0.0 }, end: Point { x: 100.0, y: 100.0 }, is_load_bearing: false },
+            Line { start: Point { x: 100.0, y: 100.0 }, end: Point { x: 0.0, y: 100.0 }, is_load_bearing: false },
+            Line { start: Point { x: 0.0, y: 100.0 }, end: Point { x: 0.0, y: 0.0 }, is_load_bearing: false },
+        ];
+        let enumeration = enumerate_cycles_within(&build_graph(&square), max_duration);
+        assert!(!enumeration.truncated);
+        assert_eq!(enumeration.cycles.len(), 1);
+    }
+
+    fn rooms_by_method(graph: &FloorplanGraph, method: CycleMethod) -> (Vec<Room>, Duration) {
+        let start = Instant::now();
+        let rooms = detect_rooms_explained(graph, 100.0, 1.5, &NamingRules::default(), false, method).rooms;
+        (rooms, start.elapsed())
+    }
+
+    #[test]
+    fn test_faces_find_every_room_dfs_finds() {
+        let fixtures = [
+            include_str!("../../test-data/test-3-rooms-horizontal.json"),
+            include_str!("../../test-data/test-apartment-6-rooms.json"),
+            include_str!("../../test-data/test-corridor.json"),
+            include_str!("../../test-data/test-grid-4-rooms.json"),
+            include_str!("../../test-data/test-l-shaped.json"),
+        ];
+
+        for fixture in fixtures {
+            let lines: Vec<Line> = serde_json::from_str(fixture).unwrap();
+            let graph = build_graph_with_door_threshold(&lines, 50.0);
+            let (face_rooms, _) = rooms_by_method(&graph, CycleMethod::Faces);
+            let (dfs_rooms, _) = rooms_by_method(&graph, CycleMethod::Dfs);
+
+            // Fixture rooms are rectangles; DFS also returns cycles that detour through door
+            // bridges and overlap real rooms, which are not
+            let rectangular = |room: &&Room| {
+                let [min_x, min_y, max_x, max_y] = room.bounding_box;
+                room.area >= 0.95 * (max_x - min_x) * (max_y - min_y)
+            };
+            let dfs_rooms: Vec<&Room> = dfs_rooms.iter().filter(rectangular).collect();
+            assert!(!dfs_rooms.is_empty());
+            for room in dfs_rooms {
+                assert!(
+                    face_rooms.iter().any(|r| r.bounding_box == room.bounding_box && (r.area - room.area).abs() < 1e-6),
+                    "Face tracing missed {:?} ({})",
+                    room.bounding_box,
+                    room.name_hint
+                );
+            }
+        }
+
+        // Where DFS runs out of time, faces still find every cell
+        let mut grid = Vec::new();
+        for i in 0..=10 {
+            for j in 0..10 {
+                let (i, j) = (i as f64 * 20.0, j as f64 * 20.0);
+                grid.push(Line { start: Point { x: j, y: i }, end: Point { x: j + 20.0, y: i }, is_load_bearing: false });
+                grid.push(Line { start: Point { x: i, y: j }, end: Point { x: i, y: j + 20.0 }, is_load_bearing: false });
+            }
+        }
+        let (rooms, elapsed) = rooms_by_method(&build_graph(&grid), CycleMethod::Faces);
+        assert_eq!(rooms.len(), 100);
+        assert!(elapsed < Duration::from_millis(DEFAULT_CYCLE_SEARCH_TIMEOUT_MS));
+    }
+
+    #[test]
+    fn test_footprint_found_by_orientation_not_ratio() {
+        // 140x100 footprint split into a 100x100 room and a 40x100 room: the footprint is
+        // only 1.4x the bigger room, under the default 1.5 ratio
+        let lines = vec![
+            Line { start: Point { x: 0.0, y: 0.0 }, end: Point { x: 140.0, y: 0.0 }, is_load_bearing: false },
+            Line { start: Point { x: 140.0, y: 0.0 }, end: Point { x: 140.0, y: 100.0 }, is_load_bearing: false },
+            Line { start: Point { x: 140.0, y: 100.0 }, end: Point { x: 0.0, y: 100.0 }, is_load_bearing: false },
+            Line { start: Point { x: 0.0, y: 100.0 }, end: Point { x: 0.0, y: 0.0 }, is_load_bearing: false },
+            Line { start: Point { x: 100.0, y: 0.0 }, end: Point { x: 100.0, y: 100.0 }, is_load_bearing: false },
+        ];
+        assert_eq!(outer_boundary_index(&[14_000.0, 10_000.0, 4_000.0], 1.5), None);
+
+        let graph = build_graph(&lines);
+        let faces = trace_faces(&graph);
+        let clockwise: Vec<f64> = faces
+            .iter()
+            .map(|face| signed_polygon_area(&cycle_points(&graph, face)))
+            .filter(|&area| area < 0.0)
+            .collect();
+        assert_eq!(clockwise, [-14_000.0]);
+
+        let rooms = detect_rooms_with_naming(&graph, 100.0, 1.5, &NamingRules::default(), true);
+        let mut areas: Vec<(RoomKind, f64)> = rooms.iter().map(|r| (r.kind, r.area)).collect();
+        areas.sort_by(|a, b| a.1.total_cmp(&b.1));
+        assert_eq!(
+            areas,
+            [(RoomKind::Enclosed, 4_000.0), (RoomKind::Enclosed, 10_000.0), (RoomKind::Footprint, 14_000.0)]
+        );
+    }
+
+    #[test]
+    fn test_faces_skip_dangling_walls() {
+        // Square with a stub poking into the room and a spur outside it
+        let lines = vec![
+            Line { start: Point { x: 0.0, y: 0.0 }, end: Point { x: 100.0, y: 0.0 }, is_load_bearing: false },
+            Line { start: Point { x: 100.0, y: 0.0 }, end: Point { x: 100.0, y: 100.0 }, is_load_bearing: false },
+            Line { start: Point { x: 100.0, y: 100.0 }, end: Point { x: 0.0, y: 100.0 }, is_load_bearing: false },
+            Line { start: Point { x: 0.0, y: 100.0 }, end: Point { x: 0.0, y: 0.0 }, is_load_bearing: false },
+            Line { start: Point { x: 50.0, y: 0.0 }, end: Point { x: 50.0, y: 30.0 }, is_load_bearing: false },
+            Line { start: Point { x: 100.0, y: 100.0 }, end: Point { x: 150.0, y: 150.0 }, is_load_bearing: false },
+        ];
+
+        // The room and its outer face share the same nodes
+        let faces = deduplicate_cycles(trace_faces(&build_graph(&lines)));
+
+        assert_eq!(faces.len(), 1);
+        assert_eq!(faces[0].first(), faces[0].last());
+        let points = cycle_points(&build_graph(&lines), &faces[0]);
+        assert!((calculate_polygon_area(&points) - 10_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_outer_boundary_index() {
+        // Largest cycle is far bigger than the rest: flagged as outer boundary
+        assert_eq!(outer_boundary_index(&[2500.0, 160000.0, 3600.0], 1.5), Some(1));
+
+        // Similar areas: no clear outer boundary
+        assert_eq!(outer_boundary_index(&[10000.0, 12000.0], 1.5), None);
+
+        // A single cycle is never treated as the outer boundary
+        assert_eq!(outer_boundary_index(&[10000.0], 1.5), None);
+    }
+
     #[test]
     fn test_deduplicate_cycles() {
         // Create cycles that are the same but with different starting points
@@ -962,9 +2120,8 @@ mod tests {
     }
 
     #[test]
-    fn test_simple_detection_horizontal_walls_ignored() {
-        // Simple algorithm only detects vertical dividers
-        // Horizontal walls should be ignored
+    fn test_simple_detection_horizontal_divider() {
+        // Horizontal divider splits the plan into top and bottom rooms
         let lines = vec![
             Line {
                 start: Point { x: 0.0, y: 0.0 },
@@ -986,7 +2143,7 @@ mod tests {
                 end: Point { x: 0.0, y: 0.0 },
                 is_load_bearing: false,
             },
-            // Horizontal divider (should be ignored by simple algorithm)
+            // Horizontal divider at y=150
             Line {
                 start: Point { x: 0.0, y: 150.0 },
                 end: Point { x: 400.0, y: 150.0 },
@@ -996,8 +2153,49 @@ mod tests {
 
         let rooms = detect_rooms_simple(&lines, 100.0, 0.3);
 
-        // Should detect 1 room since horizontal dividers are ignored
-        assert_eq!(rooms.len(), 1, "Horizontal dividers should be ignored");
+        assert_eq!(rooms.len(), 2, "Horizontal divider should split the plan");
+        assert_eq!(rooms[0].bounding_box, [0.0, 0.0, 400.0, 150.0]);
+        assert_eq!(rooms[0].name_hint, "Top Room");
+        assert_eq!(rooms[1].bounding_box, [0.0, 150.0, 400.0, 300.0]);
+        assert_eq!(rooms[1].name_hint, "Bottom Room");
+    }
+
+    #[test]
+    fn test_simple_detection_grid() {
+        // One vertical and one horizontal divider produce a 2x2 grid
+        let lines = vec![
+            Line { start: Point { x: 0.0, y: 0.0 }, end: Point { x: 400.0, y: 0.0 }, is_load_bearing: false },
+            Line { start: Point { x: 400.0, y: 0.0 }, end: Point { x: 400.0, y: 300.0 }, is_load_bearing: false },
+            Line { start: Point { x: 400.0, y: 300.0 }, end: Point { x: 0.0, y: 300.0 }, is_load_bearing: false },
+            Line { start: Point { x: 0.0, y: 300.0 }, end: Point { x: 0.0, y: 0.0 }, is_load_bearing: false },
+            Line { start: Point { x: 200.0, y: 0.0 }, end: Point { x: 200.0, y: 300.0 }, is_load_bearing: false },
+            Line { start: Point { x: 0.0, y: 100.0 }, end: Point { x: 400.0, y: 100.0 }, is_load_bearing: false },
+        ];
+
+        let rooms = detect_rooms_simple(&lines, 100.0, 0.3);
+
+        assert_eq!(rooms.len(), 4, "Should detect a 2x2 grid of rooms");
+        assert!((rooms[0].area - 20000.0).abs() < 1.0);
+        assert!((rooms[3].area - 40000.0).abs() < 1.0);
+        assert_eq!(rooms[3].bounding_box, [200.0, 100.0, 400.0, 300.0]);
+    }
+
+    #[test]
+    fn test_simple_detection_diagonal_dominant_axis() {
+        // Slightly slanted segment is classified by its dominant (vertical) axis
+        let lines = vec![
+            Line { start: Point { x: 0.0, y: 0.0 }, end: Point { x: 400.0, y: 0.0 }, is_load_bearing: false },
+            Line { start: Point { x: 400.0, y: 0.0 }, end: Point { x: 400.0, y: 300.0 }, is_load_bearing: false },
+            Line { start: Point { x: 400.0, y: 300.0 }, end: Point { x: 0.0, y: 300.0 }, is_load_bearing: false },
+            Line { start: Point { x: 0.0, y: 300.0 }, end: Point { x: 0.0, y: 0.0 }, is_load_bearing: false },
+            Line { start: Point { x: 195.0, y: 0.0 }, end: Point { x: 205.0, y: 300.0 }, is_load_bearing: false },
+        ];
+
+        let rooms = detect_rooms_simple(&lines, 100.0, 0.3);
+
+        assert_eq!(rooms.len(), 2, "Mostly vertical segment should act as a vertical divider");
+        assert_eq!(rooms[0].bounding_box, [0.0, 0.0, 200.0, 300.0]);
+        assert_eq!(rooms[0].name_hint, "Left Room");
     }
 
     #[test]
@@ -1112,6 +2310,286 @@ mod tests {
         }
     }
 
+    fn wall(x1: f64, y1: f64, x2: f64, y2: f64) -> Line {
+        Line { start: Point { x: x1, y: y1 }, end: Point { x: x2, y: y2 }, is_load_bearing: false }
+    }
+
+    #[test]
+    fn test_include_outer_boundary_returns_footprint() {
+        let lines = vec![
+            wall(0.0, 0.0, 400.0, 0.0),
+            wall(400.0, 0.0, 400.0, 400.0),
+            wall(400.0, 400.0, 0.0, 400.0),
+            wall(0.0, 400.0, 0.0, 0.0),
+            wall(100.0, 100.0, 150.0, 100.0),
+            wall(150.0, 100.0, 150.0, 150.0),
+            wall(150.0, 150.0, 100.0, 150.0),
+            wall(100.0, 150.0, 100.0, 100.0),
+        ];
+        let graph = build_graph(&lines);
+
+        let default_rooms = detect_rooms_with_naming(&graph, 100.0, 1.5, &NamingRules::default(), false);
+        assert_eq!(default_rooms.len(), 1);
+
+        let rooms = detect_rooms_with_naming(&graph, 100.0, 1.5, &NamingRules::default(), true);
+        assert_eq!(rooms.len(), 2);
+        let footprint = rooms.iter().find(|r| r.kind == RoomKind::Footprint).expect("footprint returned");
+        assert_eq!(footprint.name_hint, "Building Footprint");
+        assert!((footprint.area - 160000.0).abs() < 1e-6);
+        assert!(rooms.iter().any(|r| r.kind == RoomKind::Enclosed && (r.area - 2500.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_empty_reason_for_each_way_of_finding_nothing() {
+        let reason = |lines: &[Line], area_threshold: f64| {
+            detect_rooms_explained(
+                &build_graph(lines),
+                area_threshold,
+                1.5,
+                &NamingRules::default(),
+                false,
+                CycleMethod::default(),
+            )
+            .empty_reason
+        };
+        let nested = vec![
+            wall(0.0, 0.0, 400.0, 0.0),
+            wall(400.0, 0.0, 400.0, 400.0),
+            wall(400.0, 400.0, 0.0, 400.0),
+            wall(0.0, 400.0, 0.0, 0.0),
+            wall(100.0, 100.0, 150.0, 100.0),
+            wall(150.0, 100.0, 150.0, 150.0),
+            wall(150.0, 150.0, 100.0, 150.0),
+            wall(100.0, 150.0, 100.0, 100.0),
+        ];
+
+        assert_eq!(reason(&nested, 100.0), None);
+        // Above every room and the footprint
+        assert_eq!(reason(&nested, 200_000.0), Some(EmptyReason::BelowAreaThreshold));
+        // Above the 50x50 room but below the 400x400 footprint
+        assert_eq!(reason(&nested, 10_000.0), Some(EmptyReason::OnlyOuterBoundary));
+
+        let open_corner = vec![wall(0.0, 0.0, 100.0, 0.0), wall(100.0, 0.0, 100.0, 100.0)];
+        assert_eq!(reason(&open_corner, 100.0), Some(EmptyReason::NoCycles));
+
+        let apart = vec![wall(0.0, 0.0, 100.0, 0.0), wall(300.0, 0.0, 300.0, 100.0)];
+        assert_eq!(reason(&apart, 100.0), Some(EmptyReason::GraphDisconnected));
+    }
+
+    #[test]
+    fn test_load_bearing_top_edge_is_flagged() {
+        let lines = vec![
+            Line { is_load_bearing: true, ..wall(0.0, 0.0, 100.0, 0.0) },
+            wall(100.0, 0.0, 100.0, 100.0),
+            wall(100.0, 100.0, 0.0, 100.0),
+            wall(0.0, 100.0, 0.0, 0.0),
+        ];
+
+        let rooms = detect_rooms(&build_graph(&lines), 100.0, 1.5);
+
+        assert_eq!(rooms.len(), 1);
+        let room = &rooms[0];
+        assert_eq!(room.load_bearing_edges.len(), 1);
+        let [a, b] = room.load_bearing_edges[0];
+        assert_eq!(b, a + 1);
+        assert_eq!(room.points[a].y, 0.0);
+        assert_eq!(room.points[b].y, 0.0);
+        assert_ne!(room.points[a].x, room.points[b].x);
+    }
+
+    #[test]
+    fn test_simplify_room_remaps_load_bearing_edges() {
+        let p = |x: f64, y: f64| Point { x, y };
+        let mut room = Room {
+            id: 0,
+            bounding_box: [0.0, 0.0, 100.0, 100.0],
+            area: 10000.0,
+            name_hint: String::new(),
+            // Top edge split by a collinear midpoint, both halves load-bearing
+            points: vec![p(0.0, 0.0), p(50.0, 0.0), p(100.0, 0.0), p(100.0, 100.0), p(0.0, 100.0), p(0.0, 0.0)],
+            load_bearing_edges: vec![[0, 1], [1, 2], [3, 4]],
+            ..Room::default()
+        };
+
+        simplify_room(&mut room, 1.0);
+
+        assert_eq!(room.points.len(), 5);
+        assert_eq!(room.load_bearing_edges, vec![[0, 1], [2, 3]]);
+    }
+
+    #[test]
+    fn test_wall_lengths_and_perimeter_of_rectangle() {
+        let p = |x: f64, y: f64| Point { x, y };
+        let outline = vec![p(0.0, 0.0), p(100.0, 0.0), p(100.0, 200.0), p(0.0, 200.0), p(0.0, 0.0)];
+        assert_eq!(wall_lengths(&outline), vec![100.0, 200.0, 100.0, 200.0]);
+
+        let lines: Vec<Line> = outline.windows(2).map(|pair| wall(pair[0].x, pair[0].y, pair[1].x, pair[1].y)).collect();
+        let rooms = detect_rooms(&build_graph(&lines), 100.0, 1.5);
+
+        assert_eq!(rooms.len(), 1);
+        assert_eq!(rooms[0].perimeter, Some(600.0));
+        let mut lengths = rooms[0].wall_lengths.clone();
+        lengths.sort_by(f64::total_cmp);
+        assert_eq!(lengths, vec![100.0, 100.0, 200.0, 200.0]);
+    }
+
+    #[test]
+    fn test_normalize_rooms_to_1000_round_trips() {
+        let p = |x: f64, y: f64| Point { x, y };
+        let outline = [p(10.0, 20.0), p(410.0, 20.0), p(410.0, 320.0), p(10.0, 320.0), p(10.0, 20.0)];
+        let lines: Vec<Line> = outline.windows(2).map(|pair| wall(pair[0].x, pair[0].y, pair[1].x, pair[1].y)).collect();
+        let mut rooms = detect_rooms(&build_graph(&lines), 100.0, 1.5);
+        let original = rooms[0].clone();
+
+        let bounds = lines_bounds(&lines).unwrap();
+        assert_eq!(bounds, [10.0, 20.0, 410.0, 320.0]);
+        normalize_rooms_to_1000(&mut rooms, bounds);
+        // Each axis stretches to 0-1000 on its own, like an image's width and height
+        assert_eq!(rooms[0].bounding_box, [0.0, 0.0, 1000.0, 1000.0]);
+        assert_eq!(rooms[0].area, original.area);
+
+        denormalize_rooms_from_1000(&mut rooms, bounds);
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(rooms[0].bounding_box.iter().zip(&original.bounding_box).all(|(&a, &b)| close(a, b)));
+        assert!(rooms[0].points.iter().zip(&original.points).all(|(a, b)| close(a.x, b.x) && close(a.y, b.y)));
+        assert_eq!(lines_bounds(&[]), None);
+    }
+
+    #[test]
+    fn test_cycle_detection_nested_boundaries() {
+        // Donut-shaped plan: building outline, courtyard rim, and one real room inside
+        let lines = vec![
+            // Outer boundary (400x400)
+            Line { start: Point { x: 0.0, y: 0.0 }, end: Point { x: 400.0, y: 0.0 }, is_load_bearing: false },
+            Line { start: Point { x: 400.0, y: 0.0 }, end: Point { x: 400.0, y: 400.0 }, is_load_bearing: false },
+            Line { start: Point { x: 400.0, y: 400.0 }, end: Point { x: 0.0, y: 400.0 }, is_load_bearing: false },
+            Line { start: Point { x: 0.0, y: 400.0 }, end: Point { x: 0.0, y: 0.0 }, is_load_bearing: false },
+            // Inner boundary / hole (200x200)
+            Line { start: Point { x: 100.0, y: 100.0 }, end: Point { x: 300.0, y: 100.0 }, is_load_bearing: false },
+            Line { start: Point { x: 300.0, y: 100.0 }, end: Point { x: 300.0, y: 300.0 }, is_load_bearing: false },
+            Line { start: Point { x: 300.0, y: 300.0 }, end: Point { x: 100.0, y: 300.0 }, is_load_bearing: false },
+            Line { start: Point { x: 100.0, y: 300.0 }, end: Point { x: 100.0, y: 100.0 }, is_load_bearing: false },
+            // Real room (100x100)
+            Line { start: Point { x: 150.0, y: 150.0 }, end: Point { x: 250.0, y: 150.0 }, is_load_bearing: false },
+            Line { start: Point { x: 250.0, y: 150.0 }, end: Point { x: 250.0, y: 250.0 }, is_load_bearing: false },
+            Line { start: Point { x: 250.0, y: 250.0 }, end: Point { x: 150.0, y: 250.0 }, is_load_bearing: false },
+            Line { start: Point { x: 150.0, y: 250.0 }, end: Point { x: 150.0, y: 150.0 }, is_load_bearing: false },
+        ];
+
+        let graph = build_graph(&lines);
+        let rooms = detect_rooms(&graph, 100.0, 1.5);
+
+        assert_eq!(rooms.len(), 1, "Only the real room should survive, both boundaries filtered");
+        assert!((rooms[0].area - 10000.0).abs() < 100.0, "Surviving room should be the 100x100 room");
+    }
+
+    #[test]
+    fn test_clean_square_more_confident_than_bridged_irregular_room() {
+        let square = vec![
+            wall(0.0, 0.0, 100.0, 0.0),
+            wall(100.0, 0.0, 100.0, 100.0),
+            wall(100.0, 100.0, 0.0, 100.0),
+            wall(0.0, 100.0, 0.0, 0.0),
+        ];
+        // L-shaped room whose bottom wall has a 30-unit doorway
+        let bridged_l = vec![
+            wall(0.0, 0.0, 80.0, 0.0),
+            wall(110.0, 0.0, 200.0, 0.0),
+            wall(200.0, 0.0, 200.0, 100.0),
+            wall(200.0, 100.0, 100.0, 100.0),
+            wall(100.0, 100.0, 100.0, 200.0),
+            wall(100.0, 200.0, 0.0, 200.0),
+            wall(0.0, 200.0, 0.0, 0.0),
+        ];
+
+        let square_rooms = detect_rooms(&build_graph(&square), 100.0, 1.5);
+        let l_rooms = detect_rooms(&build_graph_with_door_threshold(&bridged_l, 40.0), 100.0, 1.5);
+        assert_eq!(square_rooms.len(), 1);
+        assert_eq!(l_rooms.len(), 1);
+
+        let square_confidence = square_rooms[0].confidence.unwrap();
+        let l_confidence = l_rooms[0].confidence.unwrap();
+        assert!((square_confidence - 1.0).abs() < 1e-9);
+        assert!(l_confidence < square_confidence);
+        assert!(l_confidence > 0.0);
+    }
+
+    #[test]
+    fn test_courtyard_surrounded_by_rooms() {
+        // 300x300 outline around a 100x100 courtyard, split into four 200x100 rooms pinwheel-style
+        let lines = vec![
+            wall(0.0, 0.0, 300.0, 0.0),
+            wall(300.0, 0.0, 300.0, 300.0),
+            wall(300.0, 300.0, 0.0, 300.0),
+            wall(0.0, 300.0, 0.0, 0.0),
+            wall(100.0, 100.0, 200.0, 100.0),
+            wall(200.0, 100.0, 200.0, 200.0),
+            wall(200.0, 200.0, 100.0, 200.0),
+            wall(100.0, 200.0, 100.0, 100.0),
+            wall(100.0, 0.0, 100.0, 100.0),
+            wall(200.0, 100.0, 300.0, 100.0),
+            wall(200.0, 200.0, 200.0, 300.0),
+            wall(0.0, 200.0, 100.0, 200.0),
+        ];
+
+        let graph = build_graph(&lines);
+        let rooms = detect_rooms(&graph, 100.0, 1.5);
+
+        assert_eq!(rooms.len(), 5, "Four rooms plus the courtyard");
+        let courtyards: Vec<&Room> = rooms.iter().filter(|r| r.kind == RoomKind::Courtyard).collect();
+        assert_eq!(courtyards.len(), 1);
+        assert_eq!(courtyards[0].bounding_box, [100.0, 100.0, 200.0, 200.0]);
+        assert!(rooms
+            .iter()
+            .filter(|r| r.kind == RoomKind::Enclosed)
+            .all(|r| (r.area - 20000.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_interior_closet_is_not_a_courtyard() {
+        // Same pinwheel plan as above; the center is either walled off or has a doorway
+        let pinwheel = |center_top: Vec<Line>| {
+            let mut lines = vec![
+                wall(0.0, 0.0, 300.0, 0.0),
+                wall(300.0, 0.0, 300.0, 300.0),
+                wall(300.0, 300.0, 0.0, 300.0),
+                wall(0.0, 300.0, 0.0, 0.0),
+                wall(200.0, 100.0, 200.0, 200.0),
+                wall(200.0, 200.0, 100.0, 200.0),
+                wall(100.0, 200.0, 100.0, 100.0),
+                wall(100.0, 0.0, 100.0, 100.0),
+                wall(200.0, 100.0, 300.0, 100.0),
+                wall(200.0, 200.0, 200.0, 300.0),
+                wall(0.0, 200.0, 100.0, 200.0),
+            ];
+            lines.extend(center_top);
+            detect_rooms(&build_graph_with_door_threshold(&lines, 40.0), 100.0, 1.5)
+        };
+        let center_kind = |rooms: &[Room]| {
+            rooms.iter().find(|r| r.bounding_box == [100.0, 100.0, 200.0, 200.0]).map(|r| r.kind)
+        };
+
+        let closet = pinwheel(vec![wall(100.0, 100.0, 130.0, 100.0), wall(160.0, 100.0, 200.0, 100.0)]);
+        let courtyard = pinwheel(vec![wall(100.0, 100.0, 200.0, 100.0)]);
+
+        assert_eq!(center_kind(&closet), Some(RoomKind::Enclosed));
+        assert!(closet.iter().all(|r| r.kind == RoomKind::Enclosed));
+        assert_eq!(center_kind(&courtyard), Some(RoomKind::Courtyard));
+    }
+
+    #[test]
+    fn test_point_in_polygon() {
+        let square = vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 10.0, y: 0.0 },
+            Point { x: 10.0, y: 10.0 },
+            Point { x: 0.0, y: 10.0 },
+        ];
+
+        assert!(point_in_polygon(&Point { x: 5.0, y: 5.0 }, &square));
+        assert!(!point_in_polygon(&Point { x: 15.0, y: 5.0 }, &square));
+    }
+
     #[test]
     fn test_cycle_detection_multiple_interior_rooms() {
         // Outer boundary + THREE inner rooms
@@ -1161,4 +2639,52 @@ mod tests {
         assert!(areas.iter().any(|&a| (a - 3600.0).abs() < 100.0), "Should include 60x60 room");
         assert!(areas.iter().any(|&a| (a - 4900.0).abs() < 100.0), "Should include 70x70 room");
     }
+
+    fn bbox_room(id: usize, bounding_box: [f64; 4]) -> Room {
+        Room {
+            id,
+            bounding_box,
+            area: 0.0,
+            name_hint: String::new(),
+            ..Room::default()
+        }
+    }
+
+    #[test]
+    fn test_adjacency_side_by_side_rooms() {
+        let rooms = vec![
+            bbox_room(0, [0.0, 0.0, 100.0, 100.0]),
+            bbox_room(1, [100.0, 0.0, 200.0, 100.0]),
+        ];
+
+        assert_eq!(compute_adjacency(&rooms, &[]), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_adjacency_ignores_corner_touch() {
+        let rooms = vec![
+            bbox_room(0, [0.0, 0.0, 100.0, 100.0]),
+            bbox_room(1, [100.0, 100.0, 200.0, 200.0]),
+        ];
+
+        assert!(compute_adjacency(&rooms, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_adjacency_through_door_gap() {
+        // Separated by a 10 unit thick wall, so the boxes never touch
+        let rooms = vec![
+            bbox_room(0, [0.0, 0.0, 100.0, 100.0]),
+            bbox_room(1, [110.0, 0.0, 200.0, 100.0]),
+            bbox_room(2, [0.0, 300.0, 100.0, 400.0]),
+        ];
+        assert!(compute_adjacency(&rooms, &[]).is_empty());
+
+        let door = Line {
+            start: Point { x: 100.0, y: 50.0 },
+            end: Point { x: 110.0, y: 50.0 },
+            is_load_bearing: false,
+        };
+        assert_eq!(compute_adjacency(&rooms, &[door]), vec![(0, 1)]);
+    }
 }
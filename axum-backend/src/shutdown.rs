@@ -0,0 +1,219 @@
+use axum::{extract::Request, middleware::Next, response::Response, Router};
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// How long shutdown waits for in-flight requests unless `SHUTDOWN_DRAIN_SECS` overrides it
+const DEFAULT_SHUTDOWN_DRAIN_SECS: u64 = 90;
+
+/// Drain timeout, overridable with `SHUTDOWN_DRAIN_SECS`
+pub fn drain_timeout() -> Duration {
+    let secs = std::env::var("SHUTDOWN_DRAIN_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_SHUTDOWN_DRAIN_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Resolves on the first SIGINT (Ctrl+C) or SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+/// What happened to the requests that were running when shutdown began
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrainOutcome {
+    pub in_flight_at_signal: usize,
+    pub drained: usize,
+    /// Requests still running when the drain timeout expired
+    pub abandoned: usize,
+}
+
+/// Count of requests currently being handled
+#[derive(Clone, Default)]
+struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn current(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Decrements on drop, so cancelled requests are released too
+struct InFlightGuard(InFlight);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        (self.0).0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+async fn track_in_flight(in_flight: InFlight, request: Request, next: Next) -> Response {
+    in_flight.0.fetch_add(1, Ordering::SeqCst);
+    let _guard = InFlightGuard(in_flight);
+    next.run(request).await
+}
+
+/// Serve `app` until `signal` resolves, then stop accepting and drain in-flight requests
+///
+/// Connections are closed as their requests finish; whatever is still running after
+/// `drain_timeout` is dropped.
+pub async fn serve_with_drain(
+    listener: TcpListener,
+    app: Router,
+    signal: impl Future<Output = ()> + Send + 'static,
+    drain_timeout: Duration,
+) -> std::io::Result<DrainOutcome> {
+    let in_flight = InFlight::default();
+    let tracked = in_flight.clone();
+    let app = app.layer(axum::middleware::from_fn(move |request: Request, next: Next| {
+        track_in_flight(tracked.clone(), request, next)
+    }));
+
+    let at_signal = Arc::new(OnceLock::new());
+    let (signalled_tx, signalled_rx) = tokio::sync::oneshot::channel::<()>();
+    let graceful = {
+        let in_flight = in_flight.clone();
+        let at_signal = at_signal.clone();
+        async move {
+            signal.await;
+            let count = in_flight.current();
+            info!("Shutting down; draining {} in-flight request(s)", count);
+            let _ = at_signal.set(count);
+            let _ = signalled_tx.send(());
+        }
+    };
+
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(graceful)
+        .into_future();
+    let deadline = async {
+        match signalled_rx.await {
+            Ok(()) => tokio::time::sleep(drain_timeout).await,
+            Err(_) => std::future::pending().await,
+        }
+    };
+
+    let outcome = tokio::select! {
+        result = server => {
+            result?;
+            let in_flight_at_signal = at_signal.get().copied().unwrap_or(0);
+            DrainOutcome { in_flight_at_signal, drained: in_flight_at_signal, abandoned: 0 }
+        }
+        _ = deadline => {
+            let in_flight_at_signal = at_signal.get().copied().unwrap_or(0);
+            let abandoned = in_flight.current().min(in_flight_at_signal);
+            warn!(
+                "Drain timeout of {}s expired with {} request(s) still running",
+                drain_timeout.as_secs(),
+                abandoned
+            );
+            DrainOutcome { in_flight_at_signal, drained: in_flight_at_signal - abandoned, abandoned }
+        }
+    };
+
+    info!(
+        "Shutdown complete: drained {} of {} in-flight request(s)",
+        outcome.drained, outcome.in_flight_at_signal
+    );
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+
+    /// `/slow` waits `delay` before answering, notifying `started` once the handler runs, by
+    /// which point the request is counted as in flight
+    fn slow_app(delay: Duration, started: Arc<tokio::sync::Notify>) -> Router {
+        Router::new().route(
+            "/slow",
+            get(move || async move {
+                started.notify_one();
+                tokio::time::sleep(delay).await;
+                "done"
+            }),
+        )
+    }
+
+    async fn bind() -> (TcpListener, std::net::SocketAddr) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        (listener, addr)
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_request_drains_then_server_stops_accepting() {
+        let (listener, addr) = bind().await;
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let started = Arc::new(tokio::sync::Notify::new());
+        let server = tokio::spawn(serve_with_drain(
+            listener,
+            slow_app(Duration::from_millis(300), started.clone()),
+            async move {
+                let _ = stop_rx.await;
+            },
+            Duration::from_secs(10),
+        ));
+
+        let request = tokio::spawn(reqwest::get(format!("http://{}/slow", addr)));
+        started.notified().await;
+        stop_tx.send(()).unwrap();
+
+        let response = request.await.unwrap().unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "done");
+
+        let outcome = server.await.unwrap().unwrap();
+        assert_eq!(outcome, DrainOutcome { in_flight_at_signal: 1, drained: 1, abandoned: 0 });
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_drain_timeout_abandons_slow_requests() {
+        let (listener, addr) = bind().await;
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let started = Arc::new(tokio::sync::Notify::new());
+        let server = tokio::spawn(serve_with_drain(
+            listener,
+            slow_app(Duration::from_secs(30), started.clone()),
+            async move {
+                let _ = stop_rx.await;
+            },
+            Duration::from_millis(200),
+        ));
+
+        let _request = tokio::spawn(reqwest::get(format!("http://{}/slow", addr)));
+        started.notified().await;
+        stop_tx.send(()).unwrap();
+
+        let outcome = tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("server should stop once the drain timeout expires")
+            .unwrap()
+            .unwrap();
+        assert_eq!(outcome, DrainOutcome { in_flight_at_signal: 1, drained: 0, abandoned: 1 });
+    }
+}
@@ -0,0 +1,110 @@
+use serde::Deserialize;
+
+use crate::Point;
+
+pub const FEET_PER_METER: f64 = 3.280_839_895;
+
+/// Real-world length unit for a calibration distance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthUnit {
+    #[default]
+    Meters,
+    Feet,
+}
+
+impl LengthUnit {
+    fn to_meters(self, length: f64) -> f64 {
+        match self {
+            LengthUnit::Meters => length,
+            LengthUnit::Feet => length / FEET_PER_METER,
+        }
+    }
+}
+
+/// How drawing units map to real-world length
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Scale {
+    /// Drawing units in one meter
+    UnitsPerMeter { units_per_meter: f64 },
+    /// Two drawing coordinates a known real-world distance apart
+    Calibration {
+        point_a: Point,
+        point_b: Point,
+        distance: f64,
+        #[serde(default)]
+        unit: LengthUnit,
+    },
+}
+
+impl Scale {
+    /// Drawing units per meter, or None when the scale is degenerate
+    pub fn units_per_meter(&self) -> Option<f64> {
+        let units_per_meter = match self {
+            Scale::UnitsPerMeter { units_per_meter } => *units_per_meter,
+            Scale::Calibration {
+                point_a,
+                point_b,
+                distance,
+                unit,
+            } => {
+                let drawing_length = ((point_b.x - point_a.x).powi(2) + (point_b.y - point_a.y).powi(2)).sqrt();
+                drawing_length / unit.to_meters(*distance)
+            }
+        };
+        (units_per_meter.is_finite() && units_per_meter > 0.0).then_some(units_per_meter)
+    }
+}
+
+/// Convert an area in square drawing units to square meters
+pub fn area_sq_meters(area: f64, units_per_meter: f64) -> f64 {
+    area / (units_per_meter * units_per_meter)
+}
+
+pub fn sq_meters_to_sq_feet(area_sq_meters: f64) -> f64 {
+    area_sq_meters * FEET_PER_METER * FEET_PER_METER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_units_per_meter_scale() {
+        let scale: Scale = serde_json::from_str(r#"{"units_per_meter": 100.0}"#).unwrap();
+        assert_eq!(scale.units_per_meter(), Some(100.0));
+    }
+
+    #[test]
+    fn test_calibration_in_feet() {
+        let scale: Scale = serde_json::from_str(
+            r#"{"point_a": {"x": 0, "y": 0}, "point_b": {"x": 30, "y": 40}, "distance": 10, "unit": "feet"}"#,
+        )
+        .unwrap();
+
+        // 50 drawing units span 10 ft
+        let expected = 50.0 / (10.0 / FEET_PER_METER);
+        assert!((scale.units_per_meter().unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_degenerate_scales_are_rejected() {
+        assert_eq!(Scale::UnitsPerMeter { units_per_meter: 0.0 }.units_per_meter(), None);
+
+        let same_point = Scale::Calibration {
+            point_a: Point { x: 5.0, y: 5.0 },
+            point_b: Point { x: 5.0, y: 5.0 },
+            distance: 3.0,
+            unit: LengthUnit::Meters,
+        };
+        assert_eq!(same_point.units_per_meter(), None);
+    }
+
+    #[test]
+    fn test_area_conversions() {
+        let sq_meters = area_sq_meters(10_000.0, 100.0);
+        assert!((sq_meters - 1.0).abs() < 1e-12);
+        assert!((sq_meters_to_sq_feet(sq_meters) - 10.763_910_4).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,56 @@
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+
+use crate::ErrorResponse;
+
+/// JSON body extractor that reports malformed bodies as a structured `VALIDATION_ERROR`
+/// naming the offending field (`lines[0]`), instead of axum's plain-text rejection
+#[derive(Debug)]
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let value = match Json::<serde_json::Value>::from_request(request, state).await {
+            Ok(Json(value)) => value,
+            Err(JsonRejection::JsonSyntaxError(err)) => {
+                return Err(validation_error(format!("Malformed JSON: {}", err.body_text())));
+            }
+            // Wrong content type or unreadable body keep axum's own response
+            Err(rejection) => return Err(rejection.into_response()),
+        };
+
+        serde_path_to_error::deserialize(value).map(ValidatedJson).map_err(|err| {
+            let path = err.path().to_string();
+            let message = if path == "." {
+                format!("Invalid request body: {}", err.inner())
+            } else {
+                format!("Invalid value at `{}`: {}", path, err.inner())
+            };
+            validation_error(message)
+        })
+    }
+}
+
+fn validation_error(message: String) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: "VALIDATION_ERROR".to_string(),
+            message,
+        }),
+    )
+        .into_response()
+}
@@ -14,6 +14,8 @@ use tracing::info;
 use base64::Engine;
 use anyhow::{Result, Context};
 use ordered_float::OrderedFloat;
+use crate::room_detector::assign_spatial_ids;
+use crate::validated_json::ValidatedJson;
 use crate::{Point, Room, ImageDetectRequest, DetectRoomsResponse, ErrorResponse};
 
 #[derive(Debug, Clone)]
@@ -23,7 +25,7 @@ pub struct LineSegment {
 }
 
 pub async fn detect_vector_graph_handler(
-    Json(request): Json<ImageDetectRequest>,
+    ValidatedJson(request): ValidatedJson<ImageDetectRequest>,
 ) -> Result<Json<DetectRoomsResponse>, (StatusCode, Json<ErrorResponse>)> {
     info!("Received vector graph detection request");
     
@@ -42,6 +44,7 @@ pub async fn detect_vector_graph_handler(
         })?;
 
     info!("Image decoded, size: {} bytes", img_bytes.len());
+    crate::check_image_size(&img_bytes)?;
 
     let start_time = Instant::now();
 
@@ -123,17 +126,23 @@ pub async fn detect_vector_graph_handler(
                 area,
                 name_hint: "Room".to_string(),
                 points,
+                ..Room::default()
             });
         }
     }
     
     let execution_time = start_time.elapsed().as_millis() as u64;
     info!("Detected {} rooms using vector graph in {}ms", rooms.len(), execution_time);
-    
-    Ok(Json(DetectRoomsResponse {
-        total_rooms: rooms.len(),
-        rooms,
-    }))
+    assign_spatial_ids(&mut rooms);
+
+    // VTracer works in image pixels; report rooms in the same 0-1000 space as other detectors
+    let source_bounds = image::ImageReader::new(std::io::Cursor::new(&img_bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .map(|(width, height)| [0.0, 0.0, width as f64, height as f64]);
+
+    Ok(Json(DetectRoomsResponse::new(rooms, None).normalized(source_bounds)))
 }
 
 async fn convert_image_to_svg_vtracer(img_bytes: &[u8], output_path: &Path) -> Result<()> {
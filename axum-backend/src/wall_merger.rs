@@ -2,6 +2,15 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use tracing::info;
 
+/// Default max angle between walls merged as collinear, in degrees
+pub const DEFAULT_COLLINEAR_ANGLE_TOLERANCE: f64 = 3.0;
+/// Default max perpendicular offset between walls merged as collinear
+pub const DEFAULT_COLLINEAR_DISTANCE_TOLERANCE: f64 = 5.0;
+/// Default max gap along the wall direction between walls merged as collinear
+pub const DEFAULT_COLLINEAR_GAP_TOLERANCE: f64 = 5.0;
+/// Default minimum confidence for keeping GPT-5 walls that VTracer didn't confirm
+pub const DEFAULT_MIN_WALL_CONFIDENCE: f64 = 0.5;
+
 /// Line segment representing a wall
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Line {
@@ -11,6 +20,9 @@ pub struct Line {
     pub is_load_bearing: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>, // "vtracer", "gpt5", or "consensus"
+    /// Per-wall confidence from the vision model; falls back to the overall score
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -81,6 +93,7 @@ impl MergeStrategy {
 /// * `vision_walls` - Wall segments from GPT-5 Vision API
 /// * `vision_confidence` - Confidence score from GPT-5 (0.0-1.0)
 /// * `threshold` - Confidence threshold for strategy selection (default: 0.75)
+/// * `min_wall_confidence` - GPT-5-only walls below this confidence are dropped
 ///
 /// # Returns
 /// Merged wall segments with metadata about the merge process
@@ -89,6 +102,7 @@ pub fn merge_wall_segments(
     vision_walls: Vec<Line>,
     vision_confidence: f64,
     threshold: f64,
+    min_wall_confidence: f64,
 ) -> MergeResult {
     let strategy = MergeStrategy::from_confidence(vision_confidence, threshold);
 
@@ -100,18 +114,33 @@ pub fn merge_wall_segments(
         vision_confidence
     );
 
+    let weights = VisionWeights {
+        confidence: vision_confidence,
+        min_wall_confidence,
+    };
     let (walls, consensus_count) = match strategy {
         MergeStrategy::VisionPrimary => {
-            merge_vision_primary(&vtracer_walls, &vision_walls)
+            merge_vision_primary(&vtracer_walls, &vision_walls, weights)
         }
         MergeStrategy::VtracerPrimary => {
-            merge_vtracer_primary(&vtracer_walls, &vision_walls)
+            merge_vtracer_primary(&vtracer_walls, &vision_walls, weights)
         }
         MergeStrategy::Consensus => {
-            merge_consensus(&vtracer_walls, &vision_walls)
+            merge_consensus(&vtracer_walls, &vision_walls, weights)
         }
     };
 
+    // Collapse duplicates and overlapping collinear pieces the strategies left behind
+    let walls = merge_collinear_walls(
+        walls,
+        DEFAULT_COLLINEAR_ANGLE_TOLERANCE,
+        DEFAULT_COLLINEAR_DISTANCE_TOLERANCE,
+        DEFAULT_COLLINEAR_GAP_TOLERANCE,
+    );
+    let consensus_count = consensus_count.max(
+        walls.iter().filter(|w| w.source.as_deref() == Some("consensus")).count(),
+    );
+
     let metadata = MergeMetadata {
         vtracer_count: vtracer_walls.len(),
         gpt5_count: vision_walls.len(),
@@ -130,24 +159,23 @@ pub fn merge_wall_segments(
 
 /// Merge with GPT-5 Vision as primary source
 /// Uses VTracer walls to supplement areas GPT-5 might have missed
-fn merge_vision_primary(vtracer_walls: &[Line], vision_walls: &[Line]) -> (Vec<Line>, usize) {
+fn merge_vision_primary(vtracer_walls: &[Line], vision_walls: &[Line], weights: VisionWeights) -> (Vec<Line>, usize) {
     let mut result = Vec::new();
     let mut used_vtracer = HashSet::new();
     let mut consensus_count = 0;
 
     // Add all GPT-5 walls first
     for wall in vision_walls.iter() {
-        let mut wall_clone = wall.clone();
-        wall_clone.source = Some("gpt5".to_string());
-
         // Check if this wall is confirmed by VTracer (consensus)
         if let Some(vtracer_idx) = find_similar_wall(wall, vtracer_walls, 5.0) {
-            wall_clone.source = Some("consensus".to_string());
+            result.push(weights.blend(&vtracer_walls[vtracer_idx], wall));
             used_vtracer.insert(vtracer_idx);
             consensus_count += 1;
+        } else if weights.trusts(wall) {
+            let mut wall_clone = wall.clone();
+            wall_clone.source = Some("gpt5".to_string());
+            result.push(wall_clone);
         }
-
-        result.push(wall_clone);
     }
 
     // Add VTracer walls that weren't matched (supplementary walls)
@@ -164,29 +192,28 @@ fn merge_vision_primary(vtracer_walls: &[Line], vision_walls: &[Line]) -> (Vec<L
 
 /// Merge with VTracer as primary source
 /// Uses GPT-5 walls for validation and filling gaps
-fn merge_vtracer_primary(vtracer_walls: &[Line], vision_walls: &[Line]) -> (Vec<Line>, usize) {
+fn merge_vtracer_primary(vtracer_walls: &[Line], vision_walls: &[Line], weights: VisionWeights) -> (Vec<Line>, usize) {
     let mut result = Vec::new();
     let mut used_vision = HashSet::new();
     let mut consensus_count = 0;
 
     // Add all VTracer walls first
     for wall in vtracer_walls.iter() {
-        let mut wall_clone = wall.clone();
-        wall_clone.source = Some("vtracer".to_string());
-
         // Check if this wall is confirmed by GPT-5 (consensus)
         if let Some(vision_idx) = find_similar_wall(wall, vision_walls, 5.0) {
-            wall_clone.source = Some("consensus".to_string());
+            result.push(weights.blend(wall, &vision_walls[vision_idx]));
             used_vision.insert(vision_idx);
             consensus_count += 1;
+        } else {
+            let mut wall_clone = wall.clone();
+            wall_clone.source = Some("vtracer".to_string());
+            result.push(wall_clone);
         }
-
-        result.push(wall_clone);
     }
 
     // Add GPT-5 walls that weren't matched (validation/gap-filling)
     for (idx, wall) in vision_walls.iter().enumerate() {
-        if !used_vision.contains(&idx) {
+        if !used_vision.contains(&idx) && weights.trusts(wall) {
             let mut wall_clone = wall.clone();
             wall_clone.source = Some("gpt5".to_string());
             result.push(wall_clone);
@@ -197,7 +224,7 @@ fn merge_vtracer_primary(vtracer_walls: &[Line], vision_walls: &[Line]) -> (Vec<
 }
 
 /// Merge with consensus voting - only keep walls that appear in both sources or are very confident
-fn merge_consensus(vtracer_walls: &[Line], vision_walls: &[Line]) -> (Vec<Line>, usize) {
+fn merge_consensus(vtracer_walls: &[Line], vision_walls: &[Line], weights: VisionWeights) -> (Vec<Line>, usize) {
     let mut result = Vec::new();
     let mut used_vtracer = HashSet::new();
     let mut used_vision = HashSet::new();
@@ -207,9 +234,7 @@ fn merge_consensus(vtracer_walls: &[Line], vision_walls: &[Line]) -> (Vec<Line>,
     for (v_idx, vtracer_wall) in vtracer_walls.iter().enumerate() {
         if let Some(vision_idx) = find_similar_wall(vtracer_wall, vision_walls, 5.0) {
             // This wall appears in both sources - high confidence
-            let mut wall = vtracer_wall.clone();
-            wall.source = Some("consensus".to_string());
-            result.push(wall);
+            result.push(weights.blend(vtracer_wall, &vision_walls[vision_idx]));
             used_vtracer.insert(v_idx);
             used_vision.insert(vision_idx);
             consensus_count += 1;
@@ -227,7 +252,7 @@ fn merge_consensus(vtracer_walls: &[Line], vision_walls: &[Line]) -> (Vec<Line>,
 
     // Add high-confidence GPT-5 walls (long walls)
     for (g_idx, wall) in vision_walls.iter().enumerate() {
-        if !used_vision.contains(&g_idx) && wall_length(wall) > 50.0 {
+        if !used_vision.contains(&g_idx) && wall_length(wall) > 50.0 && weights.trusts(wall) {
             let mut wall_clone = wall.clone();
             wall_clone.source = Some("gpt5".to_string());
             result.push(wall_clone);
@@ -237,6 +262,283 @@ fn merge_consensus(vtracer_walls: &[Line], vision_walls: &[Line]) -> (Vec<Line>,
     (result, consensus_count)
 }
 
+/// How much to trust GPT-5 walls relative to VTracer's geometry
+#[derive(Debug, Clone, Copy)]
+struct VisionWeights {
+    /// Overall GPT-5 confidence, used for walls without their own score
+    confidence: f64,
+    min_wall_confidence: f64,
+}
+
+impl VisionWeights {
+    fn wall_confidence(&self, wall: &Line) -> f64 {
+        wall.confidence.unwrap_or(self.confidence).clamp(0.0, 1.0)
+    }
+
+    /// Whether an unconfirmed GPT-5 wall is confident enough to keep
+    fn trusts(&self, vision_wall: &Line) -> bool {
+        self.wall_confidence(vision_wall) >= self.min_wall_confidence
+    }
+
+    /// Consensus wall from a matched pair, pulled toward VTracer by `1 - confidence`
+    fn blend(&self, vtracer_wall: &Line, vision_wall: &Line) -> Line {
+        let confidence = self.wall_confidence(vision_wall);
+        let vtracer_weight = 1.0 - confidence;
+
+        // Match endpoint order before averaging
+        let (vision_start, vision_end) = if vtracer_wall.start.distance_to(&vision_wall.start)
+            <= vtracer_wall.start.distance_to(&vision_wall.end)
+        {
+            (vision_wall.start, vision_wall.end)
+        } else {
+            (vision_wall.end, vision_wall.start)
+        };
+        let lerp = |vision: Point, vtracer: Point| Point {
+            x: vision.x + (vtracer.x - vision.x) * vtracer_weight,
+            y: vision.y + (vtracer.y - vision.y) * vtracer_weight,
+        };
+
+        Line {
+            start: lerp(vision_start, vtracer_wall.start),
+            end: lerp(vision_end, vtracer_wall.end),
+            is_load_bearing: vtracer_wall.is_load_bearing || vision_wall.is_load_bearing,
+            source: Some("consensus".to_string()),
+            confidence: Some(confidence),
+        }
+    }
+}
+
+/// Merge walls that are collinear and overlap (or nearly touch) into single longer walls
+///
+/// Two walls merge when their directions differ by at most `angle_tolerance` degrees, both
+/// endpoints of one lie within `distance_tolerance` of the other's line, and the gap between
+/// them along that line is at most `gap_tolerance`. Walls from different sources merge into a
+/// "consensus" wall; duplicates from one source keep that source.
+pub fn merge_collinear_walls(
+    mut walls: Vec<Line>,
+    angle_tolerance: f64,
+    distance_tolerance: f64,
+    gap_tolerance: f64,
+) -> Vec<Line> {
+    // Merging can make a wall reach further, so repeat until nothing changes
+    let mut merged_any = true;
+    while merged_any {
+        merged_any = false;
+        'search: for i in 0..walls.len() {
+            for j in (i + 1)..walls.len() {
+                if let Some(merged) =
+                    merge_if_collinear(&walls[i], &walls[j], angle_tolerance, distance_tolerance, gap_tolerance)
+                {
+                    walls[i] = merged;
+                    walls.remove(j);
+                    merged_any = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+
+    walls
+}
+
+/// Merge two walls if they are collinear within tolerances, extending along the longer wall's line
+fn merge_if_collinear(
+    a: &Line,
+    b: &Line,
+    angle_tolerance: f64,
+    distance_tolerance: f64,
+    gap_tolerance: f64,
+) -> Option<Line> {
+    let (base, other) = if wall_length(a) >= wall_length(b) { (a, b) } else { (b, a) };
+    let length = wall_length(base);
+    if length < f64::EPSILON {
+        return None;
+    }
+
+    // Unit direction of the reference wall
+    let dx = (base.end.x - base.start.x) / length;
+    let dy = (base.end.y - base.start.y) / length;
+
+    // Angle between the walls, ignoring direction
+    let other_length = wall_length(other);
+    if other_length >= f64::EPSILON {
+        let cos = ((other.end.x - other.start.x) * dx + (other.end.y - other.start.y) * dy).abs() / other_length;
+        if cos.min(1.0).acos().to_degrees() > angle_tolerance {
+            return None;
+        }
+    }
+
+    // Perpendicular offset and position along the reference line
+    let project = |p: &Point| {
+        let px = p.x - base.start.x;
+        let py = p.y - base.start.y;
+        (px * dx + py * dy, (px * dy - py * dx).abs())
+    };
+    let (t0, d0) = project(&other.start);
+    let (t1, d1) = project(&other.end);
+    if d0 > distance_tolerance || d1 > distance_tolerance {
+        return None;
+    }
+
+    let (other_min, other_max) = (t0.min(t1), t0.max(t1));
+    let gap = (other_min - length).max(-other_max);
+    if gap > gap_tolerance {
+        return None;
+    }
+
+    let t_min = other_min.min(0.0);
+    let t_max = other_max.max(length);
+    let source = if a.source == b.source {
+        a.source.clone()
+    } else {
+        Some("consensus".to_string())
+    };
+
+    Some(Line {
+        start: Point {
+            x: base.start.x + dx * t_min,
+            y: base.start.y + dy * t_min,
+        },
+        end: Point {
+            x: base.start.x + dx * t_max,
+            y: base.start.y + dy * t_max,
+        },
+        is_load_bearing: a.is_load_bearing || b.is_load_bearing,
+        source,
+        confidence: match (a.confidence, b.confidence) {
+            (Some(ca), Some(cb)) => Some(ca.max(cb)),
+            (ca, cb) => ca.or(cb),
+        },
+    })
+}
+
+/// Wall centerlines, plus the wall thickness estimated from the face pairs that were collapsed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CenterlineResult {
+    pub centerlines: Vec<Line>,
+    /// Median spacing between paired faces; `None` when no pairs were found
+    pub thickness: Option<f64>,
+}
+
+/// Collapse the two faces of each filled wall band into a single centerline
+///
+/// Faces are parallel (within `DEFAULT_COLLINEAR_ANGLE_TOLERANCE`), overlap along their
+/// direction, and lie at most `thickness_tolerance` apart. Closest pairs are collapsed
+/// first; each line joins at most one pair and unpaired lines are kept as-is. The
+/// centerline spans the average of both faces' extents, so the faces of a corner
+/// (where the outer face is longer) meet at the corner's center.
+pub fn extract_centerlines(lines: &[Line], thickness_tolerance: f64) -> CenterlineResult {
+    let mut candidates: Vec<(f64, usize, usize)> = Vec::new();
+    for i in 0..lines.len() {
+        for j in (i + 1)..lines.len() {
+            if let Some(spacing) = face_spacing(&lines[i], &lines[j], thickness_tolerance) {
+                candidates.push((spacing, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut partner: Vec<Option<usize>> = vec![None; lines.len()];
+    let mut spacings = Vec::new();
+    for (spacing, i, j) in candidates {
+        if partner[i].is_none() && partner[j].is_none() {
+            partner[i] = Some(j);
+            partner[j] = Some(i);
+            spacings.push(spacing);
+        }
+    }
+
+    let mut centerlines = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        match partner[i] {
+            Some(j) if j > i => centerlines.push(centerline(line, &lines[j])),
+            Some(_) => {}
+            None => centerlines.push(line.clone()),
+        }
+    }
+
+    // Spacings were collected closest first, so the middle one is the median
+    let thickness = (!spacings.is_empty()).then(|| spacings[spacings.len() / 2]);
+    if let Some(thickness) = thickness {
+        info!(
+            "Collapsed {} wall face pairs into centerlines, estimated thickness {:.1}",
+            spacings.len(),
+            thickness
+        );
+    }
+
+    CenterlineResult { centerlines, thickness }
+}
+
+/// Spacing between two segments if they look like opposite faces of one wall
+fn face_spacing(a: &Line, b: &Line, thickness_tolerance: f64) -> Option<f64> {
+    let (base, other) = if wall_length(a) >= wall_length(b) { (a, b) } else { (b, a) };
+    let length = wall_length(base);
+    let other_length = wall_length(other);
+    if length < f64::EPSILON || other_length < f64::EPSILON {
+        return None;
+    }
+
+    let (dx, dy) = ((base.end.x - base.start.x) / length, (base.end.y - base.start.y) / length);
+    let cos = ((other.end.x - other.start.x) * dx + (other.end.y - other.start.y) * dy).abs() / other_length;
+    if cos.min(1.0).acos().to_degrees() > DEFAULT_COLLINEAR_ANGLE_TOLERANCE {
+        return None;
+    }
+
+    let (t0, d0) = project_onto(base, &other.start);
+    let (t1, d1) = project_onto(base, &other.end);
+    let spacing = ((d0 + d1) / 2.0).abs();
+    // Collinear duplicates are merge_collinear_walls' job, not a wall's two faces
+    if spacing < f64::EPSILON || spacing > thickness_tolerance {
+        return None;
+    }
+
+    // Faces of one wall run alongside each other
+    let overlap = t0.max(t1).min(length) - t0.min(t1).max(0.0);
+    (overlap > 0.0).then_some(spacing)
+}
+
+/// Position along `line` and signed perpendicular offset (positive to the left) of `p`
+fn project_onto(line: &Line, p: &Point) -> (f64, f64) {
+    let length = wall_length(line);
+    let (dx, dy) = ((line.end.x - line.start.x) / length, (line.end.y - line.start.y) / length);
+    let (px, py) = (p.x - line.start.x, p.y - line.start.y);
+    (px * dx + py * dy, dx * py - dy * px)
+}
+
+/// Midline between two wall faces, along the longer face's direction
+fn centerline(a: &Line, b: &Line) -> Line {
+    let (base, other) = if wall_length(a) >= wall_length(b) { (a, b) } else { (b, a) };
+    let length = wall_length(base);
+    let (dx, dy) = ((base.end.x - base.start.x) / length, (base.end.y - base.start.y) / length);
+
+    let (t0, d0) = project_onto(base, &other.start);
+    let (t1, d1) = project_onto(base, &other.end);
+    let t_start = t0.min(t1) / 2.0;
+    let t_end = (length + t0.max(t1)) / 2.0;
+    let offset = (d0 + d1) / 4.0;
+
+    let at = |t: f64| Point {
+        x: base.start.x + dx * t - dy * offset,
+        y: base.start.y + dy * t + dx * offset,
+    };
+
+    Line {
+        start: at(t_start),
+        end: at(t_end),
+        is_load_bearing: a.is_load_bearing || b.is_load_bearing,
+        source: if a.source == b.source {
+            a.source.clone()
+        } else {
+            Some("consensus".to_string())
+        },
+        confidence: match (a.confidence, b.confidence) {
+            (Some(ca), Some(cb)) => Some(ca.max(cb)),
+            (ca, cb) => ca.or(cb),
+        },
+    }
+}
+
 /// Find a similar wall in a list of walls within a tolerance
 /// Returns the index of the first similar wall found
 fn find_similar_wall(wall: &Line, walls: &[Line], tolerance: f64) -> Option<usize> {
@@ -273,6 +575,7 @@ mod tests {
             end: Point { x: x2, y: y2 },
             is_load_bearing: false,
             source: None,
+            confidence: None,
         }
     }
 
@@ -316,7 +619,7 @@ mod tests {
             create_line(0.0, 100.0, 100.0, 100.0), // New wall from GPT-5
         ];
 
-        let result = merge_wall_segments(vtracer_walls, vision_walls, 0.85, 0.75);
+        let result = merge_wall_segments(vtracer_walls, vision_walls, 0.85, 0.75, DEFAULT_MIN_WALL_CONFIDENCE);
 
         assert_eq!(result.metadata.strategy_used, "vision_primary");
         assert_eq!(result.metadata.consensus_count, 1); // One wall matched
@@ -334,7 +637,7 @@ mod tests {
             create_line(0.0, 0.0, 100.0, 0.0),
         ];
 
-        let result = merge_wall_segments(vtracer_walls, vision_walls, 0.3, 0.75);
+        let result = merge_wall_segments(vtracer_walls, vision_walls, 0.3, 0.75, DEFAULT_MIN_WALL_CONFIDENCE);
 
         assert_eq!(result.metadata.strategy_used, "vtracer_primary");
         assert_eq!(result.metadata.consensus_count, 1);
@@ -353,16 +656,130 @@ mod tests {
             create_line(200.0, 0.0, 200.0, 10.0), // Short wall, won't be included
         ];
 
-        let result = merge_wall_segments(vtracer_walls, vision_walls, 0.6, 0.75);
+        let result = merge_wall_segments(vtracer_walls, vision_walls, 0.6, 0.75, DEFAULT_MIN_WALL_CONFIDENCE);
 
         assert_eq!(result.metadata.strategy_used, "consensus");
         assert_eq!(result.metadata.consensus_count, 1);
         // Only consensus walls and walls > 50 units
     }
 
+    fn sourced_line(x1: f64, y1: f64, x2: f64, y2: f64, source: &str) -> Line {
+        Line {
+            source: Some(source.to_string()),
+            ..create_line(x1, y1, x2, y2)
+        }
+    }
+
+    #[test]
+    fn test_collinear_walls_from_both_sources_merge_to_consensus() {
+        // Same edge, GPT-5 slightly offset and extending further
+        let walls = vec![
+            sourced_line(0.0, 0.0, 100.0, 0.0, "vtracer"),
+            sourced_line(2.0, 1.5, 120.0, 1.5, "gpt5"),
+        ];
+
+        let merged = merge_collinear_walls(walls, 3.0, 5.0, 5.0);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].source.as_deref(), Some("consensus"));
+        let (lo, hi) = (merged[0].start.x.min(merged[0].end.x), merged[0].start.x.max(merged[0].end.x));
+        assert!(lo.abs() < 1e-6);
+        assert!((hi - 120.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_same_source_duplicates_collapse() {
+        let walls = vec![
+            sourced_line(0.0, 0.0, 100.0, 0.0, "vtracer"),
+            sourced_line(100.0, 0.0, 0.0, 0.0, "vtracer"),
+        ];
+
+        let merged = merge_collinear_walls(walls, 3.0, 5.0, 5.0);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].source.as_deref(), Some("vtracer"));
+    }
+
+    #[test]
+    fn test_collinear_merge_respects_tolerances() {
+        let walls = vec![
+            sourced_line(0.0, 0.0, 100.0, 0.0, "vtracer"),
+            // Collinear but 20 units past the end
+            sourced_line(120.0, 0.0, 200.0, 0.0, "gpt5"),
+            // Parallel but 20 units away
+            sourced_line(0.0, 20.0, 100.0, 20.0, "gpt5"),
+            // Crossing at 10 degrees
+            sourced_line(0.0, 0.0, 100.0, 17.6, "gpt5"),
+        ];
+
+        assert_eq!(merge_collinear_walls(walls.clone(), 3.0, 5.0, 5.0).len(), 4);
+        // A wider gap tolerance joins the first two
+        assert_eq!(merge_collinear_walls(walls, 3.0, 5.0, 25.0).len(), 3);
+    }
+
+    #[test]
+    fn test_low_confidence_gpt5_only_wall_dropped() {
+        let vtracer_walls = vec![create_line(0.0, 0.0, 100.0, 0.0)];
+        let vision_walls = vec![
+            Line { confidence: Some(0.2), ..create_line(0.0, 100.0, 100.0, 100.0) },
+            Line { confidence: Some(0.9), ..create_line(100.0, 0.0, 100.0, 100.0) },
+        ];
+
+        let result = merge_wall_segments(vtracer_walls, vision_walls, 0.85, 0.75, 0.5);
+
+        assert_eq!(result.walls.len(), 2);
+        assert!(result.walls.iter().all(|w| w.start.y != 100.0 || w.end.y != 100.0));
+        assert!(result.walls.iter().any(|w| w.source.as_deref() == Some("gpt5") && w.start.x == 100.0));
+    }
+
+    #[test]
+    fn test_matched_walls_snap_toward_vtracer() {
+        let vtracer = create_line(0.0, 0.0, 100.0, 0.0);
+        // Reversed and offset by 4 units
+        let vision = create_line(100.0, 4.0, 0.0, 4.0);
+        let weights = VisionWeights { confidence: 0.25, min_wall_confidence: 0.5 };
+
+        let blended = weights.blend(&vtracer, &vision);
+
+        assert_eq!(blended.source.as_deref(), Some("consensus"));
+        assert!((blended.start.x - 0.0).abs() < 1e-9 && (blended.start.y - 1.0).abs() < 1e-9);
+        assert!((blended.end.x - 100.0).abs() < 1e-9 && (blended.end.y - 1.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_wall_length() {
         let wall = create_line(0.0, 0.0, 3.0, 4.0);
         assert_eq!(wall_length(&wall), 5.0); // 3-4-5 triangle
     }
+
+    #[test]
+    fn test_parallel_faces_collapse_to_centerline() {
+        let faces = vec![create_line(0.0, 0.0, 100.0, 0.0), create_line(100.0, 6.0, 0.0, 6.0)];
+
+        let result = extract_centerlines(&faces, 10.0);
+
+        assert_eq!(result.centerlines.len(), 1);
+        assert_eq!(result.thickness, Some(6.0));
+        let line = &result.centerlines[0];
+        assert!((line.start.y - 3.0).abs() < 1e-9 && (line.end.y - 3.0).abs() < 1e-9);
+        let (lo, hi) = (line.start.x.min(line.end.x), line.start.x.max(line.end.x));
+        assert!(lo.abs() < 1e-9 && (hi - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_distant_or_offset_lines_not_paired() {
+        let lines = vec![
+            // Two separate walls, farther apart than the tolerance
+            create_line(0.0, 0.0, 100.0, 0.0),
+            create_line(0.0, 50.0, 100.0, 50.0),
+            // Parallel and close, but end to end rather than side by side
+            create_line(200.0, 0.0, 300.0, 0.0),
+            create_line(310.0, 4.0, 400.0, 4.0),
+        ];
+
+        let result = extract_centerlines(&lines, 10.0);
+
+        assert_eq!(result.centerlines.len(), lines.len());
+        assert_eq!(result.thickness, None);
+    }
 }
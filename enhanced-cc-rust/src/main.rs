@@ -1,7 +1,9 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use image::{GrayImage, Luma};
+use imageproc::distance_transform::Norm;
 use imageproc::morphology::{dilate, erode};
-use std::collections::VecDeque;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::Path;
@@ -21,8 +23,34 @@ pub struct Room {
     pub area: f64,
     pub name_hint: String,
     pub points: Vec<Point>,
+    /// Minimum-area rotated bounding box, in pixels
+    pub oriented_box: OrientedBox,
 }
 
+/// Rotated rectangle from rotating calipers; `width` is the longer side
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct OrientedBox {
+    pub width: f64,
+    pub height: f64,
+    /// Angle of the long side from the x axis, in degrees (0-180)
+    pub angle: f64,
+}
+
+impl OrientedBox {
+    fn aspect(&self) -> f64 {
+        self.width / self.height.max(1.0)
+    }
+}
+
+/// Components more elongated than this are treated as walls
+const MAX_ROOM_ASPECT: f64 = 15.0;
+
+/// Default closing kernel size (3x3)
+const DEFAULT_MORPH_KERNEL_SIZE: u32 = 3;
+
+/// Default number of closing passes
+const DEFAULT_MORPH_ITERATIONS: u32 = 1;
+
 fn load_image(path: &Path) -> Result<GrayImage> {
     let img = image::open(path)
         .with_context(|| format!("Failed to load image: {}", path.display()))?
@@ -30,88 +58,254 @@ fn load_image(path: &Path) -> Result<GrayImage> {
     Ok(img)
 }
 
-fn threshold_image(img: &GrayImage, threshold: u8) -> GrayImage {
+/// Threshold, then morphologically close the free space (light pixels)
+///
+/// `morph_kernel_size` is the side of the square kernel (odd: 3, 5 or 7) and
+/// `morph_iterations` is how many times the close is applied. A close erases dark
+/// strokes thinner than `morph_kernel_size`, so larger kernels and more passes clean
+/// up more scan noise but also wipe out thin walls and merge the rooms either side.
+fn threshold_image(img: &GrayImage, threshold: u8, morph_kernel_size: u32, morph_iterations: u32) -> GrayImage {
     let mut binary = GrayImage::new(img.width(), img.height());
     for (x, y, pixel) in img.enumerate_pixels() {
         let val = if pixel[0] > threshold { 255 } else { 0 };
-        binary.put_pixel(*x, *y, image::Luma([val]));
+        binary.put_pixel(x, y, Luma([val]));
+    }
+
+    // A (2r+1)x(2r+1) square kernel is an L-infinity ball of radius r
+    let radius = (morph_kernel_size / 2).min(u8::MAX as u32) as u8;
+    for _ in 0..morph_iterations {
+        let dilated = dilate(&binary, Norm::LInf, radius);
+        binary = erode(&dilated, Norm::LInf, radius);
     }
-    
-    // Apply morphological closing to connect broken walls
-    let kernel = imageproc::kernels::horizontal_vertical::MORPH_KERNEL_3X3;
-    let dilated = dilate(&binary, kernel);
-    let binary = erode(&dilated, kernel);
-    
     binary
 }
 
-fn find_connected_components(img: &GrayImage) -> Vec<(usize, (u32, u32, u32, u32))> {
-    let (width, height) = img.dimensions();
-    let mut visited = vec![false; (width as usize * height as usize)];
-    let mut components = Vec::new();
-    
-    for y in 0..height {
-        for x in 0..width {
-            let idx = (y as usize * width as usize) + x as usize;
-            if img.get_pixel(x, y)[0] == 255 && !visited[idx] {
-                let (area, bbox) = flood_fill(img, x, y, &mut visited, width, height);
-                if area > 500 {
-                    components.push((area, bbox));
-                }
+/// Optional positional argument `index`, or `default` when absent
+fn numeric_arg(args: &[String], index: usize, name: &str, default: u32) -> Result<u32> {
+    match args.get(index) {
+        Some(value) => value
+            .parse()
+            .with_context(|| format!("{} must be a whole number, got {}", name, value)),
+        None => Ok(default),
+    }
+}
+
+/// Pixel bounding box, `(min_x, min_y, max_x, max_y)` inclusive
+type BBox = (u32, u32, u32, u32);
+
+/// Leftmost and rightmost pixel per row; enough to recover the convex hull
+type RowExtents = BTreeMap<u32, (u32, u32)>;
+
+fn find_connected_components(img: &GrayImage) -> Vec<(usize, BBox, OrientedBox)> {
+    label_components(img)
+        .into_iter()
+        .filter(|(area, _, _)| *area > 500)
+        .map(|(area, bbox, rows)| {
+            let hull = convex_hull(&row_extent_corners(&rows));
+            (area, bbox, min_area_oriented_box(&hull))
+        })
+        .collect()
+}
+
+/// Horizontal run of white pixels on one row, `start..=end`
+type Run = (u32, u32);
+
+fn row_runs(img: &GrayImage, y: u32) -> Vec<Run> {
+    let width = img.width() as usize;
+    let row = &img.as_raw()[y as usize * width..(y as usize + 1) * width];
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (x, &value) in row.iter().enumerate() {
+        match (value == 255, start) {
+            (true, None) => start = Some(x as u32),
+            (false, Some(s)) => {
+                runs.push((s, x as u32 - 1));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        runs.push((s, width as u32 - 1));
+    }
+    runs
+}
+
+/// Index pairs of runs on consecutive rows that are 8-connected
+fn touching_runs(above: &[Run], below: &[Run]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < above.len() && j < below.len() {
+        let (a, b) = (above[i], below[j]);
+        // Runs that are only diagonally adjacent still touch
+        if a.0 <= b.1 + 1 && b.0 <= a.1 + 1 {
+            pairs.push((i, j));
+        }
+        if a.1 < b.1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Union-find over run indices; each set's root is its first run in raster order
+struct RunSets {
+    parent: Vec<usize>,
+}
+
+impl RunSets {
+    fn new(len: usize) -> Self {
+        Self { parent: (0..len).collect() }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+/// Two-pass 8-connected labeling of white pixels: area, bounding box and row extents per component
+///
+/// Rows are split into runs and linked to the row above in parallel, then the links
+/// are merged in a union-find. Components come out in raster order of their first
+/// pixel, the same order a scanline flood fill discovers them.
+fn label_components(img: &GrayImage) -> Vec<(usize, BBox, RowExtents)> {
+    let runs: Vec<Vec<Run>> = (0..img.height()).into_par_iter().map(|y| row_runs(img, y)).collect();
+
+    let mut offsets = Vec::with_capacity(runs.len());
+    let mut run_count = 0;
+    for row in &runs {
+        offsets.push(run_count);
+        run_count += row.len();
+    }
+
+    let links: Vec<Vec<(usize, usize)>> = (1..runs.len())
+        .into_par_iter()
+        .map(|y| {
+            touching_runs(&runs[y - 1], &runs[y])
+                .into_iter()
+                .map(|(above, below)| (offsets[y - 1] + above, offsets[y] + below))
+                .collect()
+        })
+        .collect();
+
+    let mut sets = RunSets::new(run_count);
+    for &(above, below) in links.iter().flatten() {
+        sets.union(above, below);
+    }
+
+    let mut component_of = vec![usize::MAX; run_count];
+    let mut components: Vec<(usize, BBox, RowExtents)> = Vec::new();
+    for (y, row) in runs.iter().enumerate() {
+        let y = y as u32;
+        for (k, &(start, end)) in row.iter().enumerate() {
+            let root = sets.find(offsets[y as usize] + k);
+            if component_of[root] == usize::MAX {
+                component_of[root] = components.len();
+                components.push((0, (start, y, end, y), RowExtents::new()));
             }
+            let (area, bbox, rows) = &mut components[component_of[root]];
+            *area += (end - start + 1) as usize;
+            bbox.0 = bbox.0.min(start);
+            bbox.2 = bbox.2.max(end);
+            bbox.3 = y;
+            let extent = rows.entry(y).or_insert((start, end));
+            extent.0 = extent.0.min(start);
+            extent.1 = extent.1.max(end);
         }
     }
     components
 }
 
-fn flood_fill(
-    img: &GrayImage,
-    start_x: u32,
-    start_y: u32,
-    visited: &mut Vec<bool>,
-    width: u32,
-    height: u32,
-) -> (usize, (u32, u32, u32, u32)) {
-    let mut queue = VecDeque::new();
-    let mut area = 0;
-    let mut min_x = start_x;
-    let mut min_y = start_y;
-    let mut max_x = start_x;
-    let mut max_y = start_y;
-    
-    queue.push_back((start_x, start_y));
-    let start_idx = (start_y as usize * width as usize) + start_x as usize;
-    visited[start_idx] = true;
+/// Outer pixel corners of each row's extent, so a one-pixel row still has size
+fn row_extent_corners(rows: &RowExtents) -> Vec<(f64, f64)> {
+    let mut corners = Vec::with_capacity(rows.len() * 4);
+    for (&y, &(left, right)) in rows {
+        let (y, left, right) = (y as f64, left as f64, right as f64 + 1.0);
+        corners.extend([(left, y), (left, y + 1.0), (right, y), (right, y + 1.0)]);
+    }
+    corners
+}
+
+fn cross(o: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+/// Counter-clockwise convex hull (Andrew's monotone chain), without collinear points
+fn convex_hull(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
     
-    while let Some((x, y)) = queue.pop_front() {
-        area += 1;
-        min_x = min_x.min(x);
-        min_y = min_y.min(y);
-        max_x = max_x.max(x);
-        max_y = max_y.max(y);
-        
-        // 8-connectivity neighbors
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                let nx = x as i32 + dx;
-                let ny = y as i32 + dy;
-                if nx >= 0 && nx < width as i32 && ny >= 0 && ny < height as i32 {
-                    let px = nx as u32;
-                    let py = ny as u32;
-                    let nidx = (py as usize * width as usize) + px as usize;
-                    if !visited[nidx] && img.get_pixel(px, py)[0] == 255 {
-                        visited[nidx] = true;
-                        queue.push_back((px, py));
-                    }
-                }
+    let mut hull: Vec<(f64, f64)> = Vec::with_capacity(sorted.len() * 2);
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let floor = hull.len();
+        for p in pass {
+            while hull.len() >= floor + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+                hull.pop();
             }
+            hull.push(p);
         }
+        // Each chain's last point starts the other chain
+        hull.pop();
     }
+    hull
+}
+
+/// Minimum-area enclosing rectangle via rotating calipers over the hull's edges
+///
+/// The optimal rectangle has a side collinear with some hull edge, so each edge
+/// direction is tried and the hull is projected onto it and its normal.
+fn min_area_oriented_box(hull: &[(f64, f64)]) -> OrientedBox {
+    let mut best = OrientedBox { width: 0.0, height: 0.0, angle: 0.0 };
+    let mut best_area = f64::INFINITY;
     
-    (area, (min_x, min_y, max_x, max_y))
+    for i in 0..hull.len() {
+        let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+        let length = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+        if length == 0.0 {
+            continue;
+        }
+        let (ux, uy) = ((b.0 - a.0) / length, (b.1 - a.1) / length);
+        
+        let (mut min_u, mut max_u, mut min_v, mut max_v) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+        for p in hull {
+            let u = p.0 * ux + p.1 * uy;
+            let v = -p.0 * uy + p.1 * ux;
+            min_u = min_u.min(u);
+            max_u = max_u.max(u);
+            min_v = min_v.min(v);
+            max_v = max_v.max(v);
+        }
+        
+        let (along, across) = (max_u - min_u, max_v - min_v);
+        if along * across < best_area {
+            best_area = along * across;
+            let edge_angle = uy.atan2(ux).to_degrees();
+            let (width, height, angle) = if along >= across {
+                (along, across, edge_angle)
+            } else {
+                (across, along, edge_angle + 90.0)
+            };
+            best = OrientedBox { width, height, angle: angle.rem_euclid(180.0) };
+        }
+    }
+    best
 }
 
 fn normalize_bbox(bbox: (u32, u32, u32, u32), width: u32, height: u32) -> [f64; 4] {
@@ -130,6 +324,11 @@ fn main() -> Result<()> {
     } else {
         "test-data/test_blueprint_004.png".to_string()
     };
+    let morph_kernel_size = numeric_arg(&args, 2, "morph_kernel_size", DEFAULT_MORPH_KERNEL_SIZE)?;
+    let morph_iterations = numeric_arg(&args, 3, "morph_iterations", DEFAULT_MORPH_ITERATIONS)?;
+    if ![3, 5, 7].contains(&morph_kernel_size) {
+        bail!("morph_kernel_size must be 3, 5 or 7, got {}", morph_kernel_size);
+    }
     
     println!("Enhanced Connected Components Detection");
     println!("Loading image: {}", image_path);
@@ -138,7 +337,7 @@ fn main() -> Result<()> {
     let (width, height) = img.dimensions();
     
     // Threshold and clean
-    let binary = threshold_image(&img, 128);
+    let binary = threshold_image(&img, 128, morph_kernel_size, morph_iterations);
     
     // Find components
     let components = find_connected_components(&binary);
@@ -147,22 +346,17 @@ fn main() -> Result<()> {
     let min_area = 500;
     
     let mut rooms = Vec::new();
-    for (i, (area, bbox)) in components.iter().enumerate() {
-        if *area > min_area && *area < max_area {
-            let bbox_norm = normalize_bbox(*bbox, width, height);
-            let w = bbox.2 - bbox.0;
-            let h = bbox.3 - bbox.1;
-            let aspect = (w as f64 / h as f64).max(h as f64 / w as f64);
-            
-            if aspect < 15.0 {
-                rooms.push(Room {
-                    id: i,
-                    bounding_box: bbox_norm,
-                    area: *area as f64,
-                    name_hint: "Room".to_string(),
-                    points: Vec::new(), // Bounding box only
-                });
-            }
+    for (i, (area, bbox, oriented_box)) in components.iter().enumerate() {
+        // Aspect of the rotated box, so diagonal walls aren't mistaken for square rooms
+        if *area > min_area && *area < max_area && oriented_box.aspect() < MAX_ROOM_ASPECT {
+            rooms.push(Room {
+                id: i,
+                bounding_box: normalize_bbox(*bbox, width, height),
+                area: *area as f64,
+                name_hint: "Room".to_string(),
+                points: Vec::new(), // Bounding box only
+                oriented_box: *oriented_box,
+            });
         }
     }
     
@@ -181,4 +375,155 @@ fn main() -> Result<()> {
     println!("Output saved to detected_rooms_enhanced_cc.json");
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// White band along the main diagonal, `half_width` pixels either side of x == y
+    fn diagonal_band(size: u32, half_width: i64) -> GrayImage {
+        GrayImage::from_fn(size, size, |x, y| {
+            if (x as i64 - y as i64).abs() <= half_width {
+                Luma([255])
+            } else {
+                Luma([0])
+            }
+        })
+    }
+
+    fn oriented_box_of(img: &GrayImage) -> OrientedBox {
+        let (_, _, rows) = &label_components(img)[0];
+        min_area_oriented_box(&convex_hull(&row_extent_corners(rows)))
+    }
+
+    /// Reference 8-connected BFS flood fill the union-find labeling must agree with
+    fn flood_fill_components(img: &GrayImage) -> Vec<(usize, BBox, RowExtents)> {
+        let (width, height) = img.dimensions();
+        let mut visited = vec![false; width as usize * height as usize];
+        let mut components = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                if img.get_pixel(x, y)[0] != 255 || visited[(y * width + x) as usize] {
+                    continue;
+                }
+                let mut queue = VecDeque::from([(x, y)]);
+                visited[(y * width + x) as usize] = true;
+                let (mut area, mut bbox, mut rows) = (0, (x, y, x, y), RowExtents::new());
+
+                while let Some((x, y)) = queue.pop_front() {
+                    area += 1;
+                    bbox = (bbox.0.min(x), bbox.1.min(y), bbox.2.max(x), bbox.3.max(y));
+                    let extent = rows.entry(y).or_insert((x, x));
+                    extent.0 = extent.0.min(x);
+                    extent.1 = extent.1.max(x);
+                    for dy in -1..=1 {
+                        for dx in -1..=1 {
+                            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                            if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                                continue;
+                            }
+                            let (nx, ny) = (nx as u32, ny as u32);
+                            let nidx = (ny * width + nx) as usize;
+                            if !visited[nidx] && img.get_pixel(nx, ny)[0] == 255 {
+                                visited[nidx] = true;
+                                queue.push_back((nx, ny));
+                            }
+                        }
+                    }
+                }
+                components.push((area, bbox, rows));
+            }
+        }
+        components
+    }
+
+    /// Rooms of varied shapes: an L, a ring around an island, a U that wraps back,
+    /// diagonal neighbors and a one-pixel-wide line
+    fn multi_room_image() -> GrayImage {
+        let mut img = GrayImage::new(160, 120);
+        let mut fill = |x0: u32, y0: u32, x1: u32, y1: u32, value: u8| {
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    img.put_pixel(x, y, Luma([value]));
+                }
+            }
+        };
+        fill(5, 5, 40, 20, 255);
+        fill(5, 20, 20, 50, 255);
+        fill(50, 5, 100, 50, 255);
+        fill(60, 15, 90, 40, 0);
+        fill(70, 22, 80, 32, 255);
+        fill(110, 5, 150, 60, 255);
+        fill(120, 5, 140, 50, 0);
+        fill(5, 60, 30, 80, 255);
+        fill(30, 80, 60, 110, 255);
+        fill(100, 70, 101, 115, 255);
+        img
+    }
+
+    #[test]
+    fn test_union_find_labeling_matches_flood_fill() {
+        let images = [
+            multi_room_image(),
+            diagonal_band(200, 20),
+            diagonal_band(200, 0),
+            GrayImage::from_pixel(7, 3, Luma([255])),
+            GrayImage::new(5, 5),
+        ];
+
+        for img in &images {
+            assert_eq!(label_components(img), flood_fill_components(img));
+        }
+        // The diagonal neighbors merge under 8-connectivity
+        assert_eq!(label_components(&images[0]).len(), 6);
+    }
+
+    #[test]
+    fn test_morph_kernel_size_erases_thicker_strokes() {
+        // White plan split by a 4px dark wall
+        let img = GrayImage::from_fn(40, 20, |x, _| if (18..22).contains(&x) { Luma([0]) } else { Luma([255]) });
+
+        let small = threshold_image(&img, 128, 3, 1);
+        assert_eq!(small.get_pixel(20, 10)[0], 0);
+        assert_eq!(label_components(&small).len(), 2);
+
+        let large = threshold_image(&img, 128, 5, 1);
+        assert_eq!(large.get_pixel(20, 10)[0], 255);
+        assert_eq!(label_components(&large).len(), 1);
+
+        // A second 3x3 pass doesn't widen the kernel, so the wall stays
+        assert_eq!(label_components(&threshold_image(&img, 128, 3, 2)).len(), 2);
+    }
+
+    #[test]
+    fn test_oriented_box_of_rotated_rectangle() {
+        // 100x20 rectangle rotated 30 degrees
+        let (c, s) = (30f64.to_radians().cos(), 30f64.to_radians().sin());
+        let corners: Vec<(f64, f64)> = [(0.0, 0.0), (100.0, 0.0), (100.0, 20.0), (0.0, 20.0)]
+            .iter()
+            .map(|&(x, y)| (x * c - y * s, x * s + y * c))
+            .collect();
+
+        let obb = min_area_oriented_box(&convex_hull(&corners));
+
+        assert!((obb.width - 100.0).abs() < 1e-6);
+        assert!((obb.height - 20.0).abs() < 1e-6);
+        assert!((obb.angle - 30.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_diagonal_corridor_uses_oriented_aspect() {
+        // ~280px long, ~30px wide corridor at 45 degrees: a square axis-aligned box
+        let corridor = oriented_box_of(&diagonal_band(200, 20));
+        assert!((corridor.angle - 45.0).abs() < 1.0);
+        assert!(corridor.aspect() > 4.0 && corridor.aspect() < MAX_ROOM_ASPECT);
+
+        // Same length but only a few pixels thick: a wall, even though its
+        // axis-aligned box is just as square
+        let wall = oriented_box_of(&diagonal_band(200, 2));
+        assert!(wall.aspect() >= MAX_ROOM_ASPECT);
+    }
+}
@@ -1,8 +1,18 @@
+use arrow::array::{Array, RecordBatch, StringArray};
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
 use csv::ReaderBuilder;
-use image::{DynamicImage, ImageError};
+use futures::stream::{self, Stream, StreamExt};
+use image::{DynamicImage, ImageError, ImageReader};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 /// Represents a single floorplan with its metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +35,7 @@ pub enum LoaderError {
     IoError(std::io::Error),
     CsvError(csv::Error),
     ImageError(ImageError),
+    ParquetError(parquet::errors::ParquetError),
     DatasetNotFound(String),
     InvalidPath(String),
     EnvironmentError(String),
@@ -36,6 +47,7 @@ impl std::fmt::Display for LoaderError {
             LoaderError::IoError(e) => write!(f, "IO error: {}", e),
             LoaderError::CsvError(e) => write!(f, "CSV parsing error: {}", e),
             LoaderError::ImageError(e) => write!(f, "Image loading error: {}", e),
+            LoaderError::ParquetError(e) => write!(f, "Parquet parsing error: {}", e),
             LoaderError::DatasetNotFound(msg) => write!(f, "Dataset not found: {}", msg),
             LoaderError::InvalidPath(msg) => write!(f, "Invalid path: {}", msg),
             LoaderError::EnvironmentError(msg) => write!(f, "Environment error: {}", msg),
@@ -63,6 +75,18 @@ impl From<ImageError> for LoaderError {
     }
 }
 
+impl From<parquet::errors::ParquetError> for LoaderError {
+    fn from(err: parquet::errors::ParquetError) -> Self {
+        LoaderError::ParquetError(err)
+    }
+}
+
+impl From<ArrowError> for LoaderError {
+    fn from(err: ArrowError) -> Self {
+        LoaderError::ParquetError(err.into())
+    }
+}
+
 /// Finds the HuggingFace dataset snapshot directory
 pub fn find_dataset_path() -> Result<PathBuf, LoaderError> {
     let home = std::env::var("HOME").map_err(|e| {
@@ -136,6 +160,143 @@ pub fn parse_metadata(dataset_path: &Path) -> Result<Vec<FloorplanData>, LoaderE
     Ok(floorplans)
 }
 
+/// Parquet metadata file names, in order of preference
+const PARQUET_METADATA_FILES: [&str; 3] = ["metadata.parquet", "train.parquet", "data/train.parquet"];
+
+/// Find a Parquet metadata file in the dataset directory, if any
+pub fn find_parquet_metadata(dataset_path: &Path) -> Option<PathBuf> {
+    PARQUET_METADATA_FILES
+        .iter()
+        .map(|name| dataset_path.join(name))
+        .find(|path| path.exists())
+}
+
+/// Parse the `file_name`/`text` columns of a Parquet metadata file and return FloorplanData entries
+pub fn parse_metadata_parquet(dataset_path: &Path) -> Result<Vec<FloorplanData>, LoaderError> {
+    let metadata_path = find_parquet_metadata(dataset_path).ok_or_else(|| {
+        LoaderError::InvalidPath(format!(
+            "No Parquet metadata ({}) found in: {}",
+            PARQUET_METADATA_FILES.join(", "),
+            dataset_path.display()
+        ))
+    })?;
+
+    let file = fs::File::open(&metadata_path)?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+
+    let mut floorplans = Vec::new();
+
+    for batch in reader {
+        let batch = batch?;
+        let file_names = string_column(&batch, "file_name")?;
+        let texts = string_column(&batch, "text")?;
+
+        for (file_name, text) in file_names.iter().zip(texts.iter()) {
+            // Skip rows with missing values, like the CSV reader would reject them
+            let (Some(file_name), Some(text)) = (file_name, text) else {
+                continue;
+            };
+
+            floorplans.push(FloorplanData {
+                file_name: file_name.to_string(),
+                image_path: dataset_path.join(file_name),
+                description: text.to_string(),
+            });
+        }
+    }
+
+    Ok(floorplans)
+}
+
+/// Read a column as UTF-8 strings, casting from large/dictionary string types as needed
+fn string_column(batch: &RecordBatch, name: &str) -> Result<StringArray, LoaderError> {
+    let column = batch.column_by_name(name).ok_or_else(|| {
+        LoaderError::ParquetError(parquet::errors::ParquetError::General(format!(
+            "Missing column: {}",
+            name
+        )))
+    })?;
+
+    let column = arrow::compute::cast(column, &DataType::Utf8)?;
+    Ok(column
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("cast to Utf8 yields a StringArray")
+        .clone())
+}
+
+/// Load metadata, preferring Parquet when present and falling back to metadata.csv
+pub fn load_metadata(dataset_path: &Path) -> Result<Vec<FloorplanData>, LoaderError> {
+    if find_parquet_metadata(dataset_path).is_some() {
+        parse_metadata_parquet(dataset_path)
+    } else {
+        parse_metadata(dataset_path)
+    }
+}
+
+/// Parsed metadata cached next to the snapshot, written by `load_metadata_cached`
+const METADATA_CACHE_FILE: &str = "metadata.cache";
+
+/// On-disk cache of a metadata parse, valid while its source file is unchanged
+#[derive(Debug, Serialize, Deserialize)]
+struct MetadataCache {
+    dataset_path: PathBuf,
+    source: PathBuf,
+    source_modified: SystemTime,
+    source_len: u64,
+    floorplans: Vec<FloorplanData>,
+}
+
+/// The metadata file `load_metadata` would read
+fn metadata_source(dataset_path: &Path) -> PathBuf {
+    find_parquet_metadata(dataset_path).unwrap_or_else(|| dataset_path.join("metadata.csv"))
+}
+
+/// Like `load_metadata`, but reuse a cached parse when the metadata file's mtime and size match
+///
+/// Returns the floorplans and whether they came from the cache. A cache that can't be
+/// read or written is ignored, so a read-only snapshot still loads.
+pub fn load_metadata_cached(dataset_path: &Path) -> Result<(Vec<FloorplanData>, bool), LoaderError> {
+    let source = metadata_source(dataset_path);
+    let cache_path = dataset_path.join(METADATA_CACHE_FILE);
+    let source_key = fs::metadata(&source)
+        .ok()
+        .and_then(|metadata| Some((metadata.modified().ok()?, metadata.len())));
+
+    if let Some((modified, len)) = source_key {
+        let cached = fs::read(&cache_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<MetadataCache>(&bytes).ok());
+        if let Some(cache) = cached {
+            if cache.dataset_path == dataset_path
+                && cache.source == source
+                && cache.source_modified == modified
+                && cache.source_len == len
+            {
+                return Ok((cache.floorplans, true));
+            }
+        }
+    }
+
+    let floorplans = load_metadata(dataset_path)?;
+
+    if let Some((source_modified, source_len)) = source_key {
+        let cache = MetadataCache {
+            dataset_path: dataset_path.to_path_buf(),
+            source,
+            source_modified,
+            source_len,
+            floorplans,
+        };
+        if let Ok(bytes) = serde_json::to_vec(&cache) {
+            let _ = fs::write(&cache_path, bytes);
+        }
+        return Ok((cache.floorplans, false));
+    }
+
+    Ok((floorplans, false))
+}
+
 /// Load a floorplan image from disk
 pub fn load_floorplan_image(path: &Path) -> Result<DynamicImage, LoaderError> {
     Ok(image::open(path)?)
@@ -159,34 +320,101 @@ pub fn validate_images(floorplans: &[FloorplanData]) -> Result<Vec<String>, Load
     Ok(errors)
 }
 
+/// Parse the room count from a description like "A 3 room apartment" or "4-room house"
+pub fn parse_room_count(description: &str) -> Option<usize> {
+    let words: Vec<String> = description
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    words
+        .windows(2)
+        .find(|pair| pair[1].starts_with("room"))
+        .and_then(|pair| pair[0].parse().ok())
+}
+
+/// Result of a header-only image validation pass
+#[derive(Debug, Default)]
+pub struct ImageValidationReport {
+    /// (file_name, (width, height)) for every image whose header parsed
+    pub dimensions: Vec<(String, (u32, u32))>,
+    pub errors: Vec<String>,
+}
+
+/// Validate images by existence and header only, without decoding pixels
+///
+/// Much cheaper than `validate_images`, but won't catch corrupt pixel data.
+pub fn validate_images_fast(floorplans: &[FloorplanData]) -> Result<ImageValidationReport, LoaderError> {
+    let mut report = ImageValidationReport::default();
+
+    for floorplan in floorplans {
+        if !floorplan.image_path.exists() {
+            report.errors.push(format!("Missing image: {}", floorplan.file_name));
+            continue;
+        }
+
+        let dimensions = ImageReader::open(&floorplan.image_path)
+            .map_err(LoaderError::from)
+            .and_then(|reader| Ok(reader.with_guessed_format()?))
+            .and_then(|reader| Ok(reader.into_dimensions()?));
+
+        match dimensions {
+            Ok(dims) => report.dimensions.push((floorplan.file_name.clone(), dims)),
+            Err(e) => report
+                .errors
+                .push(format!("Failed to read header of {}: {:?}", floorplan.file_name, e)),
+        }
+    }
+
+    Ok(report)
+}
+
 /// Dataset iterator with batch loading and shuffling support
 pub struct FloorplanDataset {
     floorplans: Vec<FloorplanData>,
     current_index: usize,
+    loaded_from_cache: bool,
 }
 
 impl FloorplanDataset {
-    /// Create a new dataset from the HuggingFace cache
+    /// Create a new dataset from the HuggingFace cache, reusing a cached metadata parse when fresh
     pub fn new() -> Result<Self, LoaderError> {
-        let dataset_path = find_dataset_path()?;
-        let floorplans = parse_metadata(&dataset_path)?;
+        Self::from_path(&find_dataset_path()?)
+    }
+
+    /// Create a new dataset from the HuggingFace cache, always re-parsing the metadata
+    pub fn new_no_cache() -> Result<Self, LoaderError> {
+        Self::from_path_no_cache(&find_dataset_path()?)
+    }
+
+    /// Create a dataset from a custom path, reusing a cached metadata parse when fresh
+    pub fn from_path(path: &Path) -> Result<Self, LoaderError> {
+        let (floorplans, loaded_from_cache) = load_metadata_cached(path)?;
 
         Ok(Self {
             floorplans,
             current_index: 0,
+            loaded_from_cache,
         })
     }
 
-    /// Create a dataset from a custom path
-    pub fn from_path(path: &Path) -> Result<Self, LoaderError> {
-        let floorplans = parse_metadata(path)?;
+    /// Create a dataset from a custom path, always re-parsing the metadata
+    pub fn from_path_no_cache(path: &Path) -> Result<Self, LoaderError> {
+        let floorplans = load_metadata(path)?;
 
         Ok(Self {
             floorplans,
             current_index: 0,
+            loaded_from_cache: false,
         })
     }
 
+    /// Whether the metadata came from the on-disk cache rather than a fresh parse
+    pub fn loaded_from_cache(&self) -> bool {
+        self.loaded_from_cache
+    }
+
     /// Get the total number of floorplans
     pub fn len(&self) -> usize {
         self.floorplans.len()
@@ -197,21 +425,15 @@ impl FloorplanDataset {
         self.floorplans.is_empty()
     }
 
-    /// Shuffle the dataset order
+    /// Shuffle the dataset order with a random seed
     pub fn shuffle(&mut self) {
-        use std::collections::hash_map::RandomState;
-        use std::hash::{BuildHasher, Hash, Hasher};
-
-        // Simple Fisher-Yates shuffle using a basic RNG
-        let mut rng_state = RandomState::new().build_hasher().finish();
-
-        for i in (1..self.floorplans.len()).rev() {
-            // Simple linear congruential generator
-            rng_state = rng_state.wrapping_mul(6364136223846793005).wrapping_add(1);
-            let j = (rng_state as usize) % (i + 1);
-            self.floorplans.swap(i, j);
-        }
+        self.shuffle_seeded(rand::random());
+    }
 
+    /// Shuffle the dataset order reproducibly; equal seeds give equal orderings
+    pub fn shuffle_seeded(&mut self, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.floorplans.shuffle(&mut rng);
         self.current_index = 0;
     }
 
@@ -236,6 +458,40 @@ impl FloorplanDataset {
         (train, val, test)
     }
 
+    /// Split into train/val/test sets, preserving the room count distribution in each
+    ///
+    /// Plans are bucketed by `parse_room_count` (unparseable descriptions share a bucket),
+    /// each bucket is shuffled with `seed` and split by the given ratios.
+    pub fn split_stratified(
+        &self,
+        train_ratio: f64,
+        val_ratio: f64,
+        seed: u64,
+    ) -> (Vec<FloorplanData>, Vec<FloorplanData>, Vec<FloorplanData>) {
+        let mut buckets: BTreeMap<Option<usize>, Vec<FloorplanData>> = BTreeMap::new();
+        for floorplan in &self.floorplans {
+            buckets
+                .entry(parse_room_count(&floorplan.description))
+                .or_default()
+                .push(floorplan.clone());
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (mut train, mut val, mut test) = (Vec::new(), Vec::new(), Vec::new());
+        for (_, mut bucket) in buckets {
+            bucket.shuffle(&mut rng);
+            let total = bucket.len();
+            let train_size = ((total as f64 * train_ratio).round() as usize).min(total);
+            let val_size = ((total as f64 * val_ratio).round() as usize).min(total - train_size);
+
+            test.extend(bucket.split_off(train_size + val_size));
+            val.extend(bucket.split_off(train_size));
+            train.extend(bucket);
+        }
+
+        (train, val, test)
+    }
+
     /// Reset iterator to beginning
     pub fn reset(&mut self) {
         self.current_index = 0;
@@ -245,6 +501,42 @@ impl FloorplanDataset {
     pub fn all(&self) -> &[FloorplanData] {
         &self.floorplans
     }
+
+    /// Decode every floorplan's image, up to `concurrency` at a time on the blocking pool
+    ///
+    /// Items are yielded in dataset order regardless of which decode finishes first. A
+    /// missing or corrupt image yields an `Err` for that item and the stream carries on.
+    pub fn images_stream(
+        &self,
+        concurrency: usize,
+    ) -> impl Stream<Item = Result<(FloorplanData, DynamicImage), LoaderError>> {
+        stream::iter(self.floorplans.clone())
+            .map(|floorplan| async move {
+                tokio::task::spawn_blocking(move || {
+                    let image = load_floorplan_image(&floorplan.image_path)?;
+                    Ok::<_, LoaderError>((floorplan, image))
+                })
+                .await
+                .map_err(|e| LoaderError::IoError(std::io::Error::other(e)))?
+            })
+            .buffered(concurrency.max(1))
+    }
+
+    /// Floorplans matching `pred`, in dataset order
+    pub fn matching<F: Fn(&FloorplanData) -> bool>(&self, pred: F) -> Vec<FloorplanData> {
+        self.floorplans.iter().filter(|f| pred(f)).cloned().collect()
+    }
+
+    /// Floorplans whose description contains `keyword`, ignoring case
+    pub fn with_keyword(&self, keyword: &str) -> Vec<FloorplanData> {
+        let keyword = keyword.to_lowercase();
+        self.matching(|f| f.description.to_lowercase().contains(&keyword))
+    }
+
+    /// Floorplans whose description parses to exactly `count` rooms (see `parse_room_count`)
+    pub fn with_room_count(&self, count: usize) -> Vec<FloorplanData> {
+        self.matching(|f| parse_room_count(&f.description) == Some(count))
+    }
 }
 
 impl Iterator for FloorplanDataset {
@@ -291,6 +583,7 @@ mod tests {
         let dataset = FloorplanDataset {
             floorplans,
             current_index: 0,
+            loaded_from_cache: false,
         };
 
         let (train, val, test) = dataset.split(0.8, 0.1);
@@ -313,6 +606,7 @@ mod tests {
         let mut dataset = FloorplanDataset {
             floorplans,
             current_index: 0,
+            loaded_from_cache: false,
         };
 
         let batch1 = dataset.batch(3);
@@ -337,6 +631,7 @@ mod tests {
         let mut dataset = FloorplanDataset {
             floorplans,
             current_index: 0,
+            loaded_from_cache: false,
         };
 
         let collected: Vec<_> = dataset.take(3).collect();
@@ -344,4 +639,237 @@ mod tests {
         assert_eq!(collected[0].file_name, "0.jpg");
         assert_eq!(collected[2].file_name, "2.jpg");
     }
+
+    #[test]
+    fn test_shuffle_seeded_is_reproducible() {
+        let make_dataset = || FloorplanDataset {
+            floorplans: (0..20)
+                .map(|i| FloorplanData {
+                    file_name: format!("{}.jpg", i),
+                    image_path: PathBuf::from(format!("/path/{}.jpg", i)),
+                    description: format!("Room {}", i),
+                })
+                .collect(),
+            current_index: 0,
+            loaded_from_cache: false,
+        };
+        let names = |dataset: &FloorplanDataset| -> Vec<String> {
+            dataset.all().iter().map(|f| f.file_name.clone()).collect()
+        };
+
+        let mut first = make_dataset();
+        let mut second = make_dataset();
+        first.shuffle_seeded(42);
+        second.shuffle_seeded(42);
+
+        assert_eq!(names(&first), names(&second));
+        assert_ne!(names(&first), names(&make_dataset()));
+    }
+
+    #[test]
+    fn test_parse_room_count() {
+        assert_eq!(parse_room_count("A 3 room apartment."), Some(3));
+        assert_eq!(parse_room_count("Spacious 4-room house with balcony"), Some(4));
+        assert_eq!(parse_room_count("2 rooms, kitchen and bath"), Some(2));
+        assert_eq!(parse_room_count("Studio apartment"), None);
+    }
+
+    #[test]
+    fn test_split_stratified_keeps_room_distribution() {
+        // 30 one-room, 20 two-room, 10 three-room plans, ordered by complexity
+        let floorplans: Vec<FloorplanData> = [(1, 30), (2, 20), (3, 10)]
+            .iter()
+            .flat_map(|&(rooms, count)| (0..count).map(move |i| (rooms, i)))
+            .map(|(rooms, i)| FloorplanData {
+                file_name: format!("{}_{}.jpg", rooms, i),
+                image_path: PathBuf::from(format!("/path/{}_{}.jpg", rooms, i)),
+                description: format!("A {} room apartment", rooms),
+            })
+            .collect();
+        let dataset = FloorplanDataset {
+            floorplans,
+            current_index: 0,
+            loaded_from_cache: false,
+        };
+
+        let (train, val, test) = dataset.split_stratified(0.6, 0.2, 42);
+        let count = |split: &[FloorplanData], rooms: usize| {
+            split
+                .iter()
+                .filter(|f| parse_room_count(&f.description) == Some(rooms))
+                .count()
+        };
+
+        assert_eq!((count(&train, 1), count(&train, 2), count(&train, 3)), (18, 12, 6));
+        assert_eq!((count(&val, 1), count(&val, 2), count(&val, 3)), (6, 4, 2));
+        assert_eq!((count(&test, 1), count(&test, 2), count(&test, 3)), (6, 4, 2));
+    }
+
+    #[test]
+    fn test_parse_metadata_parquet() {
+        use arrow::datatypes::{Field, Schema};
+        use parquet::arrow::ArrowWriter;
+        use std::sync::Arc;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("file_name", DataType::Utf8, false),
+            Field::new("text", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["0.jpg", "1.jpg"])),
+                Arc::new(StringArray::from(vec!["A 3 room apartment", "A 2 room flat"])),
+            ],
+        )
+        .unwrap();
+
+        let dataset_path = std::env::temp_dir().join(format!("hf_parquet_test_{}", std::process::id()));
+        fs::create_dir_all(&dataset_path).unwrap();
+        let file = fs::File::create(dataset_path.join("metadata.parquet")).unwrap();
+        let mut writer = ArrowWriter::try_new(file, schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let floorplans = load_metadata(&dataset_path).unwrap();
+        fs::remove_dir_all(&dataset_path).unwrap();
+
+        assert_eq!(floorplans.len(), 2);
+        assert_eq!(floorplans[0].file_name, "0.jpg");
+        assert_eq!(floorplans[0].image_path, dataset_path.join("0.jpg"));
+        assert_eq!(floorplans[0].description, "A 3 room apartment");
+        assert_eq!(floorplans[1].file_name, "1.jpg");
+        assert_eq!(floorplans[1].description, "A 2 room flat");
+    }
+
+    #[tokio::test]
+    async fn test_images_stream_reports_corrupt_image_and_continues() {
+        let dir = std::env::temp_dir().join(format!("hf_stream_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        image::RgbImage::new(4, 3).save(dir.join("0.png")).unwrap();
+        fs::write(dir.join("1.png"), b"\x89PNG\r\n\x1a\nnot really a png").unwrap();
+        image::RgbImage::new(5, 2).save(dir.join("2.png")).unwrap();
+
+        let dataset = FloorplanDataset {
+            floorplans: (0..3)
+                .map(|i| FloorplanData {
+                    file_name: format!("{}.png", i),
+                    image_path: dir.join(format!("{}.png", i)),
+                    description: String::new(),
+                })
+                .collect(),
+            current_index: 0,
+            loaded_from_cache: false,
+        };
+
+        let results: Vec<_> = dataset.images_stream(2).collect().await;
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(results.len(), 3);
+        let (first, first_image) = results[0].as_ref().unwrap();
+        assert_eq!(first.file_name, "0.png");
+        assert_eq!((first_image.width(), first_image.height()), (4, 3));
+        assert!(matches!(results[1], Err(LoaderError::ImageError(_))));
+        let (last, last_image) = results[2].as_ref().unwrap();
+        assert_eq!(last.file_name, "2.png");
+        assert_eq!((last_image.width(), last_image.height()), (5, 2));
+    }
+
+    #[test]
+    fn test_keyword_and_room_count_filters() {
+        let descriptions = [
+            "A 2 room apartment with Balcony",
+            "Spacious 3-room house",
+            "2 rooms, kitchen and bath",
+            "Studio apartment",
+        ];
+        let dataset = FloorplanDataset {
+            floorplans: descriptions
+                .iter()
+                .enumerate()
+                .map(|(i, description)| FloorplanData {
+                    file_name: format!("{}.jpg", i),
+                    image_path: PathBuf::from(format!("/path/{}.jpg", i)),
+                    description: description.to_string(),
+                })
+                .collect(),
+            current_index: 0,
+            loaded_from_cache: false,
+        };
+        let names = |plans: Vec<FloorplanData>| -> Vec<String> { plans.into_iter().map(|f| f.file_name).collect() };
+
+        assert_eq!(names(dataset.with_keyword("APARTMENT")), vec!["0.jpg", "3.jpg"]);
+        assert_eq!(names(dataset.with_keyword("balcony")), vec!["0.jpg"]);
+        assert!(dataset.with_keyword("garage").is_empty());
+        assert_eq!(names(dataset.with_room_count(2)), vec!["0.jpg", "2.jpg"]);
+        assert_eq!(names(dataset.with_room_count(3)), vec!["1.jpg"]);
+        assert_eq!(names(dataset.matching(|f| f.file_name.starts_with('3'))), vec!["3.jpg"]);
+    }
+
+    #[test]
+    fn test_metadata_cache_reused_until_csv_changes() {
+        let dataset_path = std::env::temp_dir().join(format!("hf_cache_test_{}", std::process::id()));
+        fs::create_dir_all(&dataset_path).unwrap();
+        let csv_path = dataset_path.join("metadata.csv");
+        fs::write(&csv_path, "file_name,text\n0.jpg,A 3 room apartment\n").unwrap();
+
+        let first = FloorplanDataset::from_path(&dataset_path).unwrap();
+        let second = FloorplanDataset::from_path(&dataset_path).unwrap();
+        let bypassed = FloorplanDataset::from_path_no_cache(&dataset_path).unwrap();
+
+        assert!(!first.loaded_from_cache());
+        assert!(second.loaded_from_cache());
+        assert!(!bypassed.loaded_from_cache());
+        assert_eq!(second.all()[0].description, "A 3 room apartment");
+
+        // Same length, different contents and a newer mtime
+        fs::write(&csv_path, "file_name,text\n0.jpg,A 4 room apartment\n").unwrap();
+        let file = fs::File::options().write(true).open(&csv_path).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(10)).unwrap();
+        drop(file);
+
+        let changed = FloorplanDataset::from_path(&dataset_path).unwrap();
+        fs::remove_dir_all(&dataset_path).unwrap();
+
+        assert!(!changed.loaded_from_cache());
+        assert_eq!(changed.all()[0].description, "A 4 room apartment");
+    }
+
+    #[test]
+    fn test_validate_images_fast_rejects_truncated_header() {
+        let dir = std::env::temp_dir().join(format!("hf_validate_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let good_path = dir.join("good.png");
+        image::RgbImage::new(4, 3).save(&good_path).unwrap();
+        // PNG signature followed by nothing: exists, but the header can't be parsed
+        let truncated_path = dir.join("truncated.png");
+        fs::write(&truncated_path, b"\x89PNG\r\n\x1a\n").unwrap();
+
+        let floorplans = vec![
+            FloorplanData {
+                file_name: "good.png".to_string(),
+                image_path: good_path,
+                description: String::new(),
+            },
+            FloorplanData {
+                file_name: "truncated.png".to_string(),
+                image_path: truncated_path,
+                description: String::new(),
+            },
+            FloorplanData {
+                file_name: "missing.png".to_string(),
+                image_path: dir.join("missing.png"),
+                description: String::new(),
+            },
+        ];
+
+        let report = validate_images_fast(&floorplans).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.dimensions, vec![("good.png".to_string(), (4, 3))]);
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors[0].starts_with("Failed to read header of truncated.png"));
+        assert_eq!(report.errors[1], "Missing image: missing.png");
+    }
 }
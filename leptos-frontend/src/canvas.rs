@@ -1,9 +1,190 @@
 use crate::{Line, Point, Room};
+use room_matching::BoxMatching;
 use wasm_bindgen::JsCast;
-use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, MouseEvent};
+
+/// Outline color for the selected room
+pub const SELECTED_ROOM_COLOR: &str = "#E63946";
+
+/// Margin around the fit-all floorplan layout, in canvas pixels
+const FLOORPLAN_PADDING: f64 = 50.0;
+
+/// Smallest and largest zoom factors allowed by the view transform
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 20.0;
+
+/// Zoom and pan applied on top of the fit-all layout, in canvas pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewTransform {
+    pub scale: f64,
+    pub offset_x: f64,
+    pub offset_y: f64,
+}
+
+impl Default for ViewTransform {
+    /// Identity transform, i.e. the fit-all view
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            offset_x: 0.0,
+            offset_y: 0.0,
+        }
+    }
+}
+
+impl ViewTransform {
+    fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (x * self.scale + self.offset_x, y * self.scale + self.offset_y)
+    }
+
+    fn invert(&self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.offset_x) / self.scale, (y - self.offset_y) / self.scale)
+    }
+
+    /// Zoom by `factor`, keeping the canvas point (`anchor_x`, `anchor_y`) fixed on screen
+    ///
+    /// The resulting scale is clamped to `MIN_ZOOM..=MAX_ZOOM`.
+    pub fn zoom_at(&self, factor: f64, anchor_x: f64, anchor_y: f64) -> Self {
+        let scale = (self.scale * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let ratio = scale / self.scale;
+        Self {
+            scale,
+            offset_x: anchor_x - (anchor_x - self.offset_x) * ratio,
+            offset_y: anchor_y - (anchor_y - self.offset_y) * ratio,
+        }
+    }
+
+    /// Shift the view by a pointer movement in canvas pixels
+    pub fn pan(&self, dx: f64, dy: f64) -> Self {
+        Self {
+            offset_x: self.offset_x + dx,
+            offset_y: self.offset_y + dy,
+            ..*self
+        }
+    }
+}
+
+/// Pointer position in canvas pixels, accounting for CSS scaling of the element
+pub fn event_canvas_position(canvas: &HtmlCanvasElement, event: &MouseEvent) -> (f64, f64) {
+    let scale_x = canvas.width() as f64 / canvas.client_width().max(1) as f64;
+    let scale_y = canvas.height() as f64 / canvas.client_height().max(1) as f64;
+    (event.offset_x() as f64 * scale_x, event.offset_y() as f64 * scale_y)
+}
+
+/// Canvas pixel position in the normalized 0-1000 space drawn by `render_rooms`
+pub fn normalized_point_at(canvas: &HtmlCanvasElement, x: f64, y: f64) -> Point {
+    Point {
+        x: x / canvas.width().max(1) as f64 * 1000.0,
+        y: y / canvas.height().max(1) as f64 * 1000.0,
+    }
+}
+
+/// Canvas pixel position in floorplan coordinates, inverting the layout of `render_floorplan_with_view`
+pub fn floorplan_point_at(
+    canvas: &HtmlCanvasElement,
+    lines: &[Line],
+    rooms: &[Room],
+    view: &ViewTransform,
+    x: f64,
+    y: f64,
+) -> Option<Point> {
+    let height = canvas.height() as f64;
+    let (bounds, scale) = fit_layout(lines, rooms, canvas.width() as f64, height)?;
+    let (x, y) = view.invert(x, y);
+    Some(Point {
+        x: (x - FLOORPLAN_PADDING) / scale + bounds.min_x,
+        y: (height - y - FLOORPLAN_PADDING) / scale + bounds.min_y,
+    })
+}
+
+/// ID of the smallest room whose bounding box contains `point`, so nested rooms stay selectable
+pub fn room_at(rooms: &[Room], point: &Point) -> Option<usize> {
+    let bbox_area = |room: &Room| {
+        (room.bounding_box[2] - room.bounding_box[0]) * (room.bounding_box[3] - room.bounding_box[1])
+    };
+
+    rooms
+        .iter()
+        .filter(|room| {
+            let [min_x, min_y, max_x, max_y] = room.bounding_box;
+            (min_x..=max_x).contains(&point.x) && (min_y..=max_y).contains(&point.y)
+        })
+        .min_by(|a, b| bbox_area(a).total_cmp(&bbox_area(b)))
+        .map(|room| room.id)
+}
+
+/// Floorplan units covered by one canvas pixel at the current layout and zoom
+pub fn world_units_per_pixel(
+    canvas: &HtmlCanvasElement,
+    lines: &[Line],
+    rooms: &[Room],
+    view: &ViewTransform,
+) -> Option<f64> {
+    let (_, scale) = fit_layout(lines, rooms, canvas.width() as f64, canvas.height() as f64)?;
+    Some(1.0 / (scale * view.scale))
+}
+
+/// Nearest wall endpoint within `tolerance` of `point`, or `point` itself if none is close
+pub fn snap_to_node(lines: &[Line], point: Point, tolerance: f64) -> Point {
+    lines
+        .iter()
+        .flat_map(|line| [&line.start, &line.end])
+        .map(|node| (node, distance(node, &point)))
+        .filter(|(_, d)| *d <= tolerance)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(node, _)| node.clone())
+        .unwrap_or(point)
+}
+
+/// Index of the line closest to `point`, if it is within `tolerance`
+pub fn nearest_line(lines: &[Line], point: &Point, tolerance: f64) -> Option<usize> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| (i, distance_to_segment(point, &line.start, &line.end)))
+        .filter(|(_, d)| *d <= tolerance)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+fn distance_to_segment(point: &Point, start: &Point, end: &Point) -> f64 {
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let length_sq = dx * dx + dy * dy;
+    if length_sq == 0.0 {
+        return distance(point, start);
+    }
+
+    let t = (((point.x - start.x) * dx + (point.y - start.y) * dy) / length_sq).clamp(0.0, 1.0);
+    distance(point, &Point { x: start.x + t * dx, y: start.y + t * dy })
+}
+
+/// Extra drawing on top of the floorplan
+#[derive(Debug, Clone, Default)]
+pub struct FloorplanOverlay {
+    /// Room to outline
+    pub selected: Option<usize>,
+    /// In-progress wall segment, drawn dashed until it is committed
+    pub draft: Option<(Point, Point)>,
+}
 
 /// Render the floorplan with lines and detected rooms
 pub fn render_floorplan(canvas: &HtmlCanvasElement, lines: &[Line], rooms: &[Room]) {
+    render_floorplan_with_view(canvas, lines, rooms, &ViewTransform::default(), &FloorplanOverlay::default());
+}
+
+/// Render the floorplan with a zoom/pan view transform applied to all drawn coordinates,
+/// plus the selection outline and draft wall from `overlay`
+pub fn render_floorplan_with_view(
+    canvas: &HtmlCanvasElement,
+    lines: &[Line],
+    rooms: &[Room],
+    view: &ViewTransform,
+    overlay: &FloorplanOverlay,
+) {
     let context = canvas
         .get_context("2d")
         .unwrap()
@@ -21,12 +202,7 @@ pub fn render_floorplan(canvas: &HtmlCanvasElement, lines: &[Line], rooms: &[Roo
     context.set_fill_style(&"white".into());
     context.fill_rect(0.0, 0.0, width, height);
 
-    // Calculate bounds for scaling from lines or rooms
-    let bounds = if !lines.is_empty() {
-        calculate_bounds_from_lines(lines)
-    } else if !rooms.is_empty() {
-        calculate_bounds_from_rooms(rooms)
-    } else {
+    let Some((bounds, scale)) = fit_layout(lines, rooms, width, height) else {
         // Show placeholder text if no data
         context.set_fill_style(&"#999".into());
         context.set_font("20px sans-serif");
@@ -35,31 +211,33 @@ pub fn render_floorplan(canvas: &HtmlCanvasElement, lines: &[Line], rooms: &[Roo
             .unwrap();
         return;
     };
-
-    // Calculate scale to fit canvas with padding
-    let padding = 50.0;
-    let scale_x = (width - 2.0 * padding) / (bounds.max_x - bounds.min_x);
-    let scale_y = (height - 2.0 * padding) / (bounds.max_y - bounds.min_y);
-    let scale = scale_x.min(scale_y);
+    let padding = FLOORPLAN_PADDING;
 
     // Draw rooms first (as filled shapes)
     for room in rooms {
-        context.set_fill_style(&room_color(room.id).into());
-        context.set_global_alpha(0.3);
+        let is_selected = overlay.selected == Some(room.id);
+        context.set_fill_style(&color_for_room(room).into());
+        context.set_global_alpha(if is_selected { 0.6 } else { 0.3 });
+        context.set_stroke_style(&SELECTED_ROOM_COLOR.into());
+        context.set_line_width(4.0);
         context.begin_path();
 
         if !room.points.is_empty() {
             // Use polygon points if available
-            let first = transform_point(&room.points[0], &bounds, scale, width, height, padding);
+            let first = transform_point(&room.points[0], &bounds, scale, width, height, padding, view);
             context.move_to(first.0, first.1);
 
             for point in &room.points[1..] {
-                let (x, y) = transform_point(point, &bounds, scale, width, height, padding);
+                let (x, y) = transform_point(point, &bounds, scale, width, height, padding, view);
                 context.line_to(x, y);
             }
 
             context.close_path();
             context.fill();
+            if is_selected {
+                context.set_global_alpha(1.0);
+                context.stroke();
+            }
         } else if room.bounding_box.len() >= 4 {
             // Fall back to bounding box if no points available
             let min_x = room.bounding_box[0];
@@ -67,52 +245,196 @@ pub fn render_floorplan(canvas: &HtmlCanvasElement, lines: &[Line], rooms: &[Roo
             let max_x = room.bounding_box[2];
             let max_y = room.bounding_box[3];
 
-            let (x1, y1) = transform_point(&Point { x: min_x, y: min_y }, &bounds, scale, width, height, padding);
-            let (x2, y2) = transform_point(&Point { x: max_x, y: max_y }, &bounds, scale, width, height, padding);
+            let (x1, y1) = transform_point(&Point { x: min_x, y: min_y }, &bounds, scale, width, height, padding, view);
+            let (x2, y2) = transform_point(&Point { x: max_x, y: max_y }, &bounds, scale, width, height, padding, view);
 
             context.fill_rect(x1, y2, x2 - x1, y1 - y2);
+            if is_selected {
+                context.set_global_alpha(1.0);
+                context.stroke_rect(x1, y2, x2 - x1, y1 - y2);
+            }
         }
         context.set_global_alpha(1.0);
     }
 
-    // Draw lines (walls)
-    context.set_stroke_style(&"#333".into());
-    context.set_line_width(2.0);
-
+    // Draw lines (walls), load-bearing walls thicker and darker
     for line in lines {
+        if line.is_load_bearing {
+            context.set_stroke_style(&"#000".into());
+            context.set_line_width(4.0);
+        } else {
+            context.set_stroke_style(&"#333".into());
+            context.set_line_width(2.0);
+        }
         context.begin_path();
 
         let (start_x, start_y) =
-            transform_point(&line.start, &bounds, scale, width, height, padding);
-        let (end_x, end_y) = transform_point(&line.end, &bounds, scale, width, height, padding);
+            transform_point(&line.start, &bounds, scale, width, height, padding, view);
+        let (end_x, end_y) = transform_point(&line.end, &bounds, scale, width, height, padding, view);
+
+        context.move_to(start_x, start_y);
+        context.line_to(end_x, end_y);
+        context.stroke();
+    }
+
+    // Draft wall: dashed, with markers on the (possibly snapped) endpoints
+    if let Some((start, end)) = &overlay.draft {
+        let (start_x, start_y) = transform_point(start, &bounds, scale, width, height, padding, view);
+        let (end_x, end_y) = transform_point(end, &bounds, scale, width, height, padding, view);
 
+        context.set_stroke_style(&SELECTED_ROOM_COLOR.into());
+        context.set_line_width(2.0);
+        let _ = context.set_line_dash(&js_sys::Array::of2(&6.0.into(), &4.0.into()));
+        context.begin_path();
         context.move_to(start_x, start_y);
         context.line_to(end_x, end_y);
         context.stroke();
+        let _ = context.set_line_dash(&js_sys::Array::new());
+
+        context.set_fill_style(&SELECTED_ROOM_COLOR.into());
+        for (x, y) in [(start_x, start_y), (end_x, end_y)] {
+            context.begin_path();
+            let _ = context.arc(x, y, 4.0, 0.0, std::f64::consts::TAU);
+            context.fill();
+        }
     }
 
     // Draw room labels
+    for room in rooms {
+        if room.bounding_box.len() >= 4 {
+            let min = Point { x: room.bounding_box[0], y: room.bounding_box[1] };
+            let max = Point { x: room.bounding_box[2], y: room.bounding_box[3] };
+            let (x1, y1) = transform_point(&min, &bounds, scale, width, height, padding, view);
+            let (x2, y2) = transform_point(&max, &bounds, scale, width, height, padding, view);
+
+            // The y axis is flipped, so the max corner is the top edge on screen
+            draw_room_labels(&context, room, x1, y2, x2 - x1, y1 - y2);
+        }
+    }
+}
+
+/// Diff view fill for rooms both algorithms found
+pub const DIFF_MATCHED_COLOR: &str = "#AAB7B8";
+/// Diff view fill for rooms only the first algorithm found
+pub const DIFF_ONLY_A_COLOR: &str = "#FF6B6B";
+/// Diff view fill for rooms only the second algorithm found
+pub const DIFF_ONLY_B_COLOR: &str = "#45B7D1";
+
+/// Render two room sets from the normalized 0-1000 space on one canvas, coloring rooms by
+/// whether `matching` paired them (drawn once, from `rooms_a`) or only one side found them
+pub fn render_room_diff(canvas: &HtmlCanvasElement, rooms_a: &[Room], rooms_b: &[Room], matching: &BoxMatching) {
+    let context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()
+        .unwrap();
+
+    let width = canvas.width() as f64;
+    let height = canvas.height() as f64;
+    context.clear_rect(0.0, 0.0, width, height);
+    context.set_fill_style(&"white".into());
+    context.fill_rect(0.0, 0.0, width, height);
+
+    let layers = [
+        (matching.matched.iter().map(|&(a, _)| &rooms_a[a]).collect::<Vec<_>>(), DIFF_MATCHED_COLOR),
+        (matching.only_a.iter().map(|&a| &rooms_a[a]).collect(), DIFF_ONLY_A_COLOR),
+        (matching.only_b.iter().map(|&b| &rooms_b[b]).collect(), DIFF_ONLY_B_COLOR),
+    ];
+
+    for (rooms, color) in layers {
+        for room in rooms {
+            let x = room.bounding_box[0] / 1000.0 * width;
+            let y = room.bounding_box[1] / 1000.0 * height;
+            let w = (room.bounding_box[2] - room.bounding_box[0]) / 1000.0 * width;
+            let h = (room.bounding_box[3] - room.bounding_box[1]) / 1000.0 * height;
+
+            context.set_global_alpha(0.5);
+            context.set_fill_style(&color.into());
+            context.fill_rect(x, y, w, h);
+            context.set_global_alpha(1.0);
+            context.set_stroke_style(&color.into());
+            context.set_line_width(2.0);
+            context.stroke_rect(x, y, w, h);
+        }
+    }
+}
+
+/// Rooms smaller than this on screen get no text, so labels don't spill into neighbours
+const MIN_LABEL_WIDTH: f64 = 70.0;
+const MIN_LABEL_HEIGHT: f64 = 40.0;
+const BADGE_WIDTH: f64 = 36.0;
+const BADGE_HEIGHT: f64 = 16.0;
+
+/// Draw a room's name, area and confidence badge inside its on-screen rectangle
+pub fn draw_room_labels(
+    context: &CanvasRenderingContext2d,
+    room: &Room,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) {
+    if width < MIN_LABEL_WIDTH || height < MIN_LABEL_HEIGHT {
+        return;
+    }
+
     context.set_fill_style(&"#000".into());
     context.set_font("14px sans-serif");
+    let _ = context.fill_text(&format!("Room {}", room.id), x + 8.0, y + 20.0);
+    context.set_font("11px sans-serif");
+    let _ = context.fill_text(&format!("Area: {}", format_area(room.area)), x + 8.0, y + 35.0);
 
-    for room in rooms {
-        if room.bounding_box.len() >= 4 {
-            // Calculate center of bounding box
-            let center_x = (room.bounding_box[0] + room.bounding_box[2]) / 2.0;
-            let center_y = (room.bounding_box[1] + room.bounding_box[3]) / 2.0;
+    // Confidence badge in the top-right corner, only where it clears the name
+    if let Some(confidence) = room.confidence {
+        if width >= MIN_LABEL_WIDTH + BADGE_WIDTH {
+            let badge_x = x + width - BADGE_WIDTH - 4.0;
+            let badge_y = y + 4.0;
+            context.set_fill_style(&confidence_color(confidence).into());
+            context.fill_rect(badge_x, badge_y, BADGE_WIDTH, BADGE_HEIGHT);
 
-            let center_point = Point {
-                x: center_x,
-                y: center_y,
-            };
+            context.set_fill_style(&"#fff".into());
+            context.set_font("bold 10px sans-serif");
+            let label = format!("{:.0}%", confidence.clamp(0.0, 1.0) * 100.0);
+            let _ = context.fill_text(&label, badge_x + 5.0, badge_y + 12.0);
+        }
+    }
+}
 
-            let (label_x, label_y) =
-                transform_point(&center_point, &bounds, scale, width, height, padding);
+/// Badge color graded from red (0% confidence) through amber to green (100%)
+pub fn confidence_color(confidence: f64) -> String {
+    let hue = confidence.clamp(0.0, 1.0) * 120.0;
+    format!("hsl({:.0}, 70%, 40%)", hue)
+}
 
-            let label = format!("Room {}", room.id);
-            context.fill_text(&label, label_x - 20.0, label_y).unwrap();
+/// Area rounded to a whole number with thousands separators, e.g. "12,345"
+fn format_area(area: f64) -> String {
+    let digits = (area.max(0.0).round() as u64).to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            formatted.push(',');
         }
+        formatted.push(c);
     }
+    formatted
+}
+
+/// Bounds of the drawn data and the scale that fits it inside the padded canvas
+///
+/// Lines take precedence over rooms; `None` when there is nothing to draw.
+fn fit_layout(lines: &[Line], rooms: &[Room], width: f64, height: f64) -> Option<(Bounds, f64)> {
+    let bounds = if !lines.is_empty() {
+        calculate_bounds_from_lines(lines)
+    } else if !rooms.is_empty() {
+        calculate_bounds_from_rooms(rooms)
+    } else {
+        return None;
+    };
+
+    let scale_x = (width - 2.0 * FLOORPLAN_PADDING) / (bounds.max_x - bounds.min_x);
+    let scale_y = (height - 2.0 * FLOORPLAN_PADDING) / (bounds.max_y - bounds.min_y);
+    Some((bounds, scale_x.min(scale_y)))
 }
 
 /// Calculate the bounding box of all lines
@@ -185,18 +507,49 @@ fn transform_point(
     _width: f64,
     height: f64,
     padding: f64,
+    view: &ViewTransform,
 ) -> (f64, f64) {
     let x = (point.x - bounds.min_x) * scale + padding;
     let y = height - ((point.y - bounds.min_y) * scale + padding);
-    (x, y)
+    view.apply(x, y)
 }
 
-/// Generate a color for a room based on its ID
-fn room_color(id: usize) -> String {
-    let colors = [
-        "#FF6B6B", "#4ECDC4", "#45B7D1", "#FFA07A", "#98D8C8", "#F7DC6F", "#BB8FCE", "#85C1E2",
-        "#F8B88B", "#ABEBC6",
-    ];
+/// Fixed fill colors for classified room types
+const ROOM_TYPE_COLORS: &[(&str, &str)] = &[
+    ("kitchen", "#F4A261"),
+    ("bedroom", "#85C1E2"),
+    ("bathroom", "#4ECDC4"),
+    ("living_room", "#98D8C8"),
+    ("dining_room", "#F7DC6F"),
+    ("hallway", "#D5D8DC"),
+    ("corridor", "#D5D8DC"),
+    ("closet", "#BB8FCE"),
+    ("office", "#45B7D1"),
+    ("laundry", "#ABEBC6"),
+    ("garage", "#AAB7B8"),
+    ("entry", "#F8B88B"),
+];
+
+/// Fallback fill colors for unclassified rooms, cycled by room ID
+const ROOM_INDEX_COLORS: [&str; 10] = [
+    "#FF6B6B", "#4ECDC4", "#45B7D1", "#FFA07A", "#98D8C8", "#F7DC6F", "#BB8FCE", "#85C1E2",
+    "#F8B88B", "#ABEBC6",
+];
+
+/// Color for a room: fixed per `room_type` so the same type matches across canvases and
+/// algorithm runs, falling back to the room ID for unclassified rooms
+pub fn color_for_room(room: &Room) -> &'static str {
+    room.room_type
+        .as_deref()
+        .and_then(room_type_color)
+        .unwrap_or(ROOM_INDEX_COLORS[room.id % ROOM_INDEX_COLORS.len()])
+}
 
-    colors[id % colors.len()].to_string()
+/// Look up a room type, ignoring case and treating spaces and dashes as underscores
+fn room_type_color(room_type: &str) -> Option<&'static str> {
+    let key = room_type.trim().to_lowercase().replace([' ', '-'], "_");
+    ROOM_TYPE_COLORS
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, color)| *color)
 }
@@ -104,6 +104,14 @@ impl DetectionStrategy {
     }
 }
 
+/// What pointer input on the main canvas does
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EditMode {
+    Pan,    // Drag pans, click selects a room
+    Draw,   // Click or drag places a wall segment
+    Delete, // Click removes the nearest wall
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum InputType {
     Json,
@@ -118,6 +126,17 @@ enum SvgParser {
     Combined,     // Run both parsers and compare
 }
 
+impl SvgParser {
+    /// Value of the backend's `svg_parser` field
+    fn as_str(&self) -> &'static str {
+        match self {
+            SvgParser::Algorithmic => "algorithmic",
+            SvgParser::Gpt5Nano => "ai",
+            SvgParser::Combined => "combined",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum ImageVisionChoice {
     NoVision,      // y paths: Vectorize only, then parse SVG
@@ -200,196 +219,208 @@ fn FloorplanDetector() -> impl IntoView {
 
     let file_input_ref = NodeRef::<leptos::html::Input>::new();
     let canvas_ref = NodeRef::<leptos::html::Canvas>::new();
+    let view = RwSignal::new(ViewTransform::default());
+    let drag_origin = RwSignal::new(Option::<(f64, f64)>::None); // Last pointer position while panning
+    let drag_distance = RwSignal::new(0.0); // Pointer travel since press, to tell clicks from pans
+    let selected_room = RwSignal::new(Option::<usize>::None); // Shared by the canvas and the rooms list
+    let edit_mode = RwSignal::new(EditMode::Pan);
+    let draft_start = RwSignal::new(Option::<Point>::None); // Wall segment being drawn
+    let draft_end = RwSignal::new(Option::<Point>::None);
+
+    // Load an uploaded or dropped file (JSON, images, and SVG)
+    let load_file = move |file: web_sys::File| {
+        let file_type = file.type_();
+        let file_name = file.name();
+
+        // Auto-detect input type from file
+        let detected_type = if file_name.ends_with(".json") || file_type == "application/json" {
+            Some(InputType::Json)
+        } else if file_name.ends_with(".svg") || file_type == "image/svg+xml" {
+            Some(InputType::Svg)
+        } else if file_type.starts_with("image/") {
+            Some(InputType::Image)
+        } else {
+            error.set(Some("Unsupported file type. Please upload JSON, SVG, PNG, or JPG.".to_string()));
+            return;
+        };
 
-    // Handle file upload (JSON, images, and SVG)
-    let on_file_change = move |_| {
-        if let Some(input) = file_input_ref.get() {
-            if let Some(files) = input.files() {
-                if let Some(file) = files.get(0) {
-                    let file_type = file.type_();
-                    let file_name = file.name();
+        input_type.set(detected_type);
+        uploaded_filename.set(Some(file_name.clone()));
 
-                    // Auto-detect input type from file
-                    let detected_type = if file_name.ends_with(".json") || file_type == "application/json" {
-                        Some(InputType::Json)
-                    } else if file_name.ends_with(".svg") || file_type == "image/svg+xml" {
-                        Some(InputType::Svg)
-                    } else if file_type.starts_with("image/") {
-                        Some(InputType::Image)
-                    } else {
-                        error.set(Some("Unsupported file type. Please upload JSON, SVG, PNG, or JPG.".to_string()));
-                        return;
-                    };
+        // New input starts from the fit-all view with no wall in progress
+        view.set(ViewTransform::default());
+        draft_start.set(None);
+        draft_end.set(None);
 
-                    input_type.set(detected_type);
-                    uploaded_filename.set(Some(file_name.clone()));
+        match detected_type.unwrap() {
+            InputType::Json => {
+                // Handle JSON file
+                if file_name.ends_with(".json") || file_type == "application/json" {
+                    let reader = web_sys::FileReader::new().unwrap();
+                    let reader_clone = reader.clone();
 
-                    match detected_type.unwrap() {
-                        InputType::Json => {
-                            // Handle JSON file
-                            if file_name.ends_with(".json") || file_type == "application/json" {
-                                let reader = web_sys::FileReader::new().unwrap();
-                                let reader_clone = reader.clone();
-
-                                let onload = Closure::wrap(Box::new(move |_event: web_sys::Event| {
-                                    if let Ok(result) = reader_clone.result() {
-                                        if let Some(text) = result.as_string() {
-                                            match serde_json::from_str::<Vec<Line>>(&text) {
-                                                Ok(parsed_lines) => {
-                                                    lines.set(parsed_lines);
-                                                    error.set(None);
-                                                }
-                                                Err(e) => {
-                                                    error.set(Some(format!("Failed to parse JSON: {}", e)));
-                                                }
-                                            }
-                                        }
+                    let onload = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+                        if let Ok(result) = reader_clone.result() {
+                            if let Some(text) = result.as_string() {
+                                match serde_json::from_str::<Vec<Line>>(&text) {
+                                    Ok(parsed_lines) => {
+                                        lines.set(parsed_lines);
+                                        error.set(None);
                                     }
-                                }) as Box<dyn FnMut(_)>);
-
-                                reader.set_onload(Some(onload.as_ref().unchecked_ref()));
-                                onload.forget();
-                                let _ = reader.read_as_text(&file);
-                            } else {
-                                error.set(Some("Please upload a JSON file for JSON input type".to_string()));
+                                    Err(e) => {
+                                        error.set(Some(format!("Failed to parse JSON: {}", e)));
+                                    }
+                                }
                             }
                         }
-                        InputType::Image => {
-                            // Handle image file
-                            if file_type.starts_with("image/") {
-    // Handle image file - read as base64 and send to /vectorize-blueprint endpoint
-    loading.set(true);
-    error.set(None);
+                    }) as Box<dyn FnMut(_)>);
 
-    let reader = web_sys::FileReader::new().unwrap();
-    let reader_clone = reader.clone();
-
-    // Get image processing choices
-    let current_image_vision = image_vision.get();
-    let current_svg_parser = svg_parser.get();
-
-    let onload = Closure::wrap(Box::new(move |_event: web_sys::Event| {
-        if let Ok(result) = reader_clone.result() {
-            if let Some(data_url) = result.as_string() {
-                // Extract base64 data (remove "data:image/...;base64," prefix)
-                if let Some(base64_data) = data_url.split(',').nth(1) {
-                    let base64_owned = base64_data.to_string();
-
-                    spawn_local(async move {
-                        #[derive(Serialize)]
-                        struct VectorizeRequest {
-                            image: String,
-                            strategy: String,
-                        }
+                    reader.set_onload(Some(onload.as_ref().unchecked_ref()));
+                    onload.forget();
+                    let _ = reader.read_as_text(&file);
+                } else {
+                    error.set(Some("Please upload a JSON file for JSON input type".to_string()));
+                }
+            }
+            InputType::Image => {
+                // Handle image file
+                if file_type.starts_with("image/") {
+                    // Handle image file - read as base64 and send to /vectorize-blueprint endpoint
+                    loading.set(true);
+                    error.set(None);
 
-                        // Image: Map vision choice + parser choice to backend strategy (7 paths)
-                        let backend_strategy = match (current_image_vision, current_svg_parser) {
-                            // x) Vision only - no SVG parsing
-                            (ImageVisionChoice::VisionOnly, _) => "gpt5_only",
-
-                            // y1-y3) No vision + SVG parser choices
-                            (ImageVisionChoice::NoVision, SvgParser::Algorithmic) => "vtracer_only",
-                            (ImageVisionChoice::NoVision, SvgParser::Gpt5Nano) => "vtracer_ai_parser",
-                            (ImageVisionChoice::NoVision, SvgParser::Combined) => "vtracer_combined",
-
-                            // z1-z3) Vision + SVG parser choices
-                            (ImageVisionChoice::VisionWithSvg, SvgParser::Algorithmic) => "hybrid_vision",
-                            (ImageVisionChoice::VisionWithSvg, SvgParser::Gpt5Nano) => "hybrid_ai_parser",
-                            (ImageVisionChoice::VisionWithSvg, SvgParser::Combined) => "hybrid_combined",
-                        };
-
-                        let request = VectorizeRequest {
-                            image: base64_owned,
-                            strategy: backend_strategy.to_string(),
-                        };
-
-                        match vectorize_blueprint(request).await {
-                            Ok(response) => {
-                                // Extract rooms from response
-                                if let Some(rooms_array) = response.get("rooms") {
-                                    if let Ok(parsed_rooms) = serde_json::from_value::<Vec<Room>>(rooms_array.clone()) {
-                                        rooms.set(parsed_rooms);
-                                    }
-                                }
-                                // Extract walls for visualization (backend returns "walls" not "lines")
-                                if let Some(walls_array) = response.get("walls") {
-                                    // Convert walls to lines for display
-                                    if let Ok(parsed_walls) = serde_json::from_value::<Vec<serde_json::Value>>(walls_array.clone()) {
-                                        let wall_lines: Vec<Line> = parsed_walls
-                                            .iter()
-                                            .filter_map(|wall| {
-                                                let start = wall.get("start")?;
-                                                let end = wall.get("end")?;
-                                                Some(Line {
-                                                    start: Point {
-                                                        x: start.get("x")?.as_f64()?,
-                                                        y: start.get("y")?.as_f64()?,
-                                                    },
-                                                    end: Point {
-                                                        x: end.get("x")?.as_f64()?,
-                                                        y: end.get("y")?.as_f64()?,
-                                                    },
-                                                    is_load_bearing: false,
-                                                })
-                                            })
-                                            .collect();
-                                        lines.set(wall_lines);
-                                    }
+                    let reader = web_sys::FileReader::new().unwrap();
+                    let reader_clone = reader.clone();
+
+                    // Get image processing choices
+                    let current_image_vision = image_vision.get();
+                    let current_svg_parser = svg_parser.get();
+
+                    let onload = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+                        if let Ok(result) = reader_clone.result() {
+                            if let Some(data_url) = result.as_string() {
+                                // Extract base64 data (remove "data:image/...;base64," prefix)
+                                if let Some(base64_data) = data_url.split(',').nth(1) {
+                                    let base64_owned = base64_data.to_string();
+
+                                    spawn_local(async move {
+                                        #[derive(Serialize)]
+                                        struct VectorizeRequest {
+                                            image: String,
+                                            strategy: String,
+                                        }
+
+                                        // Image: Map vision choice + parser choice to backend strategy (7 paths)
+                                        let backend_strategy = match (current_image_vision, current_svg_parser) {
+                                            // x) Vision only - no SVG parsing
+                                            (ImageVisionChoice::VisionOnly, _) => "gpt5_only",
+
+                                            // y1-y3) No vision + SVG parser choices
+                                            (ImageVisionChoice::NoVision, SvgParser::Algorithmic) => "vtracer_only",
+                                            (ImageVisionChoice::NoVision, SvgParser::Gpt5Nano) => "vtracer_ai_parser",
+                                            (ImageVisionChoice::NoVision, SvgParser::Combined) => "vtracer_combined",
+
+                                            // z1-z3) Vision + SVG parser choices
+                                            (ImageVisionChoice::VisionWithSvg, SvgParser::Algorithmic) => "hybrid_vision",
+                                            (ImageVisionChoice::VisionWithSvg, SvgParser::Gpt5Nano) => "hybrid_ai_parser",
+                                            (ImageVisionChoice::VisionWithSvg, SvgParser::Combined) => "hybrid_combined",
+                                        };
+
+                                        let request = VectorizeRequest {
+                                            image: base64_owned,
+                                            strategy: backend_strategy.to_string(),
+                                        };
+
+                                        match vectorize_blueprint(request).await {
+                                            Ok(response) => {
+                                                // Extract rooms from response
+                                                if let Some(rooms_array) = response.get("rooms") {
+                                                    if let Ok(parsed_rooms) = serde_json::from_value::<Vec<Room>>(rooms_array.clone()) {
+                                                        rooms.set(parsed_rooms);
+                                                    }
+                                                }
+                                                // Extract walls for visualization (backend returns "walls" not "lines")
+                                                if let Some(walls_array) = response.get("walls") {
+                                                    // Convert walls to lines for display
+                                                    if let Ok(parsed_walls) = serde_json::from_value::<Vec<serde_json::Value>>(walls_array.clone()) {
+                                                        let wall_lines: Vec<Line> = parsed_walls
+                                                            .iter()
+                                                            .filter_map(|wall| {
+                                                                let start = wall.get("start")?;
+                                                                let end = wall.get("end")?;
+                                                                Some(Line {
+                                                                    start: Point {
+                                                                        x: start.get("x")?.as_f64()?,
+                                                                        y: start.get("y")?.as_f64()?,
+                                                                    },
+                                                                    end: Point {
+                                                                        x: end.get("x")?.as_f64()?,
+                                                                        y: end.get("y")?.as_f64()?,
+                                                                    },
+                                                                    is_load_bearing: false,
+                                                                })
+                                                            })
+                                                            .collect();
+                                                        lines.set(wall_lines);
+                                                    }
+                                                }
+                                                loading.set(false);
+                                                error.set(None);
+                                            }
+                                            Err(e) => {
+                                                error.set(Some(format!("Vectorization failed: {}", e)));
+                                                loading.set(false);
+                                            }
+                                        }
+                                    });
                                 }
-                                loading.set(false);
-                                error.set(None);
-                            }
-                            Err(e) => {
-                                error.set(Some(format!("Vectorization failed: {}", e)));
-                                loading.set(false);
                             }
                         }
-                    });
+                    }) as Box<dyn FnMut(_)>);
+
+                    reader.set_onload(Some(onload.as_ref().unchecked_ref()));
+                    onload.forget();
+                    let _ = reader.read_as_data_url(&file);
+                } else {
+                    error.set(Some("Please upload an image file for Image input type".to_string()));
                 }
             }
-        }
-    }) as Box<dyn FnMut(_)>);
+            InputType::Svg => {
+                // Handle SVG file
+                if file_name.ends_with(".svg") || file_type == "image/svg+xml" {
+                    let reader = web_sys::FileReader::new().unwrap();
+                    let reader_clone = reader.clone();
 
-    reader.set_onload(Some(onload.as_ref().unchecked_ref()));
-    onload.forget();
-    let _ = reader.read_as_data_url(&file);
-} else {
-                                error.set(Some("Please upload an image file for Image input type".to_string()));
+                    let onload = Closure::wrap(Box::new(move |_event: web_sys::Event| {
+                        if let Ok(result) = reader_clone.result() {
+                            if let Some(svg_text) = result.as_string() {
+                                // Store the SVG content for later processing
+                                svg_content.set(Some(svg_text));
+                                lines.set(Vec::new());
+                                rooms.set(Vec::new());
+                                error.set(None);
                             }
                         }
-                         InputType::Svg => {
-                             // Handle SVG file
-                             if file_name.ends_with(".svg") || file_type == "image/svg+xml" {
-                                 let reader = web_sys::FileReader::new().unwrap();
-                                 let reader_clone = reader.clone();
-
-                                 let onload = Closure::wrap(Box::new(move |_event: web_sys::Event| {
-                                     if let Ok(result) = reader_clone.result() {
-                                         if let Some(svg_text) = result.as_string() {
-                                             // Store the SVG content for later processing
-                                             svg_content.set(Some(svg_text));
-                                             lines.set(Vec::new());
-                                             rooms.set(Vec::new());
-                                             error.set(None);
-                                         }
-                                     }
-                                 }) as Box<dyn FnMut(_)>);
-
-                                 reader.set_onload(Some(onload.as_ref().unchecked_ref()));
-                                 onload.forget();
-                                 let _ = reader.read_as_text(&file);
-                             } else {
-                                 error.set(Some("Please upload an SVG file for SVG input type".to_string()));
-                             }
-                         }
-                    }
+                    }) as Box<dyn FnMut(_)>);
+
+                    reader.set_onload(Some(onload.as_ref().unchecked_ref()));
+                    onload.forget();
+                    let _ = reader.read_as_text(&file);
+                } else {
+                    error.set(Some("Please upload an SVG file for SVG input type".to_string()));
                 }
             }
         }
     };
 
+    let on_file_change = move |_| {
+        if let Some(file) = file_input_ref.get().and_then(|input| input.files()).and_then(|files| files.get(0)) {
+            load_file(file);
+        }
+    };
+
     // Handle detection
-    let on_detect = move |_| {
+    let on_detect = move || {
         let current_input_type = match input_type.get() {
             Some(t) => t,
             None => {
@@ -436,23 +467,14 @@ fn FloorplanDetector() -> impl IntoView {
         spawn_local(async move {
             match current_input_type {
                 InputType::Json => {
-                    let current_lines = lines.get();
-                    let current_coverage_threshold = coverage_threshold.get();
-                    let current_outer_boundary_ratio = outer_boundary_ratio.get();
-
                     // JSON: Always use GraphOnly (no vision)
-                    let backend_strategy = "GraphOnly".to_string();
-
-                    let request = DetectRequest {
-                        lines: current_lines,
-                        area_threshold: current_area_threshold,
-                        door_threshold: Some(current_door_threshold),
-                        coverage_threshold: Some(current_coverage_threshold),
-                        outer_boundary_ratio: Some(current_outer_boundary_ratio),
-                        strategy: backend_strategy.clone(),
-                        enable_vision: Some(false),
-                        enable_yolo: Some(false),
-                    };
+                    let request = graph_only_request(
+                        lines.get(),
+                        current_area_threshold,
+                        current_door_threshold,
+                        coverage_threshold.get(),
+                        outer_boundary_ratio.get(),
+                    );
 
                     match detect_rooms(request, DetectionStrategy::GraphOnly).await {
                         Ok(response) => {
@@ -471,13 +493,11 @@ fn FloorplanDetector() -> impl IntoView {
                     let current_svg = svg_content.get().unwrap();
                     let current_svg_parser = svg_parser.get();
 
-                    // SVG: Map parser choice to backend strategy
-                    // Note: Currently only SvgOnly (algorithmic) is fully implemented in backend
-                    // AI Parser and Combined options will be added in future updates
-                    let backend_strategy = DetectionStrategy::SvgOnly; // All options use algorithmic parsing for now
+                    // SVG: The parser choice is sent alongside the SvgOnly strategy
+                    let backend_strategy = DetectionStrategy::SvgOnly;
                     let enable_vision = false; // SVG vision support requires image rendering (not yet implemented)
 
-                    match detect_svg_rooms(current_svg, current_area_threshold, current_door_threshold, backend_strategy, enable_vision).await {
+                    match detect_svg_rooms(current_svg, current_area_threshold, current_door_threshold, backend_strategy, current_svg_parser, enable_vision).await {
                         Ok(response) => {
                             rooms.set(response.rooms);
                             method_used.set(response.method_used);
@@ -498,16 +518,261 @@ fn FloorplanDetector() -> impl IntoView {
         });
     };
 
-    // Render canvas whenever lines or rooms change
+    // Room IDs from a previous run don't refer to the new rooms
+    Effect::new(move |_| {
+        rooms.track();
+        selected_room.set(None);
+    });
+
+    // Render canvas whenever lines, rooms, the view, the selection or the draft wall change
     Effect::new(move |_| {
         let current_lines = lines.get();
         let current_rooms = rooms.get();
+        let current_view = view.get();
+        let overlay = FloorplanOverlay {
+            selected: selected_room.get(),
+            draft: draft_start.get().zip(draft_end.get()),
+        };
 
         if let Some(canvas) = canvas_ref.get_untracked() {
-            render_floorplan(&canvas, &current_lines, &current_rooms);
+            render_floorplan_with_view(&canvas, &current_lines, &current_rooms, &current_view, &overlay);
         }
     });
 
+    // Escape abandons the wall segment in progress
+    let escape_listener = window_event_listener(leptos::ev::keydown, move |ev| {
+        if ev.key() == "Escape" {
+            draft_start.set(None);
+            draft_end.set(None);
+        }
+    });
+    on_cleanup(move || escape_listener.remove());
+
+    // Reset to the empty state, as if nothing had been uploaded
+    let on_clear = move || {
+        lines.set(Vec::new());
+        rooms.set(Vec::new());
+        svg_content.set(None);
+        input_type.set(None);
+        uploaded_filename.set(None);
+        method_used.set(None);
+        execution_time.set(None);
+        error.set(None);
+        view.set(ViewTransform::default());
+        draft_start.set(None);
+        draft_end.set(None);
+        if let Some(input) = file_input_ref.get_untracked() {
+            input.set_value("");
+        }
+    };
+
+    let on_download = move || {
+        let current_rooms = rooms.get_untracked();
+        if current_rooms.is_empty() {
+            error.set(Some("No rooms to download yet".to_string()));
+        } else {
+            download_json(&current_rooms, "rooms.json");
+        }
+    };
+
+    // D detects, C clears, S downloads; keys typed into form fields or with modifiers are left alone
+    let shortcut_listener = window_event_listener(leptos::ev::keydown, move |ev| {
+        if ev.ctrl_key() || ev.meta_key() || ev.alt_key() || ev.repeat() {
+            return;
+        }
+        let in_form_field = ev
+            .target()
+            .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+            .is_some_and(|el| matches!(el.tag_name().as_str(), "INPUT" | "SELECT" | "TEXTAREA"));
+        if in_form_field {
+            return;
+        }
+        match ev.key().to_ascii_lowercase().as_str() {
+            "d" if !loading.get_untracked() => on_detect(),
+            "c" => on_clear(),
+            "s" => on_download(),
+            _ => return,
+        }
+        ev.prevent_default();
+    });
+    on_cleanup(move || shortcut_listener.remove());
+
+    // Dropping a file on the canvas loads it like the file input does
+    let drag_over = RwSignal::new(false);
+    let on_drop = move |ev: web_sys::DragEvent| {
+        ev.prevent_default();
+        drag_over.set(false);
+        let Some(files) = ev.data_transfer().and_then(|dt| dt.files()) else {
+            return;
+        };
+        match files.length() {
+            0 => {}
+            1 => {
+                if let Some(file) = files.get(0) {
+                    load_file(file);
+                }
+            }
+            n => error.set(Some(format!("Please drop a single file ({} were dropped)", n))),
+        }
+    };
+
+    // Pointer position in floorplan coordinates, with the edit tolerance converted to floorplan units
+    let pointer_on_plan = move |canvas: &web_sys::HtmlCanvasElement, ev: &web_sys::MouseEvent| -> Option<(Point, f64)> {
+        let (x, y) = event_canvas_position(canvas, ev);
+        let current_lines = lines.get_untracked();
+        let current_rooms = rooms.get_untracked();
+        let current_view = view.get_untracked();
+        let point = floorplan_point_at(canvas, &current_lines, &current_rooms, &current_view, x, y)?;
+        let units_per_pixel = world_units_per_pixel(canvas, &current_lines, &current_rooms, &current_view)?;
+        Some((point, EDIT_TOLERANCE_PX * units_per_pixel))
+    };
+
+    // Add the draft segment ending at `end` to the lines, unless it has zero length
+    let commit_draft = move |end: Point| {
+        if let Some(start) = draft_start.get_untracked() {
+            if start != end {
+                lines.update(|l| l.push(Line { start, end, is_load_bearing: false }));
+            }
+        }
+        draft_start.set(None);
+        draft_end.set(None);
+    };
+
+    // Wheel zooms around the cursor
+    let on_canvas_wheel = move |ev: web_sys::WheelEvent| {
+        ev.prevent_default();
+        if let Some(canvas) = canvas_ref.get_untracked() {
+            let (x, y) = event_canvas_position(&canvas, &ev);
+            let factor = if ev.delta_y() < 0.0 { 1.1 } else { 1.0 / 1.1 };
+            view.update(|v| *v = v.zoom_at(factor, x, y));
+        }
+    };
+
+    // Pointer capture keeps a drag alive outside the canvas. In draw mode a press starts a
+    // segment (or finishes one started by an earlier click), snapped to nearby wall endpoints.
+    let on_canvas_pointer_down = move |ev: web_sys::PointerEvent| {
+        let Some(canvas) = canvas_ref.get_untracked() else {
+            return;
+        };
+        let _ = canvas.set_pointer_capture(ev.pointer_id());
+        drag_origin.set(Some(event_canvas_position(&canvas, &ev)));
+        drag_distance.set(0.0);
+
+        if edit_mode.get_untracked() == EditMode::Draw {
+            if let Some((point, tolerance)) = pointer_on_plan(&canvas, &ev) {
+                let node = snap_to_node(&lines.get_untracked(), point, tolerance);
+                if draft_start.get_untracked().is_some() {
+                    commit_draft(node);
+                } else {
+                    draft_start.set(Some(node.clone()));
+                    draft_end.set(Some(node));
+                }
+            }
+        }
+    };
+
+    // Drag pans in pan mode; in draw mode the draft segment follows the pointer as a preview
+    let on_canvas_pointer_move = move |ev: web_sys::PointerEvent| {
+        let Some(canvas) = canvas_ref.get_untracked() else {
+            return;
+        };
+        let (x, y) = event_canvas_position(&canvas, &ev);
+        let pressed_at = drag_origin.get_untracked();
+        if let Some((last_x, last_y)) = pressed_at {
+            drag_distance.update(|d| *d += (x - last_x).abs() + (y - last_y).abs());
+            drag_origin.set(Some((x, y)));
+        }
+
+        match edit_mode.get_untracked() {
+            EditMode::Pan => {
+                if let Some((last_x, last_y)) = pressed_at {
+                    view.update(|v| *v = v.pan(x - last_x, y - last_y));
+                }
+            }
+            EditMode::Draw => {
+                if draft_start.get_untracked().is_some() {
+                    if let Some((point, tolerance)) = pointer_on_plan(&canvas, &ev) {
+                        draft_end.set(Some(snap_to_node(&lines.get_untracked(), point, tolerance)));
+                    }
+                }
+            }
+            EditMode::Delete => {}
+        }
+    };
+
+    // Releasing a drag in draw mode commits the segment; a plain click leaves it open for a second click
+    let on_canvas_pointer_up = move |_: web_sys::PointerEvent| {
+        drag_origin.set(None);
+        if edit_mode.get_untracked() == EditMode::Draw && drag_distance.get_untracked() > CLICK_MAX_TRAVEL {
+            if let Some(end) = draft_end.get_untracked() {
+                commit_draft(end);
+            }
+        }
+    };
+    let on_canvas_pointer_cancel = move |_: web_sys::PointerEvent| drag_origin.set(None);
+
+    // A click that wasn't a drag selects the room under the pointer, or deletes the nearest wall
+    let on_canvas_click = move |ev: web_sys::MouseEvent| {
+        if drag_distance.get_untracked() > CLICK_MAX_TRAVEL {
+            return;
+        }
+        let Some(canvas) = canvas_ref.get_untracked() else {
+            return;
+        };
+
+        match edit_mode.get_untracked() {
+            EditMode::Pan => {
+                let hit = pointer_on_plan(&canvas, &ev)
+                    .and_then(|(point, _)| room_at(&rooms.get_untracked(), &point));
+                selected_room.set(hit);
+                if let Some(id) = hit {
+                    scroll_into_view_by_id(&format!("room-card-{}", id));
+                }
+            }
+            EditMode::Delete => {
+                if let Some((point, tolerance)) = pointer_on_plan(&canvas, &ev) {
+                    if let Some(index) = nearest_line(&lines.get_untracked(), &point, tolerance) {
+                        lines.update(|l| {
+                            l.remove(index);
+                        });
+                    }
+                }
+            }
+            EditMode::Draw => {}
+        }
+    };
+
+    // Re-run graph detection on the edited lines
+    let on_redetect = move |_| {
+        let current_lines = lines.get();
+        if current_lines.is_empty() {
+            error.set(Some("Draw or upload some walls first".to_string()));
+            return;
+        }
+
+        loading.set(true);
+        error.set(None);
+        let request = graph_only_request(
+            current_lines,
+            area_threshold.get(),
+            door_threshold.get(),
+            coverage_threshold.get(),
+            outer_boundary_ratio.get(),
+        );
+
+        spawn_local(async move {
+            match detect_rooms(request, DetectionStrategy::GraphOnly).await {
+                Ok(response) => {
+                    rooms.set(response.rooms);
+                    method_used.set(response.method_used);
+                    execution_time.set(response.execution_time_ms);
+                }
+                Err(e) => error.set(Some(format!("Re-detection failed: {}", e))),
+            }
+            loading.set(false);
+        });
+    };
+
     view! {
         <div class="container">
 <header>
@@ -680,7 +945,7 @@ fn FloorplanDetector() -> impl IntoView {
                                         <div class="option-content">
                                             <strong>"b) AI Parser (GPT-5 Nano)"</strong>
                                             <p>"AI text model interprets SVG markup"</p>
-                                            <small>"🧠 AI understanding • ⏱️ Variable time • 🚧 Coming Soon"</small>
+                                            <small>"🧠 AI understanding • ⏱️ Variable time"</small>
                                         </div>
                                     </label>
                                     <label class="strategy-option">
@@ -693,7 +958,7 @@ fn FloorplanDetector() -> impl IntoView {
                                         <div class="option-content">
                                             <strong>"c) Combined Parser"</strong>
                                             <p>"Run both parsers and compare results"</p>
-                                            <small>"🔬 Validation • ⏱️ 2x time • 🚧 Coming Soon"</small>
+                                            <small>"🔬 Validation • ⏱️ 2x time • flags rooms only one parser found"</small>
                                         </div>
                                     </label>
                                 </div>
@@ -773,7 +1038,7 @@ fn FloorplanDetector() -> impl IntoView {
 
                  <button
                      class="detect-button"
-                     on:click=on_detect
+                     on:click=move |_| on_detect()
                      disabled=move || {
                          loading.get() || match input_type.get() {
                              None => true, // No file uploaded
@@ -849,12 +1114,74 @@ fn FloorplanDetector() -> impl IntoView {
                  }}
              </div>
 
-            <div class="canvas-container">
+            <div
+                class="canvas-container"
+                style=move || if drag_over.get() { "outline: 3px dashed #007bff; outline-offset: 4px;" } else { "" }
+                on:dragover=move |ev: web_sys::DragEvent| {
+                    ev.prevent_default();
+                    drag_over.set(true);
+                }
+                on:dragleave=move |_| drag_over.set(false)
+                on:drop=on_drop
+            >
+                <div class="edit-toolbar" style="display: flex; gap: 8px; align-items: center; margin-bottom: 8px;">
+                    {[
+                        (EditMode::Pan, "Pan / Select"),
+                        (EditMode::Draw, "Draw Walls"),
+                        (EditMode::Delete, "Delete Walls"),
+                    ]
+                        .into_iter()
+                        .map(|(mode, label)| view! {
+                            <button
+                                style=move || if edit_mode.get() == mode {
+                                    "padding: 6px 12px; background: #007bff; color: white; border: 1px solid #007bff; border-radius: 4px;"
+                                } else {
+                                    "padding: 6px 12px; background: white; color: #333; border: 1px solid #ccc; border-radius: 4px;"
+                                }
+                                on:click=move |_| {
+                                    edit_mode.set(mode);
+                                    draft_start.set(None);
+                                    draft_end.set(None);
+                                }
+                            >
+                                {label}
+                            </button>
+                        })
+                        .collect_view()}
+                    <button
+                        style="padding: 6px 12px;"
+                        on:click=on_redetect
+                        disabled=move || loading.get() || lines.get().is_empty()
+                    >
+                        "Re-detect"
+                    </button>
+                    <span style="font-size: 12px; color: #666;">
+                        {move || match edit_mode.get() {
+                            EditMode::Pan => "Scroll to zoom, drag to pan, click a room to select, double-click to fit; drop a file here to load it; D detects, C clears, S downloads JSON",
+                            EditMode::Draw => "Click two points or drag to add a wall; endpoints snap to nearby walls; Esc cancels",
+                            EditMode::Delete => "Click a wall to remove it",
+                        }}
+                    </span>
+                </div>
                 <canvas
                     node_ref=canvas_ref
                     width="800"
                     height="600"
-                    style="border: 1px solid #ccc;"
+                    style=move || format!(
+                        "border: 1px solid #ccc; touch-action: none; cursor: {};",
+                        match edit_mode.get() {
+                            EditMode::Pan => "grab",
+                            EditMode::Draw => "crosshair",
+                            EditMode::Delete => "pointer",
+                        }
+                    )
+                    on:wheel=on_canvas_wheel
+                    on:pointerdown=on_canvas_pointer_down
+                    on:pointermove=on_canvas_pointer_move
+                    on:pointerup=on_canvas_pointer_up
+                    on:pointercancel=on_canvas_pointer_cancel
+                    on:click=on_canvas_click
+                    on:dblclick=move |_| view.set(ViewTransform::default())
                 />
             </div>
 
@@ -863,29 +1190,43 @@ fn FloorplanDetector() -> impl IntoView {
                 <For
                     each=move || rooms.get()
                     key=|room| room.id
-                    let:room
-                >
-                    <div class="room-card">
-                        <h3>{format!("Room {}: {}", room.id,
-                            room.room_type.as_ref().unwrap_or(&room.name_hint))}</h3>
-                        <p>"Area: " {format!("{:.2}", room.area)}</p>
-                        <p>"Bounding Box: [{:.1}, {:.1}, {:.1}, {:.1}]"
-                            room.bounding_box[0]
-                            room.bounding_box[1]
-                            room.bounding_box[2]
-                            room.bounding_box[3]
-                        </p>
-                        {room.confidence.map(|conf| view! {
-                            <p>"Confidence: " {format!("{:.0}%", conf * 100.0)}</p>
-                        })}
-                        {(!room.features.is_empty()).then(|| view! {
-                            <p>"Features: " {room.features.join(", ")}</p>
-                        })}
-                        {room.detection_method.as_ref().map(|method| view! {
-                            <p style="font-size: 12px; color: #888;">"Detected by: " {method.clone()}</p>
-                        })}
-                    </div>
-                </For>
+                    children=move |room| {
+                        let room_id = room.id;
+                        view! {
+                            <div
+                                class="room-card"
+                                id=format!("room-card-{}", room_id)
+                                style=move || if selected_room.get() == Some(room_id) {
+                                    format!("cursor: pointer; outline: 3px solid {};", SELECTED_ROOM_COLOR)
+                                } else {
+                                    "cursor: pointer;".to_string()
+                                }
+                                on:click=move |_| selected_room.update(|s| {
+                                    *s = if *s == Some(room_id) { None } else { Some(room_id) };
+                                })
+                            >
+                                <h3>{format!("Room {}: {}", room.id,
+                                    room.room_type.as_ref().unwrap_or(&room.name_hint))}</h3>
+                                <p>"Area: " {format!("{:.2}", room.area)}</p>
+                                <p>"Bounding Box: [{:.1}, {:.1}, {:.1}, {:.1}]"
+                                    room.bounding_box[0]
+                                    room.bounding_box[1]
+                                    room.bounding_box[2]
+                                    room.bounding_box[3]
+                                </p>
+                                {room.confidence.map(|conf| view! {
+                                    <p>"Confidence: " {format!("{:.0}%", conf * 100.0)}</p>
+                                })}
+                                {(!room.features.is_empty()).then(|| view! {
+                                    <p>"Features: " {room.features.join(", ")}</p>
+                                })}
+                                {room.detection_method.as_ref().map(|method| view! {
+                                    <p style="font-size: 12px; color: #888;">"Detected by: " {method.clone()}</p>
+                                })}
+                            </div>
+                        }
+                    }
+                />
             </div>
         </div>
     }
@@ -913,6 +1254,26 @@ async fn vectorize_blueprint<T: Serialize>(request: T) -> Result<serde_json::Val
         .map_err(|e| format!("Failed to parse response: {}", e))
 }
 
+/// Cycle-detection request for wall lines, without vision or YOLO
+fn graph_only_request(
+    lines: Vec<Line>,
+    area_threshold: f64,
+    door_threshold: f64,
+    coverage_threshold: f64,
+    outer_boundary_ratio: f64,
+) -> DetectRequest {
+    DetectRequest {
+        lines,
+        area_threshold,
+        door_threshold: Some(door_threshold),
+        coverage_threshold: Some(coverage_threshold),
+        outer_boundary_ratio: Some(outer_boundary_ratio),
+        strategy: DetectionStrategy::GraphOnly.as_str().to_string(),
+        enable_vision: Some(false),
+        enable_yolo: Some(false),
+    }
+}
+
 async fn detect_rooms(request: DetectRequest, strategy: DetectionStrategy) -> Result<DetectResponse, String> {
     let client = reqwest::Client::new();
 
@@ -949,9 +1310,10 @@ struct SvgDetectRequest {
     door_threshold: f64,
     strategy: String,
     enable_vision: Option<bool>,
+    svg_parser: String,
 }
 
-async fn detect_svg_rooms(svg_content: String, area_threshold: f64, door_threshold: f64, strategy: DetectionStrategy, enable_vision: bool) -> Result<DetectResponse, String> {
+async fn detect_svg_rooms(svg_content: String, area_threshold: f64, door_threshold: f64, strategy: DetectionStrategy, svg_parser: SvgParser, enable_vision: bool) -> Result<DetectResponse, String> {
     let client = reqwest::Client::new();
 
     let request = SvgDetectRequest {
@@ -960,6 +1322,7 @@ async fn detect_svg_rooms(svg_content: String, area_threshold: f64, door_thresho
         door_threshold,
         strategy: strategy.as_str().to_string(),
         enable_vision: Some(enable_vision),
+        svg_parser: svg_parser.as_str().to_string(),
     };
 
     let response = client
@@ -1007,12 +1370,15 @@ fn DualAlgorithmDetector() -> impl IntoView {
     let file_input_ref = NodeRef::<leptos::html::Input>::new();
     let canvas1_ref = NodeRef::<leptos::html::Canvas>::new();
     let canvas2_ref = NodeRef::<leptos::html::Canvas>::new();
+    // Room IDs are per algorithm, so each canvas keeps its own selection
+    let selected1 = RwSignal::new(Option::<usize>::None);
+    let selected2 = RwSignal::new(Option::<usize>::None);
 
-    // Re-render canvases when results change
+    // Re-render canvases when results or selections change
     create_effect(move |_| {
         if let Some(result) = algo1_result.get() {
             if let Some(canvas) = canvas1_ref.get() {
-                render_rooms(&canvas, &result.rooms);
+                render_rooms(&canvas, &result.rooms, selected1.get());
             }
         }
     });
@@ -1020,7 +1386,24 @@ fn DualAlgorithmDetector() -> impl IntoView {
     create_effect(move |_| {
         if let Some(result) = algo2_result.get() {
             if let Some(canvas) = canvas2_ref.get() {
-                render_rooms(&canvas, &result.rooms);
+                render_rooms(&canvas, &result.rooms, selected2.get());
+            }
+        }
+    });
+
+    // Pair up rooms from both algorithms by bounding-box overlap
+    let diff_iou = RwSignal::new(0.5);
+    let canvas_diff_ref = NodeRef::<leptos::html::Canvas>::new();
+    let diff = Memo::new(move |_| {
+        let a = algo1_result.get()?;
+        let b = algo2_result.get()?;
+        Some(match_rooms(&a.rooms, &b.rooms, diff_iou.get()))
+    });
+
+    create_effect(move |_| {
+        if let (Some(a), Some(b), Some(matching)) = (algo1_result.get(), algo2_result.get(), diff.get()) {
+            if let Some(canvas) = canvas_diff_ref.get() {
+                render_room_diff(&canvas, &a.rooms, &b.rooms, &matching);
             }
         }
     });
@@ -1046,6 +1429,8 @@ fn DualAlgorithmDetector() -> impl IntoView {
                     algo1_result.set(None);
                     algo2_result.set(None);
                     gpt4o_result.set(None);
+                    selected1.set(None);
+                    selected2.set(None);
 
                     // Read as base64
                     let reader = web_sys::FileReader::new().unwrap();
@@ -1160,12 +1545,32 @@ fn DualAlgorithmDetector() -> impl IntoView {
                                 >
                                     "Download JSON"
                                 </button>
+                                <button
+                                    style="margin: 10px 0 10px 8px; padding: 8px 16px; background: #6c757d; color: white; border: none; border-radius: 4px; cursor: pointer;"
+                                    on:click=move |_| {
+                                        if let Some(res) = algo1_result.get() {
+                                            download_csv(&res.rooms, "algorithm1_flood_fill.csv");
+                                        }
+                                    }
+                                >
+                                    "Download CSV"
+                                </button>
                                 <canvas
                                     node_ref=canvas1_ref
                                     width="700"
                                     height="500"
-                                    style="border: 1px solid #ccc; width: 100%; max-width: 700px;"
+                                    style="border: 1px solid #ccc; width: 100%; max-width: 700px; cursor: pointer;"
+                                    on:click=move |ev: web_sys::MouseEvent| {
+                                        if let (Some(canvas), Some(res)) = (canvas1_ref.get_untracked(), algo1_result.get_untracked()) {
+                                            let hit = room_at_click(&canvas, &res.rooms, &ev);
+                                            selected1.set(hit);
+                                            if let Some(id) = hit {
+                                                scroll_into_view_by_id(&format!("algo1-room-{}", id));
+                                            }
+                                        }
+                                    }
                                 />
+                                <RoomChips rooms=result.rooms.clone() selected=selected1 id_prefix="algo1-room" />
                             </div>
                         }.into_any()
                     } else {
@@ -1203,12 +1608,32 @@ fn DualAlgorithmDetector() -> impl IntoView {
                                 >
                                     "Download JSON"
                                 </button>
+                                <button
+                                    style="margin: 10px 0 10px 8px; padding: 8px 16px; background: #6c757d; color: white; border: none; border-radius: 4px; cursor: pointer;"
+                                    on:click=move |_| {
+                                        if let Some(res) = algo2_result.get() {
+                                            download_csv(&res.rooms, "algorithm2_connected_components.csv");
+                                        }
+                                    }
+                                >
+                                    "Download CSV"
+                                </button>
                                 <canvas
                                     node_ref=canvas2_ref
                                     width="700"
                                     height="500"
-                                    style="border: 1px solid #ccc; width: 100%; max-width: 700px;"
+                                    style="border: 1px solid #ccc; width: 100%; max-width: 700px; cursor: pointer;"
+                                    on:click=move |ev: web_sys::MouseEvent| {
+                                        if let (Some(canvas), Some(res)) = (canvas2_ref.get_untracked(), algo2_result.get_untracked()) {
+                                            let hit = room_at_click(&canvas, &res.rooms, &ev);
+                                            selected2.set(hit);
+                                            if let Some(id) = hit {
+                                                scroll_into_view_by_id(&format!("algo2-room-{}", id));
+                                            }
+                                        }
+                                    }
                                 />
+                                <RoomChips rooms=result.rooms.clone() selected=selected2 id_prefix="algo2-room" />
                             </div>
                         }.into_any()
                     } else {
@@ -1221,6 +1646,39 @@ fn DualAlgorithmDetector() -> impl IntoView {
                 </div>
             </div>
 
+            {move || (algo1_result.with(Option::is_some) && algo2_result.with(Option::is_some)).then(|| view! {
+                <div style="margin-top: 30px; border: 2px solid #ddd; border-radius: 8px; padding: 20px;">
+                    <h2>"Differences"</h2>
+                    <label style="display: flex; align-items: center; gap: 10px; margin-bottom: 10px;">
+                        "Match IoU threshold:"
+                        <input
+                            type="range"
+                            min="0.1"
+                            max="0.9"
+                            step="0.05"
+                            prop:value=move || diff_iou.get().to_string()
+                            on:input=move |ev| {
+                                if let Ok(value) = event_target_value(&ev).parse::<f64>() {
+                                    diff_iou.set(value);
+                                }
+                            }
+                        />
+                        <span>{move || format!("{:.2}", diff_iou.get())}</span>
+                    </label>
+                    <div style="display: flex; gap: 20px; margin-bottom: 10px;">
+                        <DiffLegendItem color=DIFF_MATCHED_COLOR label="Both" count=Signal::derive(move || diff.with(|m| m.as_ref().map_or(0, |m| m.matched.len()))) />
+                        <DiffLegendItem color=DIFF_ONLY_A_COLOR label="Flood fill only" count=Signal::derive(move || diff.with(|m| m.as_ref().map_or(0, |m| m.only_a.len()))) />
+                        <DiffLegendItem color=DIFF_ONLY_B_COLOR label="Connected components only" count=Signal::derive(move || diff.with(|m| m.as_ref().map_or(0, |m| m.only_b.len()))) />
+                    </div>
+                    <canvas
+                        node_ref=canvas_diff_ref
+                        width="700"
+                        height="500"
+                        style="border: 1px solid #ccc; width: 100%; max-width: 700px;"
+                    />
+                </div>
+            })}
+
             {move || if use_gpt4o.get() {
                 view! {
                     <div style="margin-top: 30px; padding: 20px; background: #f8f9fa; border-radius: 8px;">
@@ -1303,7 +1761,7 @@ async fn run_dual_detection(
 
                         // Render
                         if let Some(canvas) = canvas1.get() {
-                            render_rooms(&canvas, &rooms);
+                            render_rooms(&canvas, &rooms, None);
                         }
                     }
                 }
@@ -1337,7 +1795,7 @@ async fn run_dual_detection(
 
                         // Render
                         if let Some(canvas) = canvas2.get() {
-                            render_rooms(&canvas, &rooms);
+                            render_rooms(&canvas, &rooms, None);
                         }
                     }
                 }
@@ -1476,13 +1934,55 @@ async fn run_gpt4o_validation(
 }
 
 fn download_json(rooms: &[Room], filename: &str) {
-    use wasm_bindgen::JsValue;
-
     let json_str = serde_json::to_string_pretty(rooms).unwrap_or_else(|_| "{}".to_string());
+    download_text(&json_str, filename);
+}
+
+fn download_csv(rooms: &[Room], filename: &str) {
+    download_text(&rooms_to_csv(rooms), filename);
+}
+
+/// One row per room; features are joined with semicolons
+fn rooms_to_csv(rooms: &[Room]) -> String {
+    let mut csv = String::from(
+        "id,name_hint,room_type,area,confidence,bbox_min_x,bbox_min_y,bbox_max_x,bbox_max_y,features\n",
+    );
+    for room in rooms {
+        let fields = [
+            room.id.to_string(),
+            room.name_hint.clone(),
+            room.room_type.clone().unwrap_or_default(),
+            room.area.to_string(),
+            room.confidence.map(|c| c.to_string()).unwrap_or_default(),
+            room.bounding_box[0].to_string(),
+            room.bounding_box[1].to_string(),
+            room.bounding_box[2].to_string(),
+            room.bounding_box[3].to_string(),
+            room.features.join(";"),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Save `contents` as a file through a temporary blob URL
+fn download_text(contents: &str, filename: &str) {
+    use wasm_bindgen::JsValue;
 
     // Create a blob
     let array = js_sys::Array::new();
-    array.push(&JsValue::from_str(&json_str));
+    array.push(&JsValue::from_str(contents));
 
     let blob = web_sys::Blob::new_with_str_sequence(&array).unwrap();
     let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap();
@@ -1500,7 +2000,29 @@ fn download_json(rooms: &[Room], filename: &str) {
     web_sys::Url::revoke_object_url(&url).unwrap();
 }
 
-fn render_rooms(canvas: &web_sys::HtmlCanvasElement, rooms: &[Room]) {
+/// Pointer travel in canvas pixels beyond which a press counts as a pan, not a click
+const CLICK_MAX_TRAVEL: f64 = 4.0;
+
+/// Distance in canvas pixels within which wall endpoints snap and walls can be picked for deletion
+const EDIT_TOLERANCE_PX: f64 = 10.0;
+
+/// Scroll the element with the given DOM id into view, if it exists
+fn scroll_into_view_by_id(id: &str) {
+    if let Some(element) = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+    {
+        element.scroll_into_view();
+    }
+}
+
+/// Room under a click on a `render_rooms` canvas, in its normalized 0-1000 space
+fn room_at_click(canvas: &web_sys::HtmlCanvasElement, rooms: &[Room], ev: &web_sys::MouseEvent) -> Option<usize> {
+    let (x, y) = event_canvas_position(canvas, ev);
+    room_at(rooms, &normalized_point_at(canvas, x, y))
+}
+
+fn render_rooms(canvas: &web_sys::HtmlCanvasElement, rooms: &[Room], selected: Option<usize>) {
     let ctx = canvas
         .get_context("2d")
         .unwrap()
@@ -1517,12 +2039,9 @@ fn render_rooms(canvas: &web_sys::HtmlCanvasElement, rooms: &[Room]) {
     ctx.fill_rect(0.0, 0.0, w, h);
 
     // Draw rooms
-    let colors = ["#FF6B6B", "#4ECDC4", "#45B7D1", "#FFA07A", "#98D8C8", "#F7DC6F", "#BB8FCE", "#85C1E2", "#F8B88B", "#AAB7B8"];
-
-    for (i, room) in rooms.iter().enumerate() {
+    for room in rooms {
         if room.bounding_box.len() >= 4 {
-            let color = colors[i % colors.len()];
-            ctx.set_fill_style(&color.into());
+            ctx.set_fill_style(&color_for_room(room).into());
             ctx.set_stroke_style(&"#333".into());
             ctx.set_line_width(2.0);
 
@@ -1537,13 +2056,69 @@ fn render_rooms(canvas: &web_sys::HtmlCanvasElement, rooms: &[Room]) {
             ctx.fill_rect(x1, y1, rw, rh);
             ctx.stroke_rect(x1, y1, rw, rh);
 
-            ctx.set_fill_style(&"#000".into());
-            ctx.set_font("14px Arial");
-            let _ = ctx.fill_text(&format!("Room {}", room.id), x1 + 10.0, y1 + 25.0);
+            if selected == Some(room.id) {
+                ctx.set_stroke_style(&SELECTED_ROOM_COLOR.into());
+                ctx.set_line_width(4.0);
+                ctx.stroke_rect(x1, y1, rw, rh);
+            }
+
+            draw_room_labels(&ctx, room, x1, y1, rw, rh);
         }
     }
 }
 
+/// Match rooms from two algorithms by bounding-box IoU
+fn match_rooms(a: &[Room], b: &[Room], threshold: f64) -> room_matching::BoxMatching {
+    let boxes_a: Vec<[f64; 4]> = a.iter().map(|r| r.bounding_box).collect();
+    let boxes_b: Vec<[f64; 4]> = b.iter().map(|r| r.bounding_box).collect();
+    room_matching::match_boxes(&boxes_a, &boxes_b, threshold)
+}
+
+#[component]
+fn DiffLegendItem(color: &'static str, label: &'static str, count: Signal<usize>) -> impl IntoView {
+    view! {
+        <div style="display: flex; align-items: center; gap: 6px;">
+            <span style=format!("display: inline-block; width: 14px; height: 14px; background: {}; border-radius: 2px;", color)></span>
+            <span>{label}": "<strong>{move || count.get()}</strong></span>
+        </div>
+    }
+}
+
+/// Clickable list of rooms under a comparison canvas, kept in sync with its selection
+#[component]
+fn RoomChips(rooms: Vec<Room>, selected: RwSignal<Option<usize>>, id_prefix: &'static str) -> impl IntoView {
+    view! {
+        <div style="display: flex; flex-wrap: wrap; gap: 6px; margin-top: 10px; max-height: 120px; overflow-y: auto;">
+            {rooms
+                .into_iter()
+                .map(|room| {
+                    let room_id = room.id;
+                    let color = color_for_room(&room);
+                    let label = match &room.room_type {
+                        Some(room_type) => format!("Room {}: {}", room_id, room_type),
+                        None => format!("Room {}", room_id),
+                    };
+                    view! {
+                        <span
+                            id=format!("{}-{}", id_prefix, room_id)
+                            style=move || format!(
+                                "padding: 4px 8px; border-radius: 12px; cursor: pointer; font-size: 12px; background: {}; border: 2px solid {};",
+                                color,
+                                if selected.get() == Some(room_id) { SELECTED_ROOM_COLOR } else { "transparent" },
+                            )
+                            on:click=move |_| selected.update(|s| {
+                                *s = if *s == Some(room_id) { None } else { Some(room_id) };
+                            })
+                        >
+                            {label}
+                        </span>
+                    }
+                })
+                .collect_view()}
+        </div>
+    }
+}
+
 #[component]
 fn AlgorithmTest() -> impl IntoView {
     let image_content = RwSignal::new(Option::<String>::None); // Store base64 image
@@ -2105,3 +2680,44 @@ pub fn main() {
     console_error_panic_hook::set_once();
     leptos::mount::mount_to_body(App);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(name_hint: &str, features: Vec<String>) -> Room {
+        Room {
+            id: 1,
+            bounding_box: [0.0, 0.0, 10.0, 20.0],
+            area: 200.0,
+            name_hint: name_hint.to_string(),
+            points: Vec::new(),
+            room_type: Some("bedroom".to_string()),
+            confidence: Some(0.5),
+            features,
+            detection_method: None,
+        }
+    }
+
+    #[test]
+    fn test_csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("Kitchen"), "Kitchen");
+        assert_eq!(csv_field("Bed, Bath"), "\"Bed, Bath\"");
+        assert_eq!(csv_field("The \"Den\""), "\"The \"\"Den\"\"\"");
+        assert_eq!(csv_field("Line\nbreak"), "\"Line\nbreak\"");
+        assert_eq!(csv_field("Carriage\rreturn"), "\"Carriage\rreturn\"");
+    }
+
+    #[test]
+    fn test_rooms_to_csv_escapes_awkward_names() {
+        let rooms = vec![room("Bed, \"Master\"\nSuite", vec!["window".to_string(), "closet, walk-in".to_string()])];
+        let csv = rooms_to_csv(&rooms);
+
+        let (header, row) = csv.split_once('\n').unwrap();
+        assert_eq!(header.split(',').count(), 10);
+        assert_eq!(
+            row,
+            "1,\"Bed, \"\"Master\"\"\nSuite\",bedroom,200,0.5,0,0,10,20,\"window;closet, walk-in\"\n"
+        );
+    }
+}
@@ -1,15 +1,16 @@
-use anyhow::{Context, Result};
-use image::GrayImage;
+use anyhow::Result;
 use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::path::Path;
 use std::process::Command;
-use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-#[derive(Debug, Serialize, Deserialize)]
+mod svg_paths;
+
+use svg_paths::parse_svg_paths;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -24,93 +25,19 @@ pub struct Room {
     pub points: Vec<Point>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct LineSegment {
     pub start: Point,
     pub end: Point,
 }
 
-fn parse_svg_paths(svg_content: &str) -> Result<Vec<LineSegment>, Box<dyn std::error::Error>> {
-    // Simple SVG path parser - approximate Bezier curves with line segments
-    let segments = Vec::new();
-    let root = svg::parser::parse(svg_content)?;
-    
-    // Parse path elements
-    for node in root.descendants() {
-        if let Some(path) = node.as_path() {
-            let path_data = path.data();
-            // Parse path d attribute (simplified - handles M, L, Z commands)
-            let d = path_data.iter().map(|d| d.to_string()).collect::<String>();
-            let tokens = d.split_whitespace().collect::<Vec<&str>>();
-            
-            let mut i = 0;
-            let mut current_pos = Point { x: 0.0, y: 0.0 };
-            let mut start_pos = Point { x: 0.0, y: 0.0 };
-            
-            while i < tokens.len() {
-                let token = tokens[i];
-                i += 1;
-                
-                match token {
-                    "M" | "m" => {
-                        if i + 1 < tokens.len() {
-                            current_pos.x = tokens[i].parse()?;
-                            current_pos.y = tokens[i+1].parse()?;
-                            start_pos = current_pos;
-                            i += 2;
-                        }
-                    }
-                    "L" | "l" => {
-                        while i + 1 < tokens.len() {
-                            let x = tokens[i].parse::<f64>()?;
-                            let y = tokens[i+1].parse::<f64>()?;
-                            segments.push(LineSegment {
-                                start: current_pos.clone(),
-                                end: Point { x: current_pos.x + x, y: current_pos.y + y },
-                            });
-                            current_pos = Point { x: current_pos.x + x, y: current_pos.y + y };
-                            i += 2;
-                        }
-                    }
-                    "Z" | "z" => {
-                        if current_pos != start_pos {
-                            segments.push(LineSegment {
-                                start: current_pos.clone(),
-                                end: start_pos.clone(),
-                            });
-                        }
-                        current_pos = start_pos;
-                    }
-                    _ => {
-                        // Skip other commands for now
-                        if let Ok(num) = token.parse::<f64>() {
-                            // Handle numeric tokens as coordinates
-                            if i < tokens.len() {
-                                let y = tokens[i].parse::<f64>()?;
-                                segments.push(LineSegment {
-                                    start: current_pos.clone(),
-                                    end: Point { x: num, y },
-                                });
-                                current_pos = Point { x: num, y };
-                                i += 1;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-    
-    Ok(segments)
-}
-
 fn find_intersections(segments: &[LineSegment]) -> Vec<Point> {
     let mut vertices = Vec::new();
     
     // Add all endpoints
     for seg in segments {
-        vertices.push(seg.start.clone());
-        vertices.push(seg.end.clone());
+        vertices.push(seg.start);
+        vertices.push(seg.end);
     }
     
     // Find intersections between segments
@@ -141,41 +68,28 @@ fn find_intersections(segments: &[LineSegment]) -> Vec<Point> {
 }
 
 fn line_intersection(seg1: &LineSegment, seg2: &LineSegment) -> Option<Point> {
-    let (x1, y1) = (seg1.start.x, seg1.start.y);
-    let (x2, y2) = (seg1.end.x, seg1.end.y);
-    let (x3, y3) = (seg2.start.x, seg2.start.y);
-    let (x4, y4) = (seg2.end.x, seg2.end.y);
-    
-    let denom = (y4 - y3) * (x2 - x1) - (x4 - x3) * (y2 - y1);
-    if denom.abs() < 1e-10 {
-        return None;
-    }
-    
-    let t = ((x1 - x3) * (y4 - y3) - (y1 - y3) * (x4 - x3)) / denom;
-    let u = -((y4 - y3) * (x1 - x3) - (x4 - x3) * (y1 - y3)) / denom;
-    
-    if t >= 0.0 && t <= 1.0 && u >= 0.0 && u <= 1.0 {
-        Some(Point {
-            x: x1 + t * (x2 - x1),
-            y: y1 + t * (y2 - y1),
-        })
-    } else {
-        None
-    }
+    let ends = |seg: &LineSegment| ((seg.start.x, seg.start.y), (seg.end.x, seg.end.y));
+    let (t, _) = segment_geometry::segment_intersection(ends(seg1), ends(seg2))?;
+    Some(Point {
+        x: seg1.start.x + t * (seg1.end.x - seg1.start.x),
+        y: seg1.start.y + t * (seg1.end.y - seg1.start.y),
+    })
 }
 
 fn build_graph(vertices: &[Point], segments: &[LineSegment]) -> HashMap<usize, Vec<usize>> {
-    let mut point_to_idx: HashMap<(f64, f64), usize> = HashMap::new();
+    // f64 isn't Hash; key on the exact bit patterns instead
+    let key = |p: &Point| (p.x.to_bits(), p.y.to_bits());
+    let mut point_to_idx: HashMap<(u64, u64), usize> = HashMap::new();
     for (i, v) in vertices.iter().enumerate() {
-        point_to_idx.insert((v.x, v.y), i);
+        point_to_idx.insert(key(v), i);
     }
     
     let mut graph: HashMap<usize, Vec<usize>> = HashMap::new();
     
     for seg in segments {
-        if let (Some(i1), Some(i2)) = (point_to_idx.get(&(seg.start.x, seg.start.y)), point_to_idx.get(&(seg.end.x, seg.end.y))) {
-            graph.entry(*i1).or_insert_with(Vec::new).push(*i2);
-            graph.entry(*i2).or_insert_with(Vec::new).push(*i1);
+        if let (Some(i1), Some(i2)) = (point_to_idx.get(&key(&seg.start)), point_to_idx.get(&key(&seg.end))) {
+            graph.entry(*i1).or_default().push(*i2);
+            graph.entry(*i2).or_default().push(*i1);
         }
     }
     
@@ -234,16 +148,10 @@ fn point_in_polygon(point: &Point, poly: &[Point]) -> bool {
         let p2x = poly[(i + 1) % n].x;
         let p2y = poly[(i + 1) % n].y;
         
-        if y > p1y.min(p2y) {
-            if y <= p1y.max(p2y) {
-                if x <= p1x.max(p2x) {
-                    if p1y != p2y {
-                        let xinters = (y - p1y) * (p2x - p1x) / (p2y - p1y) + p1x;
-                        if p1x == p2x || x <= xinters {
-                            inside = !inside;
-                        }
-                    }
-                }
+        if y > p1y.min(p2y) && y <= p1y.max(p2y) && x <= p1x.max(p2x) && p1y != p2y {
+            let xinters = (y - p1y) * (p2x - p1x) / (p2y - p1y) + p1x;
+            if p1x == p2x || x <= xinters {
+                inside = !inside;
             }
         }
         p1x = p2x;
@@ -253,19 +161,17 @@ fn point_in_polygon(point: &Point, poly: &[Point]) -> bool {
     inside
 }
 
-fn is_minimal_cycle(cycle: &[usize], vertices: &[Point], graph: &HashMap<usize, Vec<usize>>) -> bool {
+fn is_minimal_cycle(cycle: &[usize], vertices: &[Point], _graph: &HashMap<usize, Vec<usize>>) -> bool {
     if cycle.len() < 3 {
         return false;
     }
     
-    let poly_points: Vec<Point> = cycle.iter().map(|&i| vertices[i].clone()).collect();
+    let poly_points: Vec<Point> = cycle.iter().map(|&i| vertices[i]).collect();
     
     // Check if any other vertices are inside this polygon
     for (i, v) in vertices.iter().enumerate() {
-        if !cycle.contains(&i) {
-            if point_in_polygon(v, &poly_points) {
-                return false;
-            }
+        if !cycle.contains(&i) && point_in_polygon(v, &poly_points) {
+            return false;
         }
     }
     
@@ -357,9 +263,9 @@ fn main() -> Result<()> {
     
     // Step 7: Filter minimal cycles (rooms)
     let mut minimal_cycles = Vec::new();
-    for cycle in cycles {
-        if is_minimal_cycle(&cycle, &vertices, &graph) {
-            minimal_cycles.push(cycle);
+    for cycle in &cycles {
+        if is_minimal_cycle(cycle, &vertices, &graph) {
+            minimal_cycles.push(cycle.clone());
         }
     }
     println!("Found {} minimal cycles (potential rooms)", minimal_cycles.len());
@@ -367,7 +273,7 @@ fn main() -> Result<()> {
     // Step 8: Generate rooms
     let mut rooms = Vec::new();
     for (i, cycle) in minimal_cycles.iter().enumerate() {
-        let points: Vec<Point> = cycle.iter().map(|&idx| vertices[idx].clone()).collect();
+        let points: Vec<Point> = cycle.iter().map(|&idx| vertices[idx]).collect();
         let bbox = compute_bounding_box(&points);
         let area = compute_area(&points);
         
@@ -399,4 +305,21 @@ fn main() -> Result<()> {
     println!("Output saved to detected_rooms_python_graph.json");
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossing_walls_add_a_vertex() {
+        let segment = |x1, y1, x2, y2| LineSegment {
+            start: Point { x: x1, y: y1 },
+            end: Point { x: x2, y: y2 },
+        };
+        let vertices = find_intersections(&[segment(0.0, 0.0, 10.0, 0.0), segment(5.0, -5.0, 5.0, 5.0)]);
+
+        assert_eq!(vertices.len(), 5);
+        assert!(vertices.contains(&Point { x: 5.0, y: 0.0 }));
+    }
+}
@@ -0,0 +1,908 @@
+use crate::{LineSegment, Point};
+use anyhow::Result;
+use svg::node::element::tag::Type;
+use svg::node::Attributes;
+use svg::parser::Event;
+
+/// Size of the normalized coordinate space used by the rest of the pipeline
+const NORMALIZED_SIZE: f64 = 1000.0;
+
+/// Default maximum deviation (in SVG user units) when flattening curves and arcs
+pub const DEFAULT_FLATNESS_TOLERANCE: f64 = 0.5;
+
+/// 2D affine transform, laid out like SVG `matrix(a b c d e f)`:
+/// x' = a*x + c*y + e, y' = b*x + d*y + f
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self::matrix(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    pub fn matrix(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Self {
+        Self::matrix(1.0, 0.0, 0.0, 1.0, tx, ty)
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Self::matrix(sx, 0.0, 0.0, sy, 0.0, 0.0)
+    }
+
+    pub fn rotate(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self::matrix(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    /// Compose so that `other` is applied first, then `self`
+    /// (matches how a child transform nests inside its parent's)
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f,
+        }
+    }
+
+    pub fn apply(&self, p: Point) -> Point {
+        Point {
+            x: self.a * p.x + self.c * p.y + self.e,
+            y: self.b * p.x + self.d * p.y + self.f,
+        }
+    }
+}
+
+/// Parse an SVG `transform` attribute (translate, scale, rotate, matrix; lists compose left to right)
+pub fn parse_transform(attr: &str) -> Transform {
+    let mut result = Transform::identity();
+    let mut rest = attr;
+
+    while let Some(open) = rest.find('(') {
+        let name = rest[..open].trim().trim_start_matches(',').trim();
+        let Some(close) = rest[open..].find(')') else {
+            break;
+        };
+        let args: Vec<f64> = rest[open + 1..open + close]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| s.parse().ok())
+            .collect();
+
+        let transform = match (name, args.as_slice()) {
+            ("translate", [tx]) => Transform::translate(*tx, 0.0),
+            ("translate", [tx, ty]) => Transform::translate(*tx, *ty),
+            ("scale", [s]) => Transform::scale(*s, *s),
+            ("scale", [sx, sy]) => Transform::scale(*sx, *sy),
+            ("rotate", [deg]) => Transform::rotate(*deg),
+            ("rotate", [deg, cx, cy]) => Transform::translate(*cx, *cy)
+                .then(&Transform::rotate(*deg))
+                .then(&Transform::translate(-cx, -cy)),
+            ("matrix", [a, b, c, d, e, f]) => Transform::matrix(*a, *b, *c, *d, *e, *f),
+            _ => Transform::identity(),
+        };
+
+        result = result.then(&transform);
+        rest = &rest[open + close + 1..];
+    }
+
+    result
+}
+
+/// Parse a `viewBox` attribute into `[min_x, min_y, width, height]`
+pub fn parse_view_box(attr: &str) -> Option<[f64; 4]> {
+    let values: Vec<f64> = attr
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| s.parse().ok())
+        .collect();
+
+    match values.as_slice() {
+        [x, y, w, h] if *w > 0.0 && *h > 0.0 => Some([*x, *y, *w, *h]),
+        _ => None,
+    }
+}
+
+/// Transform mapping the given extent into the 0-1000 space (uniform scale keeps aspect ratio)
+fn normalization(extent: [f64; 4]) -> Transform {
+    let [min_x, min_y, width, height] = extent;
+    let scale = NORMALIZED_SIZE / width.max(height);
+    Transform::scale(scale, scale).then(&Transform::translate(-min_x, -min_y))
+}
+
+/// Elements that draw geometry; unlike containers they never open a transform scope
+const SHAPE_ELEMENTS: [&str; 5] = ["path", "rect", "line", "polyline", "polygon"];
+
+/// Parse all `<path>`, `<rect>`, `<line>`, `<polyline>` and `<polygon>` elements into line
+/// segments in normalized 0-1000 space
+///
+/// Element `transform` attributes are applied, composing through nested `<g>` groups.
+/// The root `viewBox` (or `width`/`height`, or failing that the segment extent) defines
+/// the area that is scaled into 0-1000.
+pub fn parse_svg_paths(svg_content: &str) -> Result<Vec<LineSegment>> {
+    parse_svg_paths_with_tolerance(svg_content, DEFAULT_FLATNESS_TOLERANCE)
+}
+
+/// Same as [`parse_svg_paths`], flattening curves to within `tolerance` user units
+pub fn parse_svg_paths_with_tolerance(svg_content: &str, tolerance: f64) -> Result<Vec<LineSegment>> {
+    let mut segments = Vec::new();
+    let mut extent: Option<[f64; 4]> = None;
+    // Transform stack for open container elements
+    let mut stack: Vec<Transform> = vec![Transform::identity()];
+
+    for event in svg::read(svg_content)? {
+        let Event::Tag(name, tag_type, attributes) = event else {
+            continue;
+        };
+
+        let is_shape = SHAPE_ELEMENTS.contains(&name);
+        if tag_type == Type::End {
+            if !is_shape && stack.len() > 1 {
+                stack.pop();
+            }
+            continue;
+        }
+
+        let parent = *stack.last().unwrap_or(&Transform::identity());
+        let local = attributes
+            .get("transform")
+            .map(|t| parse_transform(t))
+            .unwrap_or_else(Transform::identity);
+        let current = parent.then(&local);
+
+        if name == "svg" && extent.is_none() {
+            extent = attributes.get("viewBox").and_then(|v| parse_view_box(v)).or_else(|| {
+                let width = attributes.get("width")?.trim_end_matches("px").parse().ok()?;
+                let height = attributes.get("height")?.trim_end_matches("px").parse().ok()?;
+                Some([0.0, 0.0, width, height])
+            });
+        }
+
+        if is_shape {
+            for seg in shape_segments(name, &attributes, tolerance)? {
+                segments.push(LineSegment {
+                    start: current.apply(seg.start),
+                    end: current.apply(seg.end),
+                });
+            }
+        } else if tag_type == Type::Start {
+            stack.push(current);
+        }
+    }
+
+    // Fall back to the extent of the geometry itself
+    let extent = extent.or_else(|| segment_extent(&segments));
+    if let Some(extent) = extent {
+        let to_normalized = normalization(extent);
+        for seg in &mut segments {
+            seg.start = to_normalized.apply(seg.start);
+            seg.end = to_normalized.apply(seg.end);
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Untransformed outline of one shape element; shapes missing required attributes draw nothing
+fn shape_segments(name: &str, attributes: &Attributes, tolerance: f64) -> Result<Vec<LineSegment>> {
+    let number = |key: &str| attributes.get(key).and_then(|v| v.trim().trim_end_matches("px").parse::<f64>().ok());
+
+    let segments = match name {
+        "path" => match attributes.get("d") {
+            Some(d) => parse_path_data(d, tolerance)?,
+            None => Vec::new(),
+        },
+        "rect" => match (number("width"), number("height")) {
+            (Some(width), Some(height)) if width > 0.0 && height > 0.0 => {
+                let (x, y) = (number("x").unwrap_or(0.0), number("y").unwrap_or(0.0));
+                let corners = [
+                    Point { x, y },
+                    Point { x: x + width, y },
+                    Point { x: x + width, y: y + height },
+                    Point { x, y: y + height },
+                ];
+                polyline_segments(&corners, true)
+            }
+            _ => Vec::new(),
+        },
+        "line" => {
+            let start = Point { x: number("x1").unwrap_or(0.0), y: number("y1").unwrap_or(0.0) };
+            let end = Point { x: number("x2").unwrap_or(0.0), y: number("y2").unwrap_or(0.0) };
+            polyline_segments(&[start, end], false)
+        }
+        "polyline" | "polygon" => {
+            let points = attributes.get("points").map(|p| parse_points(p)).unwrap_or_default();
+            polyline_segments(&points, name == "polygon")
+        }
+        _ => Vec::new(),
+    };
+
+    Ok(segments)
+}
+
+/// Parse a `points` attribute (`x1,y1 x2,y2 ...`); a trailing odd coordinate is ignored
+fn parse_points(attr: &str) -> Vec<Point> {
+    let values: Vec<f64> = attr
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| s.parse().ok())
+        .collect();
+
+    values.chunks_exact(2).map(|pair| Point { x: pair[0], y: pair[1] }).collect()
+}
+
+/// Segments joining consecutive points, plus the closing edge when `closed`
+fn polyline_segments(points: &[Point], closed: bool) -> Vec<LineSegment> {
+    let closing = if closed && points.len() > 2 { points.first() } else { None };
+    points
+        .iter()
+        .zip(points.iter().skip(1).chain(closing))
+        .filter(|(start, end)| start != end)
+        .map(|(start, end)| LineSegment { start: *start, end: *end })
+        .collect()
+}
+
+fn segment_extent(segments: &[LineSegment]) -> Option<[f64; 4]> {
+    let mut min_x = f64::INFINITY;
+    let mut min_y = f64::INFINITY;
+    let mut max_x = f64::NEG_INFINITY;
+    let mut max_y = f64::NEG_INFINITY;
+
+    for p in segments.iter().flat_map(|s| [s.start, s.end]) {
+        min_x = min_x.min(p.x);
+        min_y = min_y.min(p.y);
+        max_x = max_x.max(p.x);
+        max_y = max_y.max(p.y);
+    }
+
+    if max_x > min_x || max_y > min_y {
+        Some([min_x, min_y, (max_x - min_x).max(f64::EPSILON), (max_y - min_y).max(f64::EPSILON)])
+    } else {
+        None
+    }
+}
+
+/// Token in SVG path data
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PathToken {
+    Command(char),
+    Number(f64),
+}
+
+/// Split path data into commands and numbers (handles commas, `10-5`, `.5.5` and exponents)
+fn tokenize_path_data(d: &str) -> Result<Vec<PathToken>> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = d.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == ',' {
+            i += 1;
+        } else if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+            tokens.push(PathToken::Command(c));
+            i += 1;
+        } else {
+            let start = i;
+            let mut seen_dot = false;
+            let mut seen_exp = false;
+            if chars[i] == '-' || chars[i] == '+' {
+                i += 1;
+            }
+            while i < chars.len() {
+                let ch = chars[i];
+                if ch.is_ascii_digit() {
+                    i += 1;
+                } else if ch == '.' && !seen_dot && !seen_exp {
+                    seen_dot = true;
+                    i += 1;
+                } else if (ch == 'e' || ch == 'E') && !seen_exp {
+                    seen_exp = true;
+                    i += 1;
+                    if i < chars.len() && (chars[i] == '-' || chars[i] == '+') {
+                        i += 1;
+                    }
+                } else {
+                    break;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse::<f64>()
+                .map_err(|_| anyhow::anyhow!("Invalid number '{}' in path data", text))?;
+            tokens.push(PathToken::Number(value));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Accumulates flattened segments while walking path commands
+struct PathBuilder {
+    segments: Vec<LineSegment>,
+    tolerance: f64,
+    current: Point,
+    start: Point,
+    /// Second control point of the previous C/S (for S reflection)
+    last_cubic_control: Option<Point>,
+    /// Control point of the previous Q/T (for T reflection)
+    last_quad_control: Option<Point>,
+}
+
+impl PathBuilder {
+    fn new(tolerance: f64) -> Self {
+        let origin = Point { x: 0.0, y: 0.0 };
+        Self {
+            segments: Vec::new(),
+            tolerance,
+            current: origin,
+            start: origin,
+            last_cubic_control: None,
+            last_quad_control: None,
+        }
+    }
+
+    /// Resolve a coordinate pair against the current point for relative commands
+    fn resolve(&self, relative: bool, x: f64, y: f64) -> Point {
+        if relative {
+            Point { x: self.current.x + x, y: self.current.y + y }
+        } else {
+            Point { x, y }
+        }
+    }
+
+    fn move_to(&mut self, p: Point) {
+        self.current = p;
+        self.start = p;
+    }
+
+    fn line_to(&mut self, p: Point) {
+        if p != self.current {
+            self.segments.push(LineSegment { start: self.current, end: p });
+        }
+        self.current = p;
+    }
+
+    fn close(&mut self) {
+        self.line_to(self.start);
+    }
+
+    fn cubic_to(&mut self, c1: Point, c2: Point, end: Point) {
+        let start = self.current;
+        flatten_cubic(start, c1, c2, end, self.tolerance, 0, &mut |p| self.line_to(p));
+        self.current = end;
+    }
+
+    fn quad_to(&mut self, control: Point, end: Point) {
+        // Degree-elevate to a cubic
+        let start = self.current;
+        let c1 = Point {
+            x: start.x + 2.0 / 3.0 * (control.x - start.x),
+            y: start.y + 2.0 / 3.0 * (control.y - start.y),
+        };
+        let c2 = Point {
+            x: end.x + 2.0 / 3.0 * (control.x - end.x),
+            y: end.y + 2.0 / 3.0 * (control.y - end.y),
+        };
+        self.cubic_to(c1, c2, end);
+    }
+
+    fn arc_to(&mut self, rx: f64, ry: f64, x_axis_rotation: f64, large_arc: bool, sweep: bool, end: Point) {
+        let start = self.current;
+        for p in flatten_arc(start, rx, ry, x_axis_rotation, large_arc, sweep, end, self.tolerance) {
+            self.line_to(p);
+        }
+        self.current = end;
+    }
+
+    /// Reflection of `control` about the current point, or the current point if there is none
+    fn reflect(&self, control: Option<Point>) -> Point {
+        match control {
+            Some(c) => Point { x: 2.0 * self.current.x - c.x, y: 2.0 * self.current.y - c.y },
+            None => self.current,
+        }
+    }
+}
+
+/// Maximum recursion depth for Bezier subdivision
+const MAX_SUBDIVISION_DEPTH: u32 = 16;
+
+/// Adaptive subdivision: split at t=0.5 until control points lie within `tolerance` of the chord
+fn flatten_cubic(p0: Point, p1: Point, p2: Point, p3: Point, tolerance: f64, depth: u32, emit: &mut impl FnMut(Point)) {
+    let flat = distance_to_line(p1, p0, p3) <= tolerance && distance_to_line(p2, p0, p3) <= tolerance;
+    if flat || depth >= MAX_SUBDIVISION_DEPTH {
+        emit(p3);
+        return;
+    }
+
+    let mid = |a: Point, b: Point| Point { x: (a.x + b.x) / 2.0, y: (a.y + b.y) / 2.0 };
+    let p01 = mid(p0, p1);
+    let p12 = mid(p1, p2);
+    let p23 = mid(p2, p3);
+    let p012 = mid(p01, p12);
+    let p123 = mid(p12, p23);
+    let split = mid(p012, p123);
+
+    flatten_cubic(p0, p01, p012, split, tolerance, depth + 1, emit);
+    flatten_cubic(split, p123, p23, p3, tolerance, depth + 1, emit);
+}
+
+/// Perpendicular distance from `p` to the line through `a` and `b`
+fn distance_to_line(p: Point, a: Point, b: Point) -> f64 {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    let len = (dx * dx + dy * dy).sqrt();
+    if len < f64::EPSILON {
+        return ((p.x - a.x).powi(2) + (p.y - a.y).powi(2)).sqrt();
+    }
+    ((p.x - a.x) * dy - (p.y - a.y) * dx).abs() / len
+}
+
+/// Approximate an SVG elliptical arc with points (endpoint parameterization, SVG spec F.6.5)
+#[allow(clippy::too_many_arguments)]
+fn flatten_arc(
+    start: Point,
+    rx: f64,
+    ry: f64,
+    x_axis_rotation: f64,
+    large_arc: bool,
+    sweep: bool,
+    end: Point,
+    tolerance: f64,
+) -> Vec<Point> {
+    let mut rx = rx.abs();
+    let mut ry = ry.abs();
+    if start == end {
+        return Vec::new();
+    }
+    if rx < f64::EPSILON || ry < f64::EPSILON {
+        return vec![end];
+    }
+
+    let (sin_phi, cos_phi) = x_axis_rotation.to_radians().sin_cos();
+    let dx2 = (start.x - end.x) / 2.0;
+    let dy2 = (start.y - end.y) / 2.0;
+    let x1p = cos_phi * dx2 + sin_phi * dy2;
+    let y1p = -sin_phi * dx2 + cos_phi * dy2;
+
+    // Scale radii up if they cannot span the endpoints
+    let lambda = (x1p * x1p) / (rx * rx) + (y1p * y1p) / (ry * ry);
+    if lambda > 1.0 {
+        let s = lambda.sqrt();
+        rx *= s;
+        ry *= s;
+    }
+
+    let num = rx * rx * ry * ry - rx * rx * y1p * y1p - ry * ry * x1p * x1p;
+    let den = rx * rx * y1p * y1p + ry * ry * x1p * x1p;
+    let mut coef = (num / den).max(0.0).sqrt();
+    if large_arc == sweep {
+        coef = -coef;
+    }
+    let cxp = coef * rx * y1p / ry;
+    let cyp = -coef * ry * x1p / rx;
+    let cx = cos_phi * cxp - sin_phi * cyp + (start.x + end.x) / 2.0;
+    let cy = sin_phi * cxp + cos_phi * cyp + (start.y + end.y) / 2.0;
+
+    let angle = |ux: f64, uy: f64, vx: f64, vy: f64| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+    let theta1 = angle(1.0, 0.0, (x1p - cxp) / rx, (y1p - cyp) / ry);
+    let mut delta = angle((x1p - cxp) / rx, (y1p - cyp) / ry, (-x1p - cxp) / rx, (-y1p - cyp) / ry);
+    if !sweep && delta > 0.0 {
+        delta -= 2.0 * std::f64::consts::PI;
+    } else if sweep && delta < 0.0 {
+        delta += 2.0 * std::f64::consts::PI;
+    }
+
+    // Angle step keeping the chord sagitta within tolerance on the larger radius
+    let r = rx.max(ry);
+    let max_step = if tolerance < r {
+        2.0 * (1.0 - tolerance / r).acos()
+    } else {
+        std::f64::consts::FRAC_PI_2
+    };
+    let steps = ((delta.abs() / max_step.max(1e-3)).ceil() as usize).clamp(1, 1024);
+
+    (1..=steps)
+        .map(|i| {
+            if i == steps {
+                return end;
+            }
+            let theta = theta1 + delta * i as f64 / steps as f64;
+            let (sin_t, cos_t) = theta.sin_cos();
+            Point {
+                x: cx + rx * cos_t * cos_phi - ry * sin_t * sin_phi,
+                y: cy + rx * cos_t * sin_phi + ry * sin_t * cos_phi,
+            }
+        })
+        .collect()
+}
+
+/// Parse path data into line segments in the path's local coordinates
+///
+/// Curves (C/S/Q/T) are flattened by adaptive subdivision and arcs (A) are approximated
+/// so that no point deviates from the true curve by more than `tolerance`.
+fn parse_path_data(d: &str, tolerance: f64) -> Result<Vec<LineSegment>> {
+    let tokens = tokenize_path_data(d)?;
+    let mut builder = PathBuilder::new(tolerance);
+    let mut i = 0;
+
+    // Pull the next `count` numbers; an incomplete argument group is consumed and dropped
+    let take = |i: &mut usize, count: usize| -> Option<Vec<f64>> {
+        let mut values = Vec::with_capacity(count);
+        while values.len() < count {
+            match tokens.get(*i) {
+                Some(PathToken::Number(n)) => values.push(*n),
+                _ => return None,
+            }
+            *i += 1;
+        }
+        Some(values)
+    };
+
+    // Command whose arguments are being consumed; bare numbers repeat it
+    let mut last_command: Option<char> = None;
+
+    while i < tokens.len() {
+        let command = match tokens[i] {
+            PathToken::Command(c) => {
+                i += 1;
+                c
+            }
+            PathToken::Number(_) => match last_command {
+                Some(c) => c,
+                None => {
+                    // Stray number before any command
+                    i += 1;
+                    continue;
+                }
+            },
+        };
+        let relative = command.is_ascii_lowercase();
+
+        // Extra coordinate pairs after a moveto are implicit lineto commands
+        last_command = match command {
+            'M' => Some('L'),
+            'm' => Some('l'),
+            'Z' | 'z' => None,
+            c => Some(c),
+        };
+
+        match command.to_ascii_uppercase() {
+            'M' => {
+                if let Some(v) = take(&mut i, 2) {
+                    let p = builder.resolve(relative, v[0], v[1]);
+                    builder.move_to(p);
+                }
+                builder.last_cubic_control = None;
+                builder.last_quad_control = None;
+            }
+            'L' => {
+                if let Some(v) = take(&mut i, 2) {
+                    let p = builder.resolve(relative, v[0], v[1]);
+                    builder.line_to(p);
+                }
+                builder.last_cubic_control = None;
+                builder.last_quad_control = None;
+            }
+            'H' => {
+                if let Some(v) = take(&mut i, 1) {
+                    let x = if relative { builder.current.x + v[0] } else { v[0] };
+                    let p = Point { x, y: builder.current.y };
+                    builder.line_to(p);
+                }
+                builder.last_cubic_control = None;
+                builder.last_quad_control = None;
+            }
+            'V' => {
+                if let Some(v) = take(&mut i, 1) {
+                    let y = if relative { builder.current.y + v[0] } else { v[0] };
+                    let p = Point { x: builder.current.x, y };
+                    builder.line_to(p);
+                }
+                builder.last_cubic_control = None;
+                builder.last_quad_control = None;
+            }
+            'C' => {
+                if let Some(v) = take(&mut i, 6) {
+                    let c1 = builder.resolve(relative, v[0], v[1]);
+                    let c2 = builder.resolve(relative, v[2], v[3]);
+                    let end = builder.resolve(relative, v[4], v[5]);
+                    builder.cubic_to(c1, c2, end);
+                    builder.last_cubic_control = Some(c2);
+                }
+                builder.last_quad_control = None;
+            }
+            'S' => {
+                if let Some(v) = take(&mut i, 4) {
+                    let c1 = builder.reflect(builder.last_cubic_control);
+                    let c2 = builder.resolve(relative, v[0], v[1]);
+                    let end = builder.resolve(relative, v[2], v[3]);
+                    builder.cubic_to(c1, c2, end);
+                    builder.last_cubic_control = Some(c2);
+                }
+                builder.last_quad_control = None;
+            }
+            'Q' => {
+                if let Some(v) = take(&mut i, 4) {
+                    let control = builder.resolve(relative, v[0], v[1]);
+                    let end = builder.resolve(relative, v[2], v[3]);
+                    builder.quad_to(control, end);
+                    builder.last_quad_control = Some(control);
+                }
+                builder.last_cubic_control = None;
+            }
+            'T' => {
+                if let Some(v) = take(&mut i, 2) {
+                    let control = builder.reflect(builder.last_quad_control);
+                    let end = builder.resolve(relative, v[0], v[1]);
+                    builder.quad_to(control, end);
+                    builder.last_quad_control = Some(control);
+                }
+                builder.last_cubic_control = None;
+            }
+            'A' => {
+                if let Some(v) = take(&mut i, 7) {
+                    let end = builder.resolve(relative, v[5], v[6]);
+                    builder.arc_to(v[0], v[1], v[2], v[3] != 0.0, v[4] != 0.0, end);
+                }
+                builder.last_cubic_control = None;
+                builder.last_quad_control = None;
+            }
+            'Z' => {
+                builder.close();
+                builder.last_cubic_control = None;
+                builder.last_quad_control = None;
+            }
+            _ => {
+                // Unknown command: skip its arguments
+                last_command = None;
+                while let Some(PathToken::Number(_)) = tokens.get(i) {
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    Ok(builder.segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_point(p: Point, x: f64, y: f64) {
+        assert!((p.x - x).abs() < 1e-6 && (p.y - y).abs() < 1e-6, "expected ({}, {}), got ({}, {})", x, y, p.x, p.y);
+    }
+
+    #[test]
+    fn test_group_translate_shifts_segments() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1000 1000">
+            <g transform="translate(100,50)">
+                <path d="M 0 0 L 200 0"/>
+            </g>
+        </svg>"#;
+
+        let segments = parse_svg_paths(svg).unwrap();
+
+        assert_eq!(segments.len(), 1);
+        assert_point(segments[0].start, 100.0, 50.0);
+        assert_point(segments[0].end, 300.0, 50.0);
+    }
+
+    #[test]
+    fn test_nested_group_transforms_compose() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1000 1000">
+            <g transform="translate(100,50)">
+                <g transform="scale(2)">
+                    <path d="M 10 10 l 10 0"/>
+                </g>
+            </g>
+            <path d="M 0 0 L 10 0"/>
+        </svg>"#;
+
+        let segments = parse_svg_paths(svg).unwrap();
+
+        assert_eq!(segments.len(), 2);
+        // scale first, then translate
+        assert_point(segments[0].start, 120.0, 70.0);
+        assert_point(segments[0].end, 140.0, 70.0);
+        // Outside the groups: untouched
+        assert_point(segments[1].start, 0.0, 0.0);
+        assert_point(segments[1].end, 10.0, 0.0);
+    }
+
+    #[test]
+    fn test_view_box_normalized_to_1000() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="50 50 200 100">
+            <path transform="matrix(1 0 0 1 0 0)" d="M 50 50 l 200 0"/>
+        </svg>"#;
+
+        let segments = parse_svg_paths(svg).unwrap();
+
+        assert_point(segments[0].start, 0.0, 0.0);
+        assert_point(segments[0].end, 1000.0, 0.0);
+    }
+
+    #[test]
+    fn test_rect_only_svg_detects_each_rect_as_room() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 400 200">
+            <rect x="0" y="0" width="100" height="100"/>
+            <rect x="200" y="0" width="150" height="100"/>
+            <g transform="translate(0,120)">
+                <rect width="100" height="50"></rect>
+            </g>
+        </svg>"#;
+
+        let segments = parse_svg_paths(svg).unwrap();
+        assert_eq!(segments.len(), 12);
+        // Third rect is shifted by its group, then everything scales by 1000/400
+        assert_point(segments[8].start, 0.0, 300.0);
+        assert_point(segments[10].start, 250.0, 425.0);
+
+        let vertices = crate::find_intersections(&segments);
+        let graph = crate::build_graph(&vertices, &segments);
+        // all_cycles returns each loop once per start node and direction; count distinct node sets
+        let rooms: std::collections::HashSet<Vec<usize>> = crate::all_cycles(&graph)
+            .into_iter()
+            .filter(|cycle| crate::is_minimal_cycle(cycle, &vertices, &graph))
+            .map(|mut cycle| {
+                cycle.sort();
+                cycle
+            })
+            .collect();
+        assert_eq!(rooms.len(), 3);
+    }
+
+    #[test]
+    fn test_line_polyline_and_polygon_elements() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1000 1000">
+            <line x1="0" y1="0" x2="100" y2="0"/>
+            <polyline points="0,10 50,10 50,60"/>
+            <polygon transform="translate(200,0)" points="0 0 100 0 100 100"/>
+        </svg>"#;
+
+        let segments = parse_svg_paths(svg).unwrap();
+
+        // 1 line + 2 open polyline edges + 3 closed polygon edges
+        assert_eq!(segments.len(), 6);
+        assert_point(segments[0].end, 100.0, 0.0);
+        assert_point(segments[2].end, 50.0, 60.0);
+        assert_point(segments[5].start, 300.0, 100.0);
+        assert_point(segments[5].end, 200.0, 0.0);
+    }
+
+    #[test]
+    fn test_parse_transform_list() {
+        let t = parse_transform("translate(10, 20) scale(3)");
+        let p = t.apply(Point { x: 1.0, y: 1.0 });
+        assert_point(p, 13.0, 23.0);
+
+        assert_eq!(parse_transform("matrix(1,2,3,4,5,6)"), Transform::matrix(1.0, 2.0, 3.0, 4.0, 5.0, 6.0));
+    }
+
+    fn cubic_point(p: [Point; 4], t: f64) -> Point {
+        let u = 1.0 - t;
+        let (w0, w1, w2, w3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        Point {
+            x: w0 * p[0].x + w1 * p[1].x + w2 * p[2].x + w3 * p[3].x,
+            y: w0 * p[0].y + w1 * p[1].y + w2 * p[2].y + w3 * p[3].y,
+        }
+    }
+
+    #[test]
+    fn test_single_cubic_flattened_within_tolerance() {
+        let control = [
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 0.0, y: 100.0 },
+            Point { x: 100.0, y: 100.0 },
+            Point { x: 100.0, y: 0.0 },
+        ];
+        let tolerance = 0.5;
+        let absolute = parse_path_data("M0,0 C0,100 100,100 100,0", tolerance).unwrap();
+        let relative = parse_path_data("m0 0c0 100 100 100 100 0", tolerance).unwrap();
+
+        assert!(absolute.len() > 4, "curve should be subdivided, got {} segments", absolute.len());
+        assert_eq!(absolute.len(), relative.len());
+
+        // Chain is contiguous from start to end
+        assert_point(absolute[0].start, 0.0, 0.0);
+        assert_point(absolute[absolute.len() - 1].end, 100.0, 0.0);
+        for pair in absolute.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+
+        // Every sampled curve point is close to the polyline
+        for i in 0..=100 {
+            let p = cubic_point(control, i as f64 / 100.0);
+            let nearest = absolute
+                .iter()
+                .map(|s| distance_to_line(p, s.start, s.end))
+                .fold(f64::INFINITY, f64::min);
+            assert!(nearest <= tolerance * 2.0, "curve point ({}, {}) off by {}", p.x, p.y, nearest);
+        }
+
+        for (a, r) in absolute.iter().zip(&relative) {
+            assert_point(r.start, a.start.x, a.start.y);
+            assert_point(r.end, a.end.x, a.end.y);
+        }
+    }
+
+    #[test]
+    fn test_arc_points_on_circle() {
+        // Half circle of radius 50 centered at (50, 0)
+        let segments = parse_path_data("M 0 0 A 50 50 0 0 1 100 0", 0.5).unwrap();
+
+        assert!(segments.len() > 2);
+        assert_point(segments[segments.len() - 1].end, 100.0, 0.0);
+        for seg in &segments {
+            let r = ((seg.end.x - 50.0).powi(2) + seg.end.y.powi(2)).sqrt();
+            assert!((r - 50.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_tokenize_compact_numbers() {
+        let tokens = tokenize_path_data("M10-5L.5.5,1e2").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                PathToken::Command('M'),
+                PathToken::Number(10.0),
+                PathToken::Number(-5.0),
+                PathToken::Command('L'),
+                PathToken::Number(0.5),
+                PathToken::Number(0.5),
+                PathToken::Number(100.0),
+            ]
+        );
+    }
+
+    fn assert_same_segments(actual: &[LineSegment], expected: &[LineSegment]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert_point(a.start, e.start.x, e.start.y);
+            assert_point(a.end, e.end.x, e.end.y);
+        }
+    }
+
+    #[test]
+    fn test_absolute_and_relative_squares_match() {
+        let absolute = parse_path_data("M 100 100 L 200 100 L 200 200 L 100 200 Z", 0.5).unwrap();
+        let relative = parse_path_data("m 100 100 l 100 0 l 0 100 l -100 0 z", 0.5).unwrap();
+
+        assert_eq!(absolute.len(), 4);
+        assert_point(absolute[1].start, 200.0, 100.0);
+        assert_point(absolute[1].end, 200.0, 200.0);
+        assert_point(absolute[3].end, 100.0, 100.0);
+        assert_same_segments(&relative, &absolute);
+    }
+
+    #[test]
+    fn test_implicit_repeated_coordinates() {
+        let expected = parse_path_data("M 100 100 L 200 100 L 200 200 L 100 200 Z", 0.5).unwrap();
+
+        // Repeated pairs after L/l reuse the same command
+        let absolute = parse_path_data("M 100 100 L 200 100 200 200 100 200 Z", 0.5).unwrap();
+        let relative = parse_path_data("m 100 100 l 100 0 0 100 -100 0 z", 0.5).unwrap();
+        // Extra pairs after a moveto are implicit linetos
+        let implicit_move = parse_path_data("M100,100 200,100 200,200 100,200z", 0.5).unwrap();
+        let implicit_rel_move = parse_path_data("m100 100 100 0 0 100 -100 0z", 0.5).unwrap();
+
+        assert_same_segments(&absolute, &expected);
+        assert_same_segments(&relative, &expected);
+        assert_same_segments(&implicit_move, &expected);
+        assert_same_segments(&implicit_rel_move, &expected);
+    }
+}
@@ -0,0 +1,107 @@
+//! Bounding-box matching of rooms between detection results
+//!
+//! Dependency-free so it can be shared by the backend ensembles and the wasm frontend.
+
+/// Area of a `[min_x, min_y, max_x, max_y]` box (zero for inverted boxes)
+pub fn bbox_area(bbox: &[f64; 4]) -> f64 {
+    (bbox[2] - bbox[0]).max(0.0) * (bbox[3] - bbox[1]).max(0.0)
+}
+
+/// Intersection-over-union of two `[min_x, min_y, max_x, max_y]` boxes
+pub fn bbox_iou(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+    let ix = (a[2].min(b[2]) - a[0].max(b[0])).max(0.0);
+    let iy = (a[3].min(b[3]) - a[1].max(b[1])).max(0.0);
+    let intersection = ix * iy;
+    let union = bbox_area(a) + bbox_area(b) - intersection;
+
+    if union <= 0.0 {
+        0.0
+    } else {
+        intersection / union
+    }
+}
+
+/// One-to-one pairing of two sets of boxes, by index into each input
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoxMatching {
+    /// `(index in a, index in b)` pairs, in the order they were matched
+    pub matched: Vec<(usize, usize)>,
+    /// Boxes in `a` with no partner, ascending
+    pub only_a: Vec<usize>,
+    /// Boxes in `b` with no partner, ascending
+    pub only_b: Vec<usize>,
+}
+
+/// Pair boxes from `a` and `b` greedily by IoU
+///
+/// The highest-IoU pairs are taken first and each box is used at most once. Pairs must
+/// have IoU of at least `threshold`; everything left over is reported as unmatched.
+pub fn match_boxes(a: &[[f64; 4]], b: &[[f64; 4]], threshold: f64) -> BoxMatching {
+    let mut candidates: Vec<(f64, usize, usize)> = Vec::new();
+    for (i, box_a) in a.iter().enumerate() {
+        for (j, box_b) in b.iter().enumerate() {
+            let iou = bbox_iou(box_a, box_b);
+            if iou > 0.0 && iou >= threshold {
+                candidates.push((iou, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|x, y| y.0.total_cmp(&x.0).then(x.1.cmp(&y.1)).then(x.2.cmp(&y.2)));
+
+    let mut used_a = vec![false; a.len()];
+    let mut used_b = vec![false; b.len()];
+    let mut matched = Vec::new();
+    for (_, i, j) in candidates {
+        if !used_a[i] && !used_b[j] {
+            used_a[i] = true;
+            used_b[j] = true;
+            matched.push((i, j));
+        }
+    }
+
+    BoxMatching {
+        matched,
+        only_a: (0..a.len()).filter(|&i| !used_a[i]).collect(),
+        only_b: (0..b.len()).filter(|&j| !used_b[j]).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bbox_iou() {
+        let a = [0.0, 0.0, 10.0, 10.0];
+        assert!((bbox_iou(&a, &a) - 1.0).abs() < 1e-12);
+        assert_eq!(bbox_iou(&a, &[20.0, 20.0, 30.0, 30.0]), 0.0);
+        // Half-overlapping: intersection 50, union 150
+        assert!((bbox_iou(&a, &[5.0, 0.0, 15.0, 10.0]) - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(bbox_iou(&[0.0; 4], &[0.0; 4]), 0.0);
+    }
+
+    #[test]
+    fn test_match_boxes_greedy_one_to_one() {
+        let a = [[0.0, 0.0, 10.0, 10.0], [20.0, 0.0, 30.0, 10.0], [100.0, 100.0, 110.0, 110.0]];
+        let b = [
+            [21.0, 0.0, 30.0, 10.0], // matches a[1]
+            [0.0, 0.0, 10.0, 9.0],   // matches a[0]
+            [1.0, 0.0, 10.0, 10.0],  // also overlaps a[0], but a[0] is taken by the better pair
+            [50.0, 50.0, 60.0, 60.0],
+        ];
+
+        let matching = match_boxes(&a, &b, 0.5);
+        assert_eq!(matching.matched, vec![(0, 1), (1, 0)]);
+        assert_eq!(matching.only_a, vec![2]);
+        assert_eq!(matching.only_b, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_match_boxes_threshold() {
+        let a = [[0.0, 0.0, 10.0, 10.0]];
+        let b = [[5.0, 0.0, 15.0, 10.0]]; // IoU 1/3
+
+        assert_eq!(match_boxes(&a, &b, 0.5).matched, vec![]);
+        assert_eq!(match_boxes(&a, &b, 0.3).matched, vec![(0, 0)]);
+    }
+}
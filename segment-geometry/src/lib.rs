@@ -0,0 +1,46 @@
+//! Line segment geometry shared by the wall graph builders
+//!
+//! Dependency-free and written against `(x, y)` tuples, so each crate keeps its own point type.
+
+/// Intersection parameters `(t, u)` of segments `a` and `b`, if they cross
+///
+/// The crossing point is `a.0 + t * (a.1 - a.0)`, equally `b.0 + u * (b.1 - b.0)`; both
+/// parameters are in `0..=1`, so touching endpoints count. Parallel segments never cross.
+pub fn segment_intersection(a: ((f64, f64), (f64, f64)), b: ((f64, f64), (f64, f64))) -> Option<(f64, f64)> {
+    let ((x1, y1), (x2, y2)) = a;
+    let ((x3, y3), (x4, y4)) = b;
+
+    let denom = (y4 - y3) * (x2 - x1) - (x4 - x3) * (y2 - y1);
+    if denom.abs() < 1e-10 {
+        return None;
+    }
+
+    let t = ((x3 - x1) * (y4 - y3) - (y3 - y1) * (x4 - x3)) / denom;
+    let u = ((x3 - x1) * (y2 - y1) - (y3 - y1) * (x2 - x1)) / denom;
+
+    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        Some((t, u))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_intersection() {
+        // Crossing off-center: a horizontal wall and a vertical one at x = 2.5
+        let (t, u) = segment_intersection(((0.0, 0.0), (10.0, 0.0)), ((2.5, -5.0), (2.5, 15.0))).unwrap();
+        assert!((t - 0.25).abs() < 1e-12);
+        assert!((u - 0.25).abs() < 1e-12);
+
+        // Shared endpoint
+        assert_eq!(segment_intersection(((0.0, 0.0), (10.0, 0.0)), ((10.0, 0.0), (10.0, 10.0))), Some((1.0, 0.0)));
+
+        // Would cross if extended; parallel
+        assert_eq!(segment_intersection(((0.0, 0.0), (10.0, 0.0)), ((12.0, -5.0), (12.0, 5.0))), None);
+        assert_eq!(segment_intersection(((0.0, 0.0), (10.0, 0.0)), ((0.0, 1.0), (10.0, 1.0))), None);
+    }
+}
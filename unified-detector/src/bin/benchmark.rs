@@ -98,6 +98,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     fs::write(output_path, serde_json::to_string_pretty(&report)?)?;
     println!("\n💾 Saved detailed results to: {}", output_path);
 
+    // CI-consumable exports; zero-room results count as failures with --fail-on-zero-rooms
+    let fail_on_zero_rooms = std::env::args().any(|arg| arg == "--fail-on-zero-rooms");
+    BenchmarkResult::write_junit(&all_results, "data/benchmark_junit.xml", fail_on_zero_rooms)?;
+    BenchmarkResult::write_json(&all_results, "data/benchmark_results_raw.json")?;
+    println!("💾 Saved JUnit report to: data/benchmark_junit.xml");
+
+    // Track stats across runs to spot regressions
+    let all_stats: Vec<BenchmarkStats> = all_methods
+        .iter()
+        .map(|m| BenchmarkStats::from_results(*m, &all_results))
+        .collect();
+    let history = BenchmarkHistory::new("data/benchmark_history.csv");
+    history.append(&all_stats)?;
+    print_deltas(&history.latest_deltas()?);
+
     // Generate comparison report
     generate_comparison_table(&all_methods, &all_results);
 
@@ -208,7 +223,10 @@ async fn benchmark_vision_method(
     let classifier = VisionClassifier::new(api_key, Some(model.to_string()));
 
     let image_bytes = fs::read(image_path)?;
-    let classifications = classifier.classify_floorplan(&image_bytes, None).await?;
+    let classifications = classifier
+        .classify_floorplan(&image_bytes, None)
+        .await?
+        .classifications;
 
     let avg_confidence = if classifications.is_empty() {
         0.0
@@ -264,6 +282,25 @@ fn print_stats(stats: &BenchmarkStats) {
     println!("Avg Confidence: {:.1}%", stats.avg_confidence * 100.0);
 }
 
+fn print_deltas(deltas: &[StatsDelta]) {
+    if deltas.is_empty() {
+        return;
+    }
+
+    println!("\n📉 Change Since Previous Run");
+    println!("{}", "-".repeat(80));
+    for delta in deltas {
+        println!(
+            "{:<20} time {:+.1}ms ({:+.1}%), success {:+.1}pp, rooms {:+.2}",
+            delta.method.name(),
+            delta.avg_execution_time_ms,
+            delta.avg_execution_time_pct * 100.0,
+            delta.success_rate,
+            delta.avg_rooms_per_image,
+        );
+    }
+}
+
 fn generate_comparison_table(methods: &[DetectionMethod], results: &[BenchmarkResult]) {
     println!("\n📈 Method Comparison");
     println!("{}", "=".repeat(80));
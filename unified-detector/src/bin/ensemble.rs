@@ -213,7 +213,10 @@ async fn run_vision_method(
     let classifier = VisionClassifier::new(api_key, Some(model.to_string()));
 
     let image_bytes = fs::read(image_path)?;
-    let classifications = classifier.classify_floorplan(&image_bytes, None).await?;
+    let classifications = classifier
+        .classify_floorplan(&image_bytes, None)
+        .await?
+        .classifications;
 
     let rooms: Vec<Room> = classifications
         .iter()
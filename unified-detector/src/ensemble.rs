@@ -0,0 +1,487 @@
+//! Ensemble execution across multiple detection methods
+//!
+//! `DetectionRunner` knows how to run a single method; `Ensemble` applies an
+//! `EnsembleStrategy` on top of it and produces a `ComparisonReport`.
+
+use crate::{ComparisonReport, DetectionMethod, DetectionResult, EnsembleConfig, EnsembleMetadata, EnsembleStrategy, Room};
+use futures::stream::{FuturesUnordered, StreamExt};
+use room_matching::{bbox_area, bbox_iou};
+use std::future::Future;
+use tracing::{info, warn};
+
+/// Wall line segment as `[x1, y1, x2, y2]`
+pub type LineSegment = [f64; 4];
+
+/// IoU above which rooms from different methods are treated as the same room
+const VOTE_IOU_THRESHOLD: f64 = 0.5;
+
+/// Runs a single detection method on an image and/or wall lines
+pub trait DetectionRunner {
+    fn detect(
+        &self,
+        method: DetectionMethod,
+        image: &[u8],
+        lines: &[LineSegment],
+    ) -> impl Future<Output = anyhow::Result<DetectionResult>> + Send;
+}
+
+/// Executes an `EnsembleConfig` with a `DetectionRunner`
+pub struct Ensemble<R: DetectionRunner> {
+    runner: R,
+}
+
+impl<R: DetectionRunner + Sync> Ensemble<R> {
+    pub fn new(runner: R) -> Self {
+        Self { runner }
+    }
+
+    /// Run the configured methods according to the ensemble strategy
+    ///
+    /// - `Cascade` runs methods fastest → slowest and stops at the first result whose
+    ///   `avg_confidence` meets the threshold
+    /// - `Fastest` returns the first method to complete successfully
+    /// - `HighestConfidence` awaits every method and picks the most confident
+    /// - `VoteByArea` runs every method and fuses agreeing rooms into `metadata.merged_rooms`
+    ///   (a room needs a majority of the successful methods to survive)
+    /// - `All` runs every method and reports all results
+    ///
+    /// The report's `image_path` is left empty; callers set it if they have one.
+    pub async fn run_ensemble(
+        &self,
+        image: &[u8],
+        lines: &[LineSegment],
+        config: &EnsembleConfig,
+    ) -> ComparisonReport {
+        let mut metadata = EnsembleMetadata {
+            strategy: Some(config.strategy),
+            ..Default::default()
+        };
+
+        let (results, winner) = match config.strategy {
+            EnsembleStrategy::Cascade { confidence_threshold } => {
+                let results = self
+                    .run_cascade(image, lines, &config.methods, confidence_threshold, &mut metadata)
+                    .await;
+                let winner = results
+                    .iter()
+                    .find(|r| r.metadata.avg_confidence >= confidence_threshold)
+                    .map(|r| r.method);
+                (results, winner)
+            }
+            EnsembleStrategy::Fastest => {
+                let results = self.run_fastest(image, lines, config, &mut metadata).await;
+                let winner = results.first().map(|r| r.method);
+                (results, winner)
+            }
+            EnsembleStrategy::HighestConfidence => {
+                let results = self.run_all(image, lines, config, &mut metadata).await;
+                let winner = results
+                    .iter()
+                    .max_by(|a, b| {
+                        a.metadata
+                            .avg_confidence
+                            .partial_cmp(&b.metadata.avg_confidence)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .map(|r| r.method);
+                (results, winner)
+            }
+            EnsembleStrategy::VoteByArea => {
+                let results = self.run_all(image, lines, config, &mut metadata).await;
+                let min_votes = results.len() / 2 + 1;
+                metadata.merged_rooms = vote_by_area(&results, min_votes, VOTE_IOU_THRESHOLD);
+                (results, None)
+            }
+            EnsembleStrategy::All => (self.run_all(image, lines, config, &mut metadata).await, None),
+        };
+
+        info!(
+            "Ensemble {:?} executed {} methods, skipped {}",
+            config.strategy,
+            metadata.executed.len(),
+            metadata.skipped.len()
+        );
+
+        let mut report = ComparisonReport::new(String::new(), results);
+        if let Some(winner) = winner {
+            report.winner = winner;
+        }
+        report.metadata = metadata;
+        report
+    }
+
+    /// Run methods in order of expected cost until one is confident enough
+    async fn run_cascade(
+        &self,
+        image: &[u8],
+        lines: &[LineSegment],
+        methods: &[DetectionMethod],
+        confidence_threshold: f64,
+        metadata: &mut EnsembleMetadata,
+    ) -> Vec<DetectionResult> {
+        let mut ordered = methods.to_vec();
+        ordered.sort_by_key(|m| m.expected_cost());
+
+        let mut results = Vec::new();
+        for (idx, method) in ordered.iter().enumerate() {
+            let outcome = self.runner.detect(*method, image, lines).await;
+            metadata.executed.push(*method);
+
+            match outcome {
+                Ok(result) => {
+                    let confident = result.metadata.avg_confidence >= confidence_threshold;
+                    results.push(result);
+                    if confident {
+                        metadata.skipped.extend_from_slice(&ordered[idx + 1..]);
+                        break;
+                    }
+                }
+                Err(e) => {
+                    warn!("Cascade method {} failed: {}", method.name(), e);
+                    metadata.failed.push((*method, e.to_string()));
+                }
+            }
+        }
+
+        results
+    }
+
+    /// Return the first method to complete successfully
+    async fn run_fastest(
+        &self,
+        image: &[u8],
+        lines: &[LineSegment],
+        config: &EnsembleConfig,
+        metadata: &mut EnsembleMetadata,
+    ) -> Vec<DetectionResult> {
+        if !config.parallel {
+            let mut ordered = config.methods.clone();
+            ordered.sort_by_key(|m| m.expected_cost());
+            for (idx, method) in ordered.iter().enumerate() {
+                metadata.executed.push(*method);
+                match self.runner.detect(*method, image, lines).await {
+                    Ok(result) => {
+                        metadata.skipped.extend_from_slice(&ordered[idx + 1..]);
+                        return vec![result];
+                    }
+                    Err(e) => metadata.failed.push((*method, e.to_string())),
+                }
+            }
+            return Vec::new();
+        }
+
+        let mut pending: FuturesUnordered<_> = config
+            .methods
+            .iter()
+            .map(|&method| async move { (method, self.runner.detect(method, image, lines).await) })
+            .collect();
+
+        let mut remaining = config.methods.clone();
+        while let Some((method, outcome)) = pending.next().await {
+            remaining.retain(|m| *m != method);
+            metadata.executed.push(method);
+            match outcome {
+                Ok(result) => {
+                    // Dropping `pending` cancels the slower methods
+                    metadata.skipped.extend(remaining);
+                    return vec![result];
+                }
+                Err(e) => metadata.failed.push((method, e.to_string())),
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// Run every configured method, concurrently when `config.parallel` is set
+    async fn run_all(
+        &self,
+        image: &[u8],
+        lines: &[LineSegment],
+        config: &EnsembleConfig,
+        metadata: &mut EnsembleMetadata,
+    ) -> Vec<DetectionResult> {
+        let outcomes: Vec<(DetectionMethod, anyhow::Result<DetectionResult>)> = if config.parallel {
+            futures::future::join_all(
+                config
+                    .methods
+                    .iter()
+                    .map(|&method| async move { (method, self.runner.detect(method, image, lines).await) }),
+            )
+            .await
+        } else {
+            let mut outcomes = Vec::new();
+            for &method in &config.methods {
+                outcomes.push((method, self.runner.detect(method, image, lines).await));
+            }
+            outcomes
+        };
+
+        let mut results = Vec::new();
+        for (method, outcome) in outcomes {
+            metadata.executed.push(method);
+            match outcome {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    warn!("Ensemble method {} failed: {}", method.name(), e);
+                    metadata.failed.push((method, e.to_string()));
+                }
+            }
+        }
+
+        results
+    }
+}
+
+/// Fuse rooms from several methods by bounding-box overlap
+///
+/// Rooms whose IoU with a cluster exceeds `iou` join that cluster (at most one room
+/// per method). Clusters backed by fewer than `min_votes` methods are dropped. Each
+/// surviving cluster becomes one room with an area-weighted average bounding box,
+/// the mean confidence of its voters, the majority room type, and the union of features.
+pub fn vote_by_area(results: &[DetectionResult], min_votes: usize, iou: f64) -> Vec<Room> {
+    // Each cluster holds (method, room) votes
+    let mut clusters: Vec<Vec<(DetectionMethod, &Room)>> = Vec::new();
+
+    for result in results {
+        for room in &result.rooms {
+            let best = clusters
+                .iter()
+                .enumerate()
+                .filter(|(_, cluster)| cluster.iter().all(|(m, _)| *m != result.method))
+                .map(|(idx, cluster)| {
+                    let overlap = cluster
+                        .iter()
+                        .map(|(_, r)| bbox_iou(&r.bounding_box, &room.bounding_box))
+                        .fold(0.0, f64::max);
+                    (idx, overlap)
+                })
+                .filter(|(_, overlap)| *overlap > iou)
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+            match best {
+                Some((idx, _)) => clusters[idx].push((result.method, room)),
+                None => clusters.push(vec![(result.method, room)]),
+            }
+        }
+    }
+
+    clusters
+        .into_iter()
+        .filter(|cluster| cluster.len() >= min_votes)
+        .enumerate()
+        .map(|(id, cluster)| merge_cluster(id, &cluster))
+        .collect()
+}
+
+/// Merge one vote cluster into a single room
+fn merge_cluster(id: usize, cluster: &[(DetectionMethod, &Room)]) -> Room {
+    let votes = cluster.len() as f64;
+
+    // Area-weighted bounding box (falls back to a plain mean when all areas are zero)
+    let weights: Vec<f64> = cluster.iter().map(|(_, r)| bbox_area(&r.bounding_box)).collect();
+    let total_weight: f64 = weights.iter().sum();
+    let mut bounding_box = [0.0; 4];
+    for ((_, room), weight) in cluster.iter().zip(&weights) {
+        let w = if total_weight > 0.0 { weight / total_weight } else { 1.0 / votes };
+        for (merged, value) in bounding_box.iter_mut().zip(room.bounding_box.iter()) {
+            *merged += value * w;
+        }
+    }
+
+    // Majority room type, ties broken by confidence
+    let mut type_votes: Vec<(&str, usize, f64)> = Vec::new();
+    for (_, room) in cluster {
+        match type_votes.iter_mut().find(|(t, _, _)| *t == room.room_type) {
+            Some(entry) => {
+                entry.1 += 1;
+                entry.2 = entry.2.max(room.confidence);
+            }
+            None => type_votes.push((&room.room_type, 1, room.confidence)),
+        }
+    }
+    let room_type = type_votes
+        .iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal)))
+        .map(|(t, _, _)| t.to_string())
+        .unwrap_or_default();
+
+    let mut features: Vec<String> = Vec::new();
+    for (_, room) in cluster {
+        for feature in &room.features {
+            if !features.contains(feature) {
+                features.push(feature.clone());
+            }
+        }
+    }
+
+    Room {
+        id,
+        room_type,
+        confidence: cluster.iter().map(|(_, r)| r.confidence).sum::<f64>() / votes,
+        area: cluster.iter().map(|(_, r)| r.area).sum::<f64>() / votes,
+        bounding_box,
+        features,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DetectionMetadata;
+    use std::time::Duration;
+
+    /// Runner returning a fixed confidence per method
+    struct FixedRunner;
+
+    impl DetectionRunner for FixedRunner {
+        async fn detect(
+            &self,
+            method: DetectionMethod,
+            _image: &[u8],
+            _lines: &[LineSegment],
+        ) -> anyhow::Result<DetectionResult> {
+            let (confidence, delay_ms) = match method {
+                DetectionMethod::GraphBased => (0.5, 30),
+                DetectionMethod::GraphWithDoors => (0.9, 20),
+                DetectionMethod::VisionGPT5 => (0.95, 10),
+                _ => return Err(anyhow::anyhow!("unsupported")),
+            };
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+
+            Ok(DetectionResult {
+                method,
+                rooms: vec![],
+                execution_time: Duration::from_millis(delay_ms),
+                metadata: DetectionMetadata {
+                    total_rooms: 0,
+                    avg_confidence: confidence,
+                    method_specific: serde_json::json!({}),
+                },
+            })
+        }
+    }
+
+    fn config(strategy: EnsembleStrategy, parallel: bool) -> EnsembleConfig {
+        EnsembleConfig {
+            methods: vec![
+                DetectionMethod::VisionGPT5,
+                DetectionMethod::GraphBased,
+                DetectionMethod::GraphWithDoors,
+            ],
+            strategy,
+            parallel,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cascade_short_circuits() {
+        let ensemble = Ensemble::new(FixedRunner);
+        let report = ensemble
+            .run_ensemble(&[], &[], &config(EnsembleStrategy::Cascade { confidence_threshold: 0.8 }, false))
+            .await;
+
+        // GraphBased (0.5) is not confident, GraphWithDoors (0.9) is; GPT-5 never runs
+        assert_eq!(
+            report.metadata.executed,
+            vec![DetectionMethod::GraphBased, DetectionMethod::GraphWithDoors]
+        );
+        assert_eq!(report.metadata.skipped, vec![DetectionMethod::VisionGPT5]);
+        assert_eq!(report.winner, DetectionMethod::GraphWithDoors);
+    }
+
+    #[tokio::test]
+    async fn test_highest_confidence_runs_all() {
+        let ensemble = Ensemble::new(FixedRunner);
+        let report = ensemble
+            .run_ensemble(&[], &[], &config(EnsembleStrategy::HighestConfidence, true))
+            .await;
+
+        assert_eq!(report.metadata.executed.len(), 3);
+        assert!(report.metadata.skipped.is_empty());
+        assert_eq!(report.winner, DetectionMethod::VisionGPT5);
+    }
+
+    #[tokio::test]
+    async fn test_fastest_returns_first_completed() {
+        let ensemble = Ensemble::new(FixedRunner);
+        let report = ensemble
+            .run_ensemble(&[], &[], &config(EnsembleStrategy::Fastest, true))
+            .await;
+
+        // VisionGPT5 has the shortest simulated delay in this runner
+        assert_eq!(report.results.len(), 1);
+        assert_eq!(report.winner, DetectionMethod::VisionGPT5);
+        assert_eq!(report.metadata.skipped.len(), 2);
+    }
+
+    fn room(room_type: &str, confidence: f64, bounding_box: [f64; 4], features: &[&str]) -> Room {
+        Room {
+            id: 0,
+            room_type: room_type.to_string(),
+            confidence,
+            area: bbox_area(&bounding_box),
+            bounding_box,
+            features: features.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    fn result(method: DetectionMethod, rooms: Vec<Room>) -> DetectionResult {
+        DetectionResult {
+            method,
+            metadata: DetectionMetadata {
+                total_rooms: rooms.len(),
+                avg_confidence: 0.0,
+                method_specific: serde_json::json!({}),
+            },
+            rooms,
+            execution_time: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_vote_by_area_drops_hallucinated_room() {
+        let graph = result(
+            DetectionMethod::GraphBased,
+            vec![
+                room("bedroom", 0.8, [0.0, 0.0, 100.0, 100.0], &["window"]),
+                room("kitchen", 0.8, [100.0, 0.0, 200.0, 100.0], &[]),
+                room("bathroom", 0.8, [200.0, 0.0, 300.0, 100.0], &[]),
+            ],
+        );
+        let vision = result(
+            DetectionMethod::VisionGPT5,
+            vec![
+                room("kitchen", 0.9, [102.0, 2.0, 198.0, 100.0], &["stove"]),
+                room("bedroom", 0.6, [0.0, 0.0, 98.0, 102.0], &["bed", "window"]),
+                room("bathroom", 1.0, [200.0, 0.0, 300.0, 100.0], &[]),
+                // Hallucinated room only this method sees
+                room("closet", 0.7, [500.0, 500.0, 550.0, 550.0], &[]),
+            ],
+        );
+
+        let merged = vote_by_area(&[graph, vision], 2, 0.5);
+
+        assert_eq!(merged.len(), 3, "Hallucinated fourth room should be discarded");
+        assert!(merged.iter().all(|r| r.room_type != "closet"));
+
+        let bedroom = merged.iter().find(|r| r.room_type == "bedroom").unwrap();
+        assert!((bedroom.confidence - 0.7).abs() < 1e-9);
+        assert_eq!(bedroom.features, vec!["window".to_string(), "bed".to_string()]);
+
+        let kitchen = merged.iter().find(|r| r.room_type == "kitchen").unwrap();
+        assert_eq!(kitchen.features, vec!["stove".to_string()]);
+        // Area-weighted average lies between the two input boxes
+        assert!(kitchen.bounding_box[0] > 100.0 && kitchen.bounding_box[0] < 102.0);
+    }
+
+    #[test]
+    fn test_vote_by_area_single_method_min_votes_one() {
+        let only = result(
+            DetectionMethod::GraphBased,
+            vec![room("bedroom", 0.8, [0.0, 0.0, 100.0, 100.0], &[])],
+        );
+
+        assert_eq!(vote_by_area(&[only], 1, 0.5).len(), 1);
+    }
+}
@@ -1,8 +1,14 @@
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::time::Duration;
 
+pub mod ensemble;
+pub mod scoring;
 pub mod yolo;
 
+pub use ensemble::{vote_by_area, DetectionRunner, Ensemble, LineSegment};
+pub use scoring::{score_against_truth, DetectionScore, DEFAULT_TRUTH_IOU};
+
 /// Unified detection result from any method
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DetectionResult {
@@ -40,7 +46,20 @@ impl DetectionMethod {
             Self::VisionGPT5 | Self::VisionGPT4 => {
                 std::env::var("OPENAI_API_KEY").is_ok()
             }
-            Self::YoloV8 | Self::HuggingFace => false, // Not implemented yet
+            Self::YoloV8 => yolo::is_yolo_available(),
+            Self::HuggingFace => false, // Not implemented yet
+        }
+    }
+
+    /// Relative cost ranking used to order cascades (lower runs first)
+    pub fn expected_cost(&self) -> u8 {
+        match self {
+            Self::GraphBased => 0,
+            Self::GraphWithDoors => 1,
+            Self::YoloV8 => 2,
+            Self::HuggingFace => 3,
+            Self::VisionGPT4 => 4,
+            Self::VisionGPT5 => 5,
         }
     }
 }
@@ -182,6 +201,275 @@ impl BenchmarkStats {
             (self.successful as f64 / self.total_tests as f64) * 100.0
         }
     }
+
+    /// Write stats to a CSV file (durations stored as milliseconds)
+    pub fn save_csv(stats: &[BenchmarkStats], path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let mut writer = csv::Writer::from_path(path)?;
+        for s in stats {
+            writer.serialize(StatsRecord::new(None, s))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read stats previously written by `save_csv`
+    pub fn load_csv(path: impl AsRef<Path>) -> anyhow::Result<Vec<BenchmarkStats>> {
+        let mut reader = csv::Reader::from_path(path)?;
+        reader
+            .deserialize::<StatsRecord>()
+            .map(|record| Ok(record?.into_stats()))
+            .collect()
+    }
+}
+
+impl BenchmarkResult {
+    /// Whether this result counts as a failed test case; zero-room runs fail only with `fail_on_zero_rooms`
+    pub fn is_failure(&self, fail_on_zero_rooms: bool) -> bool {
+        !self.success || (fail_on_zero_rooms && self.rooms_detected == 0)
+    }
+
+    /// Write results as JUnit XML, one test suite per method and one test case per image
+    pub fn write_junit(
+        results: &[BenchmarkResult],
+        path: impl AsRef<Path>,
+        fail_on_zero_rooms: bool,
+    ) -> anyhow::Result<()> {
+        std::fs::write(path, Self::junit_xml(results, fail_on_zero_rooms))?;
+        Ok(())
+    }
+
+    /// Write results as a pretty-printed JSON array
+    pub fn write_json(results: &[BenchmarkResult], path: impl AsRef<Path>) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(results)?)?;
+        Ok(())
+    }
+
+    fn junit_xml(results: &[BenchmarkResult], fail_on_zero_rooms: bool) -> String {
+        // Suites in first-seen method order
+        let mut methods: Vec<DetectionMethod> = Vec::new();
+        for result in results {
+            if !methods.contains(&result.method) {
+                methods.push(result.method);
+            }
+        }
+
+        let total_failures = results.iter().filter(|r| r.is_failure(fail_on_zero_rooms)).count();
+        let total_time: f64 = results.iter().map(|r| r.execution_time.as_secs_f64()).sum();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"floorplan-detection\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+            results.len(),
+            total_failures,
+            total_time
+        ));
+
+        for method in methods {
+            let suite: Vec<&BenchmarkResult> = results.iter().filter(|r| r.method == method).collect();
+            let failures = suite.iter().filter(|r| r.is_failure(fail_on_zero_rooms)).count();
+            let time: f64 = suite.iter().map(|r| r.execution_time.as_secs_f64()).sum();
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+                xml_escape(method.name()),
+                suite.len(),
+                failures,
+                time
+            ));
+
+            for result in suite {
+                xml.push_str(&format!(
+                    "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                    xml_escape(method.name()),
+                    xml_escape(&result.image_path),
+                    result.execution_time.as_secs_f64()
+                ));
+                if result.is_failure(fail_on_zero_rooms) {
+                    let message = match &result.error {
+                        Some(error) if !result.success => error.clone(),
+                        _ if !result.success => "Detection failed".to_string(),
+                        _ => "No rooms detected".to_string(),
+                    };
+                    xml.push_str(&format!(
+                        ">\n      <failure message=\"{}\"/>\n    </testcase>\n",
+                        xml_escape(&message)
+                    ));
+                } else {
+                    xml.push_str("/>\n");
+                }
+            }
+
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Flat CSV row for `BenchmarkStats`, optionally tagged with a run timestamp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StatsRecord {
+    #[serde(default)]
+    run_timestamp: Option<String>,
+    method: DetectionMethod,
+    total_tests: usize,
+    successful: usize,
+    failed: usize,
+    avg_execution_time_ms: u64,
+    min_execution_time_ms: u64,
+    max_execution_time_ms: u64,
+    avg_rooms_per_image: f64,
+    avg_confidence: f64,
+}
+
+impl StatsRecord {
+    fn new(run_timestamp: Option<String>, stats: &BenchmarkStats) -> Self {
+        Self {
+            run_timestamp,
+            method: stats.method,
+            total_tests: stats.total_tests,
+            successful: stats.successful,
+            failed: stats.failed,
+            avg_execution_time_ms: stats.avg_execution_time.as_millis() as u64,
+            min_execution_time_ms: stats.min_execution_time.as_millis() as u64,
+            max_execution_time_ms: stats.max_execution_time.as_millis() as u64,
+            avg_rooms_per_image: stats.avg_rooms_per_image,
+            avg_confidence: stats.avg_confidence,
+        }
+    }
+
+    fn into_stats(self) -> BenchmarkStats {
+        BenchmarkStats {
+            method: self.method,
+            total_tests: self.total_tests,
+            successful: self.successful,
+            failed: self.failed,
+            avg_execution_time: Duration::from_millis(self.avg_execution_time_ms),
+            min_execution_time: Duration::from_millis(self.min_execution_time_ms),
+            max_execution_time: Duration::from_millis(self.max_execution_time_ms),
+            avg_rooms_per_image: self.avg_rooms_per_image,
+            avg_confidence: self.avg_confidence,
+        }
+    }
+}
+
+/// One benchmark run recorded in a `BenchmarkHistory`
+#[derive(Debug, Clone)]
+pub struct BenchmarkRun {
+    pub timestamp: String,
+    pub stats: Vec<BenchmarkStats>,
+}
+
+/// Change in a method's stats between two benchmark runs (current - previous)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsDelta {
+    pub method: DetectionMethod,
+    /// Milliseconds; positive means slower
+    pub avg_execution_time_ms: f64,
+    /// Relative change in average execution time (0.2 = 20% slower)
+    pub avg_execution_time_pct: f64,
+    /// Percentage points
+    pub success_rate: f64,
+    pub avg_rooms_per_image: f64,
+}
+
+/// Append-only CSV log of benchmark stats across runs
+pub struct BenchmarkHistory {
+    path: std::path::PathBuf,
+}
+
+impl BenchmarkHistory {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Append one run's stats, stamped with the current UTC time
+    pub fn append(&self, stats: &[BenchmarkStats]) -> anyhow::Result<()> {
+        self.append_with_timestamp(&chrono::Utc::now().to_rfc3339(), stats)
+    }
+
+    /// Append one run's stats under an explicit timestamp
+    pub fn append_with_timestamp(&self, timestamp: &str, stats: &[BenchmarkStats]) -> anyhow::Result<()> {
+        let write_header = !self.path.exists();
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(write_header)
+            .from_writer(file);
+        for s in stats {
+            writer.serialize(StatsRecord::new(Some(timestamp.to_string()), s))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// All recorded runs, oldest first
+    pub fn runs(&self) -> anyhow::Result<Vec<BenchmarkRun>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut runs: Vec<BenchmarkRun> = Vec::new();
+        let mut reader = csv::Reader::from_path(&self.path)?;
+        for record in reader.deserialize::<StatsRecord>() {
+            let record = record?;
+            let timestamp = record.run_timestamp.clone().unwrap_or_default();
+            match runs.last_mut() {
+                Some(run) if run.timestamp == timestamp => run.stats.push(record.into_stats()),
+                _ => runs.push(BenchmarkRun {
+                    timestamp,
+                    stats: vec![record.into_stats()],
+                }),
+            }
+        }
+
+        Ok(runs)
+    }
+
+    /// Per-method deltas between the two most recent runs
+    ///
+    /// Methods missing from either run are skipped. Returns an empty list when
+    /// fewer than two runs are recorded.
+    pub fn latest_deltas(&self) -> anyhow::Result<Vec<StatsDelta>> {
+        let runs = self.runs()?;
+        if runs.len() < 2 {
+            return Ok(Vec::new());
+        }
+
+        let previous = &runs[runs.len() - 2];
+        let current = &runs[runs.len() - 1];
+
+        Ok(current
+            .stats
+            .iter()
+            .filter_map(|cur| {
+                let prev = previous.stats.iter().find(|p| p.method == cur.method)?;
+                let prev_ms = prev.avg_execution_time.as_secs_f64() * 1000.0;
+                let cur_ms = cur.avg_execution_time.as_secs_f64() * 1000.0;
+
+                Some(StatsDelta {
+                    method: cur.method,
+                    avg_execution_time_ms: cur_ms - prev_ms,
+                    avg_execution_time_pct: if prev_ms > 0.0 { (cur_ms - prev_ms) / prev_ms } else { 0.0 },
+                    success_rate: cur.success_rate() - prev.success_rate(),
+                    avg_rooms_per_image: cur.avg_rooms_per_image - prev.avg_rooms_per_image,
+                })
+            })
+            .collect())
+    }
 }
 
 /// Comparison report between methods
@@ -191,6 +479,23 @@ pub struct ComparisonReport {
     pub results: Vec<DetectionResult>,
     pub winner: DetectionMethod,
     pub ranking: Vec<(DetectionMethod, f64)>, // (method, score)
+    #[serde(default)]
+    pub metadata: EnsembleMetadata,
+}
+
+/// Which methods an ensemble run actually executed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnsembleMetadata {
+    pub strategy: Option<EnsembleStrategy>,
+    /// Methods that ran to completion (successfully or not)
+    pub executed: Vec<DetectionMethod>,
+    /// Methods never run or cancelled (cascade short-circuit, fastest race)
+    pub skipped: Vec<DetectionMethod>,
+    /// Executed methods that returned an error
+    pub failed: Vec<(DetectionMethod, String)>,
+    /// Rooms fused across methods (VoteByArea only)
+    #[serde(default)]
+    pub merged_rooms: Vec<Room>,
 }
 
 impl ComparisonReport {
@@ -214,6 +519,141 @@ impl ComparisonReport {
             results,
             winner,
             ranking,
+            metadata: EnsembleMetadata::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(method: DetectionMethod, avg_ms: u64, successful: usize, avg_rooms: f64) -> BenchmarkStats {
+        BenchmarkStats {
+            method,
+            total_tests: 10,
+            successful,
+            failed: 10 - successful,
+            avg_execution_time: Duration::from_millis(avg_ms),
+            min_execution_time: Duration::from_millis(avg_ms / 2),
+            max_execution_time: Duration::from_millis(avg_ms * 2),
+            avg_rooms_per_image: avg_rooms,
+            avg_confidence: 0.8,
+        }
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("unified-detector-{}-{}.csv", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn result(method: DetectionMethod, image_path: &str, rooms_detected: usize, error: Option<&str>) -> BenchmarkResult {
+        BenchmarkResult {
+            method,
+            image_path: image_path.to_string(),
+            success: error.is_none(),
+            execution_time: Duration::from_millis(250),
+            rooms_detected,
+            avg_confidence: 0.8,
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_junit_has_testcase_per_method_and_image() {
+        let results = vec![
+            result(DetectionMethod::GraphBased, "data/a.png", 4, None),
+            result(DetectionMethod::GraphBased, "data/b.png", 0, None),
+            result(DetectionMethod::VisionGPT5, "data/a.png", 0, Some("API error: <500>")),
+            result(DetectionMethod::VisionGPT5, "data/b.png", 5, None),
+        ];
+
+        let xml = BenchmarkResult::junit_xml(&results, false);
+        assert_eq!(xml.matches("<testcase ").count(), 4);
+        assert_eq!(xml.matches("<testsuite ").count(), 2);
+        assert_eq!(xml.matches("<failure ").count(), 1);
+        assert!(xml.contains("<failure message=\"API error: &lt;500&gt;\"/>"));
+
+        // Zero-room results only fail when asked to
+        let strict = BenchmarkResult::junit_xml(&results, true);
+        assert_eq!(strict.matches("<failure ").count(), 2);
+        assert!(strict.contains("<failure message=\"No rooms detected\"/>"));
+    }
+
+    #[test]
+    fn test_write_json_round_trip() {
+        let path = temp_path("results").with_extension("json");
+        let results = vec![result(DetectionMethod::GraphBased, "data/a.png", 4, None)];
+
+        BenchmarkResult::write_json(&results, &path).unwrap();
+        let loaded: Vec<BenchmarkResult> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].rooms_detected, 4);
+    }
+
+    #[test]
+    fn test_stats_csv_round_trip() {
+        let path = temp_path("stats");
+        let original = vec![
+            stats(DetectionMethod::GraphBased, 120, 10, 4.0),
+            stats(DetectionMethod::VisionGPT5, 9500, 8, 5.5),
+        ];
+
+        BenchmarkStats::save_csv(&original, &path).unwrap();
+        let loaded = BenchmarkStats::load_csv(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[1].method, DetectionMethod::VisionGPT5);
+        assert_eq!(loaded[1].avg_execution_time, Duration::from_millis(9500));
+        assert_eq!(loaded[1].max_execution_time, Duration::from_millis(19000));
+        assert_eq!(loaded[1].successful, 8);
+    }
+
+    #[test]
+    fn test_history_deltas_use_last_two_runs() {
+        let path = temp_path("history");
+        let history = BenchmarkHistory::new(&path);
+
+        history
+            .append_with_timestamp("2025-01-01T00:00:00Z", &[stats(DetectionMethod::GraphBased, 50, 5, 1.0)])
+            .unwrap();
+        history
+            .append_with_timestamp(
+                "2025-01-02T00:00:00Z",
+                &[
+                    stats(DetectionMethod::GraphBased, 100, 10, 4.0),
+                    stats(DetectionMethod::GraphWithDoors, 200, 9, 3.0),
+                ],
+            )
+            .unwrap();
+        history
+            .append_with_timestamp(
+                "2025-01-03T00:00:00Z",
+                &[
+                    stats(DetectionMethod::GraphBased, 120, 9, 3.5),
+                    stats(DetectionMethod::GraphWithDoors, 200, 9, 3.0),
+                ],
+            )
+            .unwrap();
+
+        let runs = history.runs().unwrap();
+        let deltas = history.latest_deltas().unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(runs.len(), 3);
+        assert_eq!(deltas.len(), 2);
+
+        let graph = deltas.iter().find(|d| d.method == DetectionMethod::GraphBased).unwrap();
+        assert!((graph.avg_execution_time_ms - 20.0).abs() < 1e-9);
+        assert!((graph.avg_execution_time_pct - 0.2).abs() < 1e-9, "GraphBased got 20% slower");
+        assert!((graph.success_rate - -10.0).abs() < 1e-9);
+        assert!((graph.avg_rooms_per_image - -0.5).abs() < 1e-9);
+
+        let doors = deltas.iter().find(|d| d.method == DetectionMethod::GraphWithDoors).unwrap();
+        assert_eq!(doors.avg_execution_time_ms, 0.0);
+    }
+}
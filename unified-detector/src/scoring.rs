@@ -0,0 +1,126 @@
+//! Accuracy of detected rooms against labeled ground truth
+//!
+//! Rooms are paired one-to-one by bounding-box IoU (greedy, best overlap first);
+//! unmatched detections are false positives and unmatched truth rooms are misses.
+
+use crate::Room;
+use room_matching::{bbox_iou, match_boxes};
+use serde::{Deserialize, Serialize};
+
+/// IoU a detection needs with a truth room to count as finding it
+pub const DEFAULT_TRUTH_IOU: f64 = 0.5;
+
+/// Precision/recall of one detection run against ground truth
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectionScore {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// Mean bounding-box IoU over matched pairs (0 when nothing matched)
+    pub mean_iou: f64,
+    pub iou_threshold: f64,
+}
+
+/// Score `detected` rooms against `truth`, matching pairs with IoU of at least `iou_threshold`
+///
+/// With nothing detected precision is 1 (no false positives); with no truth rooms recall is 1.
+pub fn score_against_truth(detected: &[Room], truth: &[Room], iou_threshold: f64) -> DetectionScore {
+    let detected_boxes: Vec<[f64; 4]> = detected.iter().map(|r| r.bounding_box).collect();
+    let truth_boxes: Vec<[f64; 4]> = truth.iter().map(|r| r.bounding_box).collect();
+    let matching = match_boxes(&detected_boxes, &truth_boxes, iou_threshold);
+
+    let true_positives = matching.matched.len();
+    let precision = ratio(true_positives, detected.len());
+    let recall = ratio(true_positives, truth.len());
+    let f1 = if precision + recall > 0.0 {
+        2.0 * precision * recall / (precision + recall)
+    } else {
+        0.0
+    };
+
+    let mean_iou = if true_positives > 0 {
+        matching
+            .matched
+            .iter()
+            .map(|&(d, t)| bbox_iou(&detected_boxes[d], &truth_boxes[t]))
+            .sum::<f64>()
+            / true_positives as f64
+    } else {
+        0.0
+    };
+
+    DetectionScore {
+        true_positives,
+        false_positives: matching.only_a.len(),
+        false_negatives: matching.only_b.len(),
+        precision,
+        recall,
+        f1,
+        mean_iou,
+        iou_threshold,
+    }
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        1.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(id: usize, bounding_box: [f64; 4]) -> Room {
+        Room {
+            id,
+            room_type: "room".to_string(),
+            confidence: 0.9,
+            area: room_matching::bbox_area(&bounding_box),
+            bounding_box,
+            features: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_two_of_three_truth_rooms_found() {
+        let truth = vec![
+            room(0, [0.0, 0.0, 100.0, 100.0]),
+            room(1, [100.0, 0.0, 200.0, 100.0]),
+            room(2, [0.0, 100.0, 200.0, 200.0]),
+        ];
+        let detected = vec![room(0, [0.0, 0.0, 100.0, 100.0]), room(1, [100.0, 0.0, 200.0, 100.0])];
+
+        let score = score_against_truth(&detected, &truth, DEFAULT_TRUTH_IOU);
+
+        assert_eq!(score.true_positives, 2);
+        assert_eq!(score.false_positives, 0);
+        assert_eq!(score.false_negatives, 1);
+        assert!((score.recall - 2.0 / 3.0).abs() < 1e-12);
+        assert!((score.precision - 1.0).abs() < 1e-12);
+        assert!((score.f1 - 0.8).abs() < 1e-12);
+        assert!((score.mean_iou - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_low_overlap_detection_is_false_positive() {
+        let truth = vec![room(0, [0.0, 0.0, 100.0, 100.0])];
+        // IoU 1/3 with the truth room
+        let detected = vec![room(0, [50.0, 0.0, 150.0, 100.0])];
+
+        let loose = score_against_truth(&detected, &truth, 0.3);
+        assert_eq!(loose.true_positives, 1);
+        assert!((loose.mean_iou - 1.0 / 3.0).abs() < 1e-12);
+
+        let strict = score_against_truth(&detected, &truth, DEFAULT_TRUTH_IOU);
+        assert_eq!(strict.true_positives, 0);
+        assert_eq!(strict.false_positives, 1);
+        assert_eq!(strict.f1, 0.0);
+        assert_eq!(strict.mean_iou, 0.0);
+    }
+}
@@ -1,11 +1,27 @@
-/// YOLO detector interface for ONNX model inference
-///
-/// This module provides a trait-based interface for YOLO detection.
-/// The actual ONNX runtime implementation will be added once the model is trained.
+//! YOLO detector interface for ONNX model inference
+//!
+//! This module provides a trait-based interface for YOLO detection.
+//! The ONNX runtime implementation is compiled in with the `onnx` feature;
+//! without it the stub detector is used.
 
 use crate::{Room, DetectionResult, DetectionMethod, DetectionMetadata};
 use std::path::Path;
 
+/// Environment variable holding the path to the YOLOv8 ONNX model
+pub const YOLO_MODEL_PATH_ENV: &str = "YOLO_MODEL_PATH";
+
+/// Default room class labels, in model class-id order
+const DEFAULT_CLASS_NAMES: &[&str] = &[
+    "bedroom",
+    "kitchen",
+    "bathroom",
+    "living_room",
+    "dining_room",
+    "hallway",
+    "closet",
+    "office",
+];
+
 /// YOLO detection configuration
 #[derive(Debug, Clone)]
 pub struct YoloConfig {
@@ -17,19 +33,33 @@ pub struct YoloConfig {
     pub nms_threshold: f64,
     /// Input image size (width, height)
     pub input_size: (u32, u32),
+    /// Class labels indexed by class id (used as room_type)
+    pub class_names: Vec<String>,
 }
 
 impl Default for YoloConfig {
     fn default() -> Self {
         Self {
-            model_path: "yolo-training/runs/detect/train/weights/best.onnx".to_string(),
+            model_path: std::env::var(YOLO_MODEL_PATH_ENV)
+                .unwrap_or_else(|_| "yolo-training/runs/detect/train/weights/best.onnx".to_string()),
             confidence_threshold: 0.25,
             nms_threshold: 0.45,
             input_size: (640, 640),
+            class_names: DEFAULT_CLASS_NAMES.iter().map(|s| s.to_string()).collect(),
         }
     }
 }
 
+impl YoloConfig {
+    /// Label for a class id, falling back to `class_<id>` for unknown ids
+    pub fn class_name(&self, class_id: usize) -> String {
+        self.class_names
+            .get(class_id)
+            .cloned()
+            .unwrap_or_else(|| format!("class_{}", class_id))
+    }
+}
+
 /// YOLO bounding box detection
 #[derive(Debug, Clone)]
 pub struct YoloDetection {
@@ -71,10 +101,175 @@ impl YoloDetector for StubYoloDetector {
     }
 }
 
+/// ONNX Runtime backed YOLOv8 detector
+#[cfg(feature = "onnx")]
+pub struct OnnxYoloDetector {
+    config: YoloConfig,
+    session: ort::session::Session,
+}
+
+#[cfg(feature = "onnx")]
+impl OnnxYoloDetector {
+    pub fn new(config: YoloConfig) -> anyhow::Result<Self> {
+        let session = ort::session::Session::builder()?
+            .with_optimization_level(ort::session::builder::GraphOptimizationLevel::Level3)?
+            .commit_from_file(&config.model_path)
+            .map_err(|e| anyhow::anyhow!("Failed to load YOLO model {}: {}", config.model_path, e))?;
+
+        Ok(Self { config, session })
+    }
+}
+
+#[cfg(feature = "onnx")]
+impl YoloDetector for OnnxYoloDetector {
+    fn detect(&self, image_bytes: &[u8]) -> anyhow::Result<Vec<YoloDetection>> {
+        let img = image::load_from_memory(image_bytes)?.to_rgb8();
+        let (orig_w, orig_h) = img.dimensions();
+        let (input_w, input_h) = self.config.input_size;
+
+        // Resize to the model input and lay out as normalized NCHW
+        let resized = image::imageops::resize(&img, input_w, input_h, image::imageops::FilterType::Triangle);
+        let plane = (input_w * input_h) as usize;
+        let mut input = vec![0f32; 3 * plane];
+        for (x, y, pixel) in resized.enumerate_pixels() {
+            let idx = (y * input_w + x) as usize;
+            for c in 0..3 {
+                input[c * plane + idx] = pixel[c] as f32 / 255.0;
+            }
+        }
+
+        let tensor = ort::value::Tensor::from_array((
+            [1usize, 3, input_h as usize, input_w as usize],
+            input.into_boxed_slice(),
+        ))?;
+        let outputs = self.session.run(ort::inputs![tensor]?)?;
+        let (shape, data) = outputs[0].try_extract_raw_tensor::<f32>()?;
+
+        let scale = (orig_w as f32 / input_w as f32, orig_h as f32 / input_h as f32);
+        let detections = decode_yolov8_output(&shape, data, &self.config, scale)?;
+
+        Ok(non_max_suppression(detections, self.config.nms_threshold as f32))
+    }
+
+    fn model_info(&self) -> String {
+        format!("ONNX YOLOv8 Detector (model path: {})", self.config.model_path)
+    }
+}
+
+/// Decode raw YOLOv8 output `[1, 4 + num_classes, num_anchors]` into detections
+///
+/// Boxes are converted from center/size in model input space to corner
+/// coordinates in original image space using `scale` (x, y).
+pub fn decode_yolov8_output(
+    shape: &[i64],
+    data: &[f32],
+    config: &YoloConfig,
+    scale: (f32, f32),
+) -> anyhow::Result<Vec<YoloDetection>> {
+    if shape.len() != 3 || shape[1] < 5 {
+        return Err(anyhow::anyhow!("Unexpected YOLOv8 output shape: {:?}", shape));
+    }
+
+    let channels = shape[1] as usize;
+    let anchors = shape[2] as usize;
+    if data.len() < channels * anchors {
+        return Err(anyhow::anyhow!(
+            "YOLOv8 output has {} values, expected {}",
+            data.len(),
+            channels * anchors
+        ));
+    }
+
+    let at = |channel: usize, anchor: usize| data[channel * anchors + anchor];
+    let mut detections = Vec::new();
+
+    for anchor in 0..anchors {
+        let (class_id, score) = (4..channels)
+            .map(|c| (c - 4, at(c, anchor)))
+            .fold((0, f32::MIN), |best, cur| if cur.1 > best.1 { cur } else { best });
+
+        if (score as f64) < config.confidence_threshold {
+            continue;
+        }
+
+        let (cx, cy, w, h) = (at(0, anchor), at(1, anchor), at(2, anchor), at(3, anchor));
+        detections.push(YoloDetection {
+            class_id,
+            class_name: config.class_name(class_id),
+            confidence: score,
+            bbox: [
+                (cx - w / 2.0) * scale.0,
+                (cy - h / 2.0) * scale.1,
+                (cx + w / 2.0) * scale.0,
+                (cy + h / 2.0) * scale.1,
+            ],
+        });
+    }
+
+    Ok(detections)
+}
+
+/// Intersection-over-union of two `[x1, y1, x2, y2]` boxes
+pub fn bbox_iou(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    let ix = (a[2].min(b[2]) - a[0].max(b[0])).max(0.0);
+    let iy = (a[3].min(b[3]) - a[1].max(b[1])).max(0.0);
+    let intersection = ix * iy;
+
+    let area_a = (a[2] - a[0]).max(0.0) * (a[3] - a[1]).max(0.0);
+    let area_b = (b[2] - b[0]).max(0.0) * (b[3] - b[1]).max(0.0);
+    let union = area_a + area_b - intersection;
+
+    if union <= 0.0 {
+        0.0
+    } else {
+        intersection / union
+    }
+}
+
+/// Class-agnostic non-max suppression
+///
+/// Keeps the highest-confidence detection and drops every other detection whose
+/// IoU with a kept box exceeds `iou_threshold`, so overlapping boxes become one room.
+pub fn non_max_suppression(mut detections: Vec<YoloDetection>, iou_threshold: f32) -> Vec<YoloDetection> {
+    detections.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut kept: Vec<YoloDetection> = Vec::new();
+    for det in detections {
+        if kept.iter().all(|k| bbox_iou(&k.bbox, &det.bbox) <= iou_threshold) {
+            kept.push(det);
+        }
+    }
+
+    kept
+}
+
+/// Create the best detector for this build: ONNX Runtime with the `onnx` feature, else the stub
+pub fn create_detector(config: YoloConfig) -> anyhow::Result<Box<dyn YoloDetector>> {
+    #[cfg(feature = "onnx")]
+    {
+        Ok(Box::new(OnnxYoloDetector::new(config)?))
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    {
+        Ok(Box::new(StubYoloDetector::new(config)?))
+    }
+}
+
+/// Run YOLO detection on an image and wrap the result as a `DetectionResult`
+pub fn detect_rooms(detector: &dyn YoloDetector, image_bytes: &[u8]) -> anyhow::Result<DetectionResult> {
+    let start = std::time::Instant::now();
+    let detections = detector.detect(image_bytes)?;
+    Ok(create_detection_result(detections, start.elapsed()))
+}
+
 /// Check if YOLO model is available
+///
+/// True when the model file (from `YOLO_MODEL_PATH` or the default path) exists
+/// and the ONNX runtime is compiled in.
 pub fn is_yolo_available() -> bool {
     let default_path = YoloConfig::default().model_path;
-    Path::new(&default_path).exists()
+    cfg!(feature = "onnx") && Path::new(&default_path).exists()
 }
 
 /// Convert YOLO detections to unified Room format
@@ -181,4 +376,59 @@ mod tests {
         assert_eq!(rooms[1].room_type, "kitchen");
         assert_eq!(rooms[1].confidence, 0.88);
     }
+
+    fn detection(confidence: f32, bbox: [f32; 4]) -> YoloDetection {
+        YoloDetection {
+            class_id: 0,
+            class_name: "bedroom".to_string(),
+            confidence,
+            bbox,
+        }
+    }
+
+    #[test]
+    fn test_bbox_iou() {
+        let a = [0.0, 0.0, 10.0, 10.0];
+        assert!((bbox_iou(&a, &a) - 1.0).abs() < 1e-6);
+        assert_eq!(bbox_iou(&a, &[20.0, 20.0, 30.0, 30.0]), 0.0);
+        // Half overlap: intersection 50, union 150
+        assert!((bbox_iou(&a, &[5.0, 0.0, 15.0, 10.0]) - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_nms_collapses_overlapping_boxes() {
+        let detections = vec![
+            detection(0.7, [0.0, 0.0, 100.0, 100.0]),
+            detection(0.9, [5.0, 5.0, 105.0, 105.0]),
+            detection(0.8, [300.0, 300.0, 400.0, 400.0]),
+        ];
+
+        let kept = non_max_suppression(detections, 0.45);
+
+        assert_eq!(kept.len(), 2, "Overlapping boxes should collapse into one");
+        assert_eq!(kept[0].confidence, 0.9);
+        assert_eq!(kept[1].confidence, 0.8);
+    }
+
+    #[test]
+    fn test_decode_yolov8_output() {
+        // Two classes, two anchors: [cx, cy, w, h, score_0, score_1] per anchor
+        let shape = [1i64, 6, 2];
+        let data = [
+            50.0, 10.0, // cx
+            50.0, 10.0, // cy
+            20.0, 4.0, // w
+            40.0, 4.0, // h
+            0.1, 0.05, // class 0 scores
+            0.9, 0.1, // class 1 scores
+        ];
+        let config = YoloConfig::default();
+
+        let detections = decode_yolov8_output(&shape, &data, &config, (2.0, 1.0)).unwrap();
+
+        assert_eq!(detections.len(), 1, "Low-score anchor should be dropped");
+        assert_eq!(detections[0].class_id, 1);
+        assert_eq!(detections[0].class_name, "kitchen");
+        assert_eq!(detections[0].bbox, [80.0, 30.0, 120.0, 70.0]);
+    }
 }
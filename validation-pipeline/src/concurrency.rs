@@ -0,0 +1,38 @@
+use futures::stream::{self, StreamExt};
+use std::future::Future;
+use tokio::sync::Semaphore;
+
+/// MAX_CONCURRENCY (default 4): images processed at once
+pub fn max_concurrency() -> usize {
+    std::env::var("MAX_CONCURRENCY")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&n: &usize| n > 0)
+        .unwrap_or(4)
+}
+
+/// Run `process` on every item with at most `limit` in flight; results come back in item order
+pub async fn process_concurrently<I, T, F, Fut>(items: Vec<I>, limit: usize, process: F) -> Vec<T>
+where
+    F: Fn(I) -> Fut,
+    Fut: Future<Output = T>,
+{
+    let limit = limit.max(1);
+    let semaphore = Semaphore::new(limit);
+
+    let mut results: Vec<(usize, T)> = stream::iter(items.into_iter().enumerate())
+        .map(|(index, item)| {
+            let semaphore = &semaphore;
+            let work = process(item);
+            async move {
+                let _permit = semaphore.acquire().await.expect("semaphore is never closed");
+                (index, work.await)
+            }
+        })
+        .buffer_unordered(limit)
+        .collect()
+        .await;
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
@@ -1,25 +1,36 @@
 use aws_config::BehaviorVersion;
-use aws_sdk_textract::{
-    primitives::Blob,
-    types::{Document, FeatureType},
-    Client as TextractClient,
-};
+use aws_sdk_s3::Client as S3Client;
+use aws_sdk_textract::Client as TextractClient;
 use hf_floorplan_loader::{FloorplanDataset, LoaderError};
+use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
+mod concurrency;
+mod s3_source;
 mod textract_parser;
+mod textract_retry;
+use concurrency::{max_concurrency, process_concurrently};
+use s3_source::{list_images, process_objects, S3Uri};
 use textract_parser::*;
+use textract_retry::{analyze_with_retry, DocumentAnalyzer, RetryPolicy};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct ValidationResult {
     file_name: String,
     success: bool,
     extracted_lines_count: usize,
+    room_labels_count: usize,
+    /// Room names read from layout text, with their centers in 0-1000 page units
+    #[serde(default)]
+    room_labels: Vec<RoomLabel>,
+    /// Textract retries after throttling or transient errors
+    #[serde(default)]
+    retry_count: u32,
     error: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct ValidationReport {
     total_processed: usize,
     successful: usize,
@@ -27,25 +38,52 @@ struct ValidationReport {
     results: Vec<ValidationResult>,
 }
 
+impl ValidationReport {
+    /// Share of processed images that succeeded, 0 when nothing ran
+    fn success_rate(&self) -> f64 {
+        if self.total_processed == 0 {
+            0.0
+        } else {
+            self.successful as f64 / self.total_processed as f64
+        }
+    }
+
+    /// Average extracted lines over successful images, 0 when none succeeded
+    fn mean_lines_per_success(&self) -> f64 {
+        let successes: Vec<usize> = self
+            .results
+            .iter()
+            .filter(|r| r.success)
+            .map(|r| r.extracted_lines_count)
+            .collect();
+        if successes.is_empty() {
+            0.0
+        } else {
+            successes.iter().sum::<usize>() as f64 / successes.len() as f64
+        }
+    }
+}
+
+/// JSON report: the full report plus aggregate stats for dashboards
+#[derive(Serialize)]
+struct ReportJson<'a> {
+    #[serde(flatten)]
+    report: &'a ValidationReport,
+    success_rate: f64,
+    mean_lines_per_success: f64,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🚀 Starting Floorplan Validation Pipeline");
     println!("==========================================\n");
 
-    // Load HuggingFace dataset
-    println!("📁 Loading HuggingFace floorplan dataset...");
-    let dataset = match FloorplanDataset::new() {
-        Ok(ds) => ds,
-        Err(e) => {
-            eprintln!("❌ Failed to load dataset: {:?}", e);
-            eprintln!("Make sure the HuggingFace dataset is downloaded to:");
-            eprintln!("  ~/.cache/huggingface/hub/datasets--umesh16071973--New_Floorplan_demo_dataset/");
-            return Ok(());
-        }
+    // S3_DATASET_URI=s3://bucket/prefix reads images from S3 instead of the local dataset
+    let s3_uri = match std::env::var("S3_DATASET_URI") {
+        Ok(uri) => Some(S3Uri::parse(&uri)?),
+        Err(_) => None,
     };
 
-    println!("✅ Loaded {} floorplan images\n", dataset.len());
-
     // Initialize AWS Textract client
     println!("🔧 Initializing AWS Textract client...");
     let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
@@ -65,12 +103,42 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let textract_client = TextractClient::new(&config);
     println!("✅ AWS Textract client ready (Region: {})\n", config.region().map(|r| r.as_ref()).unwrap_or("default"));
 
-    // Get sample size from environment or default to 5
-    let sample_size = std::env::var("SAMPLE_SIZE")
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(5)
-        .min(dataset.len());
+    let source = match s3_uri {
+        Some(uri) => {
+            println!("☁️  Listing floorplan images in s3://{}/{}...", uri.bucket, uri.prefix);
+            let client = S3Client::new(&config);
+            let keys = list_images(&client, &uri).await?;
+            println!("✅ Found {} floorplan images\n", keys.len());
+            ImageSource::S3 { client, uri, keys }
+        }
+        None => {
+            // Load HuggingFace dataset
+            println!("📁 Loading HuggingFace floorplan dataset...");
+            let dataset = match FloorplanDataset::new() {
+                Ok(ds) => ds,
+                Err(e) => {
+                    eprintln!("❌ Failed to load dataset: {:?}", e);
+                    eprintln!("Make sure the HuggingFace dataset is downloaded to:");
+                    eprintln!("  ~/.cache/huggingface/hub/datasets--umesh16071973--New_Floorplan_demo_dataset/");
+                    return Ok(());
+                }
+            };
+
+            println!("✅ Loaded {} floorplan images\n", dataset.len());
+            ImageSource::Local(dataset)
+        }
+    };
+
+    let retry_policy = RetryPolicy::from_env();
+    let max_concurrency = max_concurrency();
+    let sample_size = sample_size(std::env::var("SAMPLE_SIZE").ok().as_deref(), source.len());
+
+    // ROOM_LABELS=1 also reads layout text blocks as room labels
+    let label_mode = if std::env::var("ROOM_LABELS").is_ok_and(|v| v != "0") {
+        LabelMode::Layout
+    } else {
+        LabelMode::Off
+    };
 
     // Calculate estimated cost (Textract pricing: ~$1.50 per 1000 pages)
     let estimated_cost = (sample_size as f64 * 0.0015).max(0.01);
@@ -79,12 +147,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("   Estimated cost: ${:.2}", estimated_cost);
     println!("   (Approximate: $1.50 per 1000 pages)");
 
-    // Ask for confirmation before processing
-    println!("\n📋 Press Enter to continue or Ctrl+C to cancel...");
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
+    // Ask for confirmation before processing, unless running non-interactively
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if should_prompt(&args, std::env::var("CONFIRM").ok().as_deref()) {
+        println!("\n📋 Press Enter to continue or Ctrl+C to cancel...");
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+    }
 
-    println!("\n🔍 Processing {} images...\n", sample_size);
+    println!("\n🔍 Processing {} images, {} at a time...\n", sample_size, max_concurrency);
 
     let mut report = ValidationReport {
         total_processed: 0,
@@ -93,71 +164,155 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         results: Vec::new(),
     };
 
-    for (i, floorplan) in dataset.all().iter().take(sample_size).enumerate() {
-        println!("Processing {}/{}: {}", i + 1, sample_size, floorplan.file_name);
-
-        let result = process_floorplan(&textract_client, floorplan).await;
-
-        match &result {
-            Ok(lines) => {
-                println!("  ✅ Extracted {} lines", lines.len());
-                report.results.push(ValidationResult {
-                    file_name: floorplan.file_name.clone(),
-                    success: true,
-                    extracted_lines_count: lines.len(),
-                    error: None,
-                });
-                report.successful += 1;
-            }
-            Err(e) => {
-                println!("  ❌ Failed: {}", e);
-                report.results.push(ValidationResult {
-                    file_name: floorplan.file_name.clone(),
-                    success: false,
-                    extracted_lines_count: 0,
-                    error: Some(e.to_string()),
-                });
-                report.failed += 1;
-            }
+    // Images run concurrently; results are reported in dataset order
+    let textract = &textract_client;
+    let outcomes: Vec<(String, FloorplanOutcome)> = match &source {
+        ImageSource::Local(dataset) => {
+            let floorplans = dataset.all().iter().take(sample_size).collect();
+            process_concurrently(floorplans, max_concurrency, |floorplan| async move {
+                let outcome = match tokio::fs::read(&floorplan.image_path).await {
+                    Ok(image_bytes) => process_floorplan(textract, image_bytes, label_mode, retry_policy).await,
+                    Err(e) => FloorplanOutcome::failed(format!("Failed to read image: {}", e)),
+                };
+                (floorplan.file_name.clone(), outcome)
+            })
+            .await
         }
+        ImageSource::S3 { client, uri, keys } => {
+            // Each object's bytes go straight into the Textract request
+            process_objects(client, &uri.bucket, &keys[..sample_size], max_concurrency, |_, image_bytes| async move {
+                Ok(process_floorplan(textract, image_bytes, label_mode, retry_policy).await)
+            })
+            .await
+            .into_iter()
+            .map(|(key, result)| (key, result.unwrap_or_else(FloorplanOutcome::failed)))
+            .collect()
+        }
+    };
 
-        report.total_processed += 1;
-        println!();
+    for (i, (file_name, outcome)) in outcomes.into_iter().enumerate() {
+        println!("Processed {}/{}: {}", i + 1, sample_size, file_name);
+        record_result(&mut report, file_name, outcome);
     }
 
     // Print summary report
     print_report(&report);
 
+    // REPORT_OUTPUT=path/to/report.json also writes the report as JSON
+    if let Ok(path) = std::env::var("REPORT_OUTPUT") {
+        write_report_json(&report, Path::new(&path))?;
+        println!("📝 JSON report written to {}", path);
+    }
+
     Ok(())
 }
 
+/// Images to process: SAMPLE_SIZE (default 5, 0 for all) capped at what's available
+fn sample_size(requested: Option<&str>, available: usize) -> usize {
+    match requested.and_then(|s| s.trim().parse::<usize>().ok()).unwrap_or(5) {
+        0 => available,
+        n => n.min(available),
+    }
+}
+
+/// Whether to wait for Enter before calling Textract; `--yes`/`-y` or CONFIRM=1 skip the prompt
+fn should_prompt(args: &[String], confirm: Option<&str>) -> bool {
+    let yes_flag = args.iter().any(|a| a == "--yes" || a == "-y");
+    let confirmed = confirm.is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+    !(yes_flag || confirmed)
+}
+
+/// Where floorplan images are read from
+enum ImageSource {
+    Local(FloorplanDataset),
+    S3 {
+        client: S3Client,
+        uri: S3Uri,
+        keys: Vec<String>,
+    },
+}
+
+impl ImageSource {
+    fn len(&self) -> usize {
+        match self {
+            ImageSource::Local(dataset) => dataset.len(),
+            ImageSource::S3 { keys, .. } => keys.len(),
+        }
+    }
+}
+
+/// What processing one image produced, and how many Textract retries it took
+struct FloorplanOutcome {
+    result: Result<TextractExtraction, String>,
+    retry_count: u32,
+}
+
+impl FloorplanOutcome {
+    /// Failed before Textract was called
+    fn failed(error: String) -> Self {
+        Self {
+            result: Err(error),
+            retry_count: 0,
+        }
+    }
+}
+
+fn record_result(report: &mut ValidationReport, file_name: String, outcome: FloorplanOutcome) {
+    let retry_count = outcome.retry_count;
+    match outcome.result {
+        Ok(extraction) => {
+            println!(
+                "  ✅ Extracted {} lines, {} room labels",
+                extraction.lines.len(),
+                extraction.room_labels.len()
+            );
+            for label in &extraction.room_labels {
+                println!("     🏷  {} at ({:.0}, {:.0})", label.text, label.center.x, label.center.y);
+            }
+            report.results.push(ValidationResult {
+                file_name,
+                success: true,
+                extracted_lines_count: extraction.lines.len(),
+                room_labels_count: extraction.room_labels.len(),
+                room_labels: extraction.room_labels,
+                retry_count,
+                error: None,
+            });
+            report.successful += 1;
+        }
+        Err(e) => {
+            println!("  ❌ Failed: {}", e);
+            report.results.push(ValidationResult {
+                file_name,
+                success: false,
+                extracted_lines_count: 0,
+                room_labels_count: 0,
+                room_labels: Vec::new(),
+                retry_count,
+                error: Some(e),
+            });
+            report.failed += 1;
+        }
+    }
+
+    report.total_processed += 1;
+    println!();
+}
+
 async fn process_floorplan(
-    client: &TextractClient,
-    floorplan: &hf_floorplan_loader::FloorplanData,
-) -> Result<Vec<Line>, String> {
-    // Read image file
-    let image_bytes = fs::read(&floorplan.image_path)
-        .map_err(|e| format!("Failed to read image: {}", e))?;
-
-    // Create document blob
-    let document = Document::builder()
-        .bytes(Blob::new(image_bytes))
-        .build()
-        .map_err(|e| format!("Failed to build document: {}", e))?;
-
-    // Call Textract
-    let response = client
-        .analyze_document()
-        .document(document)
-        .feature_types(FeatureType::Layout)
-        .send()
-        .await
-        .map_err(|e| format!("Textract API error: {}", e))?;
-
-    // Parse response into lines
-    let lines = parse_textract_response(response)?;
-
-    Ok(lines)
+    analyzer: &impl DocumentAnalyzer,
+    image_bytes: Vec<u8>,
+    label_mode: LabelMode,
+    retry_policy: RetryPolicy,
+) -> FloorplanOutcome {
+    // Call Textract, retrying throttled and transient failures
+    let (response, retry_count) = analyze_with_retry(analyzer, &image_bytes, retry_policy).await;
+
+    // Parse response into lines and room labels
+    FloorplanOutcome {
+        result: response.and_then(|response| parse_textract_response(&response, label_mode)),
+        retry_count,
+    }
 }
 
 fn print_report(report: &ValidationReport) {
@@ -176,29 +331,277 @@ fn print_report(report: &ValidationReport) {
     );
 
     println!("\n📋 Detailed Results:");
-    println!("{:<15} {:<10} {:<15} {}", "File", "Status", "Lines", "Error");
-    println!("{}", "-".repeat(70));
+    println!("File            Status     Lines      Labels     Retries    Error");
+    println!("{}", "-".repeat(80));
 
     for result in &report.results {
         let status = if result.success { "✅ OK" } else { "❌ FAIL" };
         let error = result.error.as_deref().unwrap_or("");
         println!(
-            "{:<15} {:<10} {:<15} {}",
-            result.file_name, status, result.extracted_lines_count, error
+            "{:<15} {:<10} {:<10} {:<10} {:<10} {}",
+            result.file_name,
+            status,
+            result.extracted_lines_count,
+            result.room_labels_count,
+            result.retry_count,
+            error
         );
     }
 
     println!("\n==========================================");
 }
 
+fn write_report_json(report: &ValidationReport, path: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&ReportJson {
+        report,
+        success_rate: report.success_rate(),
+        mean_lines_per_success: report.mean_lines_per_success(),
+    })
+    .map_err(|e| format!("Failed to serialize report: {}", e))?;
+
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
 #[derive(Debug, Clone)]
 pub struct Line {
     pub start: Point,
     pub end: Point,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_textract::operation::analyze_document::AnalyzeDocumentOutput;
+
+    fn sample_report() -> ValidationReport {
+        ValidationReport {
+            total_processed: 3,
+            successful: 2,
+            failed: 1,
+            results: vec![
+                ValidationResult {
+                    file_name: "a.png".to_string(),
+                    success: true,
+                    extracted_lines_count: 10,
+                    room_labels_count: 2,
+                    room_labels: vec![
+                        RoomLabel {
+                            text: "Kitchen".to_string(),
+                            center: Point { x: 250.0, y: 310.0 },
+                        },
+                        RoomLabel {
+                            text: "Bedroom".to_string(),
+                            center: Point { x: 700.0, y: 300.0 },
+                        },
+                    ],
+                    retry_count: 0,
+                    error: None,
+                },
+                ValidationResult {
+                    file_name: "b.png".to_string(),
+                    success: true,
+                    extracted_lines_count: 20,
+                    room_labels_count: 0,
+                    room_labels: Vec::new(),
+                    retry_count: 1,
+                    error: None,
+                },
+                ValidationResult {
+                    file_name: "c.png".to_string(),
+                    success: false,
+                    extracted_lines_count: 0,
+                    room_labels_count: 0,
+                    room_labels: Vec::new(),
+                    retry_count: 3,
+                    error: Some("Textract API error: throttled".to_string()),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_report_round_trips_through_json() {
+        let report = sample_report();
+        let json = serde_json::to_string(&report).unwrap();
+        let parsed: ValidationReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, report);
+    }
+
+    #[test]
+    fn test_write_report_json_includes_stats() {
+        let report = sample_report();
+        let path = std::env::temp_dir().join(format!("validation-report-{}.json", std::process::id()));
+
+        write_report_json(&report, &path).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let parsed: ValidationReport = serde_json::from_str(&written).unwrap();
+        assert_eq!(parsed, report);
+
+        let value: serde_json::Value = serde_json::from_str(&written).unwrap();
+        assert!((value["success_rate"].as_f64().unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(value["mean_lines_per_success"].as_f64(), Some(15.0));
+        assert_eq!(value["results"][0]["room_labels"][0]["text"], "Kitchen");
+        assert_eq!(value["results"][0]["room_labels"][0]["center"]["x"], 250.0);
+    }
+
+    #[test]
+    fn test_non_interactive_skips_prompt() {
+        let yes = vec!["--yes".to_string()];
+        assert!(!should_prompt(&yes, None));
+        assert!(!should_prompt(&["-y".to_string()], None));
+        assert!(!should_prompt(&[], Some("1")));
+        assert!(!should_prompt(&[], Some("true")));
+
+        assert!(should_prompt(&[], None));
+        assert!(should_prompt(&[], Some("0")));
+    }
+
+    #[test]
+    fn test_sample_size_zero_means_all() {
+        assert_eq!(sample_size(Some("0"), 42), 42);
+        assert_eq!(sample_size(None, 42), 5);
+        assert_eq!(sample_size(Some("10"), 3), 3);
+        assert_eq!(sample_size(Some("abc"), 42), 5);
+    }
+
+    /// Throttles a set number of calls, then returns one wall-like LINE block
+    struct FlakyAnalyzer {
+        failures_left: std::sync::atomic::AtomicU32,
+    }
+
+    impl DocumentAnalyzer for FlakyAnalyzer {
+        async fn analyze(
+            &self,
+            _image_bytes: &[u8],
+        ) -> Result<AnalyzeDocumentOutput, textract_retry::AnalyzeError> {
+            use std::sync::atomic::Ordering;
+            if self.failures_left.load(Ordering::SeqCst) > 0 {
+                self.failures_left.fetch_sub(1, Ordering::SeqCst);
+                return Err(textract_retry::AnalyzeError {
+                    message: "ProvisionedThroughputExceededException".to_string(),
+                    retryable: true,
+                });
+            }
+
+            let bbox = aws_sdk_textract::types::BoundingBox::builder()
+                .left(0.1)
+                .top(0.5)
+                .width(0.6)
+                .height(0.01)
+                .build();
+            let block = aws_sdk_textract::types::Block::builder()
+                .id("wall")
+                .block_type(aws_sdk_textract::types::BlockType::Line)
+                .geometry(aws_sdk_textract::types::Geometry::builder().bounding_box(bbox).build())
+                .build();
+            Ok(AnalyzeDocumentOutput::builder().blocks(block).build())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_throttled_twice_then_success_records_retries() {
+        let analyzer = FlakyAnalyzer {
+            failures_left: std::sync::atomic::AtomicU32::new(2),
+        };
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: std::time::Duration::ZERO,
+        };
+
+        let outcome = process_floorplan(&analyzer, b"png".to_vec(), LabelMode::Off, policy).await;
+        let mut report = ValidationReport {
+            total_processed: 0,
+            successful: 0,
+            failed: 0,
+            results: Vec::new(),
+        };
+        record_result(&mut report, "plan.png".to_string(), outcome);
+
+        assert_eq!(report.successful, 1);
+        let result = &report.results[0];
+        assert!(result.success);
+        assert_eq!(result.retry_count, 2);
+        assert_eq!(result.extracted_lines_count, 1);
+    }
+
+    /// Takes a fixed time per call, like a Textract round trip, then returns one LINE block
+    struct SlowAnalyzer;
+
+    impl DocumentAnalyzer for SlowAnalyzer {
+        async fn analyze(
+            &self,
+            image_bytes: &[u8],
+        ) -> Result<AnalyzeDocumentOutput, textract_retry::AnalyzeError> {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            FlakyAnalyzer {
+                failures_left: std::sync::atomic::AtomicU32::new(0),
+            }
+            .analyze(image_bytes)
+            .await
+        }
+    }
+
+    async fn run_slow_batch(max_concurrency: usize) -> (ValidationReport, std::time::Duration) {
+        let images: Vec<(String, Vec<u8>)> = (0..8).map(|i| (format!("plan-{}.png", i), vec![i as u8])).collect();
+        let policy = RetryPolicy {
+            max_retries: 0,
+            base_delay: std::time::Duration::ZERO,
+        };
+
+        let start = std::time::Instant::now();
+        let outcomes = process_concurrently(images, max_concurrency, |(name, bytes)| async move {
+            (name, process_floorplan(&SlowAnalyzer, bytes, LabelMode::Off, policy).await)
+        })
+        .await;
+        let elapsed = start.elapsed();
+
+        let mut report = ValidationReport {
+            total_processed: 0,
+            successful: 0,
+            failed: 0,
+            results: Vec::new(),
+        };
+        for (name, outcome) in outcomes {
+            record_result(&mut report, name, outcome);
+        }
+        (report, elapsed)
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_processing_faster_with_same_results() {
+        let (sequential, sequential_time) = run_slow_batch(1).await;
+        let (concurrent, concurrent_time) = run_slow_batch(4).await;
+
+        assert_eq!(concurrent, sequential);
+        assert_eq!(concurrent.successful, 8);
+        let names: Vec<&str> = concurrent.results.iter().map(|r| r.file_name.as_str()).collect();
+        assert_eq!(names[0], "plan-0.png");
+        assert_eq!(names[7], "plan-7.png");
+        assert!(
+            concurrent_time < sequential_time,
+            "concurrent {:?} vs sequential {:?}",
+            concurrent_time,
+            sequential_time
+        );
+    }
+
+    #[test]
+    fn test_stats_for_empty_report() {
+        let report = ValidationReport {
+            total_processed: 0,
+            successful: 0,
+            failed: 0,
+            results: Vec::new(),
+        };
+        assert_eq!(report.success_rate(), 0.0);
+        assert_eq!(report.mean_lines_per_success(), 0.0);
+    }
+}
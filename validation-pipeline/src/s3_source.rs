@@ -0,0 +1,173 @@
+use crate::concurrency::process_concurrently;
+use std::future::Future;
+
+/// Image formats Textract accepts as document bytes
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "tif", "tiff"];
+
+/// Bucket and key prefix from an `s3://bucket/prefix` URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Uri {
+    pub bucket: String,
+    pub prefix: String,
+}
+
+impl S3Uri {
+    pub fn parse(uri: &str) -> Result<Self, String> {
+        let rest = uri
+            .strip_prefix("s3://")
+            .ok_or_else(|| format!("Expected s3://bucket/prefix, got {}", uri))?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(format!("Missing bucket in {}", uri));
+        }
+
+        Ok(Self {
+            bucket: bucket.to_string(),
+            prefix: prefix.to_string(),
+        })
+    }
+}
+
+/// The S3 operations the pipeline needs, so tests can stand in for the real client
+pub trait ObjectStore {
+    /// All object keys under the prefix
+    async fn list_keys(&self, bucket: &str, prefix: &str) -> Result<Vec<String>, String>;
+
+    async fn fetch_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>, String>;
+}
+
+impl ObjectStore for aws_sdk_s3::Client {
+    async fn list_keys(&self, bucket: &str, prefix: &str) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let page = self
+                .list_objects_v2()
+                .bucket(bucket)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await
+                .map_err(|e| format!("S3 list error: {}", e))?;
+
+            keys.extend(page.contents().iter().filter_map(|o| o.key().map(str::to_string)));
+
+            match page.next_continuation_token() {
+                Some(token) if page.is_truncated().unwrap_or(false) => {
+                    continuation_token = Some(token.to_string());
+                }
+                _ => return Ok(keys),
+            }
+        }
+    }
+
+    async fn fetch_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>, String> {
+        let object = self
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| format!("S3 get error for {}: {}", key, e))?;
+
+        let bytes = object
+            .body
+            .collect()
+            .await
+            .map_err(|e| format!("S3 read error for {}: {}", key, e))?;
+        Ok(bytes.into_bytes().to_vec())
+    }
+}
+
+fn is_image_key(key: &str) -> bool {
+    key.rsplit_once('.')
+        .is_some_and(|(_, ext)| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Image keys under the URI, sorted so runs are repeatable
+pub async fn list_images(store: &impl ObjectStore, uri: &S3Uri) -> Result<Vec<String>, String> {
+    let mut keys: Vec<String> = store
+        .list_keys(&uri.bucket, &uri.prefix)
+        .await?
+        .into_iter()
+        .filter(|key| is_image_key(key))
+        .collect();
+    keys.sort();
+    Ok(keys)
+}
+
+/// Download each key and hand its bytes to `process`, up to `max_concurrency` objects at a time.
+/// Results are in key order.
+pub async fn process_objects<T, F, Fut>(
+    store: &impl ObjectStore,
+    bucket: &str,
+    keys: &[String],
+    max_concurrency: usize,
+    process: F,
+) -> Vec<(String, Result<T, String>)>
+where
+    F: Fn(String, Vec<u8>) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let process = &process;
+    process_concurrently(keys.to_vec(), max_concurrency, |key| async move {
+        let result = match store.fetch_object(bucket, &key).await {
+            Ok(bytes) => process(key.clone(), bytes).await,
+            Err(e) => Err(e),
+        };
+        (key, result)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    /// In-memory bucket contents keyed by object key
+    struct MockStore(BTreeMap<String, Vec<u8>>);
+
+    impl ObjectStore for MockStore {
+        async fn list_keys(&self, _bucket: &str, prefix: &str) -> Result<Vec<String>, String> {
+            Ok(self.0.keys().filter(|k| k.starts_with(prefix)).cloned().collect())
+        }
+
+        async fn fetch_object(&self, _bucket: &str, key: &str) -> Result<Vec<u8>, String> {
+            self.0.get(key).cloned().ok_or_else(|| format!("NoSuchKey: {}", key))
+        }
+    }
+
+    #[test]
+    fn test_parse_s3_uri() {
+        let uri = S3Uri::parse("s3://plans/datasets/v1").unwrap();
+        assert_eq!(uri.bucket, "plans");
+        assert_eq!(uri.prefix, "datasets/v1");
+
+        assert_eq!(S3Uri::parse("s3://plans").unwrap().prefix, "");
+        assert!(S3Uri::parse("https://plans/datasets").is_err());
+        assert!(S3Uri::parse("s3:///datasets").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_both_objects_processed() {
+        let store = MockStore(BTreeMap::from([
+            ("plans/a.png".to_string(), vec![1, 2, 3]),
+            ("plans/b.JPG".to_string(), vec![4, 5]),
+            ("plans/notes.txt".to_string(), vec![0]),
+            ("other/c.png".to_string(), vec![6]),
+        ]));
+        let uri = S3Uri::parse("s3://bucket/plans/").unwrap();
+
+        let keys = list_images(&store, &uri).await.unwrap();
+        assert_eq!(keys, ["plans/a.png", "plans/b.JPG"]);
+
+        let results = process_objects(&store, &uri.bucket, &keys, 2, |_, bytes| async move { Ok(bytes.len()) }).await;
+
+        assert_eq!(
+            results,
+            vec![("plans/a.png".to_string(), Ok(3)), ("plans/b.JPG".to_string(), Ok(2))]
+        );
+    }
+}
@@ -1,150 +1,210 @@
 use crate::{Line, Point};
 use aws_sdk_textract::operation::analyze_document::AnalyzeDocumentOutput;
+use aws_sdk_textract::types::{Block, BlockType, Geometry, RelationshipType};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
-/// Parse Textract response and extract architectural lines
-pub fn parse_textract_response(response: AnalyzeDocumentOutput) -> Result<Vec<Line>, String> {
-    let mut lines = Vec::new();
+/// Textract coordinates are page fractions; lines and labels use 0-1000
+const PAGE_SCALE: f64 = 1000.0;
 
-    let blocks = response.blocks().ok_or("No blocks in response")?;
+/// A block's long side must be this many times its short side to count as a wall
+const MIN_WALL_ASPECT: f64 = 3.0;
 
-    for block in blocks {
-        // Look for LINE blocks which represent detected lines
-        if block.block_type().map(|t| t.as_str()) == Some("LINE") {
-            if let Some(geometry) = block.geometry() {
-                if let Some(bbox) = geometry.bounding_box() {
-                    // Extract bounding box coordinates
-                    let left = bbox.left().unwrap_or(0.0);
-                    let top = bbox.top().unwrap_or(0.0);
-                    let width = bbox.width().unwrap_or(0.0);
-                    let height = bbox.height().unwrap_or(0.0);
-
-                    // Determine if this is a horizontal or vertical line based on aspect ratio
-                    let aspect_ratio = width / height.max(0.001);
-
-                    if aspect_ratio > 3.0 {
-                        // Horizontal line
-                        lines.push(Line {
-                            start: Point {
-                                x: left * 1000.0,
-                                y: (top + height / 2.0) * 1000.0,
-                            },
-                            end: Point {
-                                x: (left + width) * 1000.0,
-                                y: (top + height / 2.0) * 1000.0,
-                            },
-                        });
-                    } else if aspect_ratio < 0.33 {
-                        // Vertical line
-                        lines.push(Line {
-                            start: Point {
-                                x: (left + width / 2.0) * 1000.0,
-                                y: top * 1000.0,
-                            },
-                            end: Point {
-                                x: (left + width / 2.0) * 1000.0,
-                                y: (top + height) * 1000.0,
-                            },
-                        });
-                    }
-                }
+/// Which text blocks become room labels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LabelMode {
+    /// Geometry only; text lines are treated like any other block
+    #[default]
+    Off,
+    /// LAYOUT_SECTION_HEADER and LAYOUT_TEXT blocks become room labels at their centers
+    Layout,
+}
 
-                // Also extract from polygon points if available
-                if let Some(polygon) = geometry.polygon() {
-                    if polygon.len() >= 2 {
-                        // Extract start and end points from polygon
-                        let start_point = &polygon[0];
-                        let end_point = &polygon[polygon.len() - 1];
-
-                        if let (Some(sx), Some(sy), Some(ex), Some(ey)) = (
-                            start_point.x(),
-                            start_point.y(),
-                            end_point.x(),
-                            end_point.y(),
-                        ) {
-                            lines.push(Line {
-                                start: Point {
-                                    x: sx * 1000.0,
-                                    y: sy * 1000.0,
-                                },
-                                end: Point {
-                                    x: ex * 1000.0,
-                                    y: ey * 1000.0,
-                                },
-                            });
-                        }
-                    }
-                }
+/// A text block read as a room name, e.g. "Kitchen"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomLabel {
+    pub text: String,
+    pub center: Point,
+}
+
+/// Structural lines and room labels from one Textract response
+#[derive(Debug, Clone, Default)]
+pub struct TextractExtraction {
+    pub lines: Vec<Line>,
+    pub room_labels: Vec<RoomLabel>,
+}
+
+/// Parse Textract response and extract architectural lines, plus room labels in `LabelMode::Layout`
+pub fn parse_textract_response(
+    response: &AnalyzeDocumentOutput,
+    label_mode: LabelMode,
+) -> Result<TextractExtraction, String> {
+    let blocks = response.blocks();
+    if blocks.is_empty() {
+        return Err("No blocks in response".to_string());
+    }
+
+    let mut extraction = TextractExtraction::default();
+
+    // LINE blocks whose text went into a label, so they aren't mistaken for walls
+    let mut label_line_ids: HashSet<String> = HashSet::new();
+    if label_mode == LabelMode::Layout {
+        let line_text: HashMap<&str, &str> = blocks
+            .iter()
+            .filter(|b| b.block_type() == Some(&BlockType::Line))
+            .filter_map(|b| Some((b.id()?, b.text()?)))
+            .collect();
+
+        for block in blocks {
+            if !matches!(
+                block.block_type(),
+                Some(BlockType::LayoutSectionHeader | BlockType::LayoutText)
+            ) {
+                continue;
             }
+            let Some(corners) = block.geometry().map(block_corners) else {
+                continue;
+            };
+
+            let children = child_ids(block);
+            let text = children
+                .iter()
+                .filter_map(|id| line_text.get(id.as_str()).copied())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            extraction.room_labels.push(RoomLabel {
+                text: text.trim().to_string(),
+                center: centroid(&corners),
+            });
+            label_line_ids.extend(children);
         }
+    }
+
+    for block in blocks {
+        let Some(corners) = block.geometry().map(block_corners) else {
+            continue;
+        };
 
-        // Also look for LAYOUT_FIGURE blocks which might contain architectural elements
-        if block.block_type().map(|t| t.as_str()) == Some("LAYOUT_FIGURE") {
-            if let Some(geometry) = block.geometry() {
-                if let Some(bbox) = geometry.bounding_box() {
-                    let left = bbox.left().unwrap_or(0.0);
-                    let top = bbox.top().unwrap_or(0.0);
-                    let width = bbox.width().unwrap_or(0.0);
-                    let height = bbox.height().unwrap_or(0.0);
-
-                    // Extract edges of the figure as potential walls
-                    // Top edge
-                    lines.push(Line {
-                        start: Point {
-                            x: left * 1000.0,
-                            y: top * 1000.0,
-                        },
-                        end: Point {
-                            x: (left + width) * 1000.0,
-                            y: top * 1000.0,
-                        },
-                    });
-
-                    // Right edge
-                    lines.push(Line {
-                        start: Point {
-                            x: (left + width) * 1000.0,
-                            y: top * 1000.0,
-                        },
-                        end: Point {
-                            x: (left + width) * 1000.0,
-                            y: (top + height) * 1000.0,
-                        },
-                    });
-
-                    // Bottom edge
-                    lines.push(Line {
-                        start: Point {
-                            x: (left + width) * 1000.0,
-                            y: (top + height) * 1000.0,
-                        },
-                        end: Point {
-                            x: left * 1000.0,
-                            y: (top + height) * 1000.0,
-                        },
-                    });
-
-                    // Left edge
-                    lines.push(Line {
-                        start: Point {
-                            x: left * 1000.0,
-                            y: (top + height) * 1000.0,
-                        },
-                        end: Point {
-                            x: left * 1000.0,
-                            y: top * 1000.0,
-                        },
-                    });
+        match block.block_type() {
+            // Thin LINE blocks approximate walls along their long axis
+            Some(BlockType::Line) => {
+                if block.id().is_some_and(|id| label_line_ids.contains(id)) {
+                    continue;
                 }
+                extraction.lines.extend(long_axis(&corners));
             }
+            // Figures contribute their outline as potential walls
+            Some(BlockType::LayoutFigure) => {
+                extraction.lines.extend(outline(&corners));
+            }
+            _ => {}
         }
     }
 
-    Ok(lines)
+    Ok(extraction)
+}
+
+/// Ids of a block's CHILD relationships
+fn child_ids(block: &Block) -> Vec<String> {
+    block
+        .relationships()
+        .iter()
+        .filter(|r| r.r#type() == Some(&RelationshipType::Child))
+        .flat_map(|r| r.ids().iter().cloned())
+        .collect()
+}
+
+/// Corners of a block in page units, from its polygon or, without one, its bounding box
+fn block_corners(geometry: &Geometry) -> Vec<Point> {
+    let polygon = geometry.polygon();
+    if polygon.len() >= 3 {
+        return polygon
+            .iter()
+            .map(|p| Point {
+                x: f64::from(p.x()) * PAGE_SCALE,
+                y: f64::from(p.y()) * PAGE_SCALE,
+            })
+            .collect();
+    }
+
+    let Some(bbox) = geometry.bounding_box() else {
+        return Vec::new();
+    };
+    let left = f64::from(bbox.left()) * PAGE_SCALE;
+    let top = f64::from(bbox.top()) * PAGE_SCALE;
+    let right = left + f64::from(bbox.width()) * PAGE_SCALE;
+    let bottom = top + f64::from(bbox.height()) * PAGE_SCALE;
+    vec![
+        Point { x: left, y: top },
+        Point { x: right, y: top },
+        Point { x: right, y: bottom },
+        Point { x: left, y: bottom },
+    ]
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+fn midpoint(a: &Point, b: &Point) -> Point {
+    Point {
+        x: (a.x + b.x) / 2.0,
+        y: (a.y + b.y) / 2.0,
+    }
+}
+
+fn centroid(corners: &[Point]) -> Point {
+    let n = corners.len().max(1) as f64;
+    Point {
+        x: corners.iter().map(|p| p.x).sum::<f64>() / n,
+        y: corners.iter().map(|p| p.y).sum::<f64>() / n,
+    }
+}
+
+/// Centerline along the long side of a thin quadrilateral; works for rotated blocks too
+fn long_axis(corners: &[Point]) -> Option<Line> {
+    let [a, b, c, d] = corners else {
+        return None;
+    };
+    let first = distance(a, b);
+    let second = distance(b, c);
+
+    if first >= second * MIN_WALL_ASPECT && first > 0.0 {
+        Some(Line {
+            start: midpoint(a, d),
+            end: midpoint(b, c),
+        })
+    } else if second >= first * MIN_WALL_ASPECT && second > 0.0 {
+        Some(Line {
+            start: midpoint(a, b),
+            end: midpoint(d, c),
+        })
+    } else {
+        None
+    }
+}
+
+/// Closed outline through the corners
+fn outline(corners: &[Point]) -> Vec<Line> {
+    if corners.len() < 2 {
+        return Vec::new();
+    }
+    (0..corners.len())
+        .map(|i| Line {
+            start: corners[i].clone(),
+            end: corners[(i + 1) % corners.len()].clone(),
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aws_sdk_textract::types::{BoundingBox, Relationship};
 
     #[test]
     fn test_line_creation() {
@@ -156,4 +216,82 @@ mod tests {
         assert_eq!(line.start.x, 0.0);
         assert_eq!(line.end.x, 100.0);
     }
+
+    /// Axis-aligned block geometry with both a polygon and a bounding box
+    fn geometry(left: f32, top: f32, width: f32, height: f32) -> Geometry {
+        let corner = |x: f32, y: f32| aws_sdk_textract::types::Point::builder().x(x).y(y).build();
+        Geometry::builder()
+            .bounding_box(
+                BoundingBox::builder()
+                    .left(left)
+                    .top(top)
+                    .width(width)
+                    .height(height)
+                    .build(),
+            )
+            .polygon(corner(left, top))
+            .polygon(corner(left + width, top))
+            .polygon(corner(left + width, top + height))
+            .polygon(corner(left, top + height))
+            .build()
+    }
+
+    fn block(id: &str, block_type: BlockType, geometry: Geometry) -> aws_sdk_textract::types::builders::BlockBuilder {
+        Block::builder().id(id).block_type(block_type).geometry(geometry)
+    }
+
+    /// A wall-like thin line, a figure, and a section header wrapping the text "Kitchen"
+    fn canned_response() -> AnalyzeDocumentOutput {
+        AnalyzeDocumentOutput::builder()
+            .blocks(block("wall", BlockType::Line, geometry(0.1, 0.5, 0.6, 0.01)).build())
+            .blocks(block("figure", BlockType::LayoutFigure, geometry(0.1, 0.1, 0.8, 0.8)).build())
+            .blocks(
+                block("kitchen-text", BlockType::Line, geometry(0.2, 0.3, 0.1, 0.02))
+                    .text("Kitchen")
+                    .build(),
+            )
+            .blocks(
+                block("header", BlockType::LayoutSectionHeader, geometry(0.2, 0.3, 0.1, 0.02))
+                    .relationships(
+                        Relationship::builder()
+                            .r#type(RelationshipType::Child)
+                            .ids("kitchen-text")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_canned_response_yields_lines_and_labels() {
+        let extraction = parse_textract_response(&canned_response(), LabelMode::Layout).unwrap();
+
+        // The thin line's centerline plus the figure's four edges; the label text is not a wall
+        assert_eq!(extraction.lines.len(), 5);
+        let wall = &extraction.lines[0];
+        assert!((wall.start.x - 100.0).abs() < 1e-3 && (wall.end.x - 700.0).abs() < 1e-3);
+        assert!((wall.start.y - 505.0).abs() < 1e-3 && (wall.end.y - 505.0).abs() < 1e-3);
+
+        assert_eq!(extraction.room_labels.len(), 1);
+        let label = &extraction.room_labels[0];
+        assert_eq!(label.text, "Kitchen");
+        assert!((label.center.x - 250.0).abs() < 1e-3);
+        assert!((label.center.y - 310.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_labels_off_keeps_geometry_only() {
+        let extraction = parse_textract_response(&canned_response(), LabelMode::Off).unwrap();
+
+        assert!(extraction.room_labels.is_empty());
+        // "Kitchen" is a wide text line, so without labels it reads as a wall
+        assert_eq!(extraction.lines.len(), 6);
+    }
+
+    #[test]
+    fn test_empty_response_is_an_error() {
+        let response = AnalyzeDocumentOutput::builder().build();
+        assert!(parse_textract_response(&response, LabelMode::Layout).is_err());
+    }
 }
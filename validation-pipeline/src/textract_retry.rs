@@ -0,0 +1,148 @@
+use aws_sdk_textract::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_textract::operation::analyze_document::AnalyzeDocumentOutput;
+use aws_sdk_textract::primitives::Blob;
+use aws_sdk_textract::types::{Document, FeatureType};
+use aws_sdk_textract::Client as TextractClient;
+use std::time::Duration;
+
+/// Error codes worth retrying: throttling and transient service failures
+const RETRYABLE_CODES: [&str; 5] = [
+    "ProvisionedThroughputExceededException",
+    "ThrottlingException",
+    "LimitExceededException",
+    "InternalServerError",
+    "ServiceUnavailableException",
+];
+
+/// A failed analysis, and whether trying again might help
+#[derive(Debug, Clone)]
+pub struct AnalyzeError {
+    pub message: String,
+    pub retryable: bool,
+}
+
+/// Runs Textract layout analysis on document bytes, so tests can stand in for the real client
+pub trait DocumentAnalyzer {
+    async fn analyze(&self, image_bytes: &[u8]) -> Result<AnalyzeDocumentOutput, AnalyzeError>;
+}
+
+impl DocumentAnalyzer for TextractClient {
+    async fn analyze(&self, image_bytes: &[u8]) -> Result<AnalyzeDocumentOutput, AnalyzeError> {
+        // Create document blob
+        let document = Document::builder()
+            .bytes(Blob::new(image_bytes))
+            .build();
+
+        self.analyze_document()
+            .document(document)
+            .feature_types(FeatureType::Layout)
+            .send()
+            .await
+            .map_err(|e| {
+                let retryable = match &e {
+                    SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) => true,
+                    SdkError::ResponseError(_) => true,
+                    SdkError::ServiceError(ctx) if ctx.raw().status().is_server_error() => true,
+                    _ => e.code().is_some_and(|code| RETRYABLE_CODES.contains(&code)),
+                };
+                AnalyzeError {
+                    message: format!("Textract API error: {}", e),
+                    retryable,
+                }
+            })
+    }
+}
+
+/// How often and how patiently to retry a throttled or failed Textract call
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each one after
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// TEXTRACT_MAX_RETRIES (default 3) with a 500ms base delay
+    pub fn from_env() -> Self {
+        Self {
+            max_retries: std::env::var("TEXTRACT_MAX_RETRIES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(3),
+            base_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Analyze with exponential backoff on retryable errors; also returns how many retries it took
+pub async fn analyze_with_retry(
+    analyzer: &impl DocumentAnalyzer,
+    image_bytes: &[u8],
+    policy: RetryPolicy,
+) -> (Result<AnalyzeDocumentOutput, String>, u32) {
+    let mut retries = 0;
+    loop {
+        match analyzer.analyze(image_bytes).await {
+            Ok(output) => return (Ok(output), retries),
+            Err(e) if e.retryable && retries < policy.max_retries => {
+                let delay = policy.base_delay.saturating_mul(2u32.saturating_pow(retries));
+                println!("  ⏳ {} (retry {} in {:?})", e.message, retries + 1, delay);
+                tokio::time::sleep(delay).await;
+                retries += 1;
+            }
+            Err(e) => return (Err(e.message), retries),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails every call with the given error
+    struct FailingAnalyzer {
+        retryable: bool,
+        calls: AtomicU32,
+    }
+
+    impl DocumentAnalyzer for FailingAnalyzer {
+        async fn analyze(&self, _image_bytes: &[u8]) -> Result<AnalyzeDocumentOutput, AnalyzeError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(AnalyzeError {
+                message: "Textract API error: nope".to_string(),
+                retryable: self.retryable,
+            })
+        }
+    }
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let analyzer = FailingAnalyzer { retryable: true, calls: AtomicU32::new(0) };
+
+        let (result, retries) = analyze_with_retry(&analyzer, b"png", policy(3)).await;
+
+        assert!(result.is_err());
+        assert_eq!(retries, 3);
+        assert_eq!(analyzer.calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_fails_immediately() {
+        let analyzer = FailingAnalyzer { retryable: false, calls: AtomicU32::new(0) };
+
+        let (result, retries) = analyze_with_retry(&analyzer, b"png", policy(3)).await;
+
+        assert_eq!(result.unwrap_err(), "Textract API error: nope");
+        assert_eq!(retries, 0);
+        assert_eq!(analyzer.calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -48,7 +48,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Classify
     match classifier.classify_floorplan(&image_bytes, None).await {
-        Ok(classifications) => {
+        Ok(result) => {
+            let classifications = result.classifications;
             println!("✅ Classification complete!\n");
             println!("📊 Results:");
             println!("{}", "=".repeat(70));
@@ -62,6 +63,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             println!("\n{}", "=".repeat(70));
             println!("\n📈 Summary: Detected {} rooms", classifications.len());
+            println!(
+                "🪙 Tokens: {} prompt + {} completion (~${:.4})",
+                result.usage.prompt_tokens, result.usage.completion_tokens, result.usage.estimated_cost_usd
+            );
 
             // Save results to JSON
             let output_path = "data/gpt5_classification_result.json";
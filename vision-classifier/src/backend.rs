@@ -0,0 +1,587 @@
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+const OPENAI_URL: &str = "https://api.openai.com/v1/chat/completions";
+const ANTHROPIC_URL: &str = "https://api.anthropic.com/v1/messages";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+const DEFAULT_OPENAI_MODEL: &str = "gpt-5";
+const DEFAULT_ANTHROPIC_MODEL: &str = "claude-sonnet-4-5";
+const DEFAULT_OLLAMA_MODEL: &str = "llava";
+
+/// Per-attempt timeout for a provider request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// Future returned by [`VisionBackend::complete`]
+pub type BackendFuture<'a> = Pin<Box<dyn Future<Output = anyhow::Result<VisionResponse>> + Send + 'a>>;
+
+/// A single prompt + image request, independent of provider
+#[derive(Debug, Clone)]
+pub struct VisionRequest {
+    pub prompt: String,
+    /// Base64 image data without a data URI prefix
+    pub image_base64: String,
+    /// MIME type of the image, e.g. "image/png"
+    pub media_type: String,
+    pub max_tokens: u32,
+    /// Ask the provider to constrain output to a JSON object, where supported
+    pub json_mode: bool,
+}
+
+/// Raw model output; token counts are zero when the provider doesn't report them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VisionResponse {
+    pub content: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// Retry behaviour for rate-limit (429) and 5xx responses
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total attempts including the first request
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on each subsequent retry
+    pub base_delay: Duration,
+}
+
+/// A vision model provider
+///
+/// Implementations only translate a [`VisionRequest`] into the provider's wire format and back;
+/// prompting, JSON extraction and validation stay in [`crate::VisionClassifier`].
+pub trait VisionBackend: Send + Sync {
+    /// Provider name for logs and errors
+    fn name(&self) -> &str;
+
+    /// Model identifier, also used for price lookup
+    fn model(&self) -> &str;
+
+    fn complete<'a>(&'a self, request: &'a VisionRequest, retry: RetryPolicy) -> BackendFuture<'a>;
+}
+
+/// Shared HTTP client with a 300-second timeout for slow reasoning models
+fn http_client() -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(300))
+        .build()
+        .unwrap_or_else(|_| Client::new())
+}
+
+/// OpenAI chat completions API
+pub struct OpenAIBackend {
+    client: Client,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAIBackend {
+    /// # Arguments
+    /// * `api_key` - OpenAI API key
+    /// * `model` - Model to use (default: "gpt-5")
+    pub fn new(api_key: String, model: Option<String>) -> Self {
+        Self {
+            client: http_client(),
+            api_key,
+            model: model.unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string()),
+        }
+    }
+
+    fn request_body(&self, request: &VisionRequest) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": request.prompt},
+                    {
+                        "type": "image_url",
+                        "image_url": {
+                            "url": format!("data:{};base64,{}", request.media_type, request.image_base64)
+                        }
+                    }
+                ]
+            }]
+        });
+
+        // Use max_completion_tokens for newer models, max_tokens for older ones
+        if self.model.starts_with("gpt-5") || self.model.starts_with("o1") {
+            body["max_completion_tokens"] = serde_json::json!(request.max_tokens);
+        } else {
+            body["max_tokens"] = serde_json::json!(request.max_tokens);
+        }
+
+        // JSON mode is supported from gpt-4o onwards
+        if request.json_mode && !self.model.starts_with("gpt-3") {
+            body["response_format"] = serde_json::json!({"type": "json_object"});
+        }
+
+        body
+    }
+}
+
+impl VisionBackend for OpenAIBackend {
+    fn name(&self) -> &str {
+        "OpenAI"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(&'a self, request: &'a VisionRequest, retry: RetryPolicy) -> BackendFuture<'a> {
+        Box::pin(async move {
+            let body = self.request_body(request);
+            let response = send_with_retry(self.name(), retry, || {
+                self.client
+                    .post(OPENAI_URL)
+                    .header("Authorization", format!("Bearer {}", self.api_key))
+                    .json(&body)
+            })
+            .await?;
+            response.json::<OpenAIResponse>().await?.into_vision_response()
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct OpenAIResponse {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseMessage {
+    content: String,
+}
+
+impl OpenAIResponse {
+    pub(crate) fn into_vision_response(self) -> anyhow::Result<VisionResponse> {
+        let usage = self.usage;
+        let choice = self
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No response from OpenAI API"))?;
+
+        Ok(VisionResponse {
+            content: choice.message.content,
+            prompt_tokens: usage.as_ref().map_or(0, |u| u.prompt_tokens),
+            completion_tokens: usage.as_ref().map_or(0, |u| u.completion_tokens),
+        })
+    }
+}
+
+/// Anthropic messages API
+pub struct AnthropicBackend {
+    client: Client,
+    api_key: String,
+    model: String,
+}
+
+impl AnthropicBackend {
+    /// # Arguments
+    /// * `api_key` - Anthropic API key
+    /// * `model` - Model to use (default: "claude-sonnet-4-5")
+    pub fn new(api_key: String, model: Option<String>) -> Self {
+        Self {
+            client: http_client(),
+            api_key,
+            model: model.unwrap_or_else(|| DEFAULT_ANTHROPIC_MODEL.to_string()),
+        }
+    }
+
+    fn request_body(&self, request: &VisionRequest) -> serde_json::Value {
+        // No JSON mode; the prompts already ask for bare JSON
+        serde_json::json!({
+            "model": self.model,
+            "max_tokens": request.max_tokens,
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {
+                        "type": "image",
+                        "source": {
+                            "type": "base64",
+                            "media_type": request.media_type,
+                            "data": request.image_base64
+                        }
+                    },
+                    {"type": "text", "text": request.prompt}
+                ]
+            }]
+        })
+    }
+}
+
+impl VisionBackend for AnthropicBackend {
+    fn name(&self) -> &str {
+        "Anthropic"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(&'a self, request: &'a VisionRequest, retry: RetryPolicy) -> BackendFuture<'a> {
+        Box::pin(async move {
+            let body = self.request_body(request);
+            let response = send_with_retry(self.name(), retry, || {
+                self.client
+                    .post(ANTHROPIC_URL)
+                    .header("x-api-key", &self.api_key)
+                    .header("anthropic-version", ANTHROPIC_VERSION)
+                    .json(&body)
+            })
+            .await?;
+            response.json::<AnthropicResponse>().await?.into_vision_response()
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicResponse {
+    content: Vec<AnthropicContentBlock>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+impl AnthropicResponse {
+    fn into_vision_response(self) -> anyhow::Result<VisionResponse> {
+        let content: String = self
+            .content
+            .into_iter()
+            .filter(|block| block.kind == "text")
+            .map(|block| block.text)
+            .collect();
+        if content.is_empty() {
+            return Err(anyhow::anyhow!("No response from Anthropic API"));
+        }
+
+        Ok(VisionResponse {
+            content,
+            prompt_tokens: self.usage.as_ref().map_or(0, |u| u.input_tokens),
+            completion_tokens: self.usage.as_ref().map_or(0, |u| u.output_tokens),
+        })
+    }
+}
+
+/// Local Ollama server chat API
+pub struct OllamaBackend {
+    client: Client,
+    base_url: String,
+    model: String,
+}
+
+impl OllamaBackend {
+    /// # Arguments
+    /// * `base_url` - Server address (default: "http://localhost:11434")
+    /// * `model` - Vision-capable model to use (default: "llava")
+    pub fn new(base_url: Option<String>, model: Option<String>) -> Self {
+        let base_url = base_url.unwrap_or_else(|| DEFAULT_OLLAMA_URL.to_string());
+        Self {
+            client: http_client(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string()),
+        }
+    }
+
+    fn request_body(&self, request: &VisionRequest) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "stream": false,
+            "messages": [{
+                "role": "user",
+                "content": request.prompt,
+                "images": [request.image_base64]
+            }],
+            "options": {"num_predict": request.max_tokens}
+        });
+        if request.json_mode {
+            body["format"] = serde_json::json!("json");
+        }
+        body
+    }
+}
+
+impl VisionBackend for OllamaBackend {
+    fn name(&self) -> &str {
+        "Ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn complete<'a>(&'a self, request: &'a VisionRequest, retry: RetryPolicy) -> BackendFuture<'a> {
+        Box::pin(async move {
+            let body = self.request_body(request);
+            let url = format!("{}/api/chat", self.base_url);
+            let response = send_with_retry(self.name(), retry, || self.client.post(&url).json(&body)).await?;
+            let parsed: OllamaResponse = response.json().await?;
+
+            Ok(VisionResponse {
+                content: parsed.message.content,
+                prompt_tokens: parsed.prompt_eval_count,
+                completion_tokens: parsed.eval_count,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: ResponseMessage,
+    #[serde(default)]
+    prompt_eval_count: u64,
+    #[serde(default)]
+    eval_count: u64,
+}
+
+/// Offline backend that answers each request with the next canned response
+pub struct MockBackend {
+    responses: Mutex<VecDeque<String>>,
+}
+
+impl MockBackend {
+    pub fn new(responses: Vec<String>) -> Self {
+        Self {
+            responses: Mutex::new(responses.into()),
+        }
+    }
+}
+
+impl VisionBackend for MockBackend {
+    fn name(&self) -> &str {
+        "Mock"
+    }
+
+    fn model(&self) -> &str {
+        "mock"
+    }
+
+    fn complete<'a>(&'a self, _request: &'a VisionRequest, _retry: RetryPolicy) -> BackendFuture<'a> {
+        let next = self
+            .responses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop_front();
+
+        Box::pin(async move {
+            let content = next.ok_or_else(|| anyhow::anyhow!("Mock classifier has no responses left"))?;
+            Ok(VisionResponse {
+                content,
+                ..Default::default()
+            })
+        })
+    }
+}
+
+/// Send a request built by `build`, retrying 429 and 5xx responses with exponential backoff
+///
+/// Honors the `Retry-After` header when present. Other 4xx responses fail immediately.
+async fn send_with_retry(
+    provider: &str,
+    retry: RetryPolicy,
+    build: impl Fn() -> RequestBuilder,
+) -> anyhow::Result<Response> {
+    let max_attempts = retry.max_attempts.max(1);
+    let mut attempt = 0;
+
+    loop {
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, build().send())
+            .await
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "{} API request timed out after {} seconds",
+                    provider,
+                    REQUEST_TIMEOUT.as_secs()
+                )
+            })??;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let retry_after = parse_retry_after(response.headers());
+        let error_text = response.text().await?;
+        warn!("{} API error: {} - {}", provider, status, error_text);
+
+        attempt += 1;
+        if !is_retryable_status(status) || attempt >= max_attempts {
+            return Err(anyhow::anyhow!(
+                "{} API error: {} - {} (after {} retries)",
+                provider,
+                status,
+                error_text,
+                attempt - 1
+            ));
+        }
+
+        let delay = retry_delay(retry.base_delay, attempt - 1, retry_after);
+        warn!(
+            "Retrying {} request in {:?} (attempt {}/{})",
+            provider,
+            delay,
+            attempt + 1,
+            max_attempts
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Only rate limiting and server errors are worth retrying
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Parse a `Retry-After` header given in seconds
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+/// Backoff before retry number `retry` (0-based): `Retry-After` if given, else `base * 2^retry`
+fn retry_delay(base: Duration, retry: u32, retry_after: Option<Duration>) -> Duration {
+    retry_after.unwrap_or_else(|| base.saturating_mul(2u32.saturating_pow(retry)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(json_mode: bool) -> VisionRequest {
+        VisionRequest {
+            prompt: "Describe the rooms".to_string(),
+            image_base64: "aGVsbG8=".to_string(),
+            media_type: "image/png".to_string(),
+            max_tokens: 2000,
+            json_mode,
+        }
+    }
+
+    #[test]
+    fn test_retryable_statuses() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_retryable_status(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn test_retry_delay_backoff() {
+        let base = Duration::from_millis(100);
+        assert_eq!(retry_delay(base, 0, None), Duration::from_millis(100));
+        assert_eq!(retry_delay(base, 1, None), Duration::from_millis(200));
+        assert_eq!(retry_delay(base, 2, None), Duration::from_millis(400));
+
+        // Retry-After takes precedence over the computed backoff
+        assert_eq!(retry_delay(base, 2, Some(Duration::from_secs(3))), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(reqwest::header::RETRY_AFTER, "2".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_openai_request_body() {
+        let body = OpenAIBackend::new("key".to_string(), Some("gpt-4o".to_string())).request_body(&request(true));
+        assert_eq!(body["max_tokens"], 2000);
+        assert_eq!(body["response_format"]["type"], "json_object");
+        assert_eq!(body["messages"][0]["content"][1]["image_url"]["url"], "data:image/png;base64,aGVsbG8=");
+
+        let body = OpenAIBackend::new("key".to_string(), None).request_body(&request(false));
+        assert_eq!(body["max_completion_tokens"], 2000);
+        assert!(body.get("response_format").is_none());
+    }
+
+    #[test]
+    fn test_anthropic_request_and_response() {
+        let body = AnthropicBackend::new("key".to_string(), None).request_body(&request(true));
+        let image = &body["messages"][0]["content"][0];
+        assert_eq!(image["type"], "image");
+        assert_eq!(image["source"]["media_type"], "image/png");
+        assert_eq!(image["source"]["data"], "aGVsbG8=");
+        assert_eq!(body["max_tokens"], 2000);
+
+        let response: AnthropicResponse = serde_json::from_str(
+            r#"{"id": "msg_1", "type": "message", "role": "assistant",
+                "content": [{"type": "text", "text": "[]"}],
+                "usage": {"input_tokens": 900, "output_tokens": 40}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            response.into_vision_response().unwrap(),
+            VisionResponse {
+                content: "[]".to_string(),
+                prompt_tokens: 900,
+                completion_tokens: 40,
+            }
+        );
+    }
+
+    #[test]
+    fn test_ollama_request_and_response() {
+        let backend = OllamaBackend::new(Some("http://gpu-box:11434/".to_string()), None);
+        assert_eq!(backend.base_url, "http://gpu-box:11434");
+
+        let body = backend.request_body(&request(true));
+        assert_eq!(body["model"], "llava");
+        assert_eq!(body["messages"][0]["images"][0], "aGVsbG8=");
+        assert_eq!(body["format"], "json");
+
+        let response: OllamaResponse = serde_json::from_str(
+            r#"{"model": "llava", "message": {"role": "assistant", "content": "{}"}, "done": true,
+                "prompt_eval_count": 600, "eval_count": 25}"#,
+        )
+        .unwrap();
+        assert_eq!(response.message.content, "{}");
+        assert_eq!((response.prompt_eval_count, response.eval_count), (600, 25));
+    }
+}
@@ -1,13 +1,117 @@
 use base64::{engine::general_purpose, Engine as _};
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
 use tracing::{info, warn};
 
-/// Vision-based room classifier using OpenAI Vision API (GPT-5)
+mod backend;
+
+pub use backend::{
+    AnthropicBackend, BackendFuture, MockBackend, OllamaBackend, OpenAIBackend, RetryPolicy,
+    VisionBackend, VisionRequest, VisionResponse,
+};
+
+/// Default number of attempts for transient provider failures (429 / 5xx)
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+/// Default base delay for exponential backoff between attempts
+const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Vision-based room classifier backed by a pluggable vision model provider
 pub struct VisionClassifier {
-    client: Client,
-    api_key: String,
-    model: String,
+    backend: Box<dyn VisionBackend>,
+    retry: RetryPolicy,
+    price_table: PriceTable,
+    total_usage: Mutex<TokenUsage>,
+}
+
+/// Token counts and estimated spend for one or more OpenAI requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub estimated_cost_usd: f64,
+}
+
+impl TokenUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    fn add(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.estimated_cost_usd += other.estimated_cost_usd;
+    }
+}
+
+/// Room classifications together with the tokens spent producing them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassificationResult {
+    pub classifications: Vec<RoomClassification>,
+    pub usage: TokenUsage,
+}
+
+/// USD price per million tokens for one model
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// Per-model prices, matched against the model name by longest prefix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PriceTable {
+    prices: HashMap<String, ModelPrice>,
+}
+
+impl PriceTable {
+    pub fn new(prices: HashMap<String, ModelPrice>) -> Self {
+        Self { prices }
+    }
+
+    /// Price for `model`, so dated variants like "gpt-4o-2024-08-06" resolve to "gpt-4o"
+    pub fn price_for(&self, model: &str) -> Option<ModelPrice> {
+        self.prices
+            .iter()
+            .filter(|(name, _)| model.starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, price)| *price)
+    }
+
+    /// Estimated cost in USD; unknown models cost nothing rather than failing the request
+    pub fn estimate_cost(&self, model: &str, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        self.price_for(model)
+            .map(|p| {
+                (prompt_tokens as f64 * p.input_per_million
+                    + completion_tokens as f64 * p.output_per_million)
+                    / 1_000_000.0
+            })
+            .unwrap_or(0.0)
+    }
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let price = |input_per_million, output_per_million| ModelPrice {
+            input_per_million,
+            output_per_million,
+        };
+        let prices = [
+            ("gpt-5", price(1.25, 10.0)),
+            ("gpt-5-mini", price(0.25, 2.0)),
+            ("gpt-4o", price(2.5, 10.0)),
+            ("gpt-4o-mini", price(0.15, 0.6)),
+            ("gpt-4-turbo", price(10.0, 30.0)),
+            ("gpt-4-vision-preview", price(10.0, 30.0)),
+            ("claude-sonnet-4", price(3.0, 15.0)),
+            ("claude-opus-4", price(15.0, 75.0)),
+            ("claude-haiku-4", price(1.0, 5.0)),
+            ("claude-3-5-sonnet", price(3.0, 15.0)),
+        ];
+        Self::new(prices.into_iter().map(|(m, p)| (m.to_string(), p)).collect())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +121,8 @@ pub struct RoomClassification {
     pub confidence: f64,             // 0.0-1.0
     pub features: Vec<String>,       // furniture, fixtures identified
     pub description: String,         // detailed description
+    #[serde(default)]
+    pub center: Option<WallPoint>,   // room center in normalized 0-1000 space
 }
 
 /// Wall segment extracted from vision analysis
@@ -49,73 +155,105 @@ pub struct VisionWallData {
     pub confidence: f64,
 }
 
-#[derive(Debug, Serialize)]
-struct OpenAIRequest {
-    model: String,
-    messages: Vec<Message>,
-    max_tokens: u32,
-}
-
-#[derive(Debug, Serialize)]
-struct Message {
-    role: String,
-    content: Vec<ContentItem>,
-}
-
-#[derive(Debug, Serialize)]
-#[serde(tag = "type")]
-enum ContentItem {
-    #[serde(rename = "text")]
-    Text { text: String },
-    #[serde(rename = "image_url")]
-    ImageUrl { image_url: ImageUrl },
-}
-
-#[derive(Debug, Serialize)]
-struct ImageUrl {
-    url: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct OpenAIResponse {
-    choices: Vec<Choice>,
-}
-
-#[derive(Debug, Deserialize)]
-struct Choice {
-    message: ResponseMessage,
-}
-
-#[derive(Debug, Deserialize)]
-struct ResponseMessage {
-    content: String,
-}
-
 impl VisionClassifier {
-    /// Create a new vision classifier
+    /// Create a new vision classifier using OpenAI
     ///
     /// # Arguments
     /// * `api_key` - OpenAI API key (from OPENAI_API_KEY environment variable)
     /// * `model` - Model to use (default: "gpt-5")
     pub fn new(api_key: String, model: Option<String>) -> Self {
-        // Configure client with 300-second timeout for GPT-5 reasoning
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(300))
-            .build()
-            .unwrap_or_else(|_| Client::new());
+        Self::from_backend(OpenAIBackend::new(api_key, model))
+    }
 
+    /// Create a vision classifier that sends requests through `backend`
+    pub fn from_backend(backend: impl VisionBackend + 'static) -> Self {
         Self {
-            client,
-            api_key,
-            model: model.unwrap_or_else(|| "gpt-5".to_string()),
+            backend: Box::new(backend),
+            retry: RetryPolicy {
+                max_attempts: DEFAULT_MAX_ATTEMPTS,
+                base_delay: DEFAULT_RETRY_BASE_DELAY,
+            },
+            price_table: PriceTable::default(),
+            total_usage: Mutex::new(TokenUsage::default()),
         }
     }
 
-    /// Create from environment variable
+    /// Create an offline classifier that answers requests with canned responses
+    ///
+    /// Each request consumes the next response in order, as if it were the model's message
+    /// content. Requests fail once the queue is exhausted. No network access or API key is needed.
+    pub fn mock(responses: Vec<String>) -> Self {
+        Self::from_backend(MockBackend::new(responses))
+    }
+
+    /// Model identifier of the configured backend
+    pub fn model(&self) -> &str {
+        self.backend.model()
+    }
+
+    /// Replace the per-model price table used for cost estimates
+    pub fn with_price_table(mut self, price_table: PriceTable) -> Self {
+        self.price_table = price_table;
+        self
+    }
+
+    /// Running total of tokens and estimated cost across all requests made by this classifier
+    pub fn total_usage(&self) -> TokenUsage {
+        *self.total_usage.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Token usage and estimated cost for one backend response
+    fn response_usage(&self, response: &VisionResponse) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: response.prompt_tokens,
+            completion_tokens: response.completion_tokens,
+            estimated_cost_usd: self.price_table.estimate_cost(
+                self.backend.model(),
+                response.prompt_tokens,
+                response.completion_tokens,
+            ),
+        }
+    }
+
+    /// Add usage to the running total
+    fn record_usage(&self, usage: &TokenUsage) {
+        self.total_usage
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .add(usage);
+    }
+
+    /// Configure retry behaviour for rate-limit (429) and 5xx responses
+    ///
+    /// # Arguments
+    /// * `max_attempts` - Total attempts including the first request (minimum 1)
+    /// * `base_delay` - Delay before the first retry, doubled on each subsequent retry
+    pub fn with_retry_config(mut self, max_attempts: u32, base_delay: Duration) -> Self {
+        self.retry = RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            base_delay,
+        };
+        self
+    }
+
+    /// Create from environment variables
+    ///
+    /// `VISION_PROVIDER` selects `openai` (default, needs `OPENAI_API_KEY`), `anthropic`
+    /// (needs `ANTHROPIC_API_KEY`) or `ollama` (optional `OLLAMA_URL`). `VISION_PROVIDER_MODEL`
+    /// overrides the provider's default model.
     pub fn from_env() -> anyhow::Result<Self> {
-        let api_key = std::env::var("OPENAI_API_KEY")
-            .map_err(|_| anyhow::anyhow!("OPENAI_API_KEY environment variable not set"))?;
-        Ok(Self::new(api_key, None))
+        let provider = std::env::var("VISION_PROVIDER").unwrap_or_else(|_| "openai".to_string());
+        let model = std::env::var("VISION_PROVIDER_MODEL").ok();
+        let api_key = |var: &str| {
+            std::env::var(var).map_err(|_| anyhow::anyhow!("{} environment variable not set", var))
+        };
+
+        match provider.to_lowercase().as_str() {
+            "openai" => Ok(Self::new(api_key("OPENAI_API_KEY")?, model)),
+            "anthropic" => Ok(Self::from_backend(AnthropicBackend::new(api_key("ANTHROPIC_API_KEY")?, model))),
+            "ollama" => Ok(Self::from_backend(OllamaBackend::new(std::env::var("OLLAMA_URL").ok(), model))),
+            other => Err(anyhow::anyhow!("Unknown VISION_PROVIDER: {}", other)),
+        }
     }
 
     /// Classify rooms in a floorplan image
@@ -127,8 +265,8 @@ impl VisionClassifier {
         &self,
         image_bytes: &[u8],
         num_rooms: Option<usize>,
-    ) -> anyhow::Result<Vec<RoomClassification>> {
-        info!("Classifying floorplan with GPT-5 Vision");
+    ) -> anyhow::Result<ClassificationResult> {
+        info!("Classifying floorplan with {} ({})", self.backend.name(), self.backend.model());
 
         // Encode image to base64
         let b64_image = general_purpose::STANDARD.encode(image_bytes);
@@ -141,14 +279,16 @@ impl VisionClassifier {
                  1. Identify the room type (bedroom, kitchen, bathroom, living_room, dining_room, hallway, etc.)\n\
                  2. Estimate confidence (0.0-1.0)\n\
                  3. List visible features (fixtures, furniture, doors, windows)\n\
-                 4. Provide a brief description\n\n\
+                 4. Provide a brief description\n\
+                 5. Give the room center in 0-1000 normalized coordinates (top-left is 0,0)\n\n\
                  Return ONLY a valid JSON array of objects with this exact structure:\n\
                  [{{\n  \
                    \"room_id\": 0,\n  \
                    \"room_type\": \"bedroom\",\n  \
                    \"confidence\": 0.95,\n  \
                    \"features\": [\"bed\", \"closet\", \"window\"],\n  \
-                   \"description\": \"Master bedroom with ensuite access\"\n\
+                   \"description\": \"Master bedroom with ensuite access\",\n  \
+                   \"center\": {{\"x\": 250, \"y\": 400}}\n\
                  }}]",
                 n
             )
@@ -158,92 +298,47 @@ impl VisionClassifier {
              1. Identify the room type (bedroom, kitchen, bathroom, living_room, dining_room, hallway, etc.)\n\
              2. Estimate confidence (0.0-1.0)\n\
              3. List visible features (fixtures, furniture, doors, windows)\n\
-             4. Provide a brief description\n\n\
+             4. Provide a brief description\n\
+             5. Give the room center in 0-1000 normalized coordinates (top-left is 0,0)\n\n\
              Return ONLY a valid JSON array of objects with this exact structure:\n\
              [{\n  \
                \"room_id\": 0,\n  \
                \"room_type\": \"bedroom\",\n  \
                \"confidence\": 0.95,\n  \
                \"features\": [\"bed\", \"closet\", \"window\"],\n  \
-               \"description\": \"Master bedroom with ensuite access\"\n\
+               \"description\": \"Master bedroom with ensuite access\",\n  \
+               \"center\": {\"x\": 250, \"y\": 400}\n\
              }]"
                 .to_string()
         };
 
-        // Build request
-        let mut request_body = serde_json::json!({
-            "model": self.model.clone(),
-            "messages": vec![serde_json::json!({
-                "role": "user",
-                "content": vec![
-                    serde_json::json!({
-                        "type": "text",
-                        "text": prompt
-                    }),
-                    serde_json::json!({
-                        "type": "image_url",
-                        "image_url": {
-                            "url": format!("data:image/png;base64,{}", b64_image)
-                        }
-                    })
-                ]
-            })]
-        });
-
-        // Use max_completion_tokens for newer models, max_tokens for older ones
-        if self.model.starts_with("gpt-5") || self.model.starts_with("o1") {
-            request_body["max_completion_tokens"] = serde_json::json!(2000);
-        } else {
-            request_body["max_tokens"] = serde_json::json!(2000);
-        }
-
-        info!("Sending request to OpenAI API (model: {})", self.model);
-
-        // Call OpenAI API with 180-second timeout
-        let api_call = async {
-            self.client
-                .post("https://api.openai.com/v1/chat/completions")
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .header("Content-Type", "application/json")
-                .json(&request_body)
-                .send()
-                .await
+        let request = VisionRequest {
+            prompt,
+            image_base64: b64_image,
+            media_type: "image/png".to_string(),
+            max_tokens: 2000,
+            json_mode: false,
         };
 
-        let response = tokio::time::timeout(
-            std::time::Duration::from_secs(180),
-            api_call
-        )
-        .await
-        .map_err(|_| anyhow::anyhow!("OpenAI API request timed out after 180 seconds"))??;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            warn!("OpenAI API error: {} - {}", status, error_text);
-            return Err(anyhow::anyhow!("OpenAI API error: {} - {}", status, error_text));
-        }
-
-        let api_response: OpenAIResponse = response.json().await?;
-
-        // Parse response
-        if api_response.choices.is_empty() {
-            return Err(anyhow::anyhow!("No response from OpenAI API"));
-        }
-
-        let content = &api_response.choices[0].message.content;
-        info!("Received response from OpenAI: {}", content);
+        let response = self.complete(&request).await?;
+        let usage = self.response_usage(&response);
+        info!("Received response from {}: {}", self.backend.name(), response.content);
 
         // Parse JSON from response
         // GPT might wrap the JSON in markdown code blocks, so we need to extract it
-        let json_str = extract_json_from_response(content)?;
+        let json_str = extract_json_from_response(&response.content)?;
 
         let classifications: Vec<RoomClassification> = serde_json::from_str(&json_str)
             .map_err(|e| anyhow::anyhow!("Failed to parse classifications: {}. Response: {}", e, json_str))?;
 
-        info!("Successfully classified {} rooms", classifications.len());
+        info!(
+            "Successfully classified {} rooms ({} tokens, ~${:.4})",
+            classifications.len(),
+            usage.total_tokens(),
+            usage.estimated_cost_usd
+        );
 
-        Ok(classifications)
+        Ok(ClassificationResult { classifications, usage })
     }
 
     /// Enhance existing room detections with vision-based classification
@@ -254,30 +349,27 @@ impl VisionClassifier {
         image_bytes: &[u8],
         geometric_rooms: &[crate::GeometricRoom],
     ) -> anyhow::Result<Vec<EnhancedRoom>> {
-        let classifications = self.classify_floorplan(image_bytes, Some(geometric_rooms.len())).await?;
+        let classifications = self
+            .classify_floorplan(image_bytes, Some(geometric_rooms.len()))
+            .await?
+            .classifications;
+
+        // Match classifications to geometric rooms spatially (order-independent)
+        let assignments = match_classifications(geometric_rooms, &classifications);
 
-        // Match classifications to geometric rooms (by position/area heuristics)
         let enhanced = geometric_rooms
             .iter()
-            .enumerate()
-            .map(|(i, room)| {
-                // Try to find matching classification by room_id
-                let classification = classifications
-                    .iter()
-                    .find(|c| c.room_id == i)
-                    .or_else(|| classifications.get(i));
-
-                EnhancedRoom {
-                    geometric: room.clone(),
-                    classification: classification.cloned(),
-                }
+            .zip(assignments)
+            .map(|(room, assignment)| EnhancedRoom {
+                geometric: room.clone(),
+                classification: assignment.map(|idx| classifications[idx].clone()),
             })
             .collect();
 
         Ok(enhanced)
     }
 
-    /// Extract wall segments from a blueprint image using GPT-5 Vision
+    /// Extract wall segments from a blueprint image using the configured vision backend
     ///
     /// # Arguments
     /// * `image_base64` - Base64-encoded blueprint image (in normalized 1000x1000 space)
@@ -288,7 +380,7 @@ impl VisionClassifier {
         &self,
         image_base64: &str,
     ) -> anyhow::Result<VisionWallData> {
-        info!("Extracting wall segments from blueprint using GPT-5 Vision");
+        info!("Extracting wall segments from blueprint using {} ({})", self.backend.name(), self.backend.model());
 
         let prompt = r#"You are analyzing an architectural blueprint. Extract:
 1. All wall segments as line coordinates in 0-1000 normalized coordinate space
@@ -318,84 +410,20 @@ Guidelines:
 - Confidence: your overall confidence in the wall extraction (0.0-1.0)
 - Be precise with coordinates - walls should align properly"#;
 
-        // Prepare image URL - check if already has data URI prefix
-        let image_url = if image_base64.starts_with("data:") {
-            image_base64.to_string()
-        } else {
-            format!("data:image/png;base64,{}", image_base64)
-        };
-
-        // Build request
-        let mut request_body = serde_json::json!({
-            "model": self.model.clone(),
-            "messages": vec![serde_json::json!({
-                "role": "user",
-                "content": vec![
-                    serde_json::json!({
-                        "type": "text",
-                        "text": prompt
-                    }),
-                    serde_json::json!({
-                        "type": "image_url",
-                        "image_url": {
-                            "url": image_url
-                        }
-                    })
-                ]
-            })]
-        });
-
-        // Use appropriate token parameter based on model
-        if self.model.starts_with("gpt-5") || self.model.starts_with("o1") {
-            request_body["max_completion_tokens"] = serde_json::json!(4000);
-        } else {
-            request_body["max_tokens"] = serde_json::json!(4000);
-        }
-
-        // Enable JSON mode for reliable JSON output (gpt-4o and later support this)
-        if !self.model.starts_with("gpt-3") {
-            request_body["response_format"] = serde_json::json!({"type": "json_object"});
-        }
-
-        info!("Sending wall extraction request to OpenAI API (model: {})", self.model);
-
-        // Call OpenAI API with 180-second timeout
-        let api_call = async {
-            self.client
-                .post("https://api.openai.com/v1/chat/completions")
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .header("Content-Type", "application/json")
-                .json(&request_body)
-                .send()
-                .await
+        let (media_type, data) = split_data_url(image_base64);
+        let request = VisionRequest {
+            prompt: prompt.to_string(),
+            image_base64: data.to_string(),
+            media_type: media_type.to_string(),
+            max_tokens: 4000,
+            json_mode: true,
         };
 
-        let response = tokio::time::timeout(
-            std::time::Duration::from_secs(180),
-            api_call
-        )
-        .await
-        .map_err(|_| anyhow::anyhow!("OpenAI API request timed out after 180 seconds"))??;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            warn!("OpenAI API error: {} - {}", status, error_text);
-            return Err(anyhow::anyhow!("OpenAI API error: {} - {}", status, error_text));
-        }
-
-        let api_response: OpenAIResponse = response.json().await?;
-
-        // Parse response
-        if api_response.choices.is_empty() {
-            return Err(anyhow::anyhow!("No response from OpenAI API"));
-        }
-
-        let content = &api_response.choices[0].message.content;
-        info!("Received wall extraction response from OpenAI");
+        let response = self.complete(&request).await?;
+        info!("Received wall extraction response from {}", self.backend.name());
 
         // Parse JSON from response
-        let json_str = extract_json_from_response(content)?;
+        let json_str = extract_json_from_response(&response.content)?;
 
         let mut wall_data: VisionWallData = serde_json::from_str(&json_str)
             .map_err(|e| anyhow::anyhow!("Failed to parse wall data: {}. Response: {}", e, json_str))?;
@@ -422,6 +450,86 @@ Guidelines:
 
         Ok(wall_data)
     }
+
+    /// Send a request through the backend and add its usage to the running total
+    async fn complete(&self, request: &VisionRequest) -> anyhow::Result<VisionResponse> {
+        info!("Sending request to {} (model: {})", self.backend.name(), self.backend.model());
+        let response = self.backend.complete(request, self.retry).await?;
+        self.record_usage(&self.response_usage(&response));
+        Ok(response)
+    }
+}
+
+/// Assign each geometric room at most one classification by greedy nearest-centroid matching
+///
+/// Geometric bounding box centers are normalized to 0-1000 over the extent of all rooms so
+/// they share a space with the vision centers. The closest (room, classification) pairs are
+/// taken first and no classification is reused. Classifications without a center fall back to
+/// their explicit `room_id`, if that room is still free. Rooms left over get `None`.
+fn match_classifications(
+    geometric_rooms: &[GeometricRoom],
+    classifications: &[RoomClassification],
+) -> Vec<Option<usize>> {
+    let mut assignments: Vec<Option<usize>> = vec![None; geometric_rooms.len()];
+    if geometric_rooms.is_empty() || classifications.is_empty() {
+        return assignments;
+    }
+
+    // Extent of all geometric rooms, used to normalize into 0-1000
+    let min_x = geometric_rooms.iter().map(|r| r.bounding_box[0]).fold(f64::INFINITY, f64::min);
+    let min_y = geometric_rooms.iter().map(|r| r.bounding_box[1]).fold(f64::INFINITY, f64::min);
+    let max_x = geometric_rooms.iter().map(|r| r.bounding_box[2]).fold(f64::NEG_INFINITY, f64::max);
+    let max_y = geometric_rooms.iter().map(|r| r.bounding_box[3]).fold(f64::NEG_INFINITY, f64::max);
+    let width = (max_x - min_x).max(f64::EPSILON);
+    let height = (max_y - min_y).max(f64::EPSILON);
+
+    let centers: Vec<WallPoint> = geometric_rooms
+        .iter()
+        .map(|r| WallPoint {
+            x: ((r.bounding_box[0] + r.bounding_box[2]) / 2.0 - min_x) / width * 1000.0,
+            y: ((r.bounding_box[1] + r.bounding_box[3]) / 2.0 - min_y) / height * 1000.0,
+        })
+        .collect();
+
+    // All candidate pairs, closest first
+    let mut pairs: Vec<(f64, usize, usize)> = Vec::new();
+    for (room_idx, center) in centers.iter().enumerate() {
+        for (class_idx, classification) in classifications.iter().enumerate() {
+            if let Some(c) = classification.center {
+                let distance = ((center.x - c.x).powi(2) + (center.y - c.y).powi(2)).sqrt();
+                pairs.push((distance, room_idx, class_idx));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| {
+        a.0.partial_cmp(&b.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.1.cmp(&b.1))
+            .then(a.2.cmp(&b.2))
+    });
+
+    let mut used = vec![false; classifications.len()];
+    for (_, room_idx, class_idx) in pairs {
+        if assignments[room_idx].is_none() && !used[class_idx] {
+            assignments[room_idx] = Some(class_idx);
+            used[class_idx] = true;
+        }
+    }
+
+    // Classifications without a position can only match their explicit room id
+    for (class_idx, classification) in classifications.iter().enumerate() {
+        if used[class_idx] || classification.center.is_some() {
+            continue;
+        }
+        if let Some(room_idx) = geometric_rooms.iter().position(|r| r.id == classification.room_id) {
+            if assignments[room_idx].is_none() {
+                assignments[room_idx] = Some(class_idx);
+                used[class_idx] = true;
+            }
+        }
+    }
+
+    assignments
 }
 
 /// Extract JSON from GPT response (handles markdown code blocks)
@@ -445,6 +553,17 @@ fn extract_json_from_response(content: &str) -> anyhow::Result<String> {
     }
 }
 
+/// Split a `data:<media type>;base64,` URI into its media type and payload
+///
+/// Bare base64 is assumed to be PNG.
+fn split_data_url(image: &str) -> (&str, &str) {
+    image
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(','))
+        .map(|(header, data)| (header.trim_end_matches(";base64"), data))
+        .unwrap_or(("image/png", image))
+}
+
 /// Validate that a coordinate point is within the normalized 0-1000 range
 fn is_valid_coordinate(point: &WallPoint) -> bool {
     point.x >= 0.0 && point.x <= 1000.0 && point.y >= 0.0 && point.y <= 1000.0
@@ -485,4 +604,232 @@ mod tests {
         let json = extract_json_from_response(response).unwrap();
         assert_eq!(json, r#"[{"room_id": 0, "room_type": "bedroom"}]"#);
     }
+
+    fn geometric(id: usize, bounding_box: [f64; 4]) -> GeometricRoom {
+        GeometricRoom {
+            id,
+            bounding_box,
+            area: (bounding_box[2] - bounding_box[0]) * (bounding_box[3] - bounding_box[1]),
+        }
+    }
+
+    fn classification(room_id: usize, room_type: &str, center: Option<(f64, f64)>) -> RoomClassification {
+        RoomClassification {
+            room_id,
+            room_type: room_type.to_string(),
+            confidence: 0.9,
+            features: vec![],
+            description: String::new(),
+            center: center.map(|(x, y)| WallPoint { x, y }),
+        }
+    }
+
+    fn matched_types(rooms: &[GeometricRoom], classifications: &[RoomClassification]) -> Vec<Option<String>> {
+        match_classifications(rooms, classifications)
+            .into_iter()
+            .map(|m| m.map(|idx| classifications[idx].room_type.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_match_classifications_order_independent() {
+        // Three rooms left to right across a 300x100 plan
+        let rooms = vec![
+            geometric(0, [0.0, 0.0, 100.0, 100.0]),
+            geometric(1, [100.0, 0.0, 200.0, 100.0]),
+            geometric(2, [200.0, 0.0, 300.0, 100.0]),
+        ];
+
+        let ordered = vec![
+            classification(0, "kitchen", Some((170.0, 500.0))),
+            classification(1, "bedroom", Some((500.0, 500.0))),
+            classification(2, "bathroom", Some((830.0, 500.0))),
+        ];
+        // Same classifications, shuffled and with misleading room ids
+        let shuffled = vec![
+            classification(0, "bathroom", Some((830.0, 500.0))),
+            classification(1, "kitchen", Some((170.0, 500.0))),
+            classification(2, "bedroom", Some((500.0, 500.0))),
+        ];
+
+        let expected = vec![
+            Some("kitchen".to_string()),
+            Some("bedroom".to_string()),
+            Some("bathroom".to_string()),
+        ];
+        assert_eq!(matched_types(&rooms, &ordered), expected);
+        assert_eq!(matched_types(&rooms, &shuffled), expected);
+    }
+
+    #[test]
+    fn test_match_classifications_count_mismatch() {
+        let rooms = vec![
+            geometric(0, [0.0, 0.0, 100.0, 100.0]),
+            geometric(1, [100.0, 0.0, 200.0, 100.0]),
+            geometric(2, [200.0, 0.0, 300.0, 100.0]),
+        ];
+        let classifications = vec![classification(0, "bathroom", Some((830.0, 500.0)))];
+
+        // Only the nearest room gets the label; the others stay unclassified
+        assert_eq!(
+            matched_types(&rooms, &classifications),
+            vec![None, None, Some("bathroom".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_match_classifications_without_center_uses_room_id() {
+        let rooms = vec![
+            geometric(0, [0.0, 0.0, 100.0, 100.0]),
+            geometric(1, [100.0, 0.0, 200.0, 100.0]),
+        ];
+        let classifications = vec![classification(1, "office", None)];
+
+        assert_eq!(
+            matched_types(&rooms, &classifications),
+            vec![None, Some("office".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_usage_from_canned_response() {
+        let body = r#"{
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "[]"}, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 1200, "completion_tokens": 300, "total_tokens": 1500}
+        }"#;
+        let response: backend::OpenAIResponse = serde_json::from_str(body).unwrap();
+        let response = response.into_vision_response().unwrap();
+
+        let classifier = VisionClassifier::new("test-key".to_string(), Some("gpt-4o-2024-08-06".to_string()));
+        let usage = classifier.response_usage(&response);
+        assert_eq!(usage.prompt_tokens, 1200);
+        assert_eq!(usage.completion_tokens, 300);
+        // gpt-4o: $2.50 / 1M input, $10 / 1M output
+        assert!((usage.estimated_cost_usd - 0.006).abs() < 1e-9);
+
+        classifier.record_usage(&usage);
+        classifier.record_usage(&usage);
+        let total = classifier.total_usage();
+        assert_eq!(total.total_tokens(), 3000);
+        assert!((total.estimated_cost_usd - 0.012).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_usage_missing_and_custom_prices() {
+        let response: backend::OpenAIResponse =
+            serde_json::from_str(r#"{"choices": [{"message": {"content": "[]"}}]}"#).unwrap();
+        let response = response.into_vision_response().unwrap();
+        let classifier = VisionClassifier::new("test-key".to_string(), Some("local-model".to_string()));
+        assert_eq!(classifier.response_usage(&response), TokenUsage::default());
+
+        let prices = PriceTable::new(HashMap::from([(
+            "local".to_string(),
+            ModelPrice { input_per_million: 1.0, output_per_million: 2.0 },
+        )]));
+        assert!((prices.estimate_cost("local-model", 1_000_000, 500_000) - 2.0).abs() < 1e-9);
+        assert_eq!(PriceTable::default().estimate_cost("local-model", 1_000, 1_000), 0.0);
+    }
+
+    /// Backend that answers every request with a fixed payload
+    struct FakeBackend {
+        payload: String,
+    }
+
+    impl VisionBackend for FakeBackend {
+        fn name(&self) -> &str {
+            "Fake"
+        }
+
+        fn model(&self) -> &str {
+            "claude-sonnet-4-5"
+        }
+
+        fn complete<'a>(&'a self, request: &'a VisionRequest, _retry: RetryPolicy) -> BackendFuture<'a> {
+            // Image bytes [1, 2, 3] arrive base64-encoded, with the room count hint in the prompt
+            assert_eq!(request.image_base64, "AQID");
+            assert!(request.prompt.contains("1 rooms were detected"));
+            Box::pin(async move {
+                Ok(VisionResponse {
+                    content: self.payload.clone(),
+                    prompt_tokens: 1000,
+                    completion_tokens: 100,
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_backend_flows_through_classifier() {
+        let payload = r#"[{"room_id": 0, "room_type": "bathroom", "confidence": 0.7,
+            "features": ["toilet", "shower"], "description": "Full bath"}]"#;
+        let classifier = VisionClassifier::from_backend(FakeBackend {
+            payload: payload.to_string(),
+        });
+
+        let result = classifier.classify_floorplan(&[1, 2, 3], Some(1)).await.unwrap();
+        assert_eq!(result.classifications.len(), 1);
+        assert_eq!(result.classifications[0].room_type, "bathroom");
+        assert_eq!(result.classifications[0].features, vec!["toilet", "shower"]);
+
+        // Usage is priced against the backend's model: $3 / 1M input, $15 / 1M output
+        assert_eq!(result.usage.total_tokens(), 1100);
+        assert!((result.usage.estimated_cost_usd - 0.0045).abs() < 1e-9);
+        assert_eq!(classifier.total_usage(), result.usage);
+        assert_eq!(classifier.model(), "claude-sonnet-4-5");
+    }
+
+    #[test]
+    fn test_split_data_url() {
+        assert_eq!(split_data_url("data:image/jpeg;base64,AAAA"), ("image/jpeg", "AAAA"));
+        assert_eq!(split_data_url("AAAA"), ("image/png", "AAAA"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_classify_markdown_response() {
+        let response = r#"```json
+[
+  {"room_id": 0, "room_type": "kitchen", "confidence": 0.9, "features": ["sink"],
+   "description": "Galley kitchen", "center": {"x": 170, "y": 500}},
+  {"room_id": 1, "room_type": "bedroom", "confidence": 0.8, "features": ["bed"],
+   "description": "Small bedroom", "center": {"x": 830, "y": 500}}
+]
+```"#;
+        let classifier = VisionClassifier::mock(vec![response.to_string(), response.to_string()]);
+
+        let result = classifier.classify_floorplan(&[], Some(2)).await.unwrap();
+        let types: Vec<&str> = result.classifications.iter().map(|c| c.room_type.as_str()).collect();
+        assert_eq!(types, vec!["kitchen", "bedroom"]);
+        assert_eq!(result.classifications[0].features, vec!["sink".to_string()]);
+        assert_eq!(result.usage, TokenUsage::default());
+
+        // Same response matched spatially onto geometric rooms listed in the opposite order
+        let rooms = vec![
+            geometric(7, [200.0, 0.0, 300.0, 100.0]),
+            geometric(3, [0.0, 0.0, 100.0, 100.0]),
+        ];
+        let enhanced = classifier.enhance_detections(&[], &rooms).await.unwrap();
+        let types: Vec<Option<&str>> = enhanced
+            .iter()
+            .map(|r| r.classification.as_ref().map(|c| c.room_type.as_str()))
+            .collect();
+        assert_eq!(types, vec![Some("bedroom"), Some("kitchen")]);
+
+        // Queue exhausted
+        assert!(classifier.classify_floorplan(&[], None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mock_extract_wall_segments() {
+        let response = r#"{"walls": [{"start": {"x": 100, "y": 200}, "end": {"x": 500, "y": 200}}],
+            "rooms": [{"label": "Kitchen", "center": {"x": 300, "y": 400}, "room_type": "kitchen"}],
+            "confidence": 1.4}"#;
+        let classifier = VisionClassifier::mock(vec![response.to_string()]);
+
+        let data = classifier.extract_wall_segments("aGVsbG8=").await.unwrap();
+        assert_eq!(data.walls.len(), 1);
+        assert_eq!(data.rooms[0].room_type, "kitchen");
+        assert_eq!(data.confidence, 1.0);
+    }
 }